@@ -1,5 +1,6 @@
 use arrayvec::ArrayVec;
 use generated::{Item, ItemStack};
+use libcraft_items::{Enchantment, EnchantmentKind};
 use serde::ser::Error;
 use serde::{Deserialize, Serialize, Serializer};
 use thiserror::Error;
@@ -231,7 +232,20 @@ where
 pub struct ItemNbt {
     #[serde(rename = "Damage")]
     pub damage: Option<i32>,
-    // TODO enchantments, display name, ...
+    #[serde(
+        rename = "Enchantments",
+        default,
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub enchantments: Vec<EnchantmentNbt>,
+    // TODO display name, ...
+}
+
+/// An enchantment in an item's NBT tags.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct EnchantmentNbt {
+    pub id: String,
+    pub lvl: i16,
 }
 
 impl ItemNbt {
@@ -241,8 +255,24 @@ impl ItemNbt {
             count: count as u32,
             item,
             damage: nbt.as_ref().map(|n| n.damage).flatten().map(|x| x as u32),
+            enchantments: nbt
+                .as_ref()
+                .map(ItemNbt::parse_enchantments)
+                .unwrap_or_default(),
         }
     }
+
+    /// Returns the enchantments in these tags.
+    /// Unknown enchantments are ignored.
+    pub fn parse_enchantments(&self) -> Vec<Enchantment> {
+        self.enchantments
+            .iter()
+            .filter_map(|enchantment| {
+                EnchantmentKind::from_name(&enchantment.id)
+                    .map(|kind| Enchantment::new(kind, enchantment.lvl.max(0) as u32))
+            })
+            .collect()
+    }
 }
 
 impl<S> From<S> for ItemNbt
@@ -253,6 +283,14 @@ where
         let stack = s.borrow();
         Self {
             damage: stack.damage.map(|d| d as i32),
+            enchantments: stack
+                .enchantments
+                .iter()
+                .map(|enchantment| EnchantmentNbt {
+                    id: enchantment.kind().name().to_owned(),
+                    lvl: enchantment.level() as i16,
+                })
+                .collect(),
         }
     }
 }
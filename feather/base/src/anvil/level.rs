@@ -3,7 +3,14 @@
 use generated::{Biome, Item};
 use serde::{Deserialize, Serialize};
 use std::io::{Cursor, Read, Write};
-use std::{collections::HashMap, fs::File};
+use std::{
+    collections::HashMap,
+    fs::{self, File},
+    path::{Path, PathBuf},
+};
+
+/// Data version written to newly created level files (1.16.2).
+pub const DATA_VERSION: i32 = 2578;
 
 /// Root level tag
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -15,6 +22,7 @@ pub struct Root {
 /// Represents the contents of a level file.
 #[derive(Default, Debug, Clone, Serialize, Deserialize)]
 pub struct LevelData {
+    #[serde(with = "byte_bool")]
     #[serde(rename = "allowCommands")]
     pub allow_commands: bool,
     #[serde(default)]
@@ -44,14 +52,20 @@ pub struct LevelData {
     #[serde(rename = "GameType")]
     pub game_type: i32,
 
+    #[serde(with = "byte_bool")]
     pub hardcore: bool,
 
+    #[serde(with = "byte_bool")]
     pub initialized: bool,
     #[serde(rename = "LastPlayed")]
     pub last_played: i64,
+    #[serde(with = "byte_bool")]
     pub raining: bool,
     #[serde(rename = "rainTime")]
     pub rain_time: i32,
+    /// The world seed of pre-1.16 worlds. Newer worlds
+    /// store their seed in `world_gen_settings`.
+    #[serde(default)]
     #[serde(rename = "RandomSeed")]
     pub seed: i64,
 
@@ -61,6 +75,7 @@ pub struct LevelData {
     pub spawn_y: i32,
     #[serde(rename = "SpawnZ")]
     pub spawn_z: i32,
+    #[serde(with = "byte_bool")]
     pub thundering: bool,
     #[serde(rename = "thunderTime")]
     pub thunder_time: i32,
@@ -70,10 +85,213 @@ pub struct LevelData {
     #[serde(rename = "Version")]
     pub version: LevelVersion,
 
+    #[serde(default)]
     #[serde(rename = "generatorName")]
     pub generator_name: String,
     #[serde(rename = "generatorOptions")]
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub generator_options: Option<SuperflatGeneratorOptions>,
+
+    /// World generation settings of 1.16+ worlds.
+    #[serde(rename = "WorldGenSettings")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub world_gen_settings: Option<WorldGenSettings>,
+
+    /// Game rules, stored as strings like vanilla does.
+    #[serde(default)]
+    #[serde(rename = "GameRules")]
+    pub game_rules: HashMap<String, String>,
+
+    /// Tags not covered by the fields above (data packs, boss bars, ...).
+    /// They are kept so that saving the file does not discard them.
+    #[serde(flatten)]
+    pub extra: HashMap<String, nbt::Value>,
+}
+
+/// World generation settings introduced in 1.16.
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+pub struct WorldGenSettings {
+    pub seed: i64,
+    #[serde(with = "byte_bool")]
+    pub generate_features: bool,
+    #[serde(with = "byte_bool")]
+    pub bonus_chest: bool,
+    pub dimensions: HashMap<String, Dimension>,
+}
+
+/// A dimension in [`WorldGenSettings`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Dimension {
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub generator: DimensionGenerator,
+}
+
+/// The chunk generator of a [`Dimension`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DimensionGenerator {
+    #[serde(rename = "type")]
+    pub kind: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seed: Option<i64>,
+    pub settings: GeneratorSettings,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub biome_source: Option<nbt::Value>,
+}
+
+/// Settings of a [`DimensionGenerator`]: either the name of
+/// a noise settings preset or the layers of a flat world.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum GeneratorSettings {
+    Preset(String),
+    Flat(SuperflatGeneratorOptions),
+}
+
+/// Booleans are stored as bytes, which hematite_nbt cannot
+/// deserialize as `bool` inside flattened structs.
+/// See: https://github.com/PistonDevelopers/hematite_nbt/issues/43
+mod byte_bool {
+    use serde::de::{Error, Unexpected, Visitor};
+    use serde::{Deserializer, Serializer};
+    use std::fmt;
+
+    pub fn serialize<S: Serializer>(value: &bool, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_i8(*value as i8)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<bool, D::Error> {
+        struct ByteBoolVisitor;
+
+        impl<'de> Visitor<'de> for ByteBoolVisitor {
+            type Value = bool;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a boolean or a byte")
+            }
+
+            fn visit_bool<E: Error>(self, value: bool) -> Result<bool, E> {
+                Ok(value)
+            }
+
+            fn visit_i64<E: Error>(self, value: i64) -> Result<bool, E> {
+                match value {
+                    0 => Ok(false),
+                    1 => Ok(true),
+                    _ => Err(E::invalid_value(Unexpected::Signed(value), &self)),
+                }
+            }
+
+            fn visit_u64<E: Error>(self, value: u64) -> Result<bool, E> {
+                self.visit_i64(value as i64)
+            }
+        }
+
+        deserializer.deserialize_any(ByteBoolVisitor)
+    }
+}
+
+const OVERWORLD: &str = "minecraft:overworld";
+
+impl WorldGenSettings {
+    /// Creates settings for a world with the vanilla
+    /// dimensions, using a flat overworld if `flat` is given.
+    pub fn new(seed: i64, flat: Option<SuperflatGeneratorOptions>) -> Self {
+        let overworld_generator = match flat {
+            Some(mut options) => {
+                // Required by the 1.16 flat generator settings.
+                options
+                    .structures
+                    .entry("structures".to_owned())
+                    .or_insert_with(|| nbt::Value::Compound(HashMap::new()));
+                DimensionGenerator {
+                    kind: "minecraft:flat".to_owned(),
+                    seed: None,
+                    settings: GeneratorSettings::Flat(options),
+                    biome_source: None,
+                }
+            }
+            None => DimensionGenerator::noise(
+                seed,
+                OVERWORLD,
+                biome_source(
+                    "minecraft:vanilla_layered",
+                    seed,
+                    &[("large_biomes", nbt::Value::Byte(0))],
+                ),
+            ),
+        };
+
+        let mut dimensions = HashMap::new();
+        dimensions.insert(
+            OVERWORLD.to_owned(),
+            Dimension {
+                kind: OVERWORLD.to_owned(),
+                generator: overworld_generator,
+            },
+        );
+        dimensions.insert(
+            "minecraft:the_nether".to_owned(),
+            Dimension {
+                kind: "minecraft:the_nether".to_owned(),
+                generator: DimensionGenerator::noise(
+                    seed,
+                    "minecraft:nether",
+                    biome_source(
+                        "minecraft:multi_noise",
+                        seed,
+                        &[("preset", nbt::Value::String("minecraft:nether".to_owned()))],
+                    ),
+                ),
+            },
+        );
+        dimensions.insert(
+            "minecraft:the_end".to_owned(),
+            Dimension {
+                kind: "minecraft:the_end".to_owned(),
+                generator: DimensionGenerator::noise(
+                    seed,
+                    "minecraft:end",
+                    biome_source("minecraft:the_end", seed, &[]),
+                ),
+            },
+        );
+
+        Self {
+            seed,
+            generate_features: true,
+            bonus_chest: false,
+            dimensions,
+        }
+    }
+
+    /// Returns the generator of the overworld.
+    pub fn overworld_generator(&self) -> Option<&DimensionGenerator> {
+        self.dimensions
+            .get(OVERWORLD)
+            .map(|dimension| &dimension.generator)
+    }
+}
+
+impl DimensionGenerator {
+    fn noise(seed: i64, settings: &str, biome_source: nbt::Value) -> Self {
+        Self {
+            kind: "minecraft:noise".to_owned(),
+            seed: Some(seed),
+            settings: GeneratorSettings::Preset(settings.to_owned()),
+            biome_source: Some(biome_source),
+        }
+    }
+}
+
+fn biome_source(kind: &str, seed: i64, extra: &[(&str, nbt::Value)]) -> nbt::Value {
+    let mut compound = HashMap::new();
+    compound.insert("type".to_owned(), nbt::Value::String(kind.to_owned()));
+    compound.insert("seed".to_owned(), nbt::Value::Long(seed));
+    for (key, value) in extra {
+        compound.insert((*key).to_owned(), value.clone());
+    }
+    nbt::Value::Compound(compound)
 }
 
 impl LevelData {
@@ -95,6 +313,24 @@ impl LevelData {
     }
 }
 
+/// Loads the `level.dat` file of the given world.
+pub fn load_level_data(world_dir: &Path) -> Result<LevelData, nbt::Error> {
+    let mut file = File::open(file_path(world_dir))?;
+    let root: Root = nbt::from_gzip_reader(&mut file)?;
+    Ok(root.data)
+}
+
+/// Saves the `level.dat` file of the given world.
+pub fn save_level_data(world_dir: &Path, data: &LevelData) -> anyhow::Result<()> {
+    fs::create_dir_all(world_dir)?;
+    let mut file = File::create(file_path(world_dir))?;
+    data.save_to_file(&mut file)
+}
+
+fn file_path(world_dir: &Path) -> PathBuf {
+    world_dir.join("level.dat")
+}
+
 /// Represents level version data.
 #[derive(Default, Debug, Clone, Serialize, Deserialize)]
 pub struct LevelVersion {
@@ -104,6 +340,15 @@ pub struct LevelVersion {
     name: String,
 }
 
+impl LevelVersion {
+    pub fn new(id: i32, name: impl Into<String>) -> Self {
+        Self {
+            id,
+            name: name.into(),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SuperflatGeneratorOptions {
     pub structures: HashMap<String, nbt::Value>,
@@ -124,19 +369,19 @@ impl Default for SuperflatGeneratorOptions {
             structures: default_structures,
             layers: vec![
                 SuperflatLayer {
-                    block: Item::Bedrock.name().to_string(),
+                    block: format!("minecraft:{}", Item::Bedrock.name()),
                     height: 1,
                 },
                 SuperflatLayer {
-                    block: Item::Dirt.name().to_string(),
+                    block: format!("minecraft:{}", Item::Dirt.name()),
                     height: 2,
                 },
                 SuperflatLayer {
-                    block: Item::GrassBlock.name().to_string(),
+                    block: format!("minecraft:{}", Item::GrassBlock.name()),
                     height: 1,
                 },
             ],
-            biome: Biome::Plains.name().to_string(),
+            biome: format!("minecraft:{}", Biome::Plains.name()),
         }
     }
 }
@@ -159,11 +404,53 @@ pub enum LevelGeneratorType {
 }
 
 impl LevelData {
+    /// Returns the world seed, taking it from the
+    /// 1.16 world generation settings if present.
+    pub fn world_seed(&self) -> i64 {
+        match &self.world_gen_settings {
+            Some(settings) => settings.seed,
+            None => self.seed,
+        }
+    }
+
+    /// Returns the superflat options of a flat world.
+    pub fn superflat_options(&self) -> Option<SuperflatGeneratorOptions> {
+        let from_settings = self
+            .world_gen_settings
+            .as_ref()
+            .and_then(WorldGenSettings::overworld_generator)
+            .and_then(|generator| match &generator.settings {
+                GeneratorSettings::Flat(options) => Some(options.clone()),
+                GeneratorSettings::Preset(_) => None,
+            });
+        from_settings.or_else(|| self.generator_options.clone())
+    }
+
+    /// Returns the value of the given game rule, or `None`
+    /// if it is not set or is not a boolean.
+    pub fn game_rule_bool(&self, name: &str) -> Option<bool> {
+        self.game_rules
+            .get(name)
+            .and_then(|value| value.parse().ok())
+    }
+
     pub fn generator_type(&self) -> LevelGeneratorType {
+        if let Some(generator) = self
+            .world_gen_settings
+            .as_ref()
+            .and_then(WorldGenSettings::overworld_generator)
+        {
+            return match generator.kind.as_str() {
+                "minecraft:flat" => LevelGeneratorType::Flat,
+                "minecraft:debug" => LevelGeneratorType::Debug,
+                _ => LevelGeneratorType::Default,
+            };
+        }
+
         match self.generator_name.to_lowercase().as_str() {
             "default" => LevelGeneratorType::Default,
             "flat" => LevelGeneratorType::Flat,
-            "largeBiomes" => LevelGeneratorType::LargeBiomes,
+            "largebiomes" => LevelGeneratorType::LargeBiomes,
             "amplified" => LevelGeneratorType::Amplified,
             "buffet" => LevelGeneratorType::Buffet,
             "debug_all_block_states" => LevelGeneratorType::Debug,
@@ -202,5 +489,55 @@ mod tests {
         assert_eq!(level.thunder_time, 5252);
         assert_eq!(level.generator_name, "default");
         assert!(level.generator_options.is_none());
+        assert_eq!(level.generator_type(), LevelGeneratorType::Default);
+        assert_eq!(level.world_seed(), level.seed);
+        assert!(level.extra.contains_key("DataPacks"));
+        assert!(level.extra.contains_key("LevelName"));
+    }
+
+    #[test]
+    fn test_roundtrip_keeps_unknown_tags() {
+        let cursor = Cursor::new(include_bytes!("level.dat").to_vec());
+        let level = nbt::from_gzip_reader::<_, Root>(cursor).unwrap().data;
+
+        let mut buf = Vec::new();
+        nbt::to_gzip_writer(
+            &mut buf,
+            &Root {
+                data: level.clone(),
+            },
+            None,
+        )
+        .unwrap();
+        let reloaded = nbt::from_gzip_reader::<_, Root>(Cursor::new(buf))
+            .unwrap()
+            .data;
+
+        assert_eq!(reloaded.extra, level.extra);
+        assert!(reloaded.extra.contains_key("CustomBossEvents"));
+        assert_eq!(reloaded.thundering, level.thundering);
+        assert_eq!(reloaded.day_time, level.day_time);
+        assert_eq!(reloaded.game_rules, level.game_rules);
+    }
+
+    #[test]
+    fn test_roundtrip_world_gen_settings() {
+        let level = LevelData {
+            world_gen_settings: Some(WorldGenSettings::new(
+                1234,
+                Some(SuperflatGeneratorOptions::default()),
+            )),
+            ..Default::default()
+        };
+
+        let mut buf = Vec::new();
+        nbt::to_gzip_writer(&mut buf, &Root { data: level }, None).unwrap();
+        let level = nbt::from_gzip_reader::<_, Root>(Cursor::new(buf))
+            .unwrap()
+            .data;
+
+        assert_eq!(level.world_seed(), 1234);
+        assert_eq!(level.generator_type(), LevelGeneratorType::Flat);
+        assert_eq!(level.superflat_options().unwrap().layers.len(), 3);
     }
 }
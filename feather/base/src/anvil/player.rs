@@ -26,6 +26,25 @@ pub struct PlayerData {
     pub inventory: Vec<InventorySlot>,
     #[serde(rename = "SelectedItemSlot")]
     pub held_item: i32,
+    #[serde(rename = "foodLevel", default = "default_food_level")]
+    pub food_level: i32,
+    #[serde(rename = "foodSaturationLevel", default = "default_food_saturation")]
+    pub food_saturation: f32,
+    #[serde(rename = "foodExhaustionLevel", default)]
+    pub food_exhaustion: f32,
+
+    /// Tags not covered by the fields above (abilities, XP, ender chest, ...).
+    /// They are kept so that saving the file does not discard them.
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
+}
+
+fn default_food_level() -> i32 {
+    20
+}
+
+fn default_food_saturation() -> f32 {
+    5.0
 }
 
 /// Represents a single inventory slot (including position index).
@@ -160,10 +179,12 @@ fn file_path(world_dir: &Path, uuid: Uuid) -> PathBuf {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::anvil::entity::EnchantmentNbt;
     use crate::{
         inventory::{SLOT_ARMOR_CHEST, SLOT_ARMOR_FEET, SLOT_ARMOR_HEAD, SLOT_ARMOR_LEGS},
         Gamemode,
     };
+    use libcraft_items::EnchantmentKind;
     use num_traits::ToPrimitive;
     use std::collections::HashMap;
     use std::io::Cursor;
@@ -175,7 +196,29 @@ mod tests {
         let player: PlayerData = nbt::from_gzip_reader(&mut cursor).unwrap();
         assert_eq!(player.gamemode, Gamemode::Creative.to_i32().unwrap());
         assert_eq!(player.inventory[0].item, "minecraft:diamond_shovel");
-        assert_eq!(player.inventory[0].nbt, Some(ItemNbt { damage: Some(3) }));
+        assert_eq!(
+            player.inventory[0].nbt,
+            Some(ItemNbt {
+                damage: Some(3),
+                ..Default::default()
+            })
+        );
+    }
+
+    #[test]
+    fn test_serialize_keeps_unknown_tags() {
+        let mut cursor = Cursor::new(include_bytes!("player.dat").to_vec());
+        let player: PlayerData = nbt::from_gzip_reader(&mut cursor).unwrap();
+        assert!(player.extra.contains_key("abilities"));
+        assert!(player.extra.contains_key("XpTotal"));
+
+        let mut bytes = Vec::new();
+        nbt::to_writer(&mut bytes, &player, None).unwrap();
+        let reloaded: PlayerData = nbt::from_reader(Cursor::new(bytes)).unwrap();
+        assert!(reloaded.extra.contains_key("abilities"));
+        assert!(reloaded.extra.contains_key("XpTotal"));
+        assert_eq!(reloaded.gamemode, player.gamemode);
+        assert_eq!(reloaded.inventory.len(), player.inventory.len());
     }
 
     #[test]
@@ -198,7 +241,10 @@ mod tests {
             count: 1,
             slot: 2,
             item: String::from(Item::DiamondAxe.name()),
-            nbt: Some(ItemNbt { damage: Some(42) }),
+            nbt: Some(ItemNbt {
+                damage: Some(42),
+                ..Default::default()
+            }),
         };
 
         let item_stack: ItemStack = slot.into();
@@ -207,6 +253,36 @@ mod tests {
         assert_eq!(item_stack.damage, Some(42));
     }
 
+    #[test]
+    fn test_convert_item_enchantments() {
+        let slot = InventorySlot {
+            count: 1,
+            slot: 2,
+            item: String::from(Item::DiamondPickaxe.name()),
+            nbt: Some(ItemNbt {
+                damage: Some(0),
+                enchantments: vec![
+                    EnchantmentNbt {
+                        id: String::from("minecraft:efficiency"),
+                        lvl: 5,
+                    },
+                    EnchantmentNbt {
+                        id: String::from("minecraft:unknown"),
+                        lvl: 1,
+                    },
+                ],
+            }),
+        };
+
+        let item_stack: ItemStack = slot.into();
+        assert_eq!(item_stack.enchantment_level(EnchantmentKind::Efficiency), 5);
+        assert_eq!(item_stack.enchantments.len(), 1);
+
+        let nbt = ItemNbt::from(&item_stack);
+        assert_eq!(nbt.enchantments[0].id, "minecraft:efficiency");
+        assert_eq!(nbt.enchantments[0].lvl, 5);
+    }
+
     #[test]
     fn test_convert_item_unknown_type() {
         let slot = InventorySlot {
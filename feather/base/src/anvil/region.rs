@@ -2,7 +2,7 @@
 //! of Anvil region files.
 
 use crate::{
-    chunk::{BlockStore, LightStore, PackedArray, Palette},
+    chunk::{BlockStore, LightStore, PackedArray, Palette, MIN_BITS_PER_BLOCK},
     Chunk, ChunkPosition, ChunkSection,
 };
 
@@ -11,7 +11,8 @@ use bitvec::{bitvec, vec::BitVec};
 use blocks::BlockId;
 use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
 use generated::Biome;
-use serde::{Deserialize, Serialize};
+use nbt::Value;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use std::borrow::Cow;
 use std::collections::BTreeMap;
 use std::fmt::{self, Display, Formatter};
@@ -56,10 +57,10 @@ pub struct ChunkLevel {
     #[serde(serialize_with = "nbt::i32_array")]
     biomes: Vec<i32>,
     #[serde(default)]
-    entities: Vec<EntityData>,
+    entities: Vec<Value>,
     #[serde(rename = "TileEntities")]
     #[serde(default)]
-    block_entities: Vec<BlockEntityData>,
+    block_entities: Vec<Value>,
     #[serde(rename = "ToBeTicked")]
     #[serde(default)]
     awaiting_block_updates: Vec<Vec<i16>>,
@@ -79,6 +80,33 @@ pub struct ChunkLevel {
     worldgen_status: Cow<'static, str>,
 }
 
+/// The entities and block entities of a chunk, as raw NBT compounds.
+///
+/// Unlike `EntityData` and `BlockEntityData`, this keeps the kinds
+/// and tags Feather does not know about, so that they survive a save.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ChunkEntityTags {
+    pub entities: Vec<Value>,
+    pub block_entities: Vec<Value>,
+}
+
+/// The part of a chunk's data read by `RegionHandle::load_entity_tags`.
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct EntityTagsRoot {
+    level: EntityTagsLevel,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct EntityTagsLevel {
+    #[serde(default)]
+    entities: Vec<Value>,
+    #[serde(rename = "TileEntities")]
+    #[serde(default)]
+    block_entities: Vec<Value>,
+}
+
 /// Represents a chunk section in a region file.
 #[derive(Serialize, Deserialize, Debug)]
 #[serde(rename_all = "PascalCase")]
@@ -159,10 +187,66 @@ impl RegionHandle {
     /// region file.
     pub fn load_chunk(
         &mut self,
-        mut pos: ChunkPosition,
+        pos: ChunkPosition,
     ) -> Result<(Chunk, Vec<EntityData>, Vec<BlockEntityData>), Error> {
-        // Get a copy of the original position before clipping
-        let original_pos = pos;
+        let mut root: ChunkRoot = self.read_chunk(pos)?;
+
+        // Check data version
+        if root.data_version != DATA_VERSION {
+            return Err(Error::UnsupportedDataVersion(root.data_version));
+        }
+
+        let level = &mut root.level;
+
+        let mut chunk = Chunk::new(pos);
+
+        // Read sections
+        for section in &mut level.sections {
+            read_section_into_chunk(section, &mut chunk)?;
+        }
+
+        // Read biomes
+        if level.biomes.len() != 1024 {
+            return Err(Error::IndexOutOfBounds);
+        }
+        for index in 0..1024 {
+            let id = level.biomes[index];
+            chunk.biomes_mut().as_slice_mut()[index] =
+                Biome::from_id(id as u32).ok_or(Error::InvalidBiomeId(id))?;
+        }
+
+        // chunk.recalculate_heightmap();
+
+        let entities = level
+            .entities
+            .iter()
+            .map(convert)
+            .collect::<Result<_, _>>()?;
+        let block_entities = level
+            .block_entities
+            .iter()
+            .map(convert)
+            .collect::<Result<_, _>>()?;
+        Ok((chunk, entities, block_entities))
+    }
+
+    /// Loads the entities and block entities of the chunk
+    /// at the given position (global, not region-relative)
+    /// without decoding them.
+    ///
+    /// # Panics
+    /// Panics if the specified chunk position is not within this
+    /// region file.
+    pub fn load_entity_tags(&mut self, pos: ChunkPosition) -> Result<ChunkEntityTags, Error> {
+        let root: EntityTagsRoot = self.read_chunk(pos)?;
+        Ok(ChunkEntityTags {
+            entities: root.level.entities,
+            block_entities: root.level.block_entities,
+        })
+    }
+
+    /// Reads and parses the NBT data of the chunk at the given position.
+    fn read_chunk<T: DeserializeOwned>(&mut self, mut pos: ChunkPosition) -> Result<T, Error> {
         // Clip chunk position to region-local coordinates.
         pos.x %= 32;
         pos.z %= 32;
@@ -208,39 +292,11 @@ impl RegionHandle {
 
         // Parse NBT data
         let cursor = Cursor::new(&buf[1..]);
-        let mut root: ChunkRoot = match compression_type {
-            1 => nbt::from_gzip_reader(cursor).map_err(Error::Nbt)?,
-            2 => nbt::from_zlib_reader(cursor).map_err(Error::Nbt)?,
-            _ => return Err(Error::InvalidCompression(compression_type)),
-        };
-
-        // Check data version
-        if root.data_version != DATA_VERSION {
-            return Err(Error::UnsupportedDataVersion(root.data_version));
-        }
-
-        let level = &mut root.level;
-
-        let mut chunk = Chunk::new(original_pos);
-
-        // Read sections
-        for section in &mut level.sections {
-            read_section_into_chunk(section, &mut chunk)?;
+        match compression_type {
+            1 => nbt::from_gzip_reader(cursor).map_err(Error::Nbt),
+            2 => nbt::from_zlib_reader(cursor).map_err(Error::Nbt),
+            _ => Err(Error::InvalidCompression(compression_type)),
         }
-
-        // Read biomes
-        if level.biomes.len() != 1024 {
-            return Err(Error::IndexOutOfBounds);
-        }
-        for index in 0..1024 {
-            let id = level.biomes[index];
-            chunk.biomes_mut().as_slice_mut()[index] =
-                Biome::from_id(id as u32).ok_or(Error::InvalidBiomeId(id))?;
-        }
-
-        // chunk.recalculate_heightmap();
-
-        Ok((chunk, level.entities.clone(), level.block_entities.clone()))
     }
 
     /// Saves the given chunk to this region file. The header will be updated
@@ -253,6 +309,27 @@ impl RegionHandle {
         chunk: &Chunk,
         entities: &[EntityData],
         block_entities: &[BlockEntityData],
+    ) -> Result<(), Error> {
+        let tags = ChunkEntityTags {
+            entities: entities.iter().map(convert).collect::<Result<_, _>>()?,
+            block_entities: block_entities
+                .iter()
+                .map(convert)
+                .collect::<Result<_, _>>()?,
+        };
+        self.save_chunk_with_tags(chunk, &tags)
+    }
+
+    /// Saves the given chunk to this region file along with
+    /// raw entity and block entity tags, e.g. those returned by
+    /// `load_entity_tags`.
+    ///
+    /// Behavior may be unexpected if this region file does not contain the given
+    /// chunk position.
+    pub fn save_chunk_with_tags(
+        &mut self,
+        chunk: &Chunk,
+        tags: &ChunkEntityTags,
     ) -> Result<(), Error> {
         let chunk_pos = chunk.position();
 
@@ -267,7 +344,7 @@ impl RegionHandle {
         }
 
         // Write chunk to `ChunkRoot` tag.
-        let root = chunk_to_chunk_root(chunk, entities, block_entities);
+        let root = chunk_to_chunk_root(chunk, tags);
 
         // Write to intermediate buffer, because we need to know the length.
         let mut buf = Vec::with_capacity(4096);
@@ -292,7 +369,7 @@ impl RegionHandle {
         self.file.write_all(&buf).map_err(Error::Io)?;
 
         // Write padding to align to sector count
-        let padding_count = SECTOR_BYTES - total_len % SECTOR_BYTES;
+        let padding_count = (SECTOR_BYTES - total_len % SECTOR_BYTES) % SECTOR_BYTES;
 
         for _ in 0..padding_count {
             self.file.write_u8(0).map_err(Error::Io)?;
@@ -392,18 +469,22 @@ fn read_section_into_chunk(section: &mut LevelSection, chunk: &mut Chunk) -> Res
     Ok(())
 }
 
-fn chunk_to_chunk_root(
-    chunk: &Chunk,
-    entities: &[EntityData],
-    block_entities: &[BlockEntityData],
-) -> ChunkRoot {
+/// Converts between typed tags and raw NBT
+/// by encoding and decoding them.
+fn convert<T: Serialize, U: DeserializeOwned>(tag: &T) -> Result<U, Error> {
+    let mut buf = Vec::new();
+    nbt::to_writer(&mut buf, tag, None).map_err(Error::Nbt)?;
+    nbt::from_reader(Cursor::new(buf)).map_err(Error::Nbt)
+}
+
+fn chunk_to_chunk_root(chunk: &Chunk, tags: &ChunkEntityTags) -> ChunkRoot {
     ChunkRoot {
         level: ChunkLevel {
             x_pos: chunk.position().x,
             z_pos: chunk.position().z,
             last_update: 0,    // TODO
             inhabited_time: 0, // TODO
-            block_entities: block_entities.into(),
+            block_entities: tags.block_entities.clone(),
             sections: chunk
                 .sections()
                 .iter()
@@ -412,7 +493,8 @@ fn chunk_to_chunk_root(
                 .map(|(y, mut section)| {
                     let palette = convert_palette(&mut section);
                     LevelSection {
-                        y: y as i8,
+                        // Section index 0 is the void section below y = 0.
+                        y: y as i8 - 1,
                         states: section
                             .blocks()
                             .data()
@@ -434,7 +516,7 @@ fn chunk_to_chunk_root(
                 .iter()
                 .map(|biome| biome.id() as i32)
                 .collect(),
-            entities: entities.into(),
+            entities: tags.entities.clone(),
             awaiting_block_updates: vec![vec![]; 16], // TODO
             awaiting_liquid_updates: vec![vec![]; 16], // TODO
             scheduled_block_updates: vec![],          // TODO
@@ -447,9 +529,33 @@ fn chunk_to_chunk_root(
 }
 
 fn convert_palette(section: &mut ChunkSection) -> Vec<LevelPaletteEntry> {
+    // Region files always use a section-local palette, so
+    // sections on the global palette have to be converted first.
+    if section.blocks().palette().is_none() {
+        *section.blocks_mut() = to_local_palette(section.blocks());
+    }
     raw_palette_to_palette_entries(section.blocks().palette().unwrap().as_slice())
 }
 
+fn to_local_palette(blocks: &BlockStore) -> BlockStore {
+    let mut palette = Palette::new();
+    let indices: Vec<u64> = blocks
+        .data()
+        .iter()
+        .map(|id| palette.index_or_insert(BlockId::from_vanilla_id(id as u16)) as u64)
+        .collect();
+
+    let mut bits_per_block = MIN_BITS_PER_BLOCK as usize;
+    while (1 << bits_per_block) < palette.len() {
+        bits_per_block += 1;
+    }
+
+    BlockStore::from_raw_parts(
+        Some(palette),
+        PackedArray::from_iter(indices, bits_per_block),
+    )
+}
+
 fn raw_palette_to_palette_entries(palette: &[BlockId]) -> Vec<LevelPaletteEntry> {
     palette
         .iter()
@@ -862,6 +968,29 @@ impl RegionPosition {
 mod tests {
     use super::*;
 
+    #[test]
+    fn save_and_load_chunk() {
+        let dir = std::env::temp_dir().join(format!("feather-region-test-{}", std::process::id()));
+        let pos = ChunkPosition::new(-3, 40);
+
+        let mut chunk = Chunk::new(pos);
+        chunk.set_block_at(1, 0, 2, BlockId::bedrock());
+        chunk.set_block_at(5, 64, 9, BlockId::stone());
+
+        let mut region = create_region(&dir, RegionPosition::from_chunk(pos)).unwrap();
+        region.save_chunk(&chunk, &[], &[]).unwrap();
+        drop(region);
+
+        let mut region = load_region(&dir, RegionPosition::from_chunk(pos)).unwrap();
+        let (loaded, _, _) = region.load_chunk(pos).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(loaded.position(), pos);
+        assert_eq!(loaded.block_at(1, 0, 2), Some(BlockId::bedrock()));
+        assert_eq!(loaded.block_at(5, 64, 9), Some(BlockId::stone()));
+        assert_eq!(loaded.block_at(5, 65, 9), Some(BlockId::air()));
+    }
+
     #[test]
     fn test_sector_allocator() {
         let header = RegionHeader {
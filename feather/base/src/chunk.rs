@@ -133,8 +133,8 @@ impl Chunk {
             Some(section) => {
                 let result = section.set_block_at(x, y % SECTION_HEIGHT, z, block);
                 // If the block update caused the section to contain only
-                // air, free it to conserve memory.
-                if section.is_empty() {
+                // air and default light, free it to conserve memory.
+                if section.is_empty() && section.light().is_default() {
                     self.clear_section(y);
                 }
                 result
@@ -190,13 +190,19 @@ impl Chunk {
         }
     }
 
+    /// Gets the block light at the given position within this chunk.
+    ///
+    /// Returns `None` if the coordinates are out of bounds.
     pub fn block_light_at(&self, x: usize, y: usize, z: usize) -> Option<u8> {
         match self.section_for_y(y)? {
             Some(s) => s.block_light_at(x, y % SECTION_HEIGHT, z),
-            None => Some(15),
+            None => Some(0),
         }
     }
 
+    /// Gets the sky light at the given position within this chunk.
+    ///
+    /// Returns `None` if the coordinates are out of bounds.
     pub fn sky_light_at(&self, x: usize, y: usize, z: usize) -> Option<u8> {
         match self.section_for_y(y)? {
             Some(s) => s.sky_light_at(x, y % SECTION_HEIGHT, z),
@@ -204,19 +210,31 @@ impl Chunk {
         }
     }
 
+    /// Sets the block light at the given position within this chunk.
+    ///
+    /// Empty sections are created if needed to store the light.
+    /// Returns `None` if the coordinates are out of bounds.
     pub fn set_block_light_at(&mut self, x: usize, y: usize, z: usize, light: u8) -> Option<()> {
-        if let Some(section) = self.section_for_y_mut(y)? {
-            section.set_block_light_at(x, y, z, light)
-        } else {
-            Some(())
+        match self.section_for_y_mut(y)? {
+            Some(section) => section.set_block_light_at(x, y % SECTION_HEIGHT, z, light),
+            None if light == 0 => Some(()),
+            section => section
+                .get_or_insert_with(ChunkSection::default)
+                .set_block_light_at(x, y % SECTION_HEIGHT, z, light),
         }
     }
 
+    /// Sets the sky light at the given position within this chunk.
+    ///
+    /// Empty sections are created if needed to store the light.
+    /// Returns `None` if the coordinates are out of bounds.
     pub fn set_sky_light_at(&mut self, x: usize, y: usize, z: usize, light: u8) -> Option<()> {
-        if let Some(section) = self.section_for_y_mut(y)? {
-            section.set_sky_light_at(x, y, z, light)
-        } else {
-            Some(())
+        match self.section_for_y_mut(y)? {
+            Some(section) => section.set_sky_light_at(x, y % SECTION_HEIGHT, z, light),
+            None if light >= 15 => Some(()),
+            section => section
+                .get_or_insert_with(ChunkSection::default)
+                .set_sky_light_at(x, y % SECTION_HEIGHT, z, light),
         }
     }
 
@@ -582,6 +600,23 @@ mod tests {
         }
     }
 
+    #[test]
+    fn light_in_upper_sections() {
+        let mut chunk = Chunk::default();
+
+        chunk.set_block_light_at(3, 100, 4, 12).unwrap();
+        chunk.set_sky_light_at(3, 100, 4, 5).unwrap();
+        assert_eq!(chunk.block_light_at(3, 100, 4), Some(12));
+        assert_eq!(chunk.sky_light_at(3, 100, 4), Some(5));
+        assert_eq!(chunk.block_light_at(3, 4, 4), Some(0));
+        assert_eq!(chunk.sky_light_at(3, 4, 4), Some(15));
+
+        // Sections storing light are kept when emptied.
+        chunk.set_block_at(0, 100, 0, BlockId::stone()).unwrap();
+        chunk.set_block_at(0, 100, 0, BlockId::air()).unwrap();
+        assert!(chunk.section(100 / SECTION_HEIGHT + 1).is_some());
+    }
+
     #[test]
     fn heightmaps() {
         let mut chunk = Chunk::new(ChunkPosition::new(0, 0));
@@ -16,14 +16,15 @@ impl Default for LightStore {
 }
 
 impl LightStore {
-    /// Creates a `LightStore` with all light set to 15.
+    /// Creates a `LightStore` with block light set to 0
+    /// and sky light set to 15, which is the light of
+    /// an empty section exposed to the sky.
     pub fn new() -> Self {
         let mut this = LightStore {
             block_light: PackedArray::new(SECTION_VOLUME, 4),
             sky_light: PackedArray::new(SECTION_VOLUME, 4),
         };
-        fill_with_default_light(&mut this.block_light);
-        fill_with_default_light(&mut this.sky_light);
+        this.sky_light.fill(15);
         this
     }
 
@@ -65,6 +66,13 @@ impl LightStore {
         Some(())
     }
 
+    /// Returns whether all light values equal
+    /// those of a new `LightStore`.
+    pub fn is_default(&self) -> bool {
+        self.block_light.iter().all(|light| light == 0)
+            && self.sky_light.iter().all(|light| light == 15)
+    }
+
     pub fn block_light(&self) -> &PackedArray {
         &self.block_light
     }
@@ -73,9 +81,3 @@ impl LightStore {
         &self.sky_light
     }
 }
-
-fn fill_with_default_light(arr: &mut PackedArray) {
-    for i in 0..arr.len() {
-        arr.set(i, 15);
-    }
-}
@@ -19,8 +19,9 @@ pub use chunk::{Chunk, ChunkSection, CHUNK_HEIGHT, CHUNK_WIDTH};
 pub use generated::{Area, Biome, EntityKind, Inventory, Item, ItemStack};
 pub use libcraft_blocks::{BlockKind, BlockState};
 pub use libcraft_core::{position, vec3, BlockPosition, ChunkPosition, Gamemode, Position, Vec3d};
+pub use libcraft_items::{Enchantment, EnchantmentKind};
 pub use libcraft_particles::{Particle, ParticleKind};
-pub use libcraft_text::{deserialize_text, Text, Title};
+pub use libcraft_text::{deserialize_text, Text, TextComponentBuilder, Title};
 #[doc(inline)]
 pub use metadata::EntityMetadata;
 
@@ -20,7 +20,9 @@ pub const META_INDEX_IS_CUSTOM_NAME_VISIBLE: u8 = 3;
 pub const META_INDEX_IS_SILENT: u8 = 4;
 pub const META_INDEX_NO_GRAVITY: u8 = 5;
 
-pub const META_INDEX_ITEM_SLOT: u8 = 6;
+pub const META_INDEX_POSE: u8 = 6;
+
+pub const META_INDEX_ITEM_SLOT: u8 = 7;
 
 pub const META_INDEX_FALLING_BLOCK_SPAWN_POSITION: u8 = 7;
 
@@ -0,0 +1,40 @@
+//! Periodically saves the world.
+
+use std::time::{Duration, Instant};
+
+use ecs::{SysResult, SystemExecutor};
+
+use crate::{events::AutosaveEvent, Game};
+
+pub fn register(game: &mut Game, systems: &mut SystemExecutor<Game>) {
+    game.insert_resource(AutosaveState::default());
+    systems.group::<AutosaveState>().add_system(autosave);
+}
+
+/// Time between autosaves.
+const AUTOSAVE_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+struct AutosaveState {
+    last_save: Instant,
+}
+
+impl Default for AutosaveState {
+    fn default() -> Self {
+        Self {
+            last_save: Instant::now(),
+        }
+    }
+}
+
+fn autosave(game: &mut Game, state: &mut AutosaveState) -> SysResult {
+    if state.last_save.elapsed() < AUTOSAVE_INTERVAL {
+        return Ok(());
+    }
+    state.last_save = Instant::now();
+
+    log::debug!("Autosaving world");
+    game.world.save_dirty_chunks();
+    game.ecs.insert_event(AutosaveEvent);
+
+    Ok(())
+}
@@ -0,0 +1,480 @@
+//! Placement of blocks by players.
+//!
+//! The held item is mapped to a block whose state is derived from
+//! the clicked face, the cursor position and the direction the player
+//! is looking in. Block states are only able to face horizontally,
+//! so blocks such as pistons keep their default vertical orientation.
+
+use base::{
+    Area, BlockId, BlockPosition, BlockState, EntityKind, Gamemode, Inventory, Item, Position,
+};
+use ecs::{Entity, SysResult};
+use libcraft_blocks::{
+    Bed, Bisected, BlockKind, Directional, Levelled, Orientable, Slab, Stairs, TrapDoor,
+    Waterlogged,
+};
+use libcraft_core::{
+    block::{Axis, BedPart, BlockFace as Facing, BlockHalf, SlabType, StairHalf},
+    BlockFace, Hand, Vec3f,
+};
+use quill_common::events::BlockPlacementEvent;
+
+use crate::{entities::player::HotbarSlot, Game};
+
+/// Places the block held by `player`, as requested by `event`.
+///
+/// Returns whether a block was placed. In survival mode,
+/// the held stack is decremented.
+pub fn place_block(
+    game: &mut Game,
+    player: Entity,
+    event: &BlockPlacementEvent,
+) -> SysResult<bool> {
+    let gamemode = *game.ecs.get::<Gamemode>(player)?;
+    if !matches!(gamemode, Gamemode::Survival | Gamemode::Creative) {
+        return Ok(false);
+    }
+
+    let (area, slot) = match event.hand {
+        Hand::Main => (Area::Hotbar, game.ecs.get::<HotbarSlot>(player)?.get()),
+        Hand::Offhand => (Area::Offhand, 0),
+    };
+    let item = match game
+        .ecs
+        .get::<Inventory>(player)?
+        .item(area, slot)
+        .and_then(|item| item.clone())
+    {
+        Some(item) => item,
+        None => return Ok(false),
+    };
+
+    let player_position = *game.ecs.get::<Position>(player)?;
+    let blocks = match placed_blocks(game, item.item, event, player_position) {
+        Some(blocks) => blocks,
+        None => return Ok(false),
+    };
+    if blocks
+        .iter()
+        .any(|&(position, block)| block.is_solid() && is_occupied(game, position))
+    {
+        return Ok(false);
+    }
+
+    for &(position, block) in &blocks {
+        game.set_block(position, block);
+    }
+
+    if gamemode == Gamemode::Survival {
+        let inventory = game.ecs.get::<Inventory>(player)?;
+        if let Some(mut held) = inventory.item(area, slot) {
+            if let Some(stack) = held.as_mut() {
+                stack.remove(1);
+                if stack.count() == 0 {
+                    *held = None;
+                }
+            }
+        }
+    }
+
+    Ok(true)
+}
+
+/// Returns the blocks to place, or `None` if
+/// `item` cannot be placed.
+fn placed_blocks(
+    game: &Game,
+    item: Item,
+    event: &BlockPlacementEvent,
+    player_position: Position,
+) -> Option<Vec<(BlockPosition, BlockId)>> {
+    let kind = block_kind(item)?;
+    let clicked = game.block(event.location)?;
+
+    if let Some(double_slab) = merge_slab(game, kind, event) {
+        return Some(vec![double_slab]);
+    }
+
+    let position = if clicked.is_replaceable() {
+        event.location
+    } else {
+        adjacent(event.location, event.face)
+    };
+    let replaced = game
+        .block(position)
+        .filter(|block| block.is_replaceable())?;
+
+    let mut state = match (wall_variant(kind), face_direction(event.face)) {
+        (Some(wall_kind), Some(facing)) => {
+            let mut state = BlockState::new(wall_kind);
+            set_facing(&mut state, facing);
+            state
+        }
+        (Some(_), None) if event.face == BlockFace::Bottom => return None,
+        _ => {
+            let mut state = BlockState::new(kind);
+            let facing = placement_facing(kind, event.face, player_position);
+            set_facing(&mut state, facing);
+            state
+        }
+    };
+
+    let axis = match event.face {
+        BlockFace::Top | BlockFace::Bottom => Axis::Y,
+        BlockFace::North | BlockFace::South => Axis::Z,
+        BlockFace::West | BlockFace::East => Axis::X,
+    };
+    if let Some(mut orientable) = state.data_as::<Orientable>() {
+        if orientable.set_axis(axis) {
+            state.set_data(orientable);
+        }
+    }
+
+    let top_half = clicked_top_half(event.face, event.cursor_position);
+    set_half(&mut state, top_half);
+
+    if is_water_source(replaced) {
+        if let Some(mut waterlogged) = state.data_as::<Waterlogged>() {
+            if waterlogged.set_waterlogged(true) {
+                state.set_data(waterlogged);
+            }
+        }
+    }
+
+    let mut blocks = vec![(position, state)];
+    if let Some(other) = second_part(&mut blocks[0].1, position) {
+        let replaceable = game
+            .block(other.0)
+            .map_or(false, |block| block.is_replaceable());
+        if !replaceable {
+            return None;
+        }
+        blocks.push(other);
+    }
+
+    Some(
+        blocks
+            .into_iter()
+            .map(|(position, state)| (position, BlockId::from_vanilla_id(state.id())))
+            .collect(),
+    )
+}
+
+/// Returns the block kind placed by an item.
+fn block_kind(item: Item) -> Option<BlockKind> {
+    let kind = match item {
+        Item::Redstone => BlockKind::RedstoneWire,
+        Item::String => BlockKind::Tripwire,
+        Item::WheatSeeds => BlockKind::Wheat,
+        Item::BeetrootSeeds => BlockKind::Beetroots,
+        Item::Carrot => BlockKind::Carrots,
+        Item::Potato => BlockKind::Potatoes,
+        Item::MelonSeeds => BlockKind::MelonStem,
+        Item::PumpkinSeeds => BlockKind::PumpkinStem,
+        Item::SweetBerries => BlockKind::SweetBerryBush,
+        Item::CocoaBeans => BlockKind::Cocoa,
+        item => BlockKind::from_name(item.name())?,
+    };
+    Some(kind).filter(|&kind| kind != BlockKind::Air)
+}
+
+/// Returns the variant of a block placed against walls,
+/// like `wall_torch` for `torch` or `oak_wall_sign` for `oak_sign`.
+fn wall_variant(kind: BlockKind) -> Option<BlockKind> {
+    let name = kind.name();
+    let wall_name = match name.rfind('_') {
+        Some(index) => format!("{}_wall{}", &name[..index], &name[index..]),
+        None => format!("wall_{}", name),
+    };
+    BlockKind::from_name(&wall_name)
+}
+
+/// Turns a single slab into a double slab if `kind`
+/// is placed onto the other half of it.
+fn merge_slab(
+    game: &Game,
+    kind: BlockKind,
+    event: &BlockPlacementEvent,
+) -> Option<(BlockPosition, BlockId)> {
+    let candidates = [
+        (event.location, Some(event.face)),
+        (adjacent(event.location, event.face), None),
+    ];
+    for &(position, face) in &candidates {
+        let mut state = BlockState::from_id(game.block(position)?.vanilla_id())?;
+        if state.kind() != kind {
+            continue;
+        }
+        let mut slab = match state.data_as::<Slab>() {
+            Some(slab) => slab,
+            None => continue,
+        };
+        let mergeable = match (slab.slab_type(), face) {
+            (SlabType::Double, _) => false,
+            (_, None) => true,
+            (SlabType::Bottom, Some(face)) => face == BlockFace::Top,
+            (SlabType::Top, Some(face)) => face == BlockFace::Bottom,
+        };
+        if mergeable {
+            slab.set_slab_type(SlabType::Double);
+            slab.set_waterlogged(false);
+            state.set_data(slab);
+            return Some((position, BlockId::from_vanilla_id(state.id())));
+        }
+    }
+    None
+}
+
+/// Returns the direction a placed block faces.
+fn placement_facing(kind: BlockKind, face: BlockFace, player_position: Position) -> Facing {
+    let looking = horizontal_direction(player_position.yaw);
+    let name = kind.name();
+    if name.ends_with("_stairs")
+        || name.ends_with("_door")
+        || name.ends_with("_fence_gate")
+        || name.ends_with("_bed")
+    {
+        looking
+    } else if name.ends_with("_trapdoor") || kind == BlockKind::Ladder {
+        face_direction(face).unwrap_or_else(|| opposite(looking))
+    } else {
+        opposite(looking)
+    }
+}
+
+fn set_facing(state: &mut BlockState, facing: Facing) {
+    if let Some(mut directional) = state.data_as::<Directional>() {
+        if directional.set_facing(facing) {
+            state.set_data(directional);
+        }
+    }
+}
+
+/// Places slabs, stairs and trapdoors in the top
+/// or bottom half of their block.
+fn set_half(state: &mut BlockState, top_half: bool) {
+    let (slab_type, stair_half) = if top_half {
+        (SlabType::Top, StairHalf::Top)
+    } else {
+        (SlabType::Bottom, StairHalf::Bottom)
+    };
+
+    if let Some(mut slab) = state.data_as::<Slab>() {
+        slab.set_slab_type(slab_type);
+        state.set_data(slab);
+    } else if let Some(mut stairs) = state.data_as::<Stairs>() {
+        stairs.set_stair_half(stair_half);
+        state.set_data(stairs);
+    } else if let Some(mut trapdoor) = state.data_as::<TrapDoor>() {
+        trapdoor.set_stair_half(stair_half);
+        state.set_data(trapdoor);
+    }
+}
+
+/// For blocks made of two parts, such as doors and beds, sets
+/// `state` to the first part and returns the second part.
+fn second_part(
+    state: &mut BlockState,
+    position: BlockPosition,
+) -> Option<(BlockPosition, BlockState)> {
+    if let Some(mut bisected) = state.data_as::<Bisected>() {
+        bisected.set_half(BlockHalf::Lower);
+        state.set_data(bisected);
+        let mut upper = *state;
+        if let Some(mut bisected) = upper.data_as::<Bisected>() {
+            bisected.set_half(BlockHalf::Upper);
+            upper.set_data(bisected);
+        }
+        return Some((position.up(), upper));
+    }
+
+    if let Some(mut bed) = state.data_as::<Bed>() {
+        bed.set_part(BedPart::Foot);
+        let head_position = offset_horizontally(position, bed.facing());
+        state.set_data(bed);
+        let mut head = *state;
+        if let Some(mut bed) = head.data_as::<Bed>() {
+            bed.set_part(BedPart::Head);
+            head.set_data(bed);
+        }
+        return Some((head_position, head));
+    }
+
+    None
+}
+
+/// Returns whether the cursor is in the top half of the
+/// placed block, given where the clicked block was hit.
+fn clicked_top_half(face: BlockFace, cursor_position: Vec3f) -> bool {
+    match face {
+        BlockFace::Top => false,
+        BlockFace::Bottom => true,
+        _ => cursor_position.y > 0.5,
+    }
+}
+
+fn is_water_source(block: BlockId) -> bool {
+    BlockState::from_id(block.vanilla_id())
+        .filter(|state| state.kind() == BlockKind::Water)
+        .and_then(|state| state.data_as::<Levelled>())
+        .map_or(false, |levelled| levelled.level() == 0)
+}
+
+/// Returns whether an entity which prevents
+/// building stands within the given block.
+fn is_occupied(game: &Game, position: BlockPosition) -> bool {
+    let (x, y, z) = (position.x as f64, position.y as f64, position.z as f64);
+    game.ecs
+        .query::<(&Position, &EntityKind, Option<&Gamemode>)>()
+        .iter()
+        .any(|(_, (entity_position, &kind, gamemode))| {
+            if matches!(kind, EntityKind::Item | EntityKind::ExperienceOrb)
+                || gamemode == Some(&Gamemode::Spectator)
+            {
+                return false;
+            }
+            let size = kind.bounding_box().max;
+            let half_width = size.x / 2.0;
+            entity_position.x + half_width > x
+                && entity_position.x - half_width < x + 1.0
+                && entity_position.y + size.y > y
+                && entity_position.y < y + 1.0
+                && entity_position.z + half_width > z
+                && entity_position.z - half_width < z + 1.0
+        })
+}
+
+/// Returns the horizontal direction corresponding to a yaw.
+fn horizontal_direction(yaw: f32) -> Facing {
+    match ((yaw / 90.0).round() as i32).rem_euclid(4) {
+        0 => Facing::South,
+        1 => Facing::West,
+        2 => Facing::North,
+        _ => Facing::East,
+    }
+}
+
+fn opposite(facing: Facing) -> Facing {
+    match facing {
+        Facing::North => Facing::South,
+        Facing::South => Facing::North,
+        Facing::West => Facing::East,
+        _ => Facing::West,
+    }
+}
+
+/// Returns the direction a block face points in,
+/// or `None` for the top and bottom faces.
+fn face_direction(face: BlockFace) -> Option<Facing> {
+    match face {
+        BlockFace::North => Some(Facing::North),
+        BlockFace::South => Some(Facing::South),
+        BlockFace::West => Some(Facing::West),
+        BlockFace::East => Some(Facing::East),
+        BlockFace::Top | BlockFace::Bottom => None,
+    }
+}
+
+/// Returns the block next to `position` on the given face.
+pub fn adjacent(position: BlockPosition, face: BlockFace) -> BlockPosition {
+    match face {
+        BlockFace::Top => position.up(),
+        BlockFace::Bottom => position.down(),
+        BlockFace::North => position.north(),
+        BlockFace::South => position.south(),
+        BlockFace::West => position.west(),
+        BlockFace::East => position.east(),
+    }
+}
+
+fn offset_horizontally(position: BlockPosition, facing: Facing) -> BlockPosition {
+    match facing {
+        Facing::North => position.north(),
+        Facing::South => position.south(),
+        Facing::West => position.west(),
+        _ => position.east(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use base::{position, Chunk, ChunkPosition, ItemStack};
+
+    use super::*;
+
+    fn setup(item: Item, player_position: Position) -> (Game, Entity) {
+        let mut game = Game::new();
+        game.world
+            .chunk_map_mut()
+            .insert_chunk(Chunk::new(ChunkPosition::new(0, 0)));
+        game.world
+            .set_block_at(BlockPosition::new(0, 64, 0), BlockId::stone());
+
+        let inventory = Inventory::player();
+        *inventory.item(Area::Hotbar, 0).unwrap() = Some(ItemStack::new(item, 2));
+        let player = game.ecs.spawn((
+            Gamemode::Survival,
+            HotbarSlot::new(0),
+            inventory,
+            player_position,
+            EntityKind::Player,
+        ));
+        (game, player)
+    }
+
+    fn event(face: BlockFace, cursor_y: f32) -> BlockPlacementEvent {
+        BlockPlacementEvent {
+            hand: Hand::Main,
+            location: BlockPosition::new(0, 64, 0),
+            face,
+            cursor_position: Vec3f::new(0.5, cursor_y, 0.5),
+            inside_block: false,
+        }
+    }
+
+    fn state_at(game: &Game, position: BlockPosition) -> BlockState {
+        BlockState::from_id(game.block(position).unwrap().vanilla_id()).unwrap()
+    }
+
+    fn held_count(game: &Game, player: Entity) -> u32 {
+        let inventory = game.ecs.get::<Inventory>(player).unwrap();
+        let count = inventory
+            .item(Area::Hotbar, 0)
+            .unwrap()
+            .as_ref()
+            .map_or(0, ItemStack::count);
+        count
+    }
+
+    #[test]
+    fn stairs_face_the_player_direction() {
+        let (mut game, player) = setup(Item::OakStairs, position!(0.5, 65.0, 3.5, 0.0, 180.0));
+        assert!(place_block(&mut game, player, &event(BlockFace::Top, 1.0)).unwrap());
+
+        let state = state_at(&game, BlockPosition::new(0, 65, 0));
+        assert_eq!(state.kind(), BlockKind::OakStairs);
+        let stairs = state.data_as::<Stairs>().unwrap();
+        assert_eq!(stairs.facing(), Facing::North);
+        assert_eq!(stairs.stair_half(), StairHalf::Bottom);
+        assert_eq!(held_count(&game, player), 1);
+    }
+
+    #[test]
+    fn slab_placed_in_upper_half() {
+        let (mut game, player) = setup(Item::StoneSlab, position!(0.5, 64.0, 3.5));
+        assert!(place_block(&mut game, player, &event(BlockFace::South, 0.75)).unwrap());
+
+        let slab = state_at(&game, BlockPosition::new(0, 64, 1))
+            .data_as::<Slab>()
+            .unwrap();
+        assert_eq!(slab.slab_type(), SlabType::Top);
+    }
+
+    #[test]
+    fn placement_into_player_is_rejected() {
+        let (mut game, player) = setup(Item::Stone, position!(0.5, 65.0, 0.5));
+        assert!(!place_block(&mut game, player, &event(BlockFace::Top, 1.0)).unwrap());
+        assert!(game.block(BlockPosition::new(0, 65, 0)).unwrap().is_air());
+        assert_eq!(held_count(&game, player), 2);
+    }
+}
@@ -1,4 +1,39 @@
 use base::{Text, Title};
+use ecs::{Entity, SysResult, SystemExecutor};
+use quill_common::components::Name;
+
+use crate::{events::ChatEvent, Game};
+
+pub fn register(systems: &mut SystemExecutor<Game>) {
+    systems.add_system(broadcast_chat_events);
+}
+
+/// Broadcasts the messages of [`ChatEvent`]s that were not cancelled.
+///
+/// This system runs before packets are handled, so plugins
+/// observe (and may cancel) a `ChatEvent` in the tick it is
+/// triggered, and the message is sent in the following tick.
+fn broadcast_chat_events(game: &mut Game) -> SysResult {
+    let mut messages = Vec::new();
+    for (_, event) in game.ecs.query::<&ChatEvent>().iter() {
+        if event.cancelled {
+            continue;
+        }
+        let sender = Entity::from_bits(event.sender.0);
+        // The sender may have left in the meantime.
+        if let Ok(name) = game.ecs.get::<Name>(sender) {
+            messages.push(Text::translate_with(
+                "chat.type.text",
+                vec![name.to_string(), event.message.clone()],
+            ));
+        }
+    }
+
+    for message in messages {
+        game.broadcast_chat(ChatKind::PlayerChat, message);
+    }
+    Ok(())
+}
 
 /// An entity's "mailbox" for receiving chat messages.
 ///
@@ -116,3 +151,46 @@ pub enum ChatPreference {
     /// Receive all messages.
     All,
 }
+
+#[cfg(test)]
+mod tests {
+    use ecs::EntityBuilder;
+    use quill_common::EntityId;
+
+    use super::*;
+
+    fn game() -> (Game, Entity) {
+        let mut game = Game::new();
+        let mut builder = EntityBuilder::new();
+        builder
+            .add(Name::new("Steve"))
+            .add(ChatBox::new(ChatPreference::All));
+        let player = game.ecs.spawn(builder.build());
+        (game, player)
+    }
+
+    #[test]
+    fn chat_events_are_broadcast() {
+        let (mut game, player) = game();
+        game.ecs.insert_event(ChatEvent::new(
+            EntityId(player.to_bits()),
+            "hello".to_owned(),
+        ));
+        broadcast_chat_events(&mut game).unwrap();
+
+        let mut mailbox = game.ecs.get_mut::<ChatBox>(player).unwrap();
+        assert_eq!(mailbox.drain().count(), 1);
+    }
+
+    #[test]
+    fn cancelled_chat_events_are_not_broadcast() {
+        let (mut game, player) = game();
+        let mut event = ChatEvent::new(EntityId(player.to_bits()), "hello".to_owned());
+        event.cancel();
+        game.ecs.insert_event(event);
+        broadcast_chat_events(&mut game).unwrap();
+
+        let mut mailbox = game.ecs.get_mut::<ChatBox>(player).unwrap();
+        assert_eq!(mailbox.drain().count(), 0);
+    }
+}
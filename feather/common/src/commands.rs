@@ -0,0 +1,892 @@
+//! A Brigadier-style command dispatcher.
+//!
+//! Commands are trees of literal and argument nodes. Each node
+//! may require a minimum [`PermissionLevel`] and may have an executor,
+//! which is invoked when the input ends at that node.
+//!
+//! The dispatcher is stored as a resource. Register commands with
+//! [`CommandDispatcher::register`]:
+//! ```ignore
+//! dispatcher.register(
+//!     literal("gamemode")
+//!         .requires(2)
+//!         .then(argument("mode", ArgumentKind::Gamemode).executes(|ctx| { /* ... */ Ok(()) })),
+//! );
+//! ```
+
+use std::rc::Rc;
+
+use base::{BlockPosition, Position, Text, TextComponentBuilder};
+use ecs::{Entity, SysResult};
+
+use crate::{events::PermissionLevelChangeEvent, ChatBox, Game};
+
+mod arguments;
+mod impls;
+mod reader;
+
+pub use arguments::{ArgumentKind, ArgumentValue, Coordinate, Coordinates};
+pub use reader::StringReader;
+
+/// Index of the root node in [`CommandDispatcher::nodes`].
+pub const ROOT_NODE: usize = 0;
+
+/// The permission level of an entity, from 0 to 4.
+///
+/// Entities without this component have level 0.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct PermissionLevel(pub u8);
+
+/// Returns the permission level of an entity.
+pub fn permission_level(game: &Game, entity: Entity) -> u8 {
+    game.ecs
+        .get::<PermissionLevel>(entity)
+        .map(|level| level.0)
+        .unwrap_or(0)
+}
+
+/// Sets the permission level of an entity.
+///
+/// Triggers a [`PermissionLevelChangeEvent`] so that the server
+/// sends the commands now available to the entity.
+pub fn set_permission_level(game: &mut Game, entity: Entity, level: u8) -> SysResult {
+    game.ecs.insert(entity, PermissionLevel(level))?;
+    game.ecs
+        .insert_entity_event(entity, PermissionLevelChangeEvent)?;
+    Ok(())
+}
+
+/// A function invoked when a command is executed.
+pub type Executor = Rc<dyn Fn(&mut CommandCtx) -> anyhow::Result<()>>;
+
+/// Context passed to command executors.
+pub struct CommandCtx<'a> {
+    pub game: &'a mut Game,
+    /// The entity which executed the command.
+    pub sender: Entity,
+    pub args: CommandArgs,
+}
+
+impl<'a> CommandCtx<'a> {
+    /// Sends a message to the command sender.
+    pub fn send_message(&mut self, message: impl Into<Text>) {
+        if let Ok(mut chat_box) = self.game.ecs.get_mut::<ChatBox>(self.sender) {
+            chat_box.send_system(message);
+        }
+    }
+
+    /// Returns the position of the sender, used as the
+    /// origin of relative coordinates.
+    pub fn sender_position(&self) -> Position {
+        self.game
+            .ecs
+            .get::<Position>(self.sender)
+            .map(|pos| *pos)
+            .unwrap_or_default()
+    }
+
+    /// Resolves a coordinates argument relative to the sender.
+    pub fn position(&self, name: &str) -> anyhow::Result<Position> {
+        Ok(self
+            .args
+            .coordinates(name)?
+            .into_position(self.sender_position()))
+    }
+
+    /// Resolves a block position argument relative to the sender.
+    pub fn block_position(&self, name: &str) -> anyhow::Result<BlockPosition> {
+        Ok(self
+            .args
+            .coordinates(name)?
+            .into_block_position(self.sender_position().block()))
+    }
+}
+
+/// The parsed arguments of a command, by name.
+#[derive(Clone, Debug, Default)]
+pub struct CommandArgs {
+    values: Vec<(String, ArgumentValue)>,
+}
+
+macro_rules! arg_getters {
+    ($($fn:ident, $variant:ident, $ty:ty;)*) => {
+        $(
+            #[allow(clippy::clone_on_copy)]
+            pub fn $fn(&self, name: &str) -> anyhow::Result<$ty> {
+                match self.get(name) {
+                    Some(ArgumentValue::$variant(value)) => Ok(value.clone()),
+                    Some(_) => anyhow::bail!("argument `{}` has the wrong type", name),
+                    None => anyhow::bail!("missing argument `{}`", name),
+                }
+            }
+        )*
+    }
+}
+
+impl CommandArgs {
+    pub fn get(&self, name: &str) -> Option<&ArgumentValue> {
+        self.values
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, value)| value)
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.get(name).is_some()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &ArgumentValue)> + '_ {
+        self.values
+            .iter()
+            .map(|(name, value)| (name.as_str(), value))
+    }
+
+    arg_getters! {
+        bool, Bool, bool;
+        integer, Integer, i32;
+        double, Double, f64;
+        string, String, String;
+        entities, Entities, Vec<Entity>;
+        coordinates, Coordinates, Coordinates;
+        gamemode, Gamemode, base::Gamemode;
+        item, Item, base::Item;
+        time, Time, i32;
+    }
+
+    /// Returns the single entity selected by an argument.
+    pub fn entity(&self, name: &str) -> anyhow::Result<Entity> {
+        self.entities(name)?
+            .first()
+            .copied()
+            .ok_or_else(|| anyhow::anyhow!("No entity was found"))
+    }
+}
+
+/// A node in the command tree.
+pub struct CommandNode {
+    kind: CommandNodeKind,
+    children: Vec<usize>,
+    executor: Option<Executor>,
+    permission_level: u8,
+    /// Number of registrations which created or merged into this node.
+    references: usize,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum CommandNodeKind {
+    Root,
+    Literal(String),
+    Argument { name: String, kind: ArgumentKind },
+}
+
+impl CommandNode {
+    pub fn kind(&self) -> &CommandNodeKind {
+        &self.kind
+    }
+
+    /// Returns the indices of this node's children.
+    pub fn children(&self) -> &[usize] {
+        &self.children
+    }
+
+    /// Returns whether a command ending at this node can be executed.
+    pub fn is_executable(&self) -> bool {
+        self.executor.is_some()
+    }
+
+    /// Returns the permission level required to use this node.
+    pub fn permission_level(&self) -> u8 {
+        self.permission_level
+    }
+}
+
+/// Builds a command node and its children.
+/// Created with [`literal`] or [`argument`].
+pub struct CommandBuilder {
+    kind: CommandNodeKind,
+    children: Vec<CommandBuilder>,
+    executor: Option<Executor>,
+    permission_level: u8,
+}
+
+/// Creates a node matching the given word.
+pub fn literal(name: impl Into<String>) -> CommandBuilder {
+    CommandBuilder::new(CommandNodeKind::Literal(name.into()))
+}
+
+/// Creates a node parsing an argument.
+pub fn argument(name: impl Into<String>, kind: ArgumentKind) -> CommandBuilder {
+    CommandBuilder::new(CommandNodeKind::Argument {
+        name: name.into(),
+        kind,
+    })
+}
+
+impl CommandBuilder {
+    fn new(kind: CommandNodeKind) -> Self {
+        Self {
+            kind,
+            children: Vec::new(),
+            executor: None,
+            permission_level: 0,
+        }
+    }
+
+    /// Requires the given permission level to use this node.
+    pub fn requires(mut self, permission_level: u8) -> Self {
+        self.permission_level = permission_level;
+        self
+    }
+
+    /// Adds a child node.
+    pub fn then(mut self, child: CommandBuilder) -> Self {
+        self.children.push(child);
+        self
+    }
+
+    /// Sets the function invoked when the input ends at this node.
+    pub fn executes(
+        mut self,
+        executor: impl Fn(&mut CommandCtx) -> anyhow::Result<()> + 'static,
+    ) -> Self {
+        self.executor = Some(Rc::new(executor));
+        self
+    }
+}
+
+/// An error encountered while parsing a command.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CommandError {
+    pub message: String,
+    /// Byte offset into the input where the error occurred.
+    pub cursor: usize,
+}
+
+impl CommandError {
+    fn new(message: impl Into<String>, cursor: usize) -> Self {
+        Self {
+            message: message.into(),
+            cursor,
+        }
+    }
+}
+
+/// A parsed command, ready to be executed.
+pub struct ParsedCommand {
+    executor: Executor,
+    args: CommandArgs,
+}
+
+impl ParsedCommand {
+    pub fn args(&self) -> &CommandArgs {
+        &self.args
+    }
+
+    pub fn execute(self, game: &mut Game, sender: Entity) -> anyhow::Result<()> {
+        let mut ctx = CommandCtx {
+            game,
+            sender,
+            args: self.args,
+        };
+        (self.executor)(&mut ctx)
+    }
+}
+
+/// Completions for partial command input.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Suggestions {
+    /// Byte offset into the input where the completed text starts.
+    pub start: usize,
+    pub matches: Vec<String>,
+}
+
+/// The changes made to the command tree by
+/// [`CommandDispatcher::register`], which can be
+/// undone with [`CommandDispatcher::revert`].
+#[derive(Default)]
+pub struct Registration {
+    /// Nodes created or merged into by the registration,
+    /// along with their parents.
+    nodes: Vec<(usize, usize)>,
+    /// Existing nodes whose permission level was raised, along with
+    /// the level set by the registration and the previous one.
+    raised: Vec<(usize, u8, u8)>,
+    /// Existing nodes whose executor was replaced, along with
+    /// the executor set by the registration and the previous one.
+    replaced: Vec<(usize, Executor, Option<Executor>)>,
+}
+
+/// The tree of registered commands.
+pub struct CommandDispatcher {
+    nodes: Vec<CommandNode>,
+    /// Indices of removed nodes, which can be reused.
+    free: Vec<usize>,
+}
+
+impl Default for CommandDispatcher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CommandDispatcher {
+    pub fn new() -> Self {
+        Self {
+            nodes: vec![CommandNode {
+                kind: CommandNodeKind::Root,
+                children: Vec::new(),
+                executor: None,
+                permission_level: 0,
+                references: 1,
+            }],
+            free: Vec::new(),
+        }
+    }
+
+    /// Returns all nodes. The root node is at [`ROOT_NODE`].
+    pub fn nodes(&self) -> &[CommandNode] {
+        &self.nodes
+    }
+
+    pub fn node(&self, index: usize) -> &CommandNode {
+        &self.nodes[index]
+    }
+
+    /// Registers a command. Nodes with the same name and
+    /// argument type as existing nodes are merged into them.
+    ///
+    /// Returns the changes made to the tree, so that
+    /// the command can be unregistered with [`CommandDispatcher::revert`]
+    /// even if it was merged into another command.
+    pub fn register(&mut self, command: CommandBuilder) -> Registration {
+        let mut registration = Registration::default();
+        self.insert(ROOT_NODE, command, &mut registration);
+        registration
+    }
+
+    /// Undoes a [`Registration`], restoring the executors and
+    /// permission levels it replaced and removing the nodes
+    /// no other registration uses.
+    ///
+    /// Executors and permission levels replaced again since
+    /// the registration are kept.
+    pub fn revert(&mut self, registration: Registration) {
+        for (node, executor, previous) in registration.replaced.into_iter().rev() {
+            let current = &mut self.nodes[node].executor;
+            if current
+                .as_ref()
+                .map_or(false, |current| Rc::ptr_eq(current, &executor))
+            {
+                *current = previous;
+            }
+        }
+
+        for (node, level, previous) in registration.raised.into_iter().rev() {
+            let node = &mut self.nodes[node];
+            if node.permission_level == level {
+                node.permission_level = previous;
+            }
+        }
+
+        // Children are only ever attached by registrations which also
+        // reference their parent, so a node is unreferenced only once
+        // all of its children have been removed.
+        for (parent, node) in registration.nodes.into_iter().rev() {
+            let node_ref = &mut self.nodes[node];
+            node_ref.references -= 1;
+            if node_ref.references > 0 {
+                continue;
+            }
+            node_ref.children.clear();
+            node_ref.executor = None;
+            self.nodes[parent].children.retain(|&child| child != node);
+            self.free.push(node);
+        }
+    }
+
+    /// Unregisters the command with the given name.
+    ///
+    /// Its nodes become unreachable from the root, so they
+    /// are no longer parsed or sent to clients.
+    pub fn unregister(&mut self, name: &str) {
+        let nodes = &self.nodes;
+        let position = nodes[ROOT_NODE].children.iter().position(
+            |&child| matches!(&nodes[child].kind, CommandNodeKind::Literal(literal) if literal == name),
+        );
+        if let Some(position) = position {
+            self.nodes[ROOT_NODE].children.remove(position);
+        }
+    }
+
+    fn insert(&mut self, parent: usize, builder: CommandBuilder, registration: &mut Registration) {
+        let existing = self.nodes[parent]
+            .children
+            .iter()
+            .copied()
+            // Arguments with the same name but a different type are kept
+            // as separate nodes, so the existing parser is not replaced.
+            .find(|&child| self.nodes[child].kind == builder.kind);
+
+        let index = match existing {
+            Some(index) => {
+                let node = &mut self.nodes[index];
+                node.references += 1;
+                // Merging never loosens the requirements of an existing command.
+                if builder.permission_level > node.permission_level {
+                    registration.raised.push((
+                        index,
+                        builder.permission_level,
+                        node.permission_level,
+                    ));
+                    node.permission_level = builder.permission_level;
+                }
+                if let Some(executor) = builder.executor {
+                    let previous = node.executor.replace(Rc::clone(&executor));
+                    registration.replaced.push((index, executor, previous));
+                }
+                index
+            }
+            None => {
+                let node = CommandNode {
+                    kind: builder.kind,
+                    children: Vec::new(),
+                    executor: builder.executor,
+                    permission_level: builder.permission_level,
+                    references: 1,
+                };
+                let index = match self.free.pop() {
+                    Some(index) => {
+                        self.nodes[index] = node;
+                        index
+                    }
+                    None => {
+                        self.nodes.push(node);
+                        self.nodes.len() - 1
+                    }
+                };
+                self.nodes[parent].children.push(index);
+                index
+            }
+        };
+        registration.nodes.push((parent, index));
+
+        for child in builder.children {
+            self.insert(index, child, registration);
+        }
+    }
+
+    /// Returns the children of `node` usable at the given permission level,
+    /// literals first.
+    fn permitted_children(&self, node: usize, level: u8) -> impl Iterator<Item = usize> + '_ {
+        let children = &self.nodes[node].children;
+        let literals = children
+            .iter()
+            .filter(move |&&child| matches!(self.nodes[child].kind, CommandNodeKind::Literal(_)));
+        let arguments = children
+            .iter()
+            .filter(move |&&child| !matches!(self.nodes[child].kind, CommandNodeKind::Literal(_)));
+        literals
+            .chain(arguments)
+            .copied()
+            .filter(move |&child| self.nodes[child].permission_level <= level)
+    }
+
+    /// Parses a command (without the leading slash).
+    pub fn parse(
+        &self,
+        game: &Game,
+        sender: Entity,
+        input: &str,
+    ) -> Result<ParsedCommand, CommandError> {
+        let level = permission_level(game, sender);
+        let mut values = Vec::new();
+        let node = self.parse_node(game, sender, level, ROOT_NODE, input, 0, &mut values)?;
+        Ok(ParsedCommand {
+            executor: Rc::clone(self.nodes[node].executor.as_ref().unwrap()),
+            args: CommandArgs { values },
+        })
+    }
+
+    /// Parses the input at `cursor` with the children of `node`,
+    /// returning the node at which the command ends.
+    #[allow(clippy::too_many_arguments)]
+    fn parse_node(
+        &self,
+        game: &Game,
+        sender: Entity,
+        level: u8,
+        node: usize,
+        input: &str,
+        cursor: usize,
+        values: &mut Vec<(String, ArgumentValue)>,
+    ) -> Result<usize, CommandError> {
+        if cursor == input.len() {
+            return if self.nodes[node].executor.is_some() {
+                Ok(node)
+            } else {
+                Err(CommandError::new("Unknown or incomplete command", cursor))
+            };
+        }
+
+        let mut error: Option<CommandError> = None;
+        let mut record = |e: CommandError| {
+            if error
+                .as_ref()
+                .map_or(true, |error| e.cursor >= error.cursor)
+            {
+                error = Some(e);
+            }
+        };
+
+        for child in self.permitted_children(node, level) {
+            let mut reader = StringReader::with_cursor(input, cursor);
+            let value = match &self.nodes[child].kind {
+                CommandNodeKind::Literal(name) => {
+                    if reader.read_unquoted() != name.as_str() {
+                        record(CommandError::new("Unknown command", cursor));
+                        continue;
+                    }
+                    None
+                }
+                CommandNodeKind::Argument { name, kind } => {
+                    match kind.parse(game, sender, &mut reader) {
+                        Ok(value) => Some((name.clone(), value)),
+                        Err(e) => {
+                            record(CommandError::new(e.to_string(), cursor));
+                            continue;
+                        }
+                    }
+                }
+                CommandNodeKind::Root => unreachable!("root node cannot be a child"),
+            };
+
+            let mut next = reader.cursor();
+            if reader.can_read() {
+                if reader.peek() != Some(' ') {
+                    record(CommandError::new(
+                        "Expected whitespace to end one argument, but found trailing data",
+                        next,
+                    ));
+                    continue;
+                }
+                next += 1;
+            }
+
+            let num_values = values.len();
+            values.extend(value);
+            match self.parse_node(game, sender, level, child, input, next, values) {
+                Ok(node) => return Ok(node),
+                Err(e) => {
+                    values.truncate(num_values);
+                    record(e);
+                }
+            }
+        }
+
+        Err(error.unwrap_or_else(|| CommandError::new("Incorrect argument for command", cursor)))
+    }
+
+    /// Returns completions for the last word of `input`
+    /// (without the leading slash).
+    pub fn suggest(&self, game: &Game, sender: Entity, input: &str) -> Suggestions {
+        let level = permission_level(game, sender);
+        let mut found = Vec::new();
+        self.suggest_node(game, sender, level, ROOT_NODE, input, 0, &mut found);
+
+        let start = found.iter().map(|(start, _)| *start).max().unwrap_or(0);
+        let mut matches: Vec<String> = found
+            .into_iter()
+            .filter(|(s, _)| *s == start)
+            .map(|(_, suggestion)| suggestion)
+            .collect();
+        matches.sort();
+        matches.dedup();
+        Suggestions { start, matches }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn suggest_node(
+        &self,
+        game: &Game,
+        sender: Entity,
+        level: u8,
+        node: usize,
+        input: &str,
+        cursor: usize,
+        found: &mut Vec<(usize, String)>,
+    ) {
+        let remaining = &input[cursor..];
+        for child in self.permitted_children(node, level) {
+            match &self.nodes[child].kind {
+                CommandNodeKind::Literal(name) => match remaining.find(' ') {
+                    None => {
+                        if name.starts_with(remaining) {
+                            found.push((cursor, name.clone()));
+                        }
+                    }
+                    Some(end) => {
+                        if &remaining[..end] == name.as_str() {
+                            self.suggest_node(
+                                game,
+                                sender,
+                                level,
+                                child,
+                                input,
+                                cursor + end + 1,
+                                found,
+                            );
+                        }
+                    }
+                },
+                CommandNodeKind::Argument { kind, .. } => {
+                    let mut reader = StringReader::with_cursor(input, cursor);
+                    match kind.parse(game, sender, &mut reader) {
+                        Ok(_) if reader.peek() == Some(' ') => {
+                            let next = reader.cursor() + 1;
+                            self.suggest_node(game, sender, level, child, input, next, found);
+                        }
+                        _ => found.extend(
+                            kind.suggest(game, remaining)
+                                .into_iter()
+                                .map(|suggestion| (cursor, suggestion)),
+                        ),
+                    }
+                }
+                CommandNodeKind::Root => unreachable!("root node cannot be a child"),
+            }
+        }
+    }
+}
+
+/// Inserts the dispatcher with the vanilla commands registered.
+pub fn register(game: &mut Game) {
+    let mut dispatcher = CommandDispatcher::new();
+    impls::register(&mut dispatcher);
+    game.insert_resource(dispatcher);
+}
+
+/// Parses and executes a command (without the leading slash)
+/// on behalf of `sender`. Errors are reported to the sender.
+pub fn execute_command(game: &mut Game, sender: Entity, command: &str) -> SysResult {
+    let parsed = game
+        .resources
+        .get::<CommandDispatcher>()?
+        .parse(game, sender, command);
+
+    let result = match parsed {
+        Ok(parsed) => parsed.execute(game, sender),
+        Err(e) => {
+            send_error(game, sender, &e.message);
+            let start = e.cursor.saturating_sub(10);
+            let context = command.get(start..e.cursor).unwrap_or_default();
+            send_error(game, sender, format!("...{}<--[HERE]", context));
+            return Ok(());
+        }
+    };
+
+    if let Err(e) = result {
+        send_error(game, sender, e.to_string());
+    }
+    Ok(())
+}
+
+fn send_error(game: &mut Game, sender: Entity, message: impl Into<String>) {
+    if let Ok(mut chat_box) = game.ecs.get_mut::<ChatBox>(sender) {
+        chat_box.send_system(Text::from(message.into()).red());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use quill_common::components::Name;
+
+    use super::*;
+
+    fn dispatcher(executed: Rc<Cell<i32>>) -> CommandDispatcher {
+        let mut dispatcher = CommandDispatcher::new();
+        let executed2 = Rc::clone(&executed);
+        dispatcher.register(
+            literal("time")
+                .requires(2)
+                .then(
+                    literal("set").then(argument("time", ArgumentKind::Time).executes(
+                        move |ctx| {
+                            executed.set(ctx.args.time("time")?);
+                            Ok(())
+                        },
+                    )),
+                )
+                .then(literal("query").executes(move |_| {
+                    executed2.set(-1);
+                    Ok(())
+                })),
+        );
+        dispatcher.register(literal("help").executes(|_| Ok(())));
+        dispatcher
+    }
+
+    #[test]
+    fn parse_and_execute() {
+        let executed = Rc::new(Cell::new(0));
+        let dispatcher = dispatcher(Rc::clone(&executed));
+
+        let mut game = Game::new();
+        let sender = game.ecs.spawn((Name::new("op"), PermissionLevel(4)));
+
+        let parsed = dispatcher.parse(&game, sender, "time set 1d").unwrap();
+        parsed.execute(&mut game, sender).unwrap();
+        assert_eq!(executed.get(), 24000);
+
+        let parsed = dispatcher.parse(&game, sender, "time query").unwrap();
+        parsed.execute(&mut game, sender).unwrap();
+        assert_eq!(executed.get(), -1);
+
+        assert!(dispatcher.parse(&game, sender, "time").is_err());
+        let error = dispatcher.parse(&game, sender, "time set x").err().unwrap();
+        assert_eq!(error.cursor, 9);
+    }
+
+    #[test]
+    fn permission_levels() {
+        let dispatcher = dispatcher(Rc::new(Cell::new(0)));
+
+        let mut game = Game::new();
+        let sender = game.ecs.spawn((Name::new("player"),));
+
+        assert!(dispatcher.parse(&game, sender, "help").is_ok());
+        assert!(dispatcher.parse(&game, sender, "time query").is_err());
+        assert_eq!(
+            dispatcher.suggest(&game, sender, "").matches,
+            vec!["help".to_owned()]
+        );
+    }
+
+    #[test]
+    fn suggestions() {
+        let dispatcher = dispatcher(Rc::new(Cell::new(0)));
+
+        let mut game = Game::new();
+        let sender = game.ecs.spawn((Name::new("op"), PermissionLevel(4)));
+
+        assert_eq!(
+            dispatcher.suggest(&game, sender, ""),
+            Suggestions {
+                start: 0,
+                matches: vec!["help".to_owned(), "time".to_owned()]
+            }
+        );
+        assert_eq!(
+            dispatcher.suggest(&game, sender, "time q"),
+            Suggestions {
+                start: 5,
+                matches: vec!["query".to_owned()]
+            }
+        );
+    }
+
+    #[test]
+    fn merge_registered_commands() {
+        let mut dispatcher = CommandDispatcher::new();
+        dispatcher.register(literal("a").then(literal("b").executes(|_| Ok(()))));
+        dispatcher.register(literal("a").then(literal("c").executes(|_| Ok(()))));
+
+        let root = dispatcher.node(ROOT_NODE);
+        assert_eq!(root.children().len(), 1);
+        assert_eq!(dispatcher.node(root.children()[0]).children().len(), 2);
+    }
+
+    #[test]
+    fn revert_merged_commands() {
+        let mut dispatcher = dispatcher(Rc::new(Cell::new(0)));
+        let registration =
+            dispatcher.register(literal("time").then(literal("skip").executes(|_| Ok(()))));
+        let node_count = dispatcher.nodes().len();
+        dispatcher.revert(registration);
+
+        let mut game = Game::new();
+        let sender = game.ecs.spawn((Name::new("op"), PermissionLevel(4)));
+        assert!(dispatcher.parse(&game, sender, "time skip").is_err());
+        assert!(dispatcher.parse(&game, sender, "time query").is_ok());
+
+        // The removed node is reused.
+        dispatcher.register(literal("time").then(literal("skip").executes(|_| Ok(()))));
+        assert_eq!(dispatcher.nodes().len(), node_count);
+    }
+
+    #[test]
+    fn revert_keeps_nodes_used_by_other_registrations() {
+        let mut dispatcher = dispatcher(Rc::new(Cell::new(0)));
+        let first = dispatcher.register(literal("plugin").then(literal("a").executes(|_| Ok(()))));
+        let second = dispatcher.register(literal("plugin").then(literal("b").executes(|_| Ok(()))));
+        dispatcher.revert(first);
+
+        let mut game = Game::new();
+        let sender = game.ecs.spawn((Name::new("op"), PermissionLevel(4)));
+        assert!(dispatcher.parse(&game, sender, "plugin a").is_err());
+        assert!(dispatcher.parse(&game, sender, "plugin b").is_ok());
+
+        dispatcher.revert(second);
+        assert!(dispatcher.parse(&game, sender, "plugin b").is_err());
+        assert_eq!(dispatcher.node(ROOT_NODE).children().len(), 2);
+    }
+
+    #[test]
+    fn arguments_of_different_types_are_not_merged() {
+        let mut dispatcher = CommandDispatcher::new();
+        dispatcher.register(
+            literal("size").then(
+                argument(
+                    "value",
+                    ArgumentKind::Integer {
+                        min: None,
+                        max: None,
+                    },
+                )
+                .executes(|_| Ok(())),
+            ),
+        );
+        let registration = dispatcher.register(
+            literal("size")
+                .requires(2)
+                .then(argument("value", ArgumentKind::Word).executes(|_| Ok(()))),
+        );
+
+        let mut game = Game::new();
+        let op = game.ecs.spawn((Name::new("op"), PermissionLevel(4)));
+        let player = game.ecs.spawn((Name::new("player"),));
+        assert!(dispatcher.parse(&game, op, "size 5").is_ok());
+        assert!(dispatcher.parse(&game, op, "size big").is_ok());
+        assert!(dispatcher.parse(&game, player, "size 5").is_err());
+
+        dispatcher.revert(registration);
+        assert!(dispatcher.parse(&game, player, "size 5").is_ok());
+        assert!(dispatcher.parse(&game, op, "size big").is_err());
+    }
+
+    #[test]
+    fn merging_keeps_stricter_permission_level() {
+        let mut dispatcher = dispatcher(Rc::new(Cell::new(0)));
+        dispatcher.register(literal("time").then(literal("skip").executes(|_| Ok(()))));
+        dispatcher.register(literal("help").requires(2).executes(|_| Ok(())));
+
+        let mut game = Game::new();
+        let sender = game.ecs.spawn((Name::new("player"),));
+        assert!(dispatcher.parse(&game, sender, "time skip").is_err());
+        assert!(dispatcher.parse(&game, sender, "help").is_err());
+    }
+
+    #[test]
+    fn unregister_commands() {
+        let mut dispatcher = dispatcher(Rc::new(Cell::new(0)));
+        dispatcher.unregister("time");
+
+        let mut game = Game::new();
+        let sender = game.ecs.spawn((Name::new("op"), PermissionLevel(4)));
+
+        assert!(dispatcher.parse(&game, sender, "time query").is_err());
+        assert!(dispatcher.parse(&game, sender, "help").is_ok());
+        assert_eq!(dispatcher.node(ROOT_NODE).children().len(), 1);
+    }
+}
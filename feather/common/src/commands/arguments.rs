@@ -0,0 +1,471 @@
+//! Argument types supported by the command dispatcher.
+
+use anyhow::{anyhow, bail};
+use base::{BlockPosition, EntityKind, Gamemode, Item, Position};
+use ecs::Entity;
+use quill_common::components::Name;
+use rand::seq::IteratorRandom;
+
+use crate::Game;
+
+use super::reader::StringReader;
+
+const SELECTORS: [&str; 5] = ["@a", "@e", "@p", "@r", "@s"];
+const GAMEMODES: [&str; 4] = ["survival", "creative", "adventure", "spectator"];
+
+/// Determines how the input of an argument node is parsed.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ArgumentKind {
+    Bool,
+    Integer {
+        min: Option<i32>,
+        max: Option<i32>,
+    },
+    Double {
+        min: Option<f64>,
+        max: Option<f64>,
+    },
+    /// A single word.
+    Word,
+    /// A single word or a phrase in double quotes.
+    String,
+    /// The rest of the input.
+    GreedyString,
+    /// An entity selector (`@a`, `@e`, `@p`, `@r`, `@s`)
+    /// or a player name.
+    Entities {
+        single: bool,
+        players_only: bool,
+    },
+    /// Three coordinates, each of which may be relative (`~`).
+    Coordinates,
+    /// Three integer coordinates, each of which may be relative (`~`).
+    BlockPosition,
+    Gamemode,
+    /// A chat message, taking the rest of the input.
+    Message,
+    Item,
+    /// A duration in ticks, optionally suffixed with `d`, `s` or `t`.
+    Time,
+}
+
+/// A parsed argument.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ArgumentValue {
+    Bool(bool),
+    Integer(i32),
+    Double(f64),
+    String(String),
+    Entities(Vec<Entity>),
+    Coordinates(Coordinates),
+    Gamemode(Gamemode),
+    Item(Item),
+    /// A duration in ticks.
+    Time(i32),
+}
+
+impl ArgumentKind {
+    /// Parses this argument from the reader.
+    pub(crate) fn parse(
+        &self,
+        game: &Game,
+        sender: Entity,
+        reader: &mut StringReader,
+    ) -> anyhow::Result<ArgumentValue> {
+        Ok(match self {
+            ArgumentKind::Bool => match reader.read_unquoted() {
+                "true" => ArgumentValue::Bool(true),
+                "false" => ArgumentValue::Bool(false),
+                s => bail!(
+                    "Invalid boolean, expected 'true' or 'false' but found '{}'",
+                    s
+                ),
+            },
+            ArgumentKind::Integer { min, max } => {
+                let word = reader.read_unquoted();
+                let value: i32 = word
+                    .parse()
+                    .map_err(|_| anyhow!("Invalid integer '{}'", word))?;
+                check_bounds(value, *min, *max, "Integer")?;
+                ArgumentValue::Integer(value)
+            }
+            ArgumentKind::Double { min, max } => {
+                let word = reader.read_unquoted();
+                let value: f64 = word
+                    .parse()
+                    .map_err(|_| anyhow!("Invalid double '{}'", word))?;
+                check_bounds(value, *min, *max, "Double")?;
+                ArgumentValue::Double(value)
+            }
+            ArgumentKind::Word => ArgumentValue::String(reader.read_unquoted().to_owned()),
+            ArgumentKind::String => ArgumentValue::String(reader.read_string()?),
+            ArgumentKind::GreedyString | ArgumentKind::Message => {
+                ArgumentValue::String(reader.read_remaining().to_owned())
+            }
+            ArgumentKind::Entities {
+                single,
+                players_only,
+            } => {
+                let selector = reader.read_unquoted();
+                let entities = select_entities(game, sender, selector, *single, *players_only)?;
+                ArgumentValue::Entities(entities)
+            }
+            ArgumentKind::Coordinates => {
+                ArgumentValue::Coordinates(Coordinates::parse(reader, false)?)
+            }
+            ArgumentKind::BlockPosition => {
+                ArgumentValue::Coordinates(Coordinates::parse(reader, true)?)
+            }
+            ArgumentKind::Gamemode => {
+                let word = reader.read_unquoted();
+                ArgumentValue::Gamemode(
+                    parse_gamemode(word).ok_or_else(|| anyhow!("Unknown game mode '{}'", word))?,
+                )
+            }
+            ArgumentKind::Item => {
+                let word = reader.read_unquoted();
+                let name = word.strip_prefix("minecraft:").unwrap_or(word);
+                ArgumentValue::Item(
+                    Item::from_name(name).ok_or_else(|| anyhow!("Unknown item '{}'", word))?,
+                )
+            }
+            ArgumentKind::Time => ArgumentValue::Time(parse_time(reader.read_unquoted())?),
+        })
+    }
+
+    /// Returns suggestions for completing `partial`,
+    /// which is the input starting at this argument.
+    pub(crate) fn suggest(&self, game: &Game, partial: &str) -> Vec<String> {
+        let candidates: Vec<String> = match self {
+            ArgumentKind::Bool => vec!["true".to_owned(), "false".to_owned()],
+            ArgumentKind::Entities {
+                single,
+                players_only,
+            } => {
+                let mut candidates: Vec<String> = SELECTORS
+                    .iter()
+                    .filter(|&&selector| !*single || matches!(selector, "@p" | "@r" | "@s"))
+                    .filter(|&&selector| !*players_only || selector != "@e")
+                    .map(|&selector| selector.to_owned())
+                    .collect();
+                candidates.extend(
+                    game.ecs
+                        .query::<&Name>()
+                        .iter()
+                        .map(|(_, name)| name.to_string()),
+                );
+                candidates
+            }
+            ArgumentKind::Coordinates | ArgumentKind::BlockPosition => {
+                vec!["~ ~ ~".to_owned()]
+            }
+            ArgumentKind::Gamemode => GAMEMODES.iter().map(|&s| s.to_owned()).collect(),
+            _ => Vec::new(),
+        };
+
+        candidates
+            .into_iter()
+            .filter(|candidate| candidate.starts_with(partial))
+            .collect()
+    }
+}
+
+fn check_bounds<T: PartialOrd + std::fmt::Display>(
+    value: T,
+    min: Option<T>,
+    max: Option<T>,
+    name: &str,
+) -> anyhow::Result<()> {
+    if let Some(min) = min {
+        if value < min {
+            bail!("{} must not be less than {}, found {}", name, min, value);
+        }
+    }
+    if let Some(max) = max {
+        if value > max {
+            bail!("{} must not be more than {}, found {}", name, max, value);
+        }
+    }
+    Ok(())
+}
+
+fn parse_gamemode(s: &str) -> Option<Gamemode> {
+    Some(match s {
+        "survival" => Gamemode::Survival,
+        "creative" => Gamemode::Creative,
+        "adventure" => Gamemode::Adventure,
+        "spectator" => Gamemode::Spectator,
+        _ => return None,
+    })
+}
+
+fn parse_time(s: &str) -> anyhow::Result<i32> {
+    let (number, multiplier) = match s.chars().last() {
+        Some('d') => (&s[..s.len() - 1], 24000.0),
+        Some('s') => (&s[..s.len() - 1], 20.0),
+        Some('t') => (&s[..s.len() - 1], 1.0),
+        _ => (s, 1.0),
+    };
+    let value: f32 = number
+        .parse()
+        .map_err(|_| anyhow!("Invalid time '{}'", s))?;
+    if value < 0.0 {
+        bail!("Tick count must be non-negative");
+    }
+    Ok((value * multiplier).round() as i32)
+}
+
+/// Resolves an entity selector or player name to the entities it selects.
+///
+/// See https://minecraft.gamepedia.com/Commands#Target_selectors
+fn select_entities(
+    game: &Game,
+    sender: Entity,
+    selector: &str,
+    single: bool,
+    players_only: bool,
+) -> anyhow::Result<Vec<Entity>> {
+    let entities: Vec<Entity> = match selector {
+        "@p" => {
+            let origin = game
+                .ecs
+                .get::<Position>(sender)
+                .map(|pos| *pos)
+                .unwrap_or_default();
+            game.ecs
+                .query::<(&Position, &Name)>()
+                .iter()
+                .min_by(|(_, (a, _)), (_, (b, _))| {
+                    origin
+                        .distance_squared_to(**a)
+                        .partial_cmp(&origin.distance_squared_to(**b))
+                        .unwrap_or(std::cmp::Ordering::Equal)
+                })
+                .map(|(entity, _)| entity)
+                .into_iter()
+                .collect()
+        }
+        "@r" => game
+            .ecs
+            .query::<&Name>()
+            .iter()
+            .map(|(entity, _)| entity)
+            .choose(&mut rand::thread_rng())
+            .into_iter()
+            .collect(),
+        "@a" => {
+            if single {
+                bail!("Only one entity is allowed, but the provided selector allows more than one");
+            }
+            game.ecs
+                .query::<&Name>()
+                .iter()
+                .map(|(entity, _)| entity)
+                .collect()
+        }
+        "@e" => {
+            if single {
+                bail!("Only one entity is allowed, but the provided selector allows more than one");
+            }
+            if players_only {
+                bail!("Only players may be affected by this command, but the provided selector includes entities");
+            }
+            game.ecs
+                .query::<&EntityKind>()
+                .iter()
+                .map(|(entity, _)| entity)
+                .collect()
+        }
+        "@s" => {
+            if players_only && game.ecs.get::<Name>(sender).is_err() {
+                Vec::new()
+            } else {
+                vec![sender]
+            }
+        }
+        name => game
+            .ecs
+            .query::<&Name>()
+            .iter()
+            .find(|(_, n)| n.as_str() == name)
+            .map(|(entity, _)| entity)
+            .into_iter()
+            .collect(),
+    };
+
+    if entities.is_empty() {
+        if players_only {
+            bail!("No player was found");
+        } else {
+            bail!("No entity was found");
+        }
+    }
+    Ok(entities)
+}
+
+/// A single coordinate, either absolute
+/// or relative to some origin (`~`).
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Coordinate {
+    Absolute(f64),
+    Relative(f64),
+}
+
+impl Coordinate {
+    fn parse(s: &str, integer: bool) -> anyhow::Result<Self> {
+        if let Some(offset) = s.strip_prefix('~') {
+            let offset: f64 = if offset.is_empty() {
+                0.
+            } else {
+                offset
+                    .parse()
+                    .map_err(|_| anyhow!("Invalid coordinate '{}'", s))?
+            };
+            if !offset.is_finite() {
+                bail!("Invalid coordinate '{}'", s);
+            }
+            Ok(Coordinate::Relative(offset))
+        } else if integer {
+            let value: i32 = s
+                .parse()
+                .map_err(|_| anyhow!("Invalid integer coordinate '{}'", s))?;
+            Ok(Coordinate::Absolute(value as f64))
+        } else {
+            let value: f64 = s
+                .parse()
+                .map_err(|_| anyhow!("Invalid coordinate '{}'", s))?;
+            if !value.is_finite() {
+                bail!("Invalid coordinate '{}'", s);
+            }
+            Ok(Coordinate::Absolute(value))
+        }
+    }
+
+    fn resolve(self, origin: f64) -> f64 {
+        match self {
+            Coordinate::Absolute(value) => value,
+            Coordinate::Relative(offset) => origin + offset,
+        }
+    }
+}
+
+/// Parsed `<x> <y> <z>` coordinates.
+///
+/// See https://minecraft.gamepedia.com/Commands#Tilde_and_caret_notation
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Coordinates {
+    pub x: Coordinate,
+    pub y: Coordinate,
+    pub z: Coordinate,
+}
+
+impl Coordinates {
+    fn parse(reader: &mut StringReader, integer: bool) -> anyhow::Result<Self> {
+        let x = Coordinate::parse(reader.read_unquoted(), integer)?;
+        let y = Self::parse_next(reader, integer)?;
+        let z = Self::parse_next(reader, integer)?;
+        Ok(Self { x, y, z })
+    }
+
+    fn parse_next(reader: &mut StringReader, integer: bool) -> anyhow::Result<Coordinate> {
+        if reader.peek() != Some(' ') {
+            bail!("Incomplete (expected 3 coordinates)");
+        }
+        reader.skip();
+        Coordinate::parse(reader.read_unquoted(), integer)
+    }
+
+    /// Converts these coordinates into a `Position`.
+    ///
+    /// `relative_to` is the origin of relative coordinates,
+    /// e.g. the position of the command sender. Its rotation
+    /// is kept.
+    pub fn into_position(self, relative_to: Position) -> Position {
+        Position {
+            x: self.x.resolve(relative_to.x),
+            y: self.y.resolve(relative_to.y),
+            z: self.z.resolve(relative_to.z),
+            ..relative_to
+        }
+    }
+
+    /// Converts these coordinates into a `BlockPosition`.
+    pub fn into_block_position(self, relative_to: BlockPosition) -> BlockPosition {
+        BlockPosition::new(
+            self.x.resolve(relative_to.x as f64).floor() as i32,
+            self.y.resolve(relative_to.y as f64).floor() as i32,
+            self.z.resolve(relative_to.z as f64).floor() as i32,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use base::position;
+
+    use super::*;
+
+    #[test]
+    fn parse_coordinates() {
+        let mut reader = StringReader::new("~ ~10 -5.5");
+        let coordinates = Coordinates::parse(&mut reader, false).unwrap();
+        assert_eq!(
+            coordinates.into_position(position!(1.0, 2.0, 3.0)),
+            position!(1.0, 12.0, -5.5)
+        );
+
+        let mut reader = StringReader::new("1 2");
+        assert!(Coordinates::parse(&mut reader, false).is_err());
+
+        let mut reader = StringReader::new("1 2 3.5");
+        assert!(Coordinates::parse(&mut reader, true).is_err());
+    }
+
+    #[test]
+    fn reject_non_finite_coordinates() {
+        for input in &[
+            "NaN 0 0",
+            "0 inf 0",
+            "0 0 -infinity",
+            "~NaN ~ ~",
+            "~ ~inf ~",
+        ] {
+            let mut reader = StringReader::new(input);
+            assert!(Coordinates::parse(&mut reader, false).is_err(), "{}", input);
+        }
+    }
+
+    #[test]
+    fn parse_times() {
+        assert_eq!(parse_time("20").unwrap(), 20);
+        assert_eq!(parse_time("1d").unwrap(), 24000);
+        assert_eq!(parse_time("0.5s").unwrap(), 10);
+        assert_eq!(parse_time("5t").unwrap(), 5);
+        assert!(parse_time("-1").is_err());
+        assert!(parse_time("day").is_err());
+    }
+
+    #[test]
+    fn select_players_by_name() {
+        let mut game = Game::new();
+        let sender = game.ecs.spawn((Name::new("foo"), Position::default()));
+        let other = game.ecs.spawn((Name::new("bar"), Position::default()));
+
+        assert_eq!(
+            select_entities(&game, sender, "bar", true, true).unwrap(),
+            vec![other]
+        );
+        assert_eq!(
+            select_entities(&game, sender, "@s", true, true).unwrap(),
+            vec![sender]
+        );
+        assert_eq!(
+            select_entities(&game, sender, "@a", false, true)
+                .unwrap()
+                .len(),
+            2
+        );
+        assert!(select_entities(&game, sender, "@a", true, true).is_err());
+        assert!(select_entities(&game, sender, "baz", true, true).is_err());
+    }
+}
@@ -0,0 +1,578 @@
+//! Implementations of the vanilla commands.
+
+use base::{EntityKind, Gamemode, ItemStack, Position, Text, TextComponentBuilder};
+use ecs::Entity;
+use quill_common::components::Name;
+
+use crate::{
+    chat::ChatKind,
+    events::{InventoryUpdateEvent, PlayerKickEvent, ShutdownEvent, WeatherChangeEvent},
+    window, ChatBox, Game,
+};
+
+use super::{argument, literal, ArgumentKind, CommandCtx, CommandDispatcher};
+
+const TARGETS: ArgumentKind = ArgumentKind::Entities {
+    single: false,
+    players_only: false,
+};
+const PLAYERS: ArgumentKind = ArgumentKind::Entities {
+    single: false,
+    players_only: true,
+};
+const DESTINATION: ArgumentKind = ArgumentKind::Entities {
+    single: true,
+    players_only: false,
+};
+
+/// Default duration of `/weather`, in seconds.
+const DEFAULT_WEATHER_DURATION: i32 = 300;
+
+/// Registers the vanilla commands.
+pub fn register(dispatcher: &mut CommandDispatcher) {
+    register_teleport(dispatcher);
+    register_gamemode(dispatcher);
+    register_kick(dispatcher);
+    register_say(dispatcher);
+    register_msg(dispatcher);
+    register_stop(dispatcher);
+    register_give(dispatcher);
+    register_time(dispatcher);
+    register_weather(dispatcher);
+}
+
+fn register_teleport(dispatcher: &mut CommandDispatcher) {
+    for name in &["tp", "teleport"] {
+        dispatcher.register(
+            literal(*name)
+                .requires(2)
+                .then(
+                    argument("location", ArgumentKind::Coordinates).executes(|ctx| {
+                        let sender = ctx.sender;
+                        let location = ctx.position("location")?;
+                        teleport_to_location(ctx, vec![sender], location)
+                    }),
+                )
+                .then(argument("destination", DESTINATION).executes(|ctx| {
+                    let sender = ctx.sender;
+                    let destination = ctx.args.entity("destination")?;
+                    teleport_to_entity(ctx, vec![sender], destination)
+                }))
+                .then(
+                    argument("targets", TARGETS)
+                        .then(
+                            argument("location", ArgumentKind::Coordinates).executes(|ctx| {
+                                let targets = ctx.args.entities("targets")?;
+                                let location = ctx.position("location")?;
+                                teleport_to_location(ctx, targets, location)
+                            }),
+                        )
+                        .then(argument("destination", DESTINATION).executes(|ctx| {
+                            let targets = ctx.args.entities("targets")?;
+                            let destination = ctx.args.entity("destination")?;
+                            teleport_to_entity(ctx, targets, destination)
+                        })),
+                ),
+        );
+    }
+}
+
+fn teleport_to_location(
+    ctx: &mut CommandCtx,
+    targets: Vec<Entity>,
+    location: Position,
+) -> anyhow::Result<()> {
+    for &target in &targets {
+        teleport(ctx.game, target, location);
+    }
+
+    let coordinates = vec![
+        format!("{:.2}", location.x),
+        format!("{:.2}", location.y),
+        format!("{:.2}", location.z),
+    ];
+    let message = match targets.as_slice() {
+        [target] => {
+            let mut with = vec![display_name(ctx.game, *target)];
+            with.extend(coordinates);
+            Text::translate_with("commands.teleport.success.location.single", with)
+        }
+        _ => {
+            let mut with = vec![targets.len().to_string()];
+            with.extend(coordinates);
+            Text::translate_with("commands.teleport.success.location.multiple", with)
+        }
+    };
+    ctx.send_message(message);
+    Ok(())
+}
+
+fn teleport_to_entity(
+    ctx: &mut CommandCtx,
+    targets: Vec<Entity>,
+    destination: Entity,
+) -> anyhow::Result<()> {
+    let location = *ctx.game.ecs.get::<Position>(destination)?;
+    for &target in &targets {
+        teleport(ctx.game, target, location);
+    }
+
+    let destination = display_name(ctx.game, destination);
+    let message = match targets.as_slice() {
+        [target] => Text::translate_with(
+            "commands.teleport.success.entity.single",
+            vec![display_name(ctx.game, *target), destination],
+        ),
+        _ => Text::translate_with(
+            "commands.teleport.success.entity.multiple",
+            vec![targets.len().to_string(), destination],
+        ),
+    };
+    ctx.send_message(message);
+    Ok(())
+}
+
+/// Moves an entity, keeping its rotation. The new position
+/// is sent to clients along with other entity movement.
+fn teleport(game: &mut Game, entity: Entity, location: Position) {
+    if let Ok(mut position) = game.ecs.get_mut::<Position>(entity) {
+        position.x = location.x;
+        position.y = location.y;
+        position.z = location.z;
+    }
+}
+
+fn register_gamemode(dispatcher: &mut CommandDispatcher) {
+    dispatcher.register(
+        literal("gamemode").requires(2).then(
+            argument("gamemode", ArgumentKind::Gamemode)
+                .executes(|ctx| {
+                    let sender = ctx.sender;
+                    let gamemode = ctx.args.gamemode("gamemode")?;
+                    set_gamemode(ctx, vec![sender], gamemode)
+                })
+                .then(argument("target", PLAYERS).executes(|ctx| {
+                    let gamemode = ctx.args.gamemode("gamemode")?;
+                    let targets = ctx.args.entities("target")?;
+                    set_gamemode(ctx, targets, gamemode)
+                })),
+        ),
+    );
+}
+
+fn set_gamemode(
+    ctx: &mut CommandCtx,
+    targets: Vec<Entity>,
+    gamemode: Gamemode,
+) -> anyhow::Result<()> {
+    for target in targets {
+        let _ = ctx.game.set_gamemode(target, gamemode);
+
+        if target == ctx.sender {
+            ctx.send_message(Text::translate_with(
+                "commands.gamemode.success.self",
+                vec![gamemode_name(gamemode)],
+            ));
+        } else {
+            ctx.send_message(Text::translate_with(
+                "commands.gamemode.success.other",
+                vec![
+                    Text::from(display_name(ctx.game, target)),
+                    gamemode_name(gamemode),
+                ],
+            ));
+            send_system_message(
+                ctx.game,
+                target,
+                Text::translate_with("gameMode.changed", vec![gamemode_name(gamemode)]),
+            );
+        }
+    }
+    Ok(())
+}
+
+fn gamemode_name(gamemode: Gamemode) -> Text {
+    let key = match gamemode {
+        Gamemode::Survival => "gameMode.survival",
+        Gamemode::Creative => "gameMode.creative",
+        Gamemode::Adventure => "gameMode.adventure",
+        Gamemode::Spectator => "gameMode.spectator",
+    };
+    Text::translate_with(key, Vec::<Text>::new())
+}
+
+fn register_kick(dispatcher: &mut CommandDispatcher) {
+    dispatcher.register(
+        literal("kick").requires(3).then(
+            argument("targets", PLAYERS)
+                .executes(|ctx| {
+                    let targets = ctx.args.entities("targets")?;
+                    let reason =
+                        Text::translate_with("multiplayer.disconnect.kicked", Vec::<Text>::new());
+                    kick(ctx, targets, reason)
+                })
+                .then(argument("reason", ArgumentKind::Message).executes(|ctx| {
+                    let targets = ctx.args.entities("targets")?;
+                    let reason = Text::from(ctx.args.string("reason")?);
+                    kick(ctx, targets, reason)
+                })),
+        ),
+    );
+}
+
+fn kick(ctx: &mut CommandCtx, targets: Vec<Entity>, reason: Text) -> anyhow::Result<()> {
+    for target in targets {
+        ctx.send_message(Text::translate_with(
+            "commands.kick.success",
+            vec![Text::from(display_name(ctx.game, target)), reason.clone()],
+        ));
+        ctx.game.ecs.insert_entity_event(
+            target,
+            PlayerKickEvent {
+                reason: reason.clone(),
+            },
+        )?;
+    }
+    Ok(())
+}
+
+fn register_say(dispatcher: &mut CommandDispatcher) {
+    dispatcher.register(literal("say").requires(2).then(
+        argument("message", ArgumentKind::Message).executes(|ctx| {
+            let message = Text::translate_with(
+                "chat.type.announcement",
+                vec![
+                    display_name(ctx.game, ctx.sender),
+                    ctx.args.string("message")?,
+                ],
+            );
+            ctx.game.broadcast_chat(ChatKind::System, message);
+            Ok(())
+        }),
+    ));
+}
+
+fn register_msg(dispatcher: &mut CommandDispatcher) {
+    for name in &["msg", "tell", "w"] {
+        dispatcher.register(literal(*name).then(argument("targets", PLAYERS).then(
+            argument("message", ArgumentKind::Message).executes(|ctx| {
+                let message = ctx.args.string("message")?;
+                let sender_name = display_name(ctx.game, ctx.sender);
+                for target in ctx.args.entities("targets")? {
+                    let incoming = Text::translate_with(
+                        "commands.message.display.incoming",
+                        vec![sender_name.clone(), message.clone()],
+                    )
+                    .gray()
+                    .italic();
+                    send_system_message(ctx.game, target, incoming);
+
+                    let outgoing = Text::translate_with(
+                        "commands.message.display.outgoing",
+                        vec![display_name(ctx.game, target), message.clone()],
+                    )
+                    .gray()
+                    .italic();
+                    ctx.send_message(outgoing);
+                }
+                Ok(())
+            }),
+        )));
+    }
+}
+
+fn register_stop(dispatcher: &mut CommandDispatcher) {
+    dispatcher.register(literal("stop").requires(4).executes(|ctx| {
+        ctx.send_message(Text::translate_with(
+            "commands.stop.stopping",
+            Vec::<Text>::new(),
+        ));
+        ctx.game.ecs.insert_event(ShutdownEvent);
+        Ok(())
+    }));
+}
+
+fn register_give(dispatcher: &mut CommandDispatcher) {
+    dispatcher.register(
+        literal("give").requires(2).then(
+            argument("targets", PLAYERS).then(
+                argument("item", ArgumentKind::Item)
+                    .executes(|ctx| give(ctx, 1))
+                    .then(
+                        argument(
+                            "count",
+                            ArgumentKind::Integer {
+                                min: Some(1),
+                                max: None,
+                            },
+                        )
+                        .executes(|ctx| {
+                            let count = ctx.args.integer("count")?;
+                            give(ctx, count as u32)
+                        }),
+                    ),
+            ),
+        ),
+    );
+}
+
+fn give(ctx: &mut CommandCtx, count: u32) -> anyhow::Result<()> {
+    let item = ctx.args.item("item")?;
+    let targets = ctx.args.entities("targets")?;
+    for &target in &targets {
+        if let Ok(inventory) = ctx.game.ecs.get::<base::Inventory>(target) {
+            let mut stack = ItemStack::new(item, count);
+            window::insert_item(&inventory, &mut stack);
+            if stack.count() > 0 {
+                log::debug!(
+                    "Discarding {} {} which didn't fit in the inventory",
+                    stack.count(),
+                    item.name()
+                );
+            }
+        }
+        ctx.game
+            .ecs
+            .insert_entity_event(target, InventoryUpdateEvent)?;
+    }
+
+    let item_name = format!("[{}]", item.name());
+    let message = match targets.as_slice() {
+        [target] => Text::translate_with(
+            "commands.give.success.single",
+            vec![
+                count.to_string(),
+                item_name,
+                display_name(ctx.game, *target),
+            ],
+        ),
+        _ => Text::translate_with(
+            "commands.give.success.multiple",
+            vec![count.to_string(), item_name, targets.len().to_string()],
+        ),
+    };
+    ctx.send_message(message);
+    Ok(())
+}
+
+fn register_time(dispatcher: &mut CommandDispatcher) {
+    let mut set = literal("set").then(argument("time", ArgumentKind::Time).executes(|ctx| {
+        let time = ctx.args.time("time")?;
+        set_time(ctx, time as i64)
+    }));
+    for &(name, time) in &[
+        ("day", 1000),
+        ("noon", 6000),
+        ("night", 13000),
+        ("midnight", 18000),
+    ] {
+        set = set.then(literal(name).executes(move |ctx| set_time(ctx, time)));
+    }
+
+    dispatcher.register(
+        literal("time")
+            .requires(2)
+            .then(set)
+            .then(
+                literal("add").then(argument("time", ArgumentKind::Time).executes(|ctx| {
+                    let time = ctx.args.time("time")?;
+                    let day_time = ctx.game.world.level().day_time;
+                    set_time(ctx, day_time + time as i64)
+                })),
+            )
+            .then(
+                literal("query")
+                    .then(literal("daytime").executes(|ctx| {
+                        let time = ctx.game.world.level().day_time % 24000;
+                        query_time(ctx, time)
+                    }))
+                    .then(literal("gametime").executes(|ctx| {
+                        let time = ctx.game.world.level().time % i32::MAX as i64;
+                        query_time(ctx, time)
+                    }))
+                    .then(literal("day").executes(|ctx| {
+                        let day = ctx.game.world.level().day_time / 24000 % i32::MAX as i64;
+                        query_time(ctx, day)
+                    })),
+            ),
+    );
+}
+
+/// Sets the time of day. The new time is sent to
+/// players with the next periodic time update.
+fn set_time(ctx: &mut CommandCtx, day_time: i64) -> anyhow::Result<()> {
+    ctx.game.world.level_mut().day_time = day_time;
+    ctx.send_message(Text::translate_with(
+        "commands.time.set",
+        vec![(day_time % 24000).to_string()],
+    ));
+    Ok(())
+}
+
+fn query_time(ctx: &mut CommandCtx, time: i64) -> anyhow::Result<()> {
+    ctx.send_message(Text::translate_with(
+        "commands.time.query",
+        vec![time.to_string()],
+    ));
+    Ok(())
+}
+
+fn register_weather(dispatcher: &mut CommandDispatcher) {
+    let mut weather = literal("weather").requires(2);
+    for &weather_kind in &[Weather::Clear, Weather::Rain, Weather::Thunder] {
+        weather = weather.then(
+            literal(weather_kind.name())
+                .executes(move |ctx| set_weather(ctx, weather_kind, DEFAULT_WEATHER_DURATION * 20))
+                .then(
+                    argument(
+                        "duration",
+                        ArgumentKind::Integer {
+                            min: Some(0),
+                            max: Some(1_000_000),
+                        },
+                    )
+                    .executes(move |ctx| {
+                        let duration = ctx.args.integer("duration")?;
+                        set_weather(ctx, weather_kind, duration * 20)
+                    }),
+                ),
+        );
+    }
+    dispatcher.register(weather);
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Weather {
+    Clear,
+    Rain,
+    Thunder,
+}
+
+impl Weather {
+    fn name(self) -> &'static str {
+        match self {
+            Weather::Clear => "clear",
+            Weather::Rain => "rain",
+            Weather::Thunder => "thunder",
+        }
+    }
+}
+
+/// Sets the weather for `duration` ticks.
+fn set_weather(ctx: &mut CommandCtx, weather: Weather, duration: i32) -> anyhow::Result<()> {
+    let level = ctx.game.world.level_mut();
+    match weather {
+        Weather::Clear => {
+            level.clear_weather_time = duration;
+            level.rain_time = 0;
+            level.thunder_time = 0;
+        }
+        Weather::Rain | Weather::Thunder => {
+            level.clear_weather_time = 0;
+            level.rain_time = duration;
+            level.thunder_time = duration;
+        }
+    }
+    level.raining = weather != Weather::Clear;
+    level.thundering = weather == Weather::Thunder;
+
+    let event = WeatherChangeEvent {
+        raining: level.raining,
+        thundering: level.thundering,
+    };
+    ctx.game.ecs.insert_event(event);
+
+    let key = match weather {
+        Weather::Clear => "commands.weather.set.clear",
+        Weather::Rain => "commands.weather.set.rain",
+        Weather::Thunder => "commands.weather.set.thunder",
+    };
+    ctx.send_message(Text::translate_with(key, Vec::<Text>::new()));
+    Ok(())
+}
+
+/// Returns the name shown for an entity in command feedback.
+fn display_name(game: &Game, entity: Entity) -> String {
+    if let Ok(name) = game.ecs.get::<Name>(entity) {
+        return name.to_string();
+    }
+    match game.ecs.get::<EntityKind>(entity) {
+        Ok(kind) => kind.name().to_owned(),
+        Err(_) => "Server".to_owned(),
+    }
+}
+
+fn send_system_message(game: &mut Game, entity: Entity, message: Text) {
+    if let Ok(mut chat_box) = game.ecs.get_mut::<ChatBox>(entity) {
+        chat_box.send_system(message);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use base::Item;
+    use quill_common::events::GamemodeChangeEvent;
+
+    use crate::commands::{execute_command, PermissionLevel};
+
+    use super::*;
+
+    fn game() -> (Game, Entity) {
+        let mut game = Game::new();
+        let mut dispatcher = CommandDispatcher::new();
+        register(&mut dispatcher);
+        game.insert_resource(dispatcher);
+
+        let op = game.ecs.spawn((
+            Name::new("op"),
+            PermissionLevel(4),
+            Position::default(),
+            Gamemode::Survival,
+            base::Inventory::player(),
+        ));
+        (game, op)
+    }
+
+    #[test]
+    fn teleport_to_location() {
+        let (mut game, op) = game();
+        execute_command(&mut game, op, "tp 10 ~5 -3.5").unwrap();
+
+        let position = *game.ecs.get::<Position>(op).unwrap();
+        assert_eq!(position.x, 10.0);
+        assert_eq!(position.y, Position::default().y + 5.0);
+        assert_eq!(position.z, -3.5);
+    }
+
+    #[test]
+    fn gamemode_and_give() {
+        let (mut game, op) = game();
+        execute_command(&mut game, op, "gamemode creative op").unwrap();
+        assert_eq!(*game.ecs.get::<Gamemode>(op).unwrap(), Gamemode::Creative);
+        assert_eq!(
+            game.ecs.get::<GamemodeChangeEvent>(op).unwrap().previous,
+            Gamemode::Survival
+        );
+
+        execute_command(&mut game, op, "give @s minecraft:diamond 3").unwrap();
+        let inventory = game.ecs.get::<base::Inventory>(op).unwrap();
+        assert_eq!(
+            *inventory.item(base::Area::Hotbar, 0).unwrap(),
+            Some(ItemStack::new(Item::Diamond, 3))
+        );
+    }
+
+    #[test]
+    fn time_and_weather() {
+        let (mut game, op) = game();
+        execute_command(&mut game, op, "time set night").unwrap();
+        assert_eq!(game.world.level().day_time, 13000);
+        execute_command(&mut game, op, "time add 1d").unwrap();
+        assert_eq!(game.world.level().day_time, 37000);
+
+        execute_command(&mut game, op, "weather thunder 10").unwrap();
+        let level = game.world.level();
+        assert!(level.raining && level.thundering);
+        assert_eq!(level.thunder_time, 200);
+    }
+}
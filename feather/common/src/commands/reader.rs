@@ -0,0 +1,120 @@
+use anyhow::bail;
+
+/// A cursor over command input.
+#[derive(Debug, Clone)]
+pub struct StringReader<'a> {
+    input: &'a str,
+    cursor: usize,
+}
+
+impl<'a> StringReader<'a> {
+    pub fn new(input: &'a str) -> Self {
+        Self { input, cursor: 0 }
+    }
+
+    pub fn with_cursor(input: &'a str, cursor: usize) -> Self {
+        Self { input, cursor }
+    }
+
+    /// Returns the full input.
+    pub fn input(&self) -> &'a str {
+        self.input
+    }
+
+    /// Returns the byte offset of the cursor into the input.
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    pub fn set_cursor(&mut self, cursor: usize) {
+        self.cursor = cursor;
+    }
+
+    /// Returns the input which has not been read yet.
+    pub fn remaining(&self) -> &'a str {
+        &self.input[self.cursor..]
+    }
+
+    pub fn can_read(&self) -> bool {
+        self.cursor < self.input.len()
+    }
+
+    pub fn peek(&self) -> Option<char> {
+        self.remaining().chars().next()
+    }
+
+    pub fn skip(&mut self) {
+        if let Some(c) = self.peek() {
+            self.cursor += c.len_utf8();
+        }
+    }
+
+    /// Reads until the next space or the end of input.
+    pub fn read_unquoted(&mut self) -> &'a str {
+        let remaining = self.remaining();
+        let end = remaining.find(' ').unwrap_or_else(|| remaining.len());
+        self.cursor += end;
+        &remaining[..end]
+    }
+
+    /// Reads a string which may be surrounded by double quotes.
+    /// Inside quotes, `\"` and `\\` are escapes.
+    pub fn read_string(&mut self) -> anyhow::Result<String> {
+        if self.peek() != Some('"') {
+            return Ok(self.read_unquoted().to_owned());
+        }
+        self.skip();
+
+        let mut result = String::new();
+        let mut escaped = false;
+        while let Some(c) = self.peek() {
+            self.skip();
+            if escaped {
+                if c != '"' && c != '\\' {
+                    bail!("Invalid escape sequence '\\{}' in quoted string", c);
+                }
+                result.push(c);
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == '"' {
+                return Ok(result);
+            } else {
+                result.push(c);
+            }
+        }
+        bail!("Unclosed quoted string")
+    }
+
+    /// Reads the rest of the input.
+    pub fn read_remaining(&mut self) -> &'a str {
+        let remaining = self.remaining();
+        self.cursor = self.input.len();
+        remaining
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_words() {
+        let mut reader = StringReader::new("tp player 10");
+        assert_eq!(reader.read_unquoted(), "tp");
+        reader.skip();
+        assert_eq!(reader.read_unquoted(), "player");
+        reader.skip();
+        assert_eq!(reader.read_remaining(), "10");
+        assert!(!reader.can_read());
+    }
+
+    #[test]
+    fn read_quoted_string() {
+        let mut reader = StringReader::new(r#""hello \"world\"" rest"#);
+        assert_eq!(reader.read_string().unwrap(), r#"hello "world""#);
+        assert_eq!(reader.remaining(), " rest");
+
+        assert!(StringReader::new("\"unclosed").read_string().is_err());
+    }
+}
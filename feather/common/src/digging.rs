@@ -0,0 +1,265 @@
+//! Block breaking: how long a block takes to dig
+//! and which item it drops.
+//!
+//! Break times follow the vanilla formula, based on the
+//! block's hardness, the held tool and its enchantments.
+
+use base::{
+    position, Area, BlockId, BlockPosition, EnchantmentKind, Inventory, Item, ItemStack, Position,
+};
+use blocks::BlockKind;
+use ecs::{Entity, SysResult};
+use quill_common::components::OnGround;
+
+use crate::{entities::player::held_item, Game};
+
+/// The height of a player's eyes above their feet.
+pub const PLAYER_EYE_HEIGHT: f64 = 1.62;
+
+/// The fraction of a block's break time that must have passed
+/// before a player may finish digging it. Like vanilla, this
+/// leaves room for latency between the client and server.
+pub const BREAK_TIME_TOLERANCE: f32 = 0.7;
+
+/// Component storing the block a player started digging.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Digging {
+    pub position: BlockPosition,
+    /// The tick on which the player started digging.
+    pub start_tick: u64,
+}
+
+/// The state of a player which affects how fast they dig.
+#[derive(Copy, Clone, Debug)]
+pub struct DigConditions<'a> {
+    /// The item stack the player is holding.
+    pub tool: Option<&'a ItemStack>,
+    pub on_ground: bool,
+    /// Whether the player's head is underwater.
+    pub in_water: bool,
+    /// Whether the player's helmet has Aqua Affinity.
+    pub aqua_affinity: bool,
+}
+
+/// Returns the number of ticks it takes to break `block`,
+/// or `None` if it cannot be broken. Returns 0 if the
+/// block breaks instantly.
+pub fn break_ticks(block: BlockId, conditions: &DigConditions) -> Option<u32> {
+    let kind = block.kind();
+    let hardness = kind.hardness();
+    if !kind.diggable() || hardness < 0.0 {
+        return None;
+    }
+
+    let tool = conditions.tool;
+    let mut speed = tool
+        .and_then(|tool| {
+            kind.dig_multipliers()
+                .iter()
+                .find(|(item, _)| *item == tool.item)
+        })
+        .map(|&(_, multiplier)| multiplier)
+        .unwrap_or(1.0);
+
+    if speed > 1.0 {
+        let efficiency = enchantment_level(tool, EnchantmentKind::Efficiency);
+        if efficiency > 0 {
+            speed += (efficiency * efficiency + 1) as f32;
+        }
+    }
+
+    // Vanilla divides the speed by these factors; multiplying
+    // the tick count instead avoids rounding errors.
+    let mut slowdown = if can_harvest(block, tool) {
+        30.0
+    } else {
+        100.0
+    };
+    if conditions.in_water && !conditions.aqua_affinity {
+        slowdown *= 5.0;
+    }
+    if !conditions.on_ground {
+        slowdown *= 5.0;
+    }
+
+    let ticks = hardness * slowdown / speed;
+    if ticks <= 1.0 {
+        Some(0)
+    } else {
+        Some(ticks.ceil() as u32)
+    }
+}
+
+/// Returns the number of ticks it takes `player` to break `block`
+/// with the item they are holding. See [`break_ticks`].
+pub fn player_break_ticks(game: &Game, player: Entity, block: BlockId) -> SysResult<Option<u32>> {
+    let tool = held_item(game, player)?;
+    let on_ground = game.ecs.get::<OnGround>(player)?.0;
+    let position = *game.ecs.get::<Position>(player)?;
+    let eye_position = position!(position.x, position.y + PLAYER_EYE_HEIGHT, position.z);
+    let in_water = game
+        .block(eye_position.block())
+        .map_or(false, |block| block.kind() == BlockKind::Water);
+    let aqua_affinity = game
+        .ecs
+        .get::<Inventory>(player)?
+        .item(Area::Helmet, 0)
+        .map_or(false, |helmet| {
+            enchantment_level(helmet.as_ref(), EnchantmentKind::AquaAffinity) > 0
+        });
+
+    Ok(break_ticks(
+        block,
+        &DigConditions {
+            tool: tool.as_ref(),
+            on_ground,
+            in_water,
+            aqua_affinity,
+        },
+    ))
+}
+
+/// Returns whether `block` drops an item when broken with `tool`.
+pub fn can_harvest(block: BlockId, tool: Option<&ItemStack>) -> bool {
+    match block.kind().harvest_tools() {
+        Some(tools) => tool.map_or(false, |tool| tools.contains(&tool.item)),
+        None => true,
+    }
+}
+
+/// Returns the item dropped when `block` is broken with `tool`.
+///
+/// Loot tables are not supported yet. Blocks drop their own item,
+/// except for a few common blocks which drop another item or nothing.
+pub fn block_drop(block: BlockId, tool: Option<&ItemStack>) -> Option<ItemStack> {
+    if !can_harvest(block, tool) {
+        return None;
+    }
+
+    let kind = block.kind();
+    let silk_touch = enchantment_level(tool, EnchantmentKind::SilkTouch) > 0;
+    let item = if silk_touch {
+        Item::from_name(kind.name())
+    } else {
+        dropped_item(kind)
+    };
+
+    item.filter(|&item| item != Item::Air)
+        .map(|item| ItemStack::new(item, 1))
+}
+
+/// Spawns the item dropped by `block`, which was broken
+/// at `position` with `tool`, in the center of the block.
+pub fn drop_block_item(
+    game: &mut Game,
+    position: BlockPosition,
+    block: BlockId,
+    tool: Option<&ItemStack>,
+) {
+    if let Some(item) = block_drop(block, tool) {
+        let center = position!(
+            position.x as f64 + 0.5,
+            position.y as f64 + 0.5,
+            position.z as f64 + 0.5,
+        );
+        game.drop_item(center, item);
+    }
+}
+
+fn dropped_item(kind: BlockKind) -> Option<Item> {
+    match kind {
+        BlockKind::Stone => Some(Item::Cobblestone),
+        BlockKind::GrassBlock
+        | BlockKind::Podzol
+        | BlockKind::Mycelium
+        | BlockKind::GrassPath
+        | BlockKind::Farmland => Some(Item::Dirt),
+        BlockKind::CoalOre => Some(Item::Coal),
+        BlockKind::DiamondOre => Some(Item::Diamond),
+        BlockKind::EmeraldOre => Some(Item::Emerald),
+        BlockKind::NetherQuartzOre => Some(Item::Quartz),
+        BlockKind::Bookshelf => Some(Item::Book),
+        BlockKind::Clay => Some(Item::ClayBall),
+        BlockKind::WallTorch => Some(Item::Torch),
+        BlockKind::RedstoneWallTorch => Some(Item::RedstoneTorch),
+        BlockKind::SoulWallTorch => Some(Item::SoulTorch),
+        BlockKind::Glass
+        | BlockKind::GlassPane
+        | BlockKind::Ice
+        | BlockKind::Grass
+        | BlockKind::TallGrass
+        | BlockKind::Fern
+        | BlockKind::LargeFern
+        | BlockKind::OakLeaves
+        | BlockKind::SpruceLeaves
+        | BlockKind::BirchLeaves
+        | BlockKind::JungleLeaves
+        | BlockKind::AcaciaLeaves
+        | BlockKind::DarkOakLeaves => None,
+        kind => Item::from_name(kind.name()),
+    }
+}
+
+fn enchantment_level(stack: Option<&ItemStack>, kind: EnchantmentKind) -> u32 {
+    stack.map_or(0, |stack| stack.enchantment_level(kind))
+}
+
+#[cfg(test)]
+mod tests {
+    use base::Enchantment;
+
+    use super::*;
+
+    fn conditions(tool: Option<&ItemStack>) -> DigConditions {
+        DigConditions {
+            tool,
+            on_ground: true,
+            in_water: false,
+            aqua_affinity: false,
+        }
+    }
+
+    #[test]
+    fn break_ticks_match_vanilla() {
+        let pickaxe = ItemStack::new(Item::DiamondPickaxe, 1);
+        assert_eq!(break_ticks(BlockId::stone(), &conditions(None)), Some(150));
+        assert_eq!(
+            break_ticks(BlockId::stone(), &conditions(Some(&pickaxe))),
+            Some(6)
+        );
+        assert_eq!(break_ticks(BlockId::bedrock(), &conditions(None)), None);
+        assert_eq!(break_ticks(BlockId::grass(), &conditions(None)), Some(0));
+    }
+
+    #[test]
+    fn efficiency_and_conditions_affect_break_ticks() {
+        let mut pickaxe = ItemStack::new(Item::DiamondPickaxe, 1);
+        pickaxe
+            .enchantments
+            .push(Enchantment::new(EnchantmentKind::Efficiency, 5));
+        assert_eq!(
+            break_ticks(BlockId::stone(), &conditions(Some(&pickaxe))),
+            Some(2)
+        );
+
+        let airborne = DigConditions {
+            on_ground: false,
+            ..conditions(None)
+        };
+        assert_eq!(break_ticks(BlockId::dirt(), &airborne), Some(75));
+    }
+
+    #[test]
+    fn drops_require_the_right_tool() {
+        let pickaxe = ItemStack::new(Item::WoodenPickaxe, 1);
+        assert_eq!(block_drop(BlockId::stone(), None), None);
+        assert_eq!(
+            block_drop(BlockId::stone(), Some(&pickaxe)),
+            Some(ItemStack::new(Item::Cobblestone, 1))
+        );
+        assert_eq!(
+            block_drop(BlockId::dirt(), None),
+            Some(ItemStack::new(Item::Dirt, 1))
+        );
+    }
+}
@@ -5,12 +5,18 @@
 //! add default components for that entity.
 
 use ecs::EntityBuilder;
-use quill_common::{components::OnGround, entity_init::EntityInit};
+use quill_common::{
+    components::{OnGround, Velocity},
+    entity_init::EntityInit,
+};
 use uuid::Uuid;
 
 /// Adds default components shared between all entities.
 fn build_default(builder: &mut EntityBuilder) {
-    builder.add(Uuid::new_v4()).add(OnGround(true));
+    builder
+        .add(Uuid::new_v4())
+        .add(OnGround(true))
+        .add(Velocity::default());
 }
 
 pub mod area_effect_cloud;
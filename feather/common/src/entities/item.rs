@@ -1,8 +1,375 @@
-use base::EntityKind;
-use ecs::EntityBuilder;
-use quill_common::entities::Item;
+//! Item entities: dropped item stacks which can be picked up
+//! by players, merge with nearby stacks and eventually despawn.
+
+use base::{position, EntityKind, Gamemode, Inventory, ItemStack, Position};
+use ecs::{Entity, EntityBuilder, SysResult, SystemExecutor};
+use quill_common::{components::Velocity, entities::Item};
+
+use crate::{
+    digging::PLAYER_EYE_HEIGHT,
+    events::{InventoryUpdateEvent, ItemPickupEvent, ItemStackChangeEvent},
+    health::Dead,
+    window, Game,
+};
+
+/// The number of ticks before a newly dropped item can be picked up.
+pub const DEFAULT_PICKUP_DELAY: u32 = 10;
+
+/// The number of ticks before an item thrown by a player can be picked up.
+pub const THROWN_PICKUP_DELAY: u32 = 40;
+
+/// The speed of items thrown by players, in blocks per tick.
+const THROW_SPEED: f64 = 0.3;
+
+/// The age in ticks at which item entities despawn (five minutes).
+pub const DESPAWN_AGE: u32 = 6000;
+
+/// The interval in ticks between attempts to merge nearby items.
+const MERGE_INTERVAL: u64 = 10;
+
+/// The maximum horizontal and vertical distances between
+/// two item entities which merge.
+const MERGE_DISTANCE: (f64, f64) = (0.75, 0.25);
+
+/// The maximum horizontal distance between a player
+/// and an item they pick up.
+const PICKUP_DISTANCE: f64 = 1.425;
+
+/// The height of a player, used to check whether
+/// an item is within their reach vertically.
+const PLAYER_HEIGHT: f64 = 1.8;
+
+/// Component storing the number of ticks
+/// before an item entity can be picked up.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct PickupDelay(pub u32);
+
+/// Component storing the number of ticks
+/// an item entity has existed for.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct ItemAge(pub u32);
 
 pub fn build_default(builder: &mut EntityBuilder) {
     super::build_default(builder);
-    builder.add(Item).add(EntityKind::Item);
+    builder
+        .add(Item)
+        .add(EntityKind::Item)
+        .add(PickupDelay(DEFAULT_PICKUP_DELAY))
+        .add(ItemAge::default());
+}
+
+pub fn register(systems: &mut SystemExecutor<Game>) {
+    systems
+        .add_system(age_items)
+        .add_system(merge_items)
+        .add_system(pick_up_items);
+}
+
+/// Spawns an item entity holding `item` in front of `player`,
+/// thrown in the direction they are looking. It cannot be
+/// picked up for [`THROWN_PICKUP_DELAY`] ticks.
+pub fn throw_item(game: &mut Game, player: Entity, item: ItemStack) -> SysResult<Entity> {
+    let position = *game.ecs.get::<Position>(player)?;
+    let yaw = (position.yaw as f64).to_radians();
+    let pitch = (position.pitch as f64).to_radians();
+    let velocity = Velocity::new(
+        -yaw.sin() * pitch.cos() * THROW_SPEED,
+        -pitch.sin() * THROW_SPEED + 0.1,
+        yaw.cos() * pitch.cos() * THROW_SPEED,
+    );
+
+    let position = position!(position.x, position.y + PLAYER_EYE_HEIGHT - 0.3, position.z);
+    let entity = game.drop_item(position, item);
+    game.ecs.insert(entity, PickupDelay(THROWN_PICKUP_DELAY))?;
+    game.ecs.insert(entity, velocity)?;
+    Ok(entity)
+}
+
+/// Counts down pickup delays and despawns old items.
+fn age_items(game: &mut Game) -> SysResult {
+    let mut despawned = Vec::new();
+    for (entity, (age, delay)) in game.ecs.query::<(&mut ItemAge, &mut PickupDelay)>().iter() {
+        age.0 += 1;
+        delay.0 = delay.0.saturating_sub(1);
+        if age.0 >= DESPAWN_AGE {
+            despawned.push(entity);
+        }
+    }
+
+    for entity in despawned {
+        game.remove_entity(entity)?;
+    }
+    Ok(())
+}
+
+/// Merges item stacks of the same type lying close to each other.
+/// The smaller stack moves into the larger one.
+fn merge_items(game: &mut Game) -> SysResult {
+    if game.tick_count % MERGE_INTERVAL != 0 {
+        return Ok(());
+    }
+
+    let mut items: Vec<(Entity, Position, ItemStack)> = game
+        .ecs
+        .query::<(&Position, &ItemStack, &ItemAge)>()
+        .iter()
+        .filter(|(_, (_, stack, _))| stack.count() > 0)
+        .map(|(entity, (&position, stack, _))| (entity, position, stack.clone()))
+        .collect();
+
+    let mut changed = Vec::new();
+    let mut emptied = Vec::new();
+    for i in 0..items.len() {
+        for j in i + 1..items.len() {
+            let (left, right) = items.split_at_mut(j);
+            let (a, b) = (&mut left[i], &mut right[0]);
+            if a.2.count() == 0
+                || b.2.count() == 0
+                || !a.2.has_same_type(&b.2)
+                || !within(a.1, b.1, MERGE_DISTANCE.0, MERGE_DISTANCE.1)
+            {
+                continue;
+            }
+
+            let (target, source) = if a.2.count() >= b.2.count() {
+                (a, b)
+            } else {
+                (b, a)
+            };
+            let count = target.2.count();
+            target.2.merge_with(&mut source.2);
+            if target.2.count() != count {
+                changed.push(target.0);
+                if source.2.count() == 0 {
+                    emptied.push(source.0);
+                } else {
+                    changed.push(source.0);
+                }
+            }
+        }
+    }
+
+    for (entity, _, stack) in items {
+        if emptied.contains(&entity) {
+            // The entity only despawns at the end of the tick, so store
+            // its empty stack to prevent it from being picked up meanwhile.
+            game.ecs.insert(entity, stack)?;
+            game.remove_entity(entity)?;
+        } else if changed.contains(&entity) {
+            game.ecs.insert(entity, stack)?;
+            game.ecs.insert_entity_event(entity, ItemStackChangeEvent)?;
+        }
+    }
+    Ok(())
+}
+
+/// Moves items into the inventories of living players
+/// standing close to them.
+fn pick_up_items(game: &mut Game) -> SysResult {
+    let players: Vec<(Entity, Position)> = game
+        .ecs
+        .query::<(&Position, &Gamemode, &Inventory, Option<&Dead>)>()
+        .iter()
+        .filter(|(_, (_, &gamemode, _, dead))| gamemode != Gamemode::Spectator && dead.is_none())
+        .map(|(player, (&position, _, _, _))| (player, position))
+        .collect();
+    if players.is_empty() {
+        return Ok(());
+    }
+
+    let mut pickups = Vec::new();
+    for (item, (stack, &position, delay)) in game
+        .ecs
+        .query::<(&mut ItemStack, &Position, &PickupDelay)>()
+        .iter()
+    {
+        if delay.0 > 0 || stack.count() == 0 {
+            continue;
+        }
+
+        for &(player, player_position) in &players {
+            if !can_pick_up(player_position, position) {
+                continue;
+            }
+
+            let count = stack.count();
+            let inventory = game.ecs.get::<Inventory>(player)?;
+            window::insert_item(&inventory, stack);
+            if stack.count() != count {
+                pickups.push((item, player, count - stack.count(), stack.count() == 0));
+                break;
+            }
+        }
+    }
+
+    for (item, player, count, empty) in pickups {
+        game.ecs.insert_entity_event(player, InventoryUpdateEvent)?;
+        game.ecs.insert_entity_event(
+            item,
+            ItemPickupEvent {
+                collector: player,
+                count,
+            },
+        )?;
+        if empty {
+            game.remove_entity(item)?;
+        } else {
+            game.ecs.insert_entity_event(item, ItemStackChangeEvent)?;
+        }
+    }
+    Ok(())
+}
+
+/// Returns whether a player at `player` can reach an item at `item`.
+fn can_pick_up(player: Position, item: Position) -> bool {
+    let dy = item.y - player.y;
+    (item.x - player.x).abs() <= PICKUP_DISTANCE
+        && (item.z - player.z).abs() <= PICKUP_DISTANCE
+        && dy >= -0.5
+        && dy <= PLAYER_HEIGHT + 0.5
+}
+
+fn within(a: Position, b: Position, horizontal: f64, vertical: f64) -> bool {
+    (a.x - b.x).abs() <= horizontal
+        && (a.z - b.z).abs() <= horizontal
+        && (a.y - b.y).abs() <= vertical
+}
+
+#[cfg(test)]
+mod tests {
+    use base::{Area, Item};
+
+    use crate::{entities::add_entity_components, events::EntityRemoveEvent};
+
+    use super::*;
+
+    fn game() -> Game {
+        let mut game = Game::new();
+        game.add_entity_spawn_callback(add_entity_components);
+        game
+    }
+
+    fn spawn_item(game: &mut Game, position: Position, stack: ItemStack) -> Entity {
+        let entity = game.drop_item(position, stack);
+        game.ecs.insert(entity, PickupDelay(0)).unwrap();
+        entity
+    }
+
+    #[test]
+    fn nearby_stacks_merge_into_the_larger_one() {
+        let mut game = game();
+        let small = spawn_item(
+            &mut game,
+            position!(0.0, 64.0, 0.0),
+            ItemStack::new(Item::Stone, 3),
+        );
+        let large = spawn_item(
+            &mut game,
+            position!(0.5, 64.0, 0.0),
+            ItemStack::new(Item::Stone, 10),
+        );
+        let other = spawn_item(
+            &mut game,
+            position!(0.0, 64.0, 0.5),
+            ItemStack::new(Item::Dirt, 1),
+        );
+
+        merge_items(&mut game).unwrap();
+
+        assert_eq!(game.ecs.get::<ItemStack>(large).unwrap().count(), 13);
+        assert!(game.ecs.get::<EntityRemoveEvent>(small).is_ok());
+        assert!(game.ecs.get::<EntityRemoveEvent>(other).is_err());
+    }
+
+    #[test]
+    fn players_pick_up_items_into_their_inventory() {
+        let mut game = game();
+        let inventory = Inventory::player();
+        let player = game.ecs.spawn((
+            position!(0.0, 64.0, 0.0),
+            Gamemode::Survival,
+            inventory.new_handle(),
+        ));
+        let item = spawn_item(
+            &mut game,
+            position!(1.0, 64.0, 0.0),
+            ItemStack::new(Item::Diamond, 5),
+        );
+        let far = spawn_item(
+            &mut game,
+            position!(5.0, 64.0, 0.0),
+            ItemStack::new(Item::Diamond, 5),
+        );
+
+        pick_up_items(&mut game).unwrap();
+
+        assert_eq!(
+            inventory.item(Area::Hotbar, 0).unwrap().clone(),
+            Some(ItemStack::new(Item::Diamond, 5))
+        );
+        assert_eq!(game.ecs.get::<ItemPickupEvent>(item).unwrap().count, 5);
+        assert_eq!(
+            game.ecs.get::<ItemPickupEvent>(item).unwrap().collector,
+            player
+        );
+        assert!(game.ecs.get::<EntityRemoveEvent>(item).is_ok());
+        assert!(game.ecs.get::<EntityRemoveEvent>(far).is_err());
+    }
+
+    #[test]
+    fn merged_items_are_picked_up_once() {
+        let mut game = game();
+        let inventory = Inventory::player();
+        game.ecs.spawn((
+            position!(0.0, 64.0, 0.0),
+            Gamemode::Survival,
+            inventory.new_handle(),
+        ));
+        spawn_item(
+            &mut game,
+            position!(0.5, 64.0, 0.0),
+            ItemStack::new(Item::Stone, 3),
+        );
+        spawn_item(
+            &mut game,
+            position!(1.0, 64.0, 0.0),
+            ItemStack::new(Item::Stone, 10),
+        );
+
+        merge_items(&mut game).unwrap();
+        pick_up_items(&mut game).unwrap();
+
+        assert_eq!(
+            inventory.item(Area::Hotbar, 0).unwrap().clone(),
+            Some(ItemStack::new(Item::Stone, 13))
+        );
+        assert!(inventory.item(Area::Hotbar, 1).unwrap().is_none());
+    }
+
+    #[test]
+    fn pickup_delay_prevents_pickup() {
+        let mut game = game();
+        let inventory = Inventory::player();
+        game.ecs.spawn((
+            position!(0.0, 64.0, 0.0),
+            Gamemode::Survival,
+            inventory.new_handle(),
+        ));
+        game.drop_item(position!(0.0, 64.0, 0.0), ItemStack::new(Item::Diamond, 1));
+
+        pick_up_items(&mut game).unwrap();
+
+        assert!(inventory.item(Area::Hotbar, 0).unwrap().is_none());
+    }
+
+    #[test]
+    fn items_despawn_after_five_minutes() {
+        let mut game = game();
+        let item = game.drop_item(position!(0.0, 64.0, 0.0), ItemStack::new(Item::Stone, 1));
+        game.ecs.insert(item, ItemAge(DESPAWN_AGE - 1)).unwrap();
+
+        age_items(&mut game).unwrap();
+
+        assert!(game.ecs.get::<EntityRemoveEvent>(item).is_ok());
+    }
 }
@@ -1,17 +1,30 @@
 use anyhow::bail;
-use base::EntityKind;
-use ecs::{EntityBuilder, SysResult};
+use base::{Area, EntityKind, Inventory, ItemStack, Position};
+use ecs::{Entity, EntityBuilder, SysResult};
 use quill_common::{
-    components::{CreativeFlying, Sneaking},
+    components::{CreativeFlying, Sneaking, Sprinting},
     entities::Player,
 };
 
+use crate::{
+    entities::item::throw_item,
+    health::{DamageCooldown, FallDistance, Food, Health},
+    movement::MovementTracker,
+    Game,
+};
+
 pub fn build_default(builder: &mut EntityBuilder) {
     super::build_default(builder);
     builder
         .add(Player)
         .add(CreativeFlying(false))
         .add(Sneaking(false))
+        .add(Sprinting(false))
+        .add(MovementTracker::default())
+        .add(Health::default())
+        .add(Food::default())
+        .add(FallDistance::default())
+        .add(DamageCooldown::default())
         .add(EntityKind::Player);
 }
 
@@ -41,3 +54,71 @@ impl HotbarSlot {
         Ok(())
     }
 }
+
+/// Returns the item stack in the hotbar slot
+/// the player has selected.
+pub fn held_item(game: &Game, player: Entity) -> SysResult<Option<ItemStack>> {
+    let slot = game.ecs.get::<HotbarSlot>(player)?.get();
+    let inventory = game.ecs.get::<Inventory>(player)?;
+    let item = inventory
+        .item(Area::Hotbar, slot)
+        .and_then(|item| item.clone());
+    Ok(item)
+}
+
+/// Drops the item stack in the hotbar slot the player has
+/// selected: the whole stack if `whole_stack` is set,
+/// otherwise a single item.
+pub fn drop_held_item(game: &mut Game, player: Entity, whole_stack: bool) -> SysResult {
+    let slot = game.ecs.get::<HotbarSlot>(player)?.get();
+    let dropped = {
+        let inventory = game.ecs.get::<Inventory>(player)?;
+        let mut held = match inventory.item(Area::Hotbar, slot) {
+            Some(held) => held,
+            None => return Ok(()),
+        };
+        let dropped = match held.as_mut() {
+            Some(stack) if whole_stack => stack.take(stack.count()),
+            Some(stack) => stack.take(1),
+            None => return Ok(()),
+        };
+        if held.as_ref().map_or(false, |stack| stack.count() == 0) {
+            *held = None;
+        }
+        dropped
+    };
+
+    throw_item(game, player, dropped)?;
+    Ok(())
+}
+
+/// Drops all items in a player's inventory
+/// where they stand, e.g. when they die.
+pub fn drop_inventory(game: &mut Game, player: Entity) -> SysResult {
+    let position = *game.ecs.get::<Position>(player)?;
+    let mut stacks = Vec::new();
+    {
+        let inventory = game.ecs.get::<Inventory>(player)?;
+        for &area in &[
+            Area::Hotbar,
+            Area::Storage,
+            Area::Helmet,
+            Area::Chestplate,
+            Area::Leggings,
+            Area::Boots,
+            Area::Offhand,
+            Area::CraftingInput,
+        ] {
+            let mut i = 0;
+            while let Some(mut slot) = inventory.item(area, i) {
+                stacks.extend(slot.take());
+                i += 1;
+            }
+        }
+    }
+
+    for stack in stacks {
+        game.drop_item(position, stack);
+    }
+    Ok(())
+}
@@ -1,19 +1,19 @@
 use std::sync::Arc;
 
-use base::{Chunk, ChunkPosition};
+use base::{Chunk, ChunkPosition, Text};
+use ecs::Entity;
 use parking_lot::RwLock;
 
-use crate::view::View;
+use crate::{health::DamageCause, view::View};
 
 mod block_change;
 mod plugin_message;
 
 pub use block_change::BlockChangeEvent;
 pub use plugin_message::PluginMessageEvent;
-
-/// Triggered when a player joins the `Game`.
-#[derive(Debug)]
-pub struct PlayerJoinEvent;
+pub use quill_common::events::{
+    ChatEvent, EntityCreateEvent, EntityRemoveEvent, PlayerJoinEvent, PlayerLeaveEvent,
+};
 
 /// Event triggered when a player changes their `View`,
 /// meaning they crossed into a new chunk.
@@ -60,19 +60,110 @@ pub struct ChunkLoadEvent {
     pub chunk: Arc<RwLock<Chunk>>,
 }
 
+/// Triggered when the light in a loaded chunk changes.
+#[derive(Debug)]
+pub struct LightUpdateEvent {
+    pub position: ChunkPosition,
+    pub chunk: Arc<RwLock<Chunk>>,
+}
+
 /// Triggered when an error occurs while loading a chunk.
 #[derive(Debug)]
 pub struct ChunkLoadFailEvent {
     pub position: ChunkPosition,
 }
 
-/// Triggered when an entity is removed from the world.
+/// Triggered when the world is autosaved.
+///
+/// Systems persisting other data (e.g. player data)
+/// should save it when this event is observed.
+#[derive(Debug)]
+pub struct AutosaveEvent;
+
+/// Triggered when a player is kicked from the server.
+///
+/// The server disconnects the player with the given reason.
+#[derive(Debug)]
+pub struct PlayerKickEvent {
+    pub reason: Text,
+}
+
+/// Triggered when the [`PermissionLevel`](crate::commands::PermissionLevel)
+/// of an entity changes.
+#[derive(Debug)]
+pub struct PermissionLevelChangeEvent;
+
+/// Triggered when commands are registered or unregistered
+/// after the server started, e.g. when a plugin is reloaded.
+#[derive(Debug)]
+pub struct CommandsChangeEvent;
+
+/// Triggered when the contents of a player's inventory are
+/// changed by the server rather than through the player's window,
+/// e.g. by the `/give` command.
+///
+/// The server sends the new contents to the player.
+#[derive(Debug)]
+pub struct InventoryUpdateEvent;
+
+/// Triggered on an item entity when a player picks up
+/// some of its items.
+///
+/// If the whole stack was picked up, the item entity
+/// is removed in the same tick.
+#[derive(Debug)]
+pub struct ItemPickupEvent {
+    /// The player who picked up the items.
+    pub collector: Entity,
+    /// The number of items picked up.
+    pub count: u32,
+}
+
+/// Triggered on an item entity when the size of its
+/// `ItemStack` changes, e.g. after merging with another item.
+#[derive(Debug)]
+pub struct ItemStackChangeEvent;
+
+/// Triggered when an entity takes damage.
+#[derive(Debug)]
+pub struct EntityDamageEvent {
+    pub cause: DamageCause,
+    /// The amount of health the entity lost.
+    pub amount: f32,
+}
+
+/// Triggered when the health or food level of an entity changes.
 ///
-/// The entity will remain alive for one tick after it is
-/// destroyed to allow systems to observe this event.
+/// The server sends the new values to the player.
 #[derive(Debug)]
-pub struct EntityRemoveEvent;
+pub struct HealthUpdateEvent;
 
-/// Triggered when an entity is added into the world.
+/// Triggered when an entity dies.
+///
+/// Dead players remain in the world, marked with
+/// [`Dead`](crate::health::Dead), until they respawn.
+/// Other entities are removed in the same tick.
+#[derive(Debug)]
+pub struct EntityDeathEvent {
+    pub cause: DamageCause,
+    /// The death message shown to players.
+    pub message: Text,
+}
+
+/// Triggered when a dead player respawns.
+#[derive(Debug)]
+pub struct PlayerRespawnEvent;
+
+/// Triggered when the weather changes.
+#[derive(Debug)]
+pub struct WeatherChangeEvent {
+    pub raining: bool,
+    pub thundering: bool,
+}
+
+/// Triggered when the server is asked to stop, e.g.
+/// by the `/stop` command.
+///
+/// The tick loop exits at the end of the tick.
 #[derive(Debug)]
-pub struct EntityCreateEvent;
+pub struct ShutdownEvent;
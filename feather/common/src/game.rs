@@ -1,16 +1,22 @@
 use std::{cell::RefCell, mem, rc::Rc, sync::Arc};
 
-use base::{BlockId, BlockPosition, ChunkPosition, Position, Text, Title};
+use base::{BlockId, BlockPosition, ChunkPosition, Gamemode, ItemStack, Position, Text, Title};
 use ecs::{
     Ecs, Entity, EntityBuilder, HasEcs, HasResources, NoSuchEntity, Resources, SysResult,
     SystemExecutor,
 };
-use quill_common::{entities::Player, entity_init::EntityInit};
+use quill_common::{
+    components::CreativeFlying,
+    entities::Player,
+    entity_init::EntityInit,
+    events::{BlockChangeEvent as PluginBlockChangeEvent, GamemodeChangeEvent},
+};
 
 use crate::{
     chat::{ChatKind, ChatMessage},
     chunk_entities::ChunkEntities,
     events::{BlockChangeEvent, EntityCreateEvent, EntityRemoveEvent, PlayerJoinEvent},
+    movement::can_fly,
     ChatBox, World,
 };
 
@@ -150,6 +156,13 @@ impl Game {
         }
     }
 
+    /// Spawns an item entity holding `item` at the given position.
+    pub fn drop_item(&mut self, position: Position, item: ItemStack) -> Entity {
+        let mut builder = self.create_entity_builder(position, EntityInit::Item);
+        builder.add(item);
+        self.spawn_entity(builder)
+    }
+
     /// Causes the given entity to be removed on the next tick.
     /// In the meantime, triggers `EntityRemoveEvent`.
     pub fn remove_entity(&mut self, entity: Entity) -> Result<(), NoSuchEntity> {
@@ -180,6 +193,25 @@ impl Game {
         Ok(())
     }
 
+    /// Changes the gamemode of an entity.
+    ///
+    /// Spectators always fly, and entities which
+    /// may no longer fly stop flying.
+    /// Triggers a `GamemodeChangeEvent` if the gamemode changed.
+    pub fn set_gamemode(&mut self, entity: Entity, gamemode: Gamemode) -> SysResult {
+        let previous = mem::replace(&mut *self.ecs.get_mut::<Gamemode>(entity)?, gamemode);
+        if previous == gamemode {
+            return Ok(());
+        }
+
+        if let Ok(mut flying) = self.ecs.get_mut::<CreativeFlying>(entity) {
+            flying.0 = gamemode == Gamemode::Spectator || (flying.0 && can_fly(gamemode));
+        }
+        self.ecs
+            .insert_entity_event(entity, GamemodeChangeEvent::new(gamemode, previous))?;
+        Ok(())
+    }
+
     /// Gets the block at the given position.
     pub fn block(&self, pos: BlockPosition) -> Option<BlockId> {
         self.world.block_at(pos)
@@ -187,11 +219,14 @@ impl Game {
 
     /// Sets the block at the given position.
     ///
-    /// Triggers necessary `BlockChangeEvent`s.
+    /// Triggers necessary `BlockChangeEvent`s, including
+    /// the one observed by plugins.
     pub fn set_block(&mut self, pos: BlockPosition, block: BlockId) -> bool {
         let was_successful = self.world.set_block_at(pos, block);
         if was_successful {
             self.ecs.insert_event(BlockChangeEvent::single(pos));
+            self.ecs
+                .insert_event(PluginBlockChangeEvent::Single { position: pos });
         }
         was_successful
     }
@@ -211,15 +246,23 @@ impl Game {
         };
 
         let was_successful = chunk.fill_section(section_y + 1, block);
+        drop(chunk);
 
         if !was_successful {
             return false;
         }
 
+        self.world.mark_chunk_dirty(chunk_pos);
+
         self.ecs.insert_event(BlockChangeEvent::fill_chunk_section(
             chunk_pos,
             section_y as u32,
         ));
+        self.ecs
+            .insert_event(PluginBlockChangeEvent::FillChunkSection {
+                chunk: chunk_pos,
+                section: section_y as u32,
+            });
 
         true
     }
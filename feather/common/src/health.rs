@@ -0,0 +1,677 @@
+//! Health and hunger: damage from falls, the void, attacks
+//! and starvation, death and respawning.
+
+use base::{EnchantmentKind, EntityKind, Gamemode, Inventory, Item, ItemStack, Position, Text};
+use ecs::{Entity, SysResult, SystemExecutor};
+use libcraft_core::InteractionType;
+use quill_common::{
+    components::{CreativeFlying, CustomName, Name, OnGround, Velocity},
+    entities::Player,
+    events::InteractEntityEvent,
+};
+
+use crate::{
+    chat::ChatKind,
+    entities::player::{drop_inventory, held_item},
+    events::{
+        EntityDamageEvent, EntityDeathEvent, HealthUpdateEvent, InventoryUpdateEvent,
+        PlayerRespawnEvent,
+    },
+    movement::{self, MAX_MOVE_DISTANCE},
+    physics::{Physics, VOID_Y},
+    Game,
+};
+
+/// The health of a player with full health.
+pub const MAX_HEALTH: f32 = 20.0;
+
+/// The food level of a player who is not hungry.
+pub const MAX_FOOD: u32 = 20;
+
+/// The number of ticks after taking damage during which an entity
+/// only takes damage from hits stronger than the last one.
+const DAMAGE_COOLDOWN: u32 = 10;
+
+/// The distance an entity may fall without taking damage.
+const SAFE_FALL_DISTANCE: f64 = 3.0;
+
+/// The damage dealt each tick to entities in the void.
+const VOID_DAMAGE: f32 = 4.0;
+
+/// The interval in ticks at which players heal
+/// when well fed, or starve when their food runs out.
+const FOOD_TICK_INTERVAL: u32 = 80;
+
+/// The food level from which players heal over time.
+const REGENERATION_FOOD_LEVEL: u32 = 18;
+
+/// The exhaustion which costs a point of saturation or food.
+const EXHAUSTION_PER_FOOD_POINT: f32 = 4.0;
+
+/// The exhaustion caused by healing one point of health.
+const REGENERATION_EXHAUSTION: f32 = 6.0;
+
+/// The exhaustion caused by attacking or being attacked.
+const ATTACK_EXHAUSTION: f32 = 0.1;
+
+/// The strength with which attacked entities are knocked back.
+const KNOCKBACK_STRENGTH: f64 = 0.4;
+
+/// Component storing the health of an entity.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Health(pub f32);
+
+impl Default for Health {
+    fn default() -> Self {
+        Self(MAX_HEALTH)
+    }
+}
+
+/// Component storing the hunger of a player.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Food {
+    pub level: u32,
+    /// Consumed before the food level drops. Never
+    /// greater than the food level.
+    pub saturation: f32,
+    /// Accumulated by actions such as attacking. Every
+    /// [`EXHAUSTION_PER_FOOD_POINT`] costs a point of
+    /// saturation or food.
+    pub exhaustion: f32,
+    /// Ticks since the player last healed or starved.
+    timer: u32,
+}
+
+impl Food {
+    pub fn new(level: u32, saturation: f32, exhaustion: f32) -> Self {
+        Self {
+            level,
+            saturation,
+            exhaustion,
+            timer: 0,
+        }
+    }
+}
+
+impl Default for Food {
+    fn default() -> Self {
+        Self::new(MAX_FOOD, 5.0, 0.0)
+    }
+}
+
+/// Component storing the distance an entity has fallen
+/// since it last stood on the ground.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct FallDistance {
+    distance: f64,
+    last_y: Option<f64>,
+}
+
+impl FallDistance {
+    pub fn get(&self) -> f64 {
+        self.distance
+    }
+}
+
+/// Component storing the damage an entity took recently.
+/// See [`DAMAGE_COOLDOWN`].
+#[derive(Copy, Clone, Debug, Default)]
+pub struct DamageCooldown {
+    ticks: u32,
+    last_damage: f32,
+}
+
+/// Marker component for players who died and have not respawned yet.
+#[derive(Copy, Clone, Debug)]
+pub struct Dead;
+
+/// The reason an entity took damage.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum DamageCause {
+    Fall,
+    Void,
+    Starvation,
+    /// Attacked by the given entity.
+    Attack(Entity),
+}
+
+pub fn register(systems: &mut SystemExecutor<Game>) {
+    systems
+        .add_system(cool_down_damage)
+        .add_system(handle_attacks)
+        .add_system(track_falls)
+        .add_system(damage_in_void)
+        .add_system(update_food);
+}
+
+/// Deals `amount` damage to `entity`, killing it if its health
+/// drops to zero. Returns the damage which was actually dealt.
+pub fn damage(game: &mut Game, entity: Entity, amount: f32, cause: DamageCause) -> SysResult<f32> {
+    if game.ecs.get::<Dead>(entity).is_ok() || is_invulnerable(game, entity, cause) {
+        return Ok(0.0);
+    }
+
+    let amount = match game.ecs.get_mut::<DamageCooldown>(entity) {
+        Ok(mut cooldown) if cooldown.ticks > 0 => {
+            // Only the part exceeding the last hit is dealt.
+            let extra = amount - cooldown.last_damage;
+            cooldown.last_damage = cooldown.last_damage.max(amount);
+            extra
+        }
+        Ok(mut cooldown) => {
+            cooldown.ticks = DAMAGE_COOLDOWN;
+            cooldown.last_damage = amount;
+            amount
+        }
+        Err(_) => amount,
+    };
+    if amount <= 0.0 {
+        return Ok(0.0);
+    }
+
+    let health = {
+        let mut health = game.ecs.get_mut::<Health>(entity)?;
+        health.0 = (health.0 - amount).max(0.0);
+        health.0
+    };
+    game.ecs
+        .insert_entity_event(entity, EntityDamageEvent { cause, amount })?;
+    game.ecs.insert_entity_event(entity, HealthUpdateEvent)?;
+    if let DamageCause::Attack(_) = cause {
+        exhaust(game, entity, ATTACK_EXHAUSTION);
+    }
+
+    if health <= 0.0 {
+        kill(game, entity, cause)?;
+    }
+    Ok(amount)
+}
+
+/// Kills `entity`. Players drop their inventory unless the
+/// `keepInventory` game rule is set, and stay dead until
+/// they respawn. Other entities are removed.
+pub fn kill(game: &mut Game, entity: Entity, cause: DamageCause) -> SysResult {
+    if let Ok(mut health) = game.ecs.get_mut::<Health>(entity) {
+        health.0 = 0.0;
+    }
+
+    let message = death_message(game, entity, cause);
+    game.ecs.insert_entity_event(
+        entity,
+        EntityDeathEvent {
+            cause,
+            message: message.clone(),
+        },
+    )?;
+
+    if game.ecs.get::<Player>(entity).is_err() {
+        game.remove_entity(entity)?;
+        return Ok(());
+    }
+
+    game.ecs.insert(entity, Dead)?;
+    let level = game.world.level();
+    let keep_inventory = level.game_rule_bool("keepInventory").unwrap_or(false);
+    let show_message = level.game_rule_bool("showDeathMessages").unwrap_or(true);
+
+    if !keep_inventory && game.ecs.get::<Inventory>(entity).is_ok() {
+        drop_inventory(game, entity)?;
+        game.ecs.insert_entity_event(entity, InventoryUpdateEvent)?;
+    }
+    if show_message {
+        game.broadcast_chat(ChatKind::System, message);
+    }
+    Ok(())
+}
+
+/// Brings a dead player back to life at `position`
+/// with full health and food.
+pub fn respawn(game: &mut Game, player: Entity, position: Position) -> SysResult {
+    if game.ecs.remove::<Dead>(player).is_err() {
+        return Ok(());
+    }
+
+    *game.ecs.get_mut::<Position>(player)? = position;
+    game.ecs.insert(player, Health::default())?;
+    game.ecs.insert(player, Food::default())?;
+    game.ecs.insert(player, FallDistance::default())?;
+    game.ecs.insert(player, DamageCooldown::default())?;
+    game.ecs.insert(player, Velocity::default())?;
+
+    game.ecs.insert_entity_event(player, PlayerRespawnEvent)?;
+    game.ecs.insert_entity_event(player, HealthUpdateEvent)?;
+    Ok(())
+}
+
+/// Makes `attacker` hit `target` with the item they are holding,
+/// damaging it and knocking it back.
+pub fn attack(game: &mut Game, attacker: Entity, target: Entity) -> SysResult {
+    if game.ecs.get::<Dead>(attacker).is_ok()
+        || game.ecs.get::<Health>(target).is_err()
+        || game
+            .ecs
+            .get::<Gamemode>(attacker)
+            .map_or(false, |gamemode| *gamemode == Gamemode::Spectator)
+    {
+        return Ok(());
+    }
+
+    let weapon = held_item(game, attacker).unwrap_or(None);
+    let amount = attack_damage(weapon.as_ref());
+    if damage(game, target, amount, DamageCause::Attack(attacker))? <= 0.0 {
+        return Ok(());
+    }
+
+    let knockback = weapon.as_ref().map_or(0, |weapon| {
+        weapon.enchantment_level(EnchantmentKind::Knockback)
+    });
+    let yaw = game.ecs.get::<Position>(attacker)?.yaw;
+    knock_back(
+        game,
+        target,
+        yaw,
+        KNOCKBACK_STRENGTH + 0.5 * knockback as f64,
+    )?;
+    exhaust(game, attacker, ATTACK_EXHAUSTION);
+    Ok(())
+}
+
+/// Returns the damage dealt by a hit with `weapon`,
+/// or with a bare hand if `None`.
+pub fn attack_damage(weapon: Option<&ItemStack>) -> f32 {
+    let damage = match weapon.map_or(Item::Air, |weapon| weapon.item) {
+        Item::WoodenSword | Item::GoldenSword => 4.0,
+        Item::StoneSword => 5.0,
+        Item::IronSword => 6.0,
+        Item::DiamondSword => 7.0,
+        Item::NetheriteSword => 8.0,
+        Item::WoodenAxe | Item::GoldenAxe => 7.0,
+        Item::StoneAxe | Item::IronAxe | Item::DiamondAxe | Item::Trident => 9.0,
+        Item::NetheriteAxe => 10.0,
+        Item::WoodenPickaxe | Item::GoldenPickaxe => 2.0,
+        Item::StonePickaxe => 3.0,
+        Item::IronPickaxe => 4.0,
+        Item::DiamondPickaxe => 5.0,
+        Item::NetheritePickaxe => 6.0,
+        Item::WoodenShovel | Item::GoldenShovel => 2.5,
+        Item::StoneShovel => 3.5,
+        Item::IronShovel => 4.5,
+        Item::DiamondShovel => 5.5,
+        Item::NetheriteShovel => 6.5,
+        _ => 1.0,
+    };
+
+    let sharpness = weapon.map_or(0, |weapon| {
+        weapon.enchantment_level(EnchantmentKind::Sharpness)
+    });
+    if sharpness > 0 {
+        damage + 0.5 * sharpness as f32 + 0.5
+    } else {
+        damage
+    }
+}
+
+/// Pushes `entity` away in the direction given by `yaw`.
+fn knock_back(game: &mut Game, entity: Entity, yaw: f32, strength: f64) -> SysResult {
+    let on_ground = game.ecs.get::<OnGround>(entity)?.0;
+    let simulated = game
+        .ecs
+        .get::<EntityKind>(entity)
+        .map_or(false, |kind| Physics::of(*kind).is_some());
+    let mut velocity = game.ecs.get_mut::<Velocity>(entity)?;
+
+    // The server does not know the velocity of entities
+    // which move on the client, such as players.
+    let base = if simulated {
+        *velocity
+    } else {
+        Velocity::default()
+    };
+    let yaw = (yaw as f64).to_radians();
+    velocity.x = base.x / 2.0 - yaw.sin() * strength;
+    velocity.z = base.z / 2.0 + yaw.cos() * strength;
+    if on_ground {
+        velocity.y = (base.y / 2.0 + strength).min(0.4);
+    }
+    Ok(())
+}
+
+/// Adds exhaustion to the food of `entity`, if it has any.
+pub fn exhaust(game: &Game, entity: Entity, amount: f32) {
+    if has_invulnerable_gamemode(game, entity) {
+        return;
+    }
+    if let Ok(mut food) = game.ecs.get_mut::<Food>(entity) {
+        food.exhaustion += amount;
+    }
+}
+
+/// Returns whether `entity` is protected from damage with `cause`.
+/// Players in creative and spectator mode only take damage
+/// from the void.
+fn is_invulnerable(game: &Game, entity: Entity, cause: DamageCause) -> bool {
+    cause != DamageCause::Void && has_invulnerable_gamemode(game, entity)
+}
+
+fn has_invulnerable_gamemode(game: &Game, entity: Entity) -> bool {
+    game.ecs.get::<Gamemode>(entity).map_or(false, |gamemode| {
+        matches!(*gamemode, Gamemode::Creative | Gamemode::Spectator)
+    })
+}
+
+fn death_message(game: &Game, entity: Entity, cause: DamageCause) -> Text {
+    let name = display_name(game, entity);
+    match cause {
+        DamageCause::Fall => Text::translate_with("death.attack.fall", vec![name]),
+        DamageCause::Void => Text::translate_with("death.attack.outOfWorld", vec![name]),
+        DamageCause::Starvation => Text::translate_with("death.attack.starve", vec![name]),
+        DamageCause::Attack(attacker) => {
+            let key = if game.ecs.get::<Player>(attacker).is_ok() {
+                "death.attack.player"
+            } else {
+                "death.attack.mob"
+            };
+            Text::translate_with(key, vec![name, display_name(game, attacker)])
+        }
+    }
+}
+
+fn display_name(game: &Game, entity: Entity) -> String {
+    if let Ok(name) = game.ecs.get::<Name>(entity) {
+        return name.to_string();
+    }
+    if let Ok(name) = game.ecs.get::<CustomName>(entity) {
+        return name.to_string();
+    }
+    game.ecs
+        .get::<EntityKind>(entity)
+        .map_or_else(|_| String::new(), |kind| kind.display_name().to_owned())
+}
+
+fn cool_down_damage(game: &mut Game) -> SysResult {
+    for (_, cooldown) in game.ecs.query::<&mut DamageCooldown>().iter() {
+        cooldown.ticks = cooldown.ticks.saturating_sub(1);
+    }
+    Ok(())
+}
+
+/// Damages entities attacked by players.
+fn handle_attacks(game: &mut Game) -> SysResult {
+    let attacks: Vec<(Entity, Entity)> = game
+        .ecs
+        .query::<&InteractEntityEvent>()
+        .iter()
+        .filter(|(_, event)| matches!(event.ty, InteractionType::Attack))
+        .map(|(attacker, event)| (attacker, Entity::from_bits(event.target.0)))
+        .collect();
+
+    for (attacker, target) in attacks {
+        attack(game, attacker, target)?;
+    }
+    Ok(())
+}
+
+/// Tracks how far entities fall and damages
+/// them when they hit the ground.
+fn track_falls(game: &mut Game) -> SysResult {
+    let mut landed = Vec::new();
+    for (entity, (position, on_ground, fall, flying)) in game
+        .ecs
+        .query::<(
+            &Position,
+            &OnGround,
+            &mut FallDistance,
+            Option<&CreativeFlying>,
+        )>()
+        .iter()
+    {
+        let last_y = fall.last_y.replace(position.y);
+        let held_up = game
+            .block(position.block())
+            .map_or(false, movement::holds_up);
+        if held_up || flying.map_or(false, |flying| flying.0) {
+            fall.distance = 0.0;
+            continue;
+        }
+
+        if let Some(last_y) = last_y {
+            let fallen = last_y - position.y;
+            // Larger moves are teleports, which reset the distance.
+            if fallen > MAX_MOVE_DISTANCE {
+                fall.distance = 0.0;
+            } else if fallen > 0.0 {
+                fall.distance += fallen;
+            }
+        }
+
+        if on_ground.0 {
+            let damage = (fall.distance - SAFE_FALL_DISTANCE).ceil();
+            if damage > 0.0 {
+                landed.push((entity, damage as f32));
+            }
+            fall.distance = 0.0;
+        }
+    }
+
+    for (entity, amount) in landed {
+        damage(game, entity, amount, DamageCause::Fall)?;
+    }
+    Ok(())
+}
+
+fn damage_in_void(game: &mut Game) -> SysResult {
+    let in_void: Vec<Entity> = game
+        .ecs
+        .query::<(&Position, &Health)>()
+        .iter()
+        .filter(|(_, (position, _))| position.y < VOID_Y)
+        .map(|(entity, _)| entity)
+        .collect();
+
+    for entity in in_void {
+        damage(game, entity, VOID_DAMAGE, DamageCause::Void)?;
+    }
+    Ok(())
+}
+
+/// Consumes exhausted food, heals well fed players
+/// and starves players without food.
+fn update_food(game: &mut Game) -> SysResult {
+    let level = game.world.level();
+    let peaceful = level.difficulty == 0;
+    let regeneration = level.game_rule_bool("naturalRegeneration").unwrap_or(true);
+    // Starvation stops at this health, depending on the difficulty.
+    let starvation_limit = match level.difficulty {
+        3 => 0.0,
+        2 => 1.0,
+        _ => 10.0,
+    };
+
+    let mut updated = Vec::new();
+    let mut starving = Vec::new();
+    for (entity, (food, health, dead)) in game
+        .ecs
+        .query::<(&mut Food, &mut Health, Option<&Dead>)>()
+        .iter()
+    {
+        if dead.is_some() {
+            continue;
+        }
+        let (old_level, old_saturation, old_health) = (food.level, food.saturation, health.0);
+
+        if food.exhaustion > EXHAUSTION_PER_FOOD_POINT {
+            food.exhaustion -= EXHAUSTION_PER_FOOD_POINT;
+            if food.saturation > 0.0 {
+                food.saturation = (food.saturation - 1.0).max(0.0);
+            } else if !peaceful {
+                food.level = food.level.saturating_sub(1);
+            }
+        }
+
+        if regeneration && food.level >= REGENERATION_FOOD_LEVEL && health.0 < MAX_HEALTH {
+            food.timer += 1;
+            if food.timer >= FOOD_TICK_INTERVAL {
+                health.0 = (health.0 + 1.0).min(MAX_HEALTH);
+                food.exhaustion += REGENERATION_EXHAUSTION;
+                food.timer = 0;
+            }
+        } else if food.level == 0 {
+            food.timer += 1;
+            if food.timer >= FOOD_TICK_INTERVAL {
+                if health.0 > starvation_limit {
+                    starving.push(entity);
+                }
+                food.timer = 0;
+            }
+        } else {
+            food.timer = 0;
+        }
+
+        if (food.level, food.saturation, health.0) != (old_level, old_saturation, old_health) {
+            updated.push(entity);
+        }
+    }
+
+    for entity in updated {
+        game.ecs.insert_entity_event(entity, HealthUpdateEvent)?;
+    }
+    for entity in starving {
+        damage(game, entity, 1.0, DamageCause::Starvation)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use base::{position, Area, BlockId, Chunk, ChunkPosition};
+    use quill_common::entity_init::EntityInit;
+
+    use crate::{
+        entities::{add_entity_components, player::HotbarSlot},
+        events::EntityRemoveEvent,
+    };
+
+    use super::*;
+
+    fn game() -> (Game, Entity, Inventory) {
+        let mut game = Game::new();
+        game.add_entity_spawn_callback(add_entity_components);
+        let mut chunk = Chunk::new(ChunkPosition::new(0, 0));
+        for x in 0..16 {
+            for z in 0..16 {
+                chunk.set_block_at(x, 63, z, BlockId::stone());
+            }
+        }
+        game.world.chunk_map_mut().insert_chunk(chunk);
+
+        let inventory = Inventory::player();
+        let mut builder = game.create_entity_builder(position!(8.5, 64.0, 8.5), EntityInit::Player);
+        builder
+            .add(Gamemode::Survival)
+            .add(inventory.new_handle())
+            .add(HotbarSlot::default());
+        let player = game.spawn_entity(builder);
+        (game, player, inventory)
+    }
+
+    fn health(game: &Game, entity: Entity) -> f32 {
+        game.ecs.get::<Health>(entity).unwrap().0
+    }
+
+    #[test]
+    fn landing_after_a_long_fall_deals_damage() {
+        let (mut game, player, _) = game();
+        game.ecs.get_mut::<OnGround>(player).unwrap().0 = false;
+        for y in &[74.0, 70.0, 66.0, 64.0] {
+            game.ecs.get_mut::<Position>(player).unwrap().y = *y;
+            track_falls(&mut game).unwrap();
+        }
+        assert_eq!(health(&game, player), MAX_HEALTH);
+
+        game.ecs.get_mut::<OnGround>(player).unwrap().0 = true;
+        track_falls(&mut game).unwrap();
+        assert_eq!(health(&game, player), MAX_HEALTH - 7.0);
+        assert_eq!(
+            game.ecs.get::<EntityDamageEvent>(player).unwrap().cause,
+            DamageCause::Fall
+        );
+    }
+
+    #[test]
+    fn damage_cooldown_limits_repeated_hits() {
+        let (mut game, player, _) = game();
+        damage(&mut game, player, 4.0, DamageCause::Void).unwrap();
+        damage(&mut game, player, 3.0, DamageCause::Void).unwrap();
+        assert_eq!(health(&game, player), MAX_HEALTH - 4.0);
+
+        damage(&mut game, player, 6.0, DamageCause::Void).unwrap();
+        assert_eq!(health(&game, player), MAX_HEALTH - 6.0);
+
+        *game.ecs.get_mut::<Gamemode>(player).unwrap() = Gamemode::Creative;
+        for _ in 0..DAMAGE_COOLDOWN {
+            cool_down_damage(&mut game).unwrap();
+        }
+        damage(&mut game, player, 6.0, DamageCause::Fall).unwrap();
+        assert_eq!(health(&game, player), MAX_HEALTH - 6.0);
+    }
+
+    #[test]
+    fn dying_drops_the_inventory_and_respawning_restores_health() {
+        let (mut game, player, inventory) = game();
+        *inventory.item(Area::Hotbar, 0).unwrap() = Some(ItemStack::new(Item::Diamond, 3));
+
+        damage(&mut game, player, 25.0, DamageCause::Void).unwrap();
+        assert_eq!(health(&game, player), 0.0);
+        assert!(game.ecs.get::<Dead>(player).is_ok());
+        assert!(game.ecs.get::<EntityDeathEvent>(player).is_ok());
+        assert!(inventory.item(Area::Hotbar, 0).unwrap().is_none());
+        assert_eq!(
+            game.ecs
+                .query::<&ItemStack>()
+                .iter()
+                .map(|(_, stack)| stack.clone())
+                .collect::<Vec<_>>(),
+            vec![ItemStack::new(Item::Diamond, 3)]
+        );
+        assert_eq!(
+            damage(&mut game, player, 1.0, DamageCause::Fall).unwrap(),
+            0.0
+        );
+
+        respawn(&mut game, player, position!(0.5, 64.0, 0.5)).unwrap();
+        assert!(game.ecs.get::<Dead>(player).is_err());
+        assert_eq!(health(&game, player), MAX_HEALTH);
+        assert_eq!(game.ecs.get::<Food>(player).unwrap().level, MAX_FOOD);
+        assert_eq!(game.ecs.get::<Position>(player).unwrap().x, 0.5);
+        assert!(game.ecs.get::<EntityRemoveEvent>(player).is_err());
+    }
+
+    #[test]
+    fn starving_players_lose_health_down_to_the_difficulty_limit() {
+        let (mut game, player, _) = game();
+        game.world.level_mut().difficulty = 2;
+        game.ecs.insert(player, Food::new(0, 0.0, 0.0)).unwrap();
+        game.ecs.insert(player, Health(2.0)).unwrap();
+
+        for _ in 0..FOOD_TICK_INTERVAL * 3 {
+            update_food(&mut game).unwrap();
+        }
+        assert_eq!(health(&game, player), 1.0);
+    }
+
+    #[test]
+    fn exhaustion_consumes_saturation_then_food() {
+        let (mut game, player, _) = game();
+        game.world.level_mut().difficulty = 2;
+        game.ecs.insert(player, Food::new(10, 1.0, 0.0)).unwrap();
+
+        exhaust(&game, player, 4.5);
+        update_food(&mut game).unwrap();
+        exhaust(&game, player, 4.0);
+        update_food(&mut game).unwrap();
+
+        let food = *game.ecs.get::<Food>(player).unwrap();
+        assert_eq!(food.saturation, 0.0);
+        assert_eq!(food.level, 9);
+    }
+}
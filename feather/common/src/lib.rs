@@ -24,13 +24,32 @@ pub mod world_source;
 pub mod world;
 pub use world::World;
 
+pub mod lighting;
+
+pub mod digging;
+
+pub mod block_placement;
+
+pub mod physics;
+
+pub mod movement;
+
+pub mod health;
+
 mod chunk_loading;
 
+mod autosave;
+
+mod time;
+
 mod chunk_entities;
 
 pub mod chat;
 pub use chat::ChatBox;
 
+pub mod commands;
+pub use commands::CommandDispatcher;
+
 pub mod entities;
 
 pub mod interactable;
@@ -39,8 +58,16 @@ pub mod interactable;
 pub fn register(game: &mut Game, systems: &mut SystemExecutor<Game>) {
     view::register(game, systems);
     chunk_loading::register(game, systems);
+    autosave::register(game, systems);
+    time::register(systems);
+    chat::register(systems);
+    lighting::register(systems);
     chunk_entities::register(systems);
+    entities::item::register(systems);
+    physics::register(systems);
+    health::register(systems);
     interactable::register(game);
+    commands::register(game);
 
     game.add_entity_spawn_callback(entities::add_entity_components);
 }
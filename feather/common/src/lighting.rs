@@ -0,0 +1,526 @@
+//! Block and sky light.
+//!
+//! Light spreads by flood fill: a transparent block receives the
+//! light of its brightest neighbor minus one. Sky light also
+//! travels straight down from the top of the world without
+//! diminishing until it reaches an opaque block.
+//!
+//! Generated chunks are lit with [`light_chunk`] on the worldgen
+//! worker threads. Afterward, light is updated incrementally for each
+//! `BlockChangeEvent`, and a `LightUpdateEvent` is triggered for each
+//! chunk whose light changed. Light does not spread between chunks when
+//! they are first lit, only when blocks near their borders change.
+
+use std::collections::VecDeque;
+
+use ahash::{AHashMap, AHashSet};
+use base::{BlockPosition, Chunk, ChunkPosition, CHUNK_HEIGHT, CHUNK_WIDTH};
+use blocks::BlockId;
+use ecs::{SysResult, SystemExecutor};
+use parking_lot::RwLockWriteGuard;
+
+use crate::{
+    events::{BlockChangeEvent, LightUpdateEvent},
+    world::ChunkMap,
+    Game,
+};
+
+/// The light emitted by the sky and the brightest light sources.
+const MAX_LIGHT: u8 = 15;
+
+/// Number of blocks changed by a single event above which the
+/// affected chunks are relit from scratch instead of incrementally.
+const RELIGHT_THRESHOLD: usize = 256;
+
+pub fn register(systems: &mut SystemExecutor<Game>) {
+    systems.add_system(update_light);
+}
+
+/// Computes the block and sky light of a chunk from scratch,
+/// ignoring light from neighboring chunks.
+pub fn light_chunk(chunk: &mut Chunk) {
+    let origin = chunk.position();
+    let mut block_queue = VecDeque::new();
+    let mut sky_queue = VecDeque::new();
+
+    // The lowest y coordinate lit directly by the sky in each column.
+    let mut heights = [[0; CHUNK_WIDTH]; CHUNK_WIDTH];
+    for x in 0..CHUNK_WIDTH {
+        for z in 0..CHUNK_WIDTH {
+            let mut sky_light = MAX_LIGHT;
+            for y in (0..CHUNK_HEIGHT).rev() {
+                let block = chunk.block_at(x, y, z).unwrap_or_else(BlockId::air);
+                if block.is_opaque() && sky_light == MAX_LIGHT {
+                    heights[x][z] = y + 1;
+                    sky_light = 0;
+                }
+                chunk.set_sky_light_at(x, y, z, sky_light);
+
+                let emission = block.light_emission();
+                chunk.set_block_light_at(x, y, z, emission);
+                if emission > 0 {
+                    block_queue.push_back(world_pos(origin, x, y, z));
+                }
+            }
+        }
+    }
+
+    // Sky light spreads sideways from the parts of each
+    // column that are exposed to the sky into the dark
+    // parts of the neighboring columns.
+    for x in 0..CHUNK_WIDTH {
+        for z in 0..CHUNK_WIDTH {
+            let neighbor_heights = [
+                x.checked_sub(1).map(|x| heights[x][z]),
+                heights.get(x + 1).map(|column| column[z]),
+                z.checked_sub(1).map(|z| heights[x][z]),
+                heights[x].get(z + 1).copied(),
+            ];
+            let max_height = neighbor_heights.iter().flatten().copied().max();
+            for y in heights[x][z]..max_height.unwrap_or(0) {
+                sky_queue.push_back(world_pos(origin, x, y, z));
+            }
+        }
+    }
+
+    propagate(chunk, LightKind::Block, &mut block_queue);
+    propagate(chunk, LightKind::Sky, &mut sky_queue);
+}
+
+/// Updates light for the blocks changed in this tick.
+fn update_light(game: &mut Game) -> SysResult {
+    let mut relit_chunks = AHashSet::new();
+    let mut changed_blocks = Vec::new();
+    for (_, event) in game.ecs.query::<&BlockChangeEvent>().iter() {
+        if event.count() >= RELIGHT_THRESHOLD {
+            relit_chunks.extend(
+                event
+                    .iter_affected_chunk_sections()
+                    .map(|(chunk, _, _)| chunk),
+            );
+        } else {
+            changed_blocks.extend(event.iter_changed_blocks());
+        }
+    }
+
+    if relit_chunks.is_empty() && changed_blocks.is_empty() {
+        return Ok(());
+    }
+
+    let mut world = WorldLight::new(game.world.chunk_map());
+    for &chunk in &relit_chunks {
+        relight_chunk(&mut world, chunk);
+    }
+    for pos in changed_blocks {
+        if !relit_chunks.contains(&pos.chunk()) {
+            update_block(&mut world, pos);
+        }
+    }
+    let changed_chunks = world.into_changed_chunks();
+
+    for position in changed_chunks {
+        game.world.mark_chunk_dirty(position);
+        if let Some(chunk) = game.world.chunk_map().chunk_handle_at(position) {
+            game.ecs.insert_event(LightUpdateEvent { position, chunk });
+        }
+    }
+
+    Ok(())
+}
+
+/// Relights a chunk from scratch, then lets light
+/// flow across its borders to and from its neighbors.
+fn relight_chunk(world: &mut WorldLight, chunk_pos: ChunkPosition) {
+    match world.chunk(chunk_pos) {
+        Some(chunk) => light_chunk(chunk),
+        None => return,
+    }
+    world.changed_chunks.insert(chunk_pos);
+
+    for &kind in &[LightKind::Block, LightKind::Sky] {
+        let mut queue = VecDeque::new();
+        for y in 0..CHUNK_HEIGHT {
+            for i in 0..CHUNK_WIDTH {
+                let border = [(i, 0), (i, CHUNK_WIDTH - 1), (0, i), (CHUNK_WIDTH - 1, i)];
+                for &(x, z) in &border {
+                    let pos = world_pos(chunk_pos, x, y, z);
+                    queue.push_back(pos);
+                    queue.extend(
+                        neighbors(pos)
+                            .iter()
+                            .map(|&(neighbor, _)| neighbor)
+                            .filter(|neighbor| neighbor.chunk() != chunk_pos),
+                    );
+                }
+            }
+        }
+        propagate(world, kind, &mut queue);
+    }
+}
+
+/// Updates light after the block at `pos` changed.
+fn update_block(storage: &mut impl LightStorage, pos: BlockPosition) {
+    let block = match storage.block(pos) {
+        Some(block) => block,
+        None => return,
+    };
+
+    for &kind in &[LightKind::Block, LightKind::Sky] {
+        let mut queue = VecDeque::new();
+        remove(storage, kind, pos, &mut queue);
+
+        let source = match kind {
+            LightKind::Block => block.light_emission(),
+            LightKind::Sky if pos.y == CHUNK_HEIGHT as i32 - 1 && !block.is_opaque() => MAX_LIGHT,
+            LightKind::Sky => 0,
+        };
+        if source > 0 {
+            storage.set_light(kind, pos, source);
+            queue.push_back(pos);
+        }
+
+        // Light flows back in from the neighbors
+        // if the new block is transparent.
+        if !block.is_opaque() {
+            queue.extend(neighbors(pos).iter().map(|&(neighbor, _)| neighbor));
+        }
+
+        propagate(storage, kind, &mut queue);
+    }
+}
+
+/// Spreads light from the blocks in `queue`
+/// until no more blocks are brightened.
+fn propagate(
+    storage: &mut impl LightStorage,
+    kind: LightKind,
+    queue: &mut VecDeque<BlockPosition>,
+) {
+    while let Some(pos) = queue.pop_front() {
+        let light = match storage.light(kind, pos) {
+            Some(light) => light,
+            None => continue,
+        };
+
+        for &(neighbor, downward) in &neighbors(pos) {
+            let new_light = spread(kind, light, downward);
+            if new_light == 0 {
+                continue;
+            }
+            match storage.block(neighbor) {
+                Some(block) if !block.is_opaque() => (),
+                _ => continue,
+            }
+            if storage.light(kind, neighbor).unwrap_or(MAX_LIGHT) < new_light {
+                storage.set_light(kind, neighbor, new_light);
+                queue.push_back(neighbor);
+            }
+        }
+    }
+}
+
+/// Darkens the block at `start` and all blocks which received
+/// their light from it. Blocks bordering the darkened area and
+/// light sources within it are added to `queue` so that
+/// `propagate` fills the area with the remaining light.
+fn remove(
+    storage: &mut impl LightStorage,
+    kind: LightKind,
+    start: BlockPosition,
+    queue: &mut VecDeque<BlockPosition>,
+) {
+    let light = match storage.light(kind, start) {
+        Some(light) if light > 0 => light,
+        _ => return,
+    };
+    storage.set_light(kind, start, 0);
+
+    let mut removal_queue = VecDeque::new();
+    removal_queue.push_back((start, light));
+    let mut sources = Vec::new();
+    while let Some((pos, light)) = removal_queue.pop_front() {
+        for &(neighbor, downward) in &neighbors(pos) {
+            let neighbor_light = match storage.light(kind, neighbor) {
+                Some(light) if light > 0 => light,
+                _ => continue,
+            };
+
+            let was_lit_by_pos = neighbor_light < light
+                || (neighbor_light == MAX_LIGHT && spread(kind, light, downward) == MAX_LIGHT);
+            if !was_lit_by_pos {
+                queue.push_back(neighbor);
+                continue;
+            }
+
+            storage.set_light(kind, neighbor, 0);
+            removal_queue.push_back((neighbor, neighbor_light));
+            if kind == LightKind::Block {
+                let emission = storage.block(neighbor).map_or(0, BlockId::light_emission);
+                if emission > 0 {
+                    sources.push((neighbor, emission));
+                }
+            }
+        }
+    }
+
+    for (pos, emission) in sources {
+        storage.set_light(kind, pos, emission);
+        queue.push_back(pos);
+    }
+}
+
+/// Returns the light that spreads from a
+/// block with `light` to one of its neighbors.
+fn spread(kind: LightKind, light: u8, downward: bool) -> u8 {
+    if kind == LightKind::Sky && downward && light == MAX_LIGHT {
+        MAX_LIGHT
+    } else {
+        light.saturating_sub(1)
+    }
+}
+
+/// Returns the six neighbors of a block, along
+/// with whether each neighbor is below the block.
+fn neighbors(pos: BlockPosition) -> [(BlockPosition, bool); 6] {
+    [
+        (pos.down(), true),
+        (pos.up(), false),
+        (pos.north(), false),
+        (pos.south(), false),
+        (pos.east(), false),
+        (pos.west(), false),
+    ]
+}
+
+fn world_pos(chunk: ChunkPosition, x: usize, y: usize, z: usize) -> BlockPosition {
+    BlockPosition::new(
+        chunk.x * CHUNK_WIDTH as i32 + x as i32,
+        y as i32,
+        chunk.z * CHUNK_WIDTH as i32 + z as i32,
+    )
+}
+
+/// Converts a block position to coordinates
+/// within the chunk at `chunk`.
+fn chunk_relative_pos(chunk: ChunkPosition, pos: BlockPosition) -> Option<(usize, usize, usize)> {
+    if pos.chunk() != chunk || pos.y < 0 || pos.y >= CHUNK_HEIGHT as i32 {
+        return None;
+    }
+    Some((pos.x as usize & 0xf, pos.y as usize, pos.z as usize & 0xf))
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum LightKind {
+    Block,
+    Sky,
+}
+
+/// Access to the blocks and light of one or more chunks.
+///
+/// Methods return `None` for blocks in chunks that are not
+/// accessible, which stops light from spreading there.
+trait LightStorage {
+    fn block(&mut self, pos: BlockPosition) -> Option<BlockId>;
+
+    fn light(&mut self, kind: LightKind, pos: BlockPosition) -> Option<u8>;
+
+    fn set_light(&mut self, kind: LightKind, pos: BlockPosition, light: u8);
+}
+
+impl LightStorage for Chunk {
+    fn block(&mut self, pos: BlockPosition) -> Option<BlockId> {
+        let (x, y, z) = chunk_relative_pos(self.position(), pos)?;
+        self.block_at(x, y, z)
+    }
+
+    fn light(&mut self, kind: LightKind, pos: BlockPosition) -> Option<u8> {
+        let (x, y, z) = chunk_relative_pos(self.position(), pos)?;
+        match kind {
+            LightKind::Block => self.block_light_at(x, y, z),
+            LightKind::Sky => self.sky_light_at(x, y, z),
+        }
+    }
+
+    fn set_light(&mut self, kind: LightKind, pos: BlockPosition, light: u8) {
+        if let Some((x, y, z)) = chunk_relative_pos(self.position(), pos) {
+            match kind {
+                LightKind::Block => self.set_block_light_at(x, y, z, light),
+                LightKind::Sky => self.set_sky_light_at(x, y, z, light),
+            };
+        }
+    }
+}
+
+/// Light storage spanning all loaded chunks.
+///
+/// Each chunk is locked once, on first access, and
+/// remains locked until this struct is dropped.
+struct WorldLight<'a> {
+    chunk_map: &'a ChunkMap,
+    chunks: AHashMap<ChunkPosition, Option<RwLockWriteGuard<'a, Chunk>>>,
+    changed_chunks: AHashSet<ChunkPosition>,
+}
+
+impl<'a> WorldLight<'a> {
+    fn new(chunk_map: &'a ChunkMap) -> Self {
+        Self {
+            chunk_map,
+            chunks: AHashMap::new(),
+            changed_chunks: AHashSet::new(),
+        }
+    }
+
+    fn chunk(&mut self, pos: ChunkPosition) -> Option<&mut Chunk> {
+        let chunk_map = self.chunk_map;
+        self.chunks
+            .entry(pos)
+            .or_insert_with(|| chunk_map.chunk_at_mut(pos))
+            .as_deref_mut()
+    }
+
+    /// Unlocks all chunks and returns the
+    /// chunks whose light was changed.
+    fn into_changed_chunks(self) -> AHashSet<ChunkPosition> {
+        self.changed_chunks
+    }
+}
+
+impl LightStorage for WorldLight<'_> {
+    fn block(&mut self, pos: BlockPosition) -> Option<BlockId> {
+        self.chunk(pos.chunk())?.block(pos)
+    }
+
+    fn light(&mut self, kind: LightKind, pos: BlockPosition) -> Option<u8> {
+        self.chunk(pos.chunk())?.light(kind, pos)
+    }
+
+    fn set_light(&mut self, kind: LightKind, pos: BlockPosition, light: u8) {
+        let chunk = match self.chunk(pos.chunk()) {
+            Some(chunk) => chunk,
+            None => return,
+        };
+        if chunk.light(kind, pos).map_or(false, |old| old != light) {
+            chunk.set_light(kind, pos, light);
+            self.changed_chunks.insert(pos.chunk());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunk_map() -> ChunkMap {
+        let mut chunk_map = ChunkMap::new();
+        for x in -1..=1 {
+            for z in -1..=1 {
+                chunk_map.insert_chunk(Chunk::new(ChunkPosition::new(x, z)));
+            }
+        }
+        chunk_map
+    }
+
+    fn set_block(
+        chunk_map: &ChunkMap,
+        pos: BlockPosition,
+        block: BlockId,
+    ) -> AHashSet<ChunkPosition> {
+        chunk_map.set_block_at(pos, block);
+        let mut world = WorldLight::new(chunk_map);
+        update_block(&mut world, pos);
+        world.into_changed_chunks()
+    }
+
+    fn block_light(chunk_map: &ChunkMap, pos: BlockPosition) -> u8 {
+        WorldLight::new(chunk_map)
+            .light(LightKind::Block, pos)
+            .unwrap()
+    }
+
+    fn sky_light(chunk_map: &ChunkMap, pos: BlockPosition) -> u8 {
+        WorldLight::new(chunk_map)
+            .light(LightKind::Sky, pos)
+            .unwrap()
+    }
+
+    #[test]
+    fn light_source_spreads_across_chunks() {
+        let chunk_map = chunk_map();
+        let pos = BlockPosition::new(0, 64, 0);
+        let changed = set_block(&chunk_map, pos, BlockId::glowstone());
+
+        assert_eq!(block_light(&chunk_map, pos), 15);
+        assert_eq!(block_light(&chunk_map, BlockPosition::new(0, 63, 0)), 14);
+        assert_eq!(block_light(&chunk_map, BlockPosition::new(-1, 63, 0)), 13);
+        assert_eq!(block_light(&chunk_map, BlockPosition::new(-14, 64, 0)), 1);
+        assert_eq!(block_light(&chunk_map, BlockPosition::new(-15, 64, 0)), 0);
+        assert!(changed.contains(&ChunkPosition::new(-1, 0)));
+
+        set_block(&chunk_map, pos, BlockId::air());
+        assert_eq!(block_light(&chunk_map, pos), 0);
+        assert_eq!(block_light(&chunk_map, BlockPosition::new(-1, 63, 0)), 0);
+    }
+
+    #[test]
+    fn removing_one_of_two_sources() {
+        let chunk_map = chunk_map();
+        set_block(
+            &chunk_map,
+            BlockPosition::new(0, 64, 0),
+            BlockId::glowstone(),
+        );
+        set_block(
+            &chunk_map,
+            BlockPosition::new(4, 64, 0),
+            BlockId::glowstone(),
+        );
+        set_block(&chunk_map, BlockPosition::new(0, 64, 0), BlockId::air());
+
+        assert_eq!(block_light(&chunk_map, BlockPosition::new(4, 64, 0)), 15);
+        assert_eq!(block_light(&chunk_map, BlockPosition::new(0, 64, 0)), 11);
+        assert_eq!(block_light(&chunk_map, BlockPosition::new(-1, 64, 0)), 10);
+    }
+
+    #[test]
+    fn opaque_block_casts_shadow() {
+        let chunk_map = chunk_map();
+        let roof = BlockPosition::new(5, 100, 5);
+        set_block(&chunk_map, roof, BlockId::stone());
+
+        assert_eq!(sky_light(&chunk_map, BlockPosition::new(5, 99, 5)), 14);
+        assert_eq!(sky_light(&chunk_map, BlockPosition::new(5, 0, 5)), 14);
+        assert_eq!(sky_light(&chunk_map, BlockPosition::new(6, 99, 5)), 15);
+
+        set_block(&chunk_map, roof, BlockId::air());
+        assert_eq!(sky_light(&chunk_map, BlockPosition::new(5, 99, 5)), 15);
+        assert_eq!(sky_light(&chunk_map, BlockPosition::new(5, 0, 5)), 15);
+    }
+
+    #[test]
+    fn light_chunk_darkens_enclosed_space() {
+        let mut chunk = Chunk::new(ChunkPosition::new(0, 0));
+        for x in 0..CHUNK_WIDTH {
+            for z in 0..CHUNK_WIDTH {
+                for y in 0..64 {
+                    chunk.set_block_at(x, y, z, BlockId::stone());
+                }
+            }
+        }
+        // A cave with a torch, and a shaft open to the sky.
+        chunk.set_block_at(8, 10, 8, BlockId::air());
+        chunk.set_block_at(8, 11, 8, BlockId::glowstone());
+        chunk.set_block_at(2, 63, 2, BlockId::air());
+        chunk.set_block_at(2, 62, 2, BlockId::air());
+        chunk.set_block_at(3, 62, 2, BlockId::air());
+
+        light_chunk(&mut chunk);
+
+        assert_eq!(chunk.sky_light_at(8, 10, 8), Some(0));
+        assert_eq!(chunk.block_light_at(8, 10, 8), Some(14));
+        assert_eq!(chunk.block_light_at(8, 11, 8), Some(15));
+        assert_eq!(chunk.sky_light_at(8, 64, 8), Some(15));
+        assert_eq!(chunk.sky_light_at(2, 62, 2), Some(15));
+        assert_eq!(chunk.sky_light_at(3, 62, 2), Some(14));
+        assert_eq!(chunk.block_light_at(3, 62, 2), Some(0));
+    }
+}
@@ -0,0 +1,607 @@
+//! Validation of the movement sent by players' clients.
+//!
+//! Players move on the client, so a modified client could
+//! fly, walk through walls or teleport. Moves which break
+//! the rules are rejected with a [`MovementViolation`].
+
+use base::{BlockId, EntityKind, Gamemode, Position};
+use blocks::{BlockKind, SimplifiedBlockKind};
+use ecs::{Entity, SysResult};
+use quill_common::{
+    components::{CreativeFlying, Sprinting},
+    events::MovementViolation,
+};
+
+use crate::{
+    physics::{self, Aabb},
+    Game,
+};
+
+/// Vertical moves farther than this are teleports rather
+/// than falls, since falling entities never move this fast.
+pub const MAX_MOVE_DISTANCE: f64 = 10.0;
+
+/// The maximum horizontal distance a walking player may move
+/// per tick. It is above the vanilla walking speed to allow
+/// for the momentum of jumps.
+const MAX_WALKING_SPEED: f64 = 0.5;
+
+/// The maximum horizontal distance a sprinting player may move
+/// per tick, allowing for sprint-jumping.
+const MAX_SPRINTING_SPEED: f64 = 1.0;
+
+/// The maximum horizontal distance a player flying in
+/// creative mode may move per tick.
+const MAX_FLYING_SPEED: f64 = 1.2;
+
+/// The maximum horizontal distance a spectator may move per tick.
+const MAX_SPECTATOR_SPEED: f64 = 5.0;
+
+/// The maximum vertical distance a player may move per tick,
+/// slightly above the terminal velocity of falling entities.
+const MAX_VERTICAL_SPEED: f64 = 4.0;
+
+/// The distance a move may exceed the maximum speed by.
+const SPEED_TOLERANCE: f64 = 0.1;
+
+/// The maximum number of ticks a player may save up movement
+/// for by not moving, e.g. while their connection lags.
+const MAX_ELAPSED_TICKS: u64 = 10;
+
+/// The vertical velocity of a jump without Jump Boost.
+const JUMP_VELOCITY: f64 = 0.42;
+
+/// Vertical velocity lost by falling players each tick,
+/// before drag is applied.
+const GRAVITY: f64 = 0.08;
+
+/// Factor by which the vertical velocity of
+/// falling players is multiplied each tick.
+const DRAG: f64 = 0.98;
+
+/// How far a player in the air may be above the
+/// height they would reach by jumping.
+const FLIGHT_TOLERANCE: f64 = 1.0;
+
+/// The number of ticks a player's moves may lag behind the server.
+/// Used to count the ticks a player has been in the air when
+/// their client sends fewer moves than ticks pass.
+const MAX_LAG_TICKS: u64 = 20;
+
+/// The height of the player's bounding box checked for collision
+/// with blocks. It is the height of a crawling player, so
+/// crawling and swimming through low gaps is not rejected.
+const COLLISION_HEIGHT: f64 = 0.6;
+
+/// Component storing the levels of the status effects
+/// changing how a player may move.
+///
+/// Players without this component have no such effects.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct MovementEffects {
+    /// Level of Speed, increasing speed by 20% per level.
+    pub speed: u8,
+    /// Level of Jump Boost, increasing jump
+    /// velocity by 0.1 per level.
+    pub jump_boost: u8,
+    /// Whether the player has Slow Falling.
+    pub slow_falling: bool,
+}
+
+/// Component added to players gliding with an elytra.
+/// It is removed once they land.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Gliding;
+
+/// Component tracking a player's accepted moves
+/// to validate their speed and flight.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct MovementTracker {
+    /// The position after the last accepted move.
+    last_position: Option<Position>,
+    /// The tick `budget` was last refilled.
+    last_tick: u64,
+    /// The horizontal distance the player may still move.
+    /// Refilled each tick according to the player's speed.
+    budget: f64,
+    airborne: Option<Airborne>,
+    /// The vertical velocity of the player's next bounce
+    /// after landing on a slime block or a bed.
+    bounce_velocity: Option<f64>,
+}
+
+/// The state of a player in the air.
+#[derive(Copy, Clone, Debug)]
+struct Airborne {
+    /// The tick before the first move in the air.
+    start_tick: u64,
+    /// The height the player left the ground at.
+    start_y: f64,
+    /// The highest height reached in the air.
+    peak_y: f64,
+    /// The vertical velocity the player may have left the ground with.
+    velocity: f64,
+    /// The number of moves in the air.
+    moves: u64,
+}
+
+impl MovementTracker {
+    /// Refills the movement budget for the ticks elapsed since
+    /// the previous move. Returns the number of ticks elapsed.
+    fn refill(&mut self, tick: u64, from: Position, max_speed: f64) -> u64 {
+        let moved_by_server = self
+            .last_position
+            .map_or(true, |last| last.distance_squared_to(from) > 1e-6);
+        if moved_by_server {
+            // The player joined, respawned or was teleported.
+            *self = Self {
+                last_position: Some(from),
+                last_tick: tick,
+                budget: max_speed,
+                airborne: None,
+                bounce_velocity: None,
+            };
+            return 1;
+        }
+
+        let elapsed = tick.saturating_sub(self.last_tick).min(MAX_ELAPSED_TICKS);
+        self.last_tick = tick;
+        self.budget =
+            (self.budget + max_speed * elapsed as f64).min(max_speed * MAX_ELAPSED_TICKS as f64);
+        elapsed.max(1)
+    }
+
+    fn accept(&mut self, to: Position, distance: f64) {
+        self.last_position = Some(to);
+        self.budget = (self.budget - distance).max(0.0);
+    }
+}
+
+/// Returns whether a player in `gamemode` may fly.
+pub fn can_fly(gamemode: Gamemode) -> bool {
+    matches!(gamemode, Gamemode::Creative | Gamemode::Spectator)
+}
+
+/// Checks whether `player` may move from their current
+/// position to `to`. Returns the rule the move breaks, if any.
+///
+/// Accepted moves are recorded in the player's
+/// [`MovementTracker`], so the move must be applied
+/// if this returns `None`.
+pub fn validate_movement(
+    game: &Game,
+    player: Entity,
+    to: Position,
+) -> SysResult<Option<MovementViolation>> {
+    let from = *game.ecs.get::<Position>(player)?;
+    let gamemode = *game.ecs.get::<Gamemode>(player)?;
+
+    if to.chunk() != from.chunk() && !game.world.is_chunk_loaded(to.chunk()) {
+        return Ok(Some(MovementViolation::UnloadedChunk));
+    }
+
+    let flying = can_fly(gamemode) && game.ecs.get::<CreativeFlying>(player)?.0;
+    let gliding = game.ecs.get::<Gliding>(player).is_ok();
+    let sprinting = game
+        .ecs
+        .get::<Sprinting>(player)
+        .map_or(false, |sprinting| sprinting.0);
+    let effects = game
+        .ecs
+        .get::<MovementEffects>(player)
+        .map(|effects| *effects)
+        .unwrap_or_default();
+    let mut tracker = game.ecs.get_mut::<MovementTracker>(player)?;
+
+    let max_speed = max_speed(gamemode, flying, sprinting, effects);
+    let elapsed = tracker.refill(game.tick_count, from, max_speed);
+    let distance = ((to.x - from.x).powi(2) + (to.z - from.z).powi(2)).sqrt();
+    if distance > tracker.budget + SPEED_TOLERANCE
+        || (to.y - from.y).abs() > MAX_VERTICAL_SPEED * elapsed as f64 + SPEED_TOLERANCE
+    {
+        return Ok(Some(MovementViolation::TooFast));
+    }
+
+    // Spectators fly through blocks.
+    if gamemode == Gamemode::Spectator {
+        tracker.accept(to, distance);
+        return Ok(None);
+    }
+
+    // Players stuck in a block may move out of it. Blocks
+    // with complex shapes are ignored, since their approximate
+    // boxes cover space players may walk through.
+    if physics::collides_with_exact_blocks(game, collision_box(to))
+        && !physics::collides_with_exact_blocks(game, collision_box(from))
+    {
+        return Ok(Some(MovementViolation::Collision));
+    }
+
+    if flying || gliding || effects.slow_falling {
+        tracker.airborne = None;
+        tracker.bounce_velocity = None;
+    } else if is_supported(game, to) {
+        if is_on_bouncy_block(game, to) {
+            // Players bounce back up about as fast as they landed.
+            if let Some(airborne) = tracker.airborne {
+                tracker.bounce_velocity = Some(landing_speed(airborne.peak_y - to.y));
+            }
+        } else {
+            tracker.bounce_velocity = None;
+        }
+        tracker.airborne = None;
+    } else {
+        let velocity = tracker
+            .bounce_velocity
+            .take()
+            .unwrap_or_default()
+            .max(jump_velocity(effects));
+        let airborne = tracker.airborne.get_or_insert(Airborne {
+            start_tick: game.tick_count.saturating_sub(1),
+            start_y: from.y,
+            peak_y: from.y,
+            velocity,
+            moves: 0,
+        });
+        airborne.moves += 1;
+        airborne.peak_y = airborne.peak_y.max(to.y);
+        let ticks = airborne.moves.max(
+            game.tick_count
+                .saturating_sub(airborne.start_tick)
+                .saturating_sub(MAX_LAG_TICKS),
+        );
+        let max_y = airborne.start_y + jump_height(airborne.velocity, ticks) + FLIGHT_TOLERANCE;
+        if to.y > max_y {
+            return Ok(Some(MovementViolation::Flight));
+        }
+    }
+
+    tracker.accept(to, distance);
+    Ok(None)
+}
+
+/// Returns the horizontal distance a player may move per tick.
+fn max_speed(gamemode: Gamemode, flying: bool, sprinting: bool, effects: MovementEffects) -> f64 {
+    let speed = if gamemode == Gamemode::Spectator {
+        MAX_SPECTATOR_SPEED
+    } else if flying {
+        MAX_FLYING_SPEED
+    } else if sprinting {
+        MAX_SPRINTING_SPEED
+    } else {
+        MAX_WALKING_SPEED
+    };
+    speed * (1.0 + 0.2 * effects.speed as f64)
+}
+
+fn jump_velocity(effects: MovementEffects) -> f64 {
+    JUMP_VELOCITY + 0.1 * effects.jump_boost as f64
+}
+
+/// Returns the height a player jumping with the given vertical
+/// velocity reaches after falling for `ticks` ticks.
+fn jump_height(velocity: f64, ticks: u64) -> f64 {
+    // The velocity approaches the terminal velocity
+    // geometrically, so the height has a closed form.
+    let terminal_velocity = GRAVITY * DRAG / (1.0 - DRAG);
+    let ticks = ticks.min(1000);
+    let decay = DRAG.powi(ticks as i32);
+    (velocity + terminal_velocity) * (1.0 - decay) / (1.0 - DRAG) - terminal_velocity * ticks as f64
+}
+
+/// Returns the speed of a player landing after
+/// falling `height` blocks from rest.
+fn landing_speed(height: f64) -> f64 {
+    let (mut fallen, mut velocity) = (0.0, 0.0);
+    while fallen < height {
+        velocity = (velocity + GRAVITY) * DRAG;
+        fallen += velocity;
+    }
+    velocity
+}
+
+/// Returns whether entities inside `block` are held up by it,
+/// so that they can climb or swim instead of falling.
+pub(crate) fn holds_up(block: BlockId) -> bool {
+    block.waterlogged() == Some(true)
+        || matches!(
+            block.kind(),
+            BlockKind::Water
+                | BlockKind::Lava
+                | BlockKind::Kelp
+                | BlockKind::KelpPlant
+                | BlockKind::Seagrass
+                | BlockKind::TallSeagrass
+                | BlockKind::BubbleColumn
+                | BlockKind::Ladder
+                | BlockKind::Vine
+                | BlockKind::Scaffolding
+                | BlockKind::Cobweb
+                | BlockKind::TwistingVines
+                | BlockKind::TwistingVinesPlant
+                | BlockKind::WeepingVines
+                | BlockKind::WeepingVinesPlant
+        )
+}
+
+fn collision_box(position: Position) -> Aabb {
+    let mut bbox = Aabb::of_entity(EntityKind::Player, position);
+    bbox.max[1] = bbox.min[1] + COLLISION_HEIGHT;
+    bbox
+}
+
+/// Returns whether a player at `position` is held up by
+/// a block, i.e. standing on a block or climbing.
+fn is_supported(game: &Game, position: Position) -> bool {
+    let feet = position.block();
+    let held_up = [feet, feet.up()]
+        .iter()
+        .any(|&position| game.block(position).map_or(false, holds_up));
+    if held_up {
+        return true;
+    }
+
+    // Look for a block just below the player's feet.
+    let mut below = Aabb::of_entity(EntityKind::Player, position);
+    for axis in &[0, 2] {
+        below.min[*axis] -= 0.0625;
+        below.max[*axis] += 0.0625;
+    }
+    below.max[1] = below.min[1];
+    below.min[1] -= 0.55;
+    physics::collides_with_blocks(game, below)
+}
+
+/// Returns whether a player at `position` stands on
+/// a block bouncing them back up, like a slime block.
+fn is_on_bouncy_block(game: &Game, position: Position) -> bool {
+    let feet = position.block();
+    [feet, feet.down()].iter().any(|&position| {
+        game.block(position).map_or(false, |block| {
+            block.kind() == BlockKind::SlimeBlock
+                || block.simplified_kind() == SimplifiedBlockKind::Bed
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use base::{position, BlockId, BlockPosition, Chunk, ChunkPosition};
+
+    use super::*;
+
+    fn game() -> (Game, Entity) {
+        let mut game = Game::new();
+        let mut chunk = Chunk::new(ChunkPosition::new(0, 0));
+        for x in 0..16 {
+            for z in 0..16 {
+                chunk.set_block_at(x, 63, z, BlockId::stone());
+            }
+        }
+        game.world.chunk_map_mut().insert_chunk(chunk);
+        let player = game.ecs.spawn((
+            position!(8.5, 64.0, 8.5),
+            Gamemode::Survival,
+            CreativeFlying(false),
+            Sprinting(false),
+            MovementTracker::default(),
+        ));
+        (game, player)
+    }
+
+    /// Validates a move and applies it if it is accepted.
+    fn move_to(game: &mut Game, player: Entity, to: Position) -> Option<MovementViolation> {
+        let violation = validate_movement(game, player, to).unwrap();
+        if violation.is_none() {
+            *game.ecs.get_mut::<Position>(player).unwrap() = to;
+        }
+        violation
+    }
+
+    #[test]
+    fn walking_is_allowed() {
+        let (game, player) = game();
+        assert_eq!(
+            validate_movement(&game, player, position!(8.7, 64.0, 8.5)).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn teleporting_and_unloaded_chunks_are_rejected() {
+        let (game, player) = game();
+        assert_eq!(
+            validate_movement(&game, player, position!(0.5, 64.0, 0.5)).unwrap(),
+            Some(MovementViolation::TooFast)
+        );
+        assert_eq!(
+            validate_movement(&game, player, position!(-1.0, 64.0, 8.5)).unwrap(),
+            Some(MovementViolation::UnloadedChunk)
+        );
+    }
+
+    #[test]
+    fn moving_faster_than_the_speed_per_tick_is_rejected() {
+        let (mut game, player) = game();
+        assert_eq!(
+            move_to(&mut game, player, position!(9.2, 64.0, 8.5)),
+            Some(MovementViolation::TooFast)
+        );
+
+        // Sending more moves per tick does not make players faster.
+        game.tick_count += 1;
+        assert_eq!(move_to(&mut game, player, position!(8.9, 64.0, 8.5)), None);
+        assert_eq!(move_to(&mut game, player, position!(9.3, 64.0, 8.5)), None);
+        assert_eq!(
+            move_to(&mut game, player, position!(9.7, 64.0, 8.5)),
+            Some(MovementViolation::TooFast)
+        );
+
+        game.tick_count += 1;
+        assert_eq!(move_to(&mut game, player, position!(9.7, 64.0, 8.5)), None);
+    }
+
+    #[test]
+    fn sprinting_increases_speed() {
+        let (mut game, player) = game();
+        game.ecs.get_mut::<Sprinting>(player).unwrap().0 = true;
+        assert_eq!(move_to(&mut game, player, position!(9.2, 64.0, 8.5)), None);
+    }
+
+    #[test]
+    fn speed_effect_increases_speed() {
+        let (mut game, player) = game();
+        game.ecs
+            .insert(
+                player,
+                MovementEffects {
+                    speed: 2,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        assert_eq!(move_to(&mut game, player, position!(9.25, 64.0, 8.5)), None);
+    }
+
+    #[test]
+    fn moving_into_blocks_is_rejected() {
+        let (mut game, player) = game();
+        game.set_block(BlockPosition::new(9, 64, 8), BlockId::stone());
+        assert_eq!(
+            validate_movement(&game, player, position!(8.8, 64.0, 8.5)).unwrap(),
+            Some(MovementViolation::Collision)
+        );
+    }
+
+    #[test]
+    fn moving_into_doors_and_ladders_is_allowed() {
+        let (mut game, player) = game();
+        game.set_block(BlockPosition::new(9, 64, 8), BlockId::oak_door());
+        assert_eq!(
+            validate_movement(&game, player, position!(8.8, 64.0, 8.5)).unwrap(),
+            None
+        );
+
+        game.set_block(BlockPosition::new(9, 64, 8), BlockId::ladder());
+        assert_eq!(
+            validate_movement(&game, player, position!(8.8, 64.0, 8.5)).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn jumping_is_allowed() {
+        let (mut game, player) = game();
+        let mut y = 64.0;
+        let mut velocity = JUMP_VELOCITY;
+        while y + velocity > 64.0 {
+            y += velocity;
+            velocity = (velocity - GRAVITY) * DRAG;
+            game.tick_count += 1;
+            assert_eq!(move_to(&mut game, player, position!(8.5, y, 8.5)), None);
+        }
+    }
+
+    #[test]
+    fn floating_is_rejected() {
+        let (mut game, player) = game();
+        *game.ecs.get_mut::<Position>(player).unwrap() = position!(8.5, 70.0, 8.5);
+        let mut violation = None;
+        for _ in 0..40 {
+            game.tick_count += 1;
+            violation = move_to(&mut game, player, position!(8.5, 70.0, 8.5));
+            if violation.is_some() {
+                break;
+            }
+        }
+        assert_eq!(violation, Some(MovementViolation::Flight));
+
+        *game.ecs.get_mut::<Gamemode>(player).unwrap() = Gamemode::Creative;
+        game.ecs.get_mut::<CreativeFlying>(player).unwrap().0 = true;
+        assert_eq!(move_to(&mut game, player, position!(8.5, 70.0, 8.5)), None);
+    }
+
+    #[test]
+    fn descending_slowly_is_rejected() {
+        let (mut game, player) = game();
+        let mut y = 70.0;
+        *game.ecs.get_mut::<Position>(player).unwrap() = position!(8.5, y, 8.5);
+        let mut violation = None;
+        for _ in 0..40 {
+            game.tick_count += 1;
+            y -= 0.03;
+            violation = move_to(&mut game, player, position!(8.5, y, 8.5));
+            if violation.is_some() {
+                break;
+            }
+        }
+        assert_eq!(violation, Some(MovementViolation::Flight));
+    }
+
+    #[test]
+    fn bouncing_on_slime_is_allowed() {
+        let (mut game, player) = game();
+        game.set_block(BlockPosition::new(8, 63, 8), BlockId::slime_block());
+        let mut y = 72.0;
+        *game.ecs.get_mut::<Position>(player).unwrap() = position!(8.5, y, 8.5);
+
+        let mut velocity = 0.0;
+        while y > 64.0 {
+            velocity = (velocity - GRAVITY) * DRAG;
+            y = (y + velocity).max(64.0);
+            game.tick_count += 1;
+            assert_eq!(move_to(&mut game, player, position!(8.5, y, 8.5)), None);
+        }
+
+        velocity = -velocity;
+        while velocity > 0.0 {
+            y += velocity;
+            velocity = (velocity - GRAVITY) * DRAG;
+            game.tick_count += 1;
+            assert_eq!(move_to(&mut game, player, position!(8.5, y, 8.5)), None);
+        }
+    }
+
+    #[test]
+    fn gliding_and_slow_falling_are_allowed() {
+        let (mut game, player) = game();
+        let mut y = 70.0;
+        *game.ecs.get_mut::<Position>(player).unwrap() = position!(8.5, y, 8.5);
+        game.ecs.insert(player, Gliding).unwrap();
+        for _ in 0..40 {
+            game.tick_count += 1;
+            y -= 0.03;
+            assert_eq!(move_to(&mut game, player, position!(8.5, y, 8.5)), None);
+        }
+
+        game.ecs.remove::<Gliding>(player).unwrap();
+        game.ecs
+            .insert(
+                player,
+                MovementEffects {
+                    slow_falling: true,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        for _ in 0..40 {
+            game.tick_count += 1;
+            y -= 0.03;
+            assert_eq!(move_to(&mut game, player, position!(8.5, y, 8.5)), None);
+        }
+    }
+
+    #[test]
+    fn swimming_up_through_kelp_is_allowed() {
+        let (mut game, player) = game();
+        for y in 64..80 {
+            game.set_block(BlockPosition::new(8, y, 8), BlockId::kelp_plant());
+        }
+        let mut y = 64.0;
+        for _ in 0..40 {
+            game.tick_count += 1;
+            y += 0.3;
+            assert_eq!(move_to(&mut game, player, position!(8.5, y, 8.5)), None);
+        }
+    }
+}
@@ -0,0 +1,492 @@
+//! Physics for entities other than players: gravity,
+//! drag and collision with blocks.
+//!
+//! Players move on the client, so their positions
+//! come from movement packets instead.
+
+use base::{BlockId, BlockPosition, EntityKind, Position};
+use blocks::{BlockKind, SimplifiedBlockKind, SlabKind};
+use ecs::{SysResult, SystemExecutor};
+use quill_common::components::{OnGround, Velocity};
+
+use crate::Game;
+
+/// The factor by which horizontal velocity is multiplied
+/// when an entity slides on the ground.
+const GROUND_FRICTION: f64 = 0.6;
+
+/// The height below which entities are in the void. Entities
+/// moved by physics are removed when they fall below it.
+pub const VOID_Y: f64 = -64.0;
+
+/// Tolerance used when comparing box faces.
+const EPSILON: f64 = 1e-7;
+
+pub fn register(systems: &mut SystemExecutor<Game>) {
+    systems.add_system(entity_physics);
+}
+
+/// How gravity and drag affect an entity.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Physics {
+    /// Downward acceleration in blocks per tick squared.
+    pub gravity: f64,
+    /// The factor by which velocity is multiplied each tick.
+    pub drag: f64,
+}
+
+impl Physics {
+    const fn new(gravity: f64, drag: f64) -> Self {
+        Self { gravity, drag }
+    }
+
+    /// Returns the physics of an entity kind, or `None`
+    /// for entities which are not moved by physics:
+    /// players, flying mobs and entities attached to blocks.
+    pub fn of(kind: EntityKind) -> Option<Self> {
+        use EntityKind as Kind;
+        match kind {
+            Kind::Player
+            | Kind::Painting
+            | Kind::ItemFrame
+            | Kind::LeashKnot
+            | Kind::LightningBolt
+            | Kind::AreaEffectCloud
+            | Kind::EndCrystal
+            | Kind::EvokerFangs
+            | Kind::EyeOfEnder
+            | Kind::Bat
+            | Kind::Bee
+            | Kind::Blaze
+            | Kind::Ghast
+            | Kind::Phantom
+            | Kind::Vex
+            | Kind::Parrot
+            | Kind::Wither
+            | Kind::EnderDragon
+            | Kind::Shulker => None,
+            Kind::Item
+            | Kind::FallingBlock
+            | Kind::Tnt
+            | Kind::Boat
+            | Kind::Minecart
+            | Kind::ChestMinecart
+            | Kind::CommandBlockMinecart
+            | Kind::FurnaceMinecart
+            | Kind::HopperMinecart
+            | Kind::SpawnerMinecart
+            | Kind::TntMinecart => Some(Self::new(0.04, 0.98)),
+            Kind::ExperienceOrb => Some(Self::new(0.03, 0.98)),
+            Kind::Arrow | Kind::SpectralArrow | Kind::Trident => Some(Self::new(0.05, 0.99)),
+            Kind::Snowball | Kind::Egg | Kind::EnderPearl => Some(Self::new(0.03, 0.99)),
+            Kind::Potion => Some(Self::new(0.05, 0.99)),
+            Kind::ExperienceBottle => Some(Self::new(0.07, 0.99)),
+            Kind::LlamaSpit => Some(Self::new(0.06, 0.99)),
+            Kind::FishingBobber => Some(Self::new(0.03, 0.92)),
+            Kind::Fireball
+            | Kind::SmallFireball
+            | Kind::DragonFireball
+            | Kind::WitherSkull
+            | Kind::ShulkerBullet => Some(Self::new(0.0, 0.95)),
+            Kind::FireworkRocket => Some(Self::new(0.0, 1.0)),
+            _ => Some(Self::new(0.08, 0.98)),
+        }
+    }
+}
+
+/// An axis-aligned bounding box.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub(crate) struct Aabb {
+    pub min: [f64; 3],
+    pub max: [f64; 3],
+}
+
+impl Aabb {
+    /// Returns the bounding box of an entity of the given kind at `position`.
+    pub fn of_entity(kind: EntityKind, position: Position) -> Self {
+        let size = kind.bounding_box().max;
+        let half_width = size.x / 2.0;
+        Self {
+            min: [position.x - half_width, position.y, position.z - half_width],
+            max: [
+                position.x + half_width,
+                position.y + size.y,
+                position.z + half_width,
+            ],
+        }
+    }
+
+    pub fn offset(mut self, axis: usize, amount: f64) -> Self {
+        self.min[axis] += amount;
+        self.max[axis] += amount;
+        self
+    }
+
+    /// Returns this box stretched to cover `motion`.
+    fn expand(mut self, motion: [f64; 3]) -> Self {
+        for (axis, &distance) in motion.iter().enumerate() {
+            if distance < 0.0 {
+                self.min[axis] += distance;
+            } else {
+                self.max[axis] += distance;
+            }
+        }
+        self
+    }
+
+    fn intersects(&self, other: &Aabb) -> bool {
+        (0..3).all(|a| self.max[a] > other.min[a] + EPSILON && self.min[a] < other.max[a] - EPSILON)
+    }
+
+    /// Returns whether the boxes overlap on every axis except `axis`.
+    fn overlaps_except(&self, other: &Aabb, axis: usize) -> bool {
+        (0..3)
+            .filter(|&a| a != axis)
+            .all(|a| self.max[a] > other.min[a] + EPSILON && self.min[a] < other.max[a] - EPSILON)
+    }
+
+    /// Clips `motion` along `axis` so that this box,
+    /// moved by it, does not enter `obstacle`.
+    fn clip(&self, obstacle: &Aabb, axis: usize, motion: f64) -> f64 {
+        if !self.overlaps_except(obstacle, axis) {
+            return motion;
+        }
+        if motion > 0.0 && obstacle.min[axis] >= self.max[axis] - EPSILON {
+            motion.min(obstacle.min[axis] - self.max[axis])
+        } else if motion < 0.0 && obstacle.max[axis] <= self.min[axis] + EPSILON {
+            motion.max(obstacle.max[axis] - self.min[axis])
+        } else {
+            motion
+        }
+    }
+}
+
+/// Returns the bottom and top of a block's collision box,
+/// relative to the block, or `None` if entities pass through it.
+///
+/// Blocks are approximated by a single box which covers
+/// the whole block horizontally.
+fn collision_height(block: BlockId) -> Option<(f64, f64)> {
+    if !block.is_solid() {
+        return None;
+    }
+    let height = match block.simplified_kind() {
+        SimplifiedBlockKind::Slab => match block.slab_kind() {
+            Some(SlabKind::Top) => return Some((0.5, 1.0)),
+            Some(SlabKind::Bottom) => 0.5,
+            _ => 1.0,
+        },
+        SimplifiedBlockKind::Bed => 0.5625,
+        SimplifiedBlockKind::Carpet => 0.0625,
+        SimplifiedBlockKind::Fence => 1.5,
+        _ => match block.kind() {
+            BlockKind::Farmland | BlockKind::GrassPath => 0.9375,
+            BlockKind::SoulSand => 0.875,
+            BlockKind::Snow => {
+                let layers = block.layers().unwrap_or(1);
+                if layers <= 1 {
+                    return None;
+                }
+                (layers - 1) as f64 / 8.0
+            }
+            _ => 1.0,
+        },
+    };
+    Some((0.0, height))
+}
+
+/// Returns whether the box returned by `collision_height` is
+/// the real collision shape of a block. Blocks with more complex
+/// shapes, like doors, stairs and panes, are only approximated.
+fn has_exact_collision_box(block: BlockId) -> bool {
+    use SimplifiedBlockKind::*;
+    !matches!(
+        block.simplified_kind(),
+        WoodenDoor
+            | IronDoor
+            | CrimsonDoor
+            | WarpedDoor
+            | WoodenTrapdoor
+            | IronTrapdoor
+            | CrimsonTrapdoor
+            | WarpedTrapdoor
+            | FenceGate
+            | Fence
+            | Stairs
+            | GlassPane
+            | StainedGlassPane
+            | IronBars
+            | Chain
+            | Ladder
+            | Scaffolding
+            | CobblestoneWall
+            | MossyCobblestoneWall
+            | BrickWall
+            | PrismarineWall
+            | RedSandstoneWall
+            | MossyStoneBrickWall
+            | GraniteWall
+            | StoneBrickWall
+            | NetherBrickWall
+            | AndesiteWall
+            | RedNetherBrickWall
+            | SandstoneWall
+            | EndStoneBrickWall
+            | DioriteWall
+            | BlackstoneWall
+            | PolishedBlackstoneBrickWall
+            | PolishedBlackstoneWall
+            | Anvil
+            | Bell
+            | Cactus
+            | Cake
+            | Chest
+            | TrappedChest
+            | EnderChest
+            | Cauldron
+            | Hopper
+            | BrewingStand
+            | EnchantingTable
+            | EndPortalFrame
+            | DaylightDetector
+            | Lectern
+            | Grindstone
+            | Stonecutter
+            | Composter
+            | Campfire
+            | SoulCampfire
+            | Lantern
+            | SoulLantern
+            | Conduit
+            | EndRod
+            | ChorusPlant
+            | ChorusFlower
+            | Bamboo
+            | DragonEgg
+            | FlowerPot
+            | SeaPickle
+            | TurtleEgg
+            | PistonHead
+            | LilyPad
+            | Repeater
+            | Comparator
+            | HoneyBlock
+            | SkeletonSkull
+            | SkeletonWallSkull
+            | WitherSkeletonSkull
+            | WitherSkeletonWallSkull
+            | ZombieHead
+            | ZombieWallHead
+            | PlayerHead
+            | PlayerWallHead
+            | CreeperHead
+            | CreeperWallHead
+            | DragonHead
+            | DragonWallHead
+    ) && !block.kind().name().starts_with("potted_")
+}
+
+/// Returns the collision boxes of blocks within `region`.
+/// Unloaded blocks within the world's height are treated as
+/// full blocks so that entities cannot move into unloaded chunks.
+///
+/// Only blocks for which `include` returns `true` are considered.
+fn block_boxes(game: &Game, region: Aabb, include: impl Fn(BlockId) -> bool) -> Vec<Aabb> {
+    let mut boxes = Vec::new();
+    let floor = |c: f64| c.floor() as i32;
+    // Start one block lower to find blocks
+    // taller than one block, like fences.
+    for y in floor(region.min[1]) - 1..=floor(region.max[1]) {
+        for x in floor(region.min[0])..=floor(region.max[0]) {
+            for z in floor(region.min[2])..=floor(region.max[2]) {
+                let height = match game.block(BlockPosition::new(x, y, z)) {
+                    Some(block) if include(block) => collision_height(block),
+                    Some(_) => None,
+                    None if (0..256).contains(&y) => Some((0.0, 1.0)),
+                    None => None,
+                };
+                if let Some((bottom, top)) = height {
+                    let (x, y, z) = (x as f64, y as f64, z as f64);
+                    boxes.push(Aabb {
+                        min: [x, y + bottom, z],
+                        max: [x + 1.0, y + top, z + 1.0],
+                    });
+                }
+            }
+        }
+    }
+    boxes
+}
+
+/// Returns whether `bbox` intersects the collision box of a block.
+pub(crate) fn collides_with_blocks(game: &Game, bbox: Aabb) -> bool {
+    block_boxes(game, bbox, |_| true)
+        .iter()
+        .any(|block| bbox.intersects(block))
+}
+
+/// Returns whether `bbox` intersects the collision box of a block,
+/// ignoring blocks whose collision box is only approximated.
+/// Unlike `collides_with_blocks`, this never reports
+/// collisions with e.g. the empty half of an open door.
+pub(crate) fn collides_with_exact_blocks(game: &Game, bbox: Aabb) -> bool {
+    block_boxes(game, bbox, has_exact_collision_box)
+        .iter()
+        .any(|block| bbox.intersects(block))
+}
+
+/// Moves a bounding box by `motion`, stopping it at blocks.
+/// Returns the distance actually moved.
+fn move_with_collision(game: &Game, bbox: Aabb, motion: [f64; 3]) -> [f64; 3] {
+    let obstacles = block_boxes(game, bbox.expand(motion), |_| true);
+
+    let mut bbox = bbox;
+    let mut moved = [0.0; 3];
+    // Like vanilla, resolve the vertical axis first.
+    for &axis in &[1, 0, 2] {
+        let mut distance = motion[axis];
+        for obstacle in &obstacles {
+            distance = bbox.clip(obstacle, axis, distance);
+        }
+        bbox = bbox.offset(axis, distance);
+        moved[axis] = distance;
+    }
+    moved
+}
+
+/// Applies gravity, drag and velocity to entities
+/// other than players.
+fn entity_physics(game: &mut Game) -> SysResult {
+    let mut fallen = Vec::new();
+    for (entity, (position, velocity, on_ground, &kind)) in game
+        .ecs
+        .query::<(&mut Position, &mut Velocity, &mut OnGround, &EntityKind)>()
+        .iter()
+    {
+        let physics = match Physics::of(kind) {
+            Some(physics) => physics,
+            None => continue,
+        };
+
+        if position.y < VOID_Y {
+            fallen.push(entity);
+            continue;
+        }
+
+        // Entities in unloaded chunks are frozen.
+        let block = match game.block(position.block()) {
+            Some(block) => Some(block.kind()),
+            None if (0.0..256.0).contains(&position.y) => continue,
+            None => None,
+        };
+
+        // Liquids reduce gravity and slow entities down.
+        let (gravity, liquid_drag) = match block {
+            Some(BlockKind::Water) => (physics.gravity / 4.0, Some(0.8)),
+            Some(BlockKind::Lava) => (physics.gravity / 4.0, Some(0.5)),
+            _ => (physics.gravity, None),
+        };
+        velocity.y -= gravity;
+
+        if velocity.x == 0.0 && velocity.y == 0.0 && velocity.z == 0.0 {
+            continue;
+        }
+
+        let motion = [velocity.x, velocity.y, velocity.z];
+        let moved = move_with_collision(game, Aabb::of_entity(kind, *position), motion);
+        position.x += moved[0];
+        position.y += moved[1];
+        position.z += moved[2];
+
+        // Stop movement along axes where a block was hit.
+        if moved[0] != motion[0] {
+            velocity.x = 0.0;
+        }
+        if moved[1] != motion[1] {
+            velocity.y = 0.0;
+        }
+        if moved[2] != motion[2] {
+            velocity.z = 0.0;
+        }
+        on_ground.0 = motion[1] < 0.0 && moved[1] != motion[1];
+
+        match liquid_drag {
+            Some(drag) => {
+                velocity.x *= drag;
+                velocity.y *= drag;
+                velocity.z *= drag;
+            }
+            None => {
+                let friction = if on_ground.0 { GROUND_FRICTION } else { 1.0 };
+                velocity.x *= physics.drag * friction;
+                velocity.y *= physics.drag;
+                velocity.z *= physics.drag * friction;
+            }
+        }
+    }
+
+    for entity in fallen {
+        game.remove_entity(entity)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use base::{position, Chunk, ChunkPosition, Item, ItemStack};
+
+    use crate::entities::add_entity_components;
+
+    use super::*;
+
+    fn game() -> Game {
+        let mut game = Game::new();
+        game.add_entity_spawn_callback(add_entity_components);
+        let mut chunk = Chunk::new(ChunkPosition::new(0, 0));
+        for x in 0..16 {
+            for z in 0..16 {
+                chunk.set_block_at(x, 63, z, BlockId::stone());
+            }
+        }
+        game.world.chunk_map_mut().insert_chunk(chunk);
+        game
+    }
+
+    #[test]
+    fn items_fall_and_land_on_blocks() {
+        let mut game = game();
+        let item = game.drop_item(position!(8.5, 66.0, 8.5), ItemStack::new(Item::Stone, 1));
+        game.ecs.get_mut::<OnGround>(item).unwrap().0 = false;
+
+        for _ in 0..100 {
+            entity_physics(&mut game).unwrap();
+        }
+
+        let position = *game.ecs.get::<Position>(item).unwrap();
+        assert!((position.y - 64.0).abs() < 1e-6, "{}", position.y);
+        assert!(game.ecs.get::<OnGround>(item).unwrap().0);
+        assert_eq!(game.ecs.get::<Velocity>(item).unwrap().y, 0.0);
+    }
+
+    #[test]
+    fn walls_stop_horizontal_movement() {
+        let mut game = game();
+        game.set_block(BlockPosition::new(9, 64, 8), BlockId::stone());
+        let item = game.drop_item(position!(8.5, 64.0, 8.5), ItemStack::new(Item::Stone, 1));
+        *game.ecs.get_mut::<Velocity>(item).unwrap() = Velocity::new(1.0, 0.0, 0.0);
+
+        entity_physics(&mut game).unwrap();
+
+        let position = *game.ecs.get::<Position>(item).unwrap();
+        assert!((position.x - 8.875).abs() < 1e-6, "{}", position.x);
+        assert_eq!(game.ecs.get::<Velocity>(item).unwrap().x, 0.0);
+    }
+
+    #[test]
+    fn slabs_have_half_height() {
+        let slab = BlockId::stone_slab().with_slab_kind(SlabKind::Bottom);
+        assert_eq!(collision_height(slab), Some((0.0, 0.5)));
+        assert_eq!(collision_height(BlockId::stone()), Some((0.0, 1.0)));
+        assert_eq!(collision_height(BlockId::air()), None);
+    }
+}
@@ -0,0 +1,18 @@
+//! Advances the world time each tick.
+
+use ecs::{SysResult, SystemExecutor};
+
+use crate::Game;
+
+pub fn register(systems: &mut SystemExecutor<Game>) {
+    systems.add_system(advance_time);
+}
+
+fn advance_time(game: &mut Game) -> SysResult {
+    let level = game.world.level_mut();
+    level.time += 1;
+    if level.game_rule_bool("doDaylightCycle").unwrap_or(true) {
+        level.day_time += 1;
+    }
+    Ok(())
+}
@@ -2,7 +2,7 @@ use ahash::AHashSet;
 use base::{ChunkPosition, Position};
 use ecs::{SysResult, SystemExecutor};
 use itertools::Either;
-use quill_common::components::Name;
+use quill_common::components::{Name, ViewDistance};
 
 use crate::{
     events::{PlayerJoinEvent, ViewUpdateEvent},
@@ -16,15 +16,50 @@ pub fn register(_game: &mut Game, systems: &mut SystemExecutor<Game>) {
         .add_system(update_view_on_join);
 }
 
-/// Updates players' views when they change chunks.
+/// The maximum view distance allowed by the server.
+///
+/// Stored as a resource. If it is missing,
+/// view distances are not limited.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct MaxViewDistance(pub u32);
+
+/// The view distance requested by a player's client.
+///
+/// Kept apart from the `ViewDistance` component, which plugins
+/// may set; a player's view uses the smaller of the two.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ClientViewDistance(pub u32);
+
+/// Updates players' views when they change chunks
+/// or their view distance changes.
 fn update_player_views(game: &mut Game) -> SysResult {
+    let max_view_distance = game
+        .resources
+        .get::<MaxViewDistance>()
+        .map_or(u32::MAX, |max| max.0);
+
     let mut events = Vec::new();
-    for (player, (view, &position, name)) in
-        game.ecs.query::<(&mut View, &Position, &Name)>().iter()
+    for (player, (view, &position, view_distance, client_view_distance, name)) in game
+        .ecs
+        .query::<(
+            &mut View,
+            &Position,
+            Option<&ViewDistance>,
+            Option<&ClientViewDistance>,
+            &Name,
+        )>()
+        .iter()
     {
-        if position.chunk() != view.center() {
+        let view_distance = match (view_distance, client_view_distance) {
+            (Some(distance), Some(client)) => distance.0.min(client.0),
+            (Some(distance), None) => distance.0,
+            (None, Some(client)) => client.0,
+            (None, None) => view.view_distance(),
+        }
+        .min(max_view_distance);
+        if position.chunk() != view.center() || view_distance != view.view_distance() {
             let old_view = *view;
-            let new_view = View::new(position.chunk(), old_view.view_distance);
+            let new_view = View::new(position.chunk(), view_distance);
 
             let event = ViewUpdateEvent::new(old_view, new_view);
             events.push((player, event));
@@ -163,3 +198,57 @@ impl View {
         self.center.z + self.view_distance as i32
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use base::position;
+
+    use super::*;
+
+    #[test]
+    fn changing_view_distance_resizes_view() {
+        let mut game = Game::new();
+        let player = game.ecs.spawn((
+            View::new(ChunkPosition::new(0, 0), 2),
+            position!(0.0, 64.0, 0.0),
+            ViewDistance(2),
+            Name::new("test"),
+        ));
+
+        update_player_views(&mut game).unwrap();
+        assert!(game.ecs.get::<ViewUpdateEvent>(player).is_err());
+
+        game.ecs.get_mut::<ViewDistance>(player).unwrap().0 = 1;
+        update_player_views(&mut game).unwrap();
+
+        let event = game.ecs.get::<ViewUpdateEvent>(player).unwrap();
+        assert!(event.new_chunks.is_empty());
+        assert_eq!(event.old_chunks.len(), 5 * 5 - 3 * 3);
+        assert_eq!(game.ecs.get::<View>(player).unwrap().view_distance(), 1);
+    }
+
+    #[test]
+    fn view_distance_is_limited_by_client_and_server() {
+        let mut game = Game::new();
+        game.insert_resource(MaxViewDistance(4));
+        let player = game.ecs.spawn((
+            View::new(ChunkPosition::new(0, 0), 4),
+            position!(0.0, 64.0, 0.0),
+            ViewDistance(8),
+            ClientViewDistance(4),
+            Name::new("test"),
+        ));
+
+        update_player_views(&mut game).unwrap();
+        assert!(game.ecs.get::<ViewUpdateEvent>(player).is_err());
+
+        game.ecs.get_mut::<ClientViewDistance>(player).unwrap().0 = 3;
+        update_player_views(&mut game).unwrap();
+        assert_eq!(game.ecs.get::<View>(player).unwrap().view_distance(), 3);
+
+        game.ecs.get_mut::<ViewDistance>(player).unwrap().0 = 2;
+        game.ecs.get_mut::<ClientViewDistance>(player).unwrap().0 = 16;
+        update_player_views(&mut game).unwrap();
+        assert_eq!(game.ecs.get::<View>(player).unwrap().view_distance(), 2);
+    }
+}
@@ -1,7 +1,7 @@
 use std::mem;
 
 use anyhow::{anyhow, bail};
-use base::{Area, Item, ItemStack};
+use base::{Area, Inventory, Item, ItemStack};
 
 use ecs::SysResult;
 pub use generated::Window as BackingWindow;
@@ -207,6 +207,39 @@ impl Window {
     }
 }
 
+/// Adds an item stack to a player's inventory, filling stacks
+/// of the same type in the hotbar and main inventory before
+/// empty slots.
+///
+/// Items which don't fit are left in `stack`.
+pub fn insert_item(inventory: &Inventory, stack: &mut ItemStack) {
+    for &area in &[Area::Hotbar, Area::Storage] {
+        let mut i = 0;
+        while let Some(mut slot) = inventory.item(area, i) {
+            if let Some(slot) = slot.as_mut() {
+                if slot.has_same_type(stack) {
+                    stack.transfer_to(u32::MAX, slot);
+                }
+            }
+            i += 1;
+        }
+    }
+
+    for &area in &[Area::Hotbar, Area::Storage] {
+        let mut i = 0;
+        while let Some(mut slot) = inventory.item(area, i) {
+            if stack.count() == 0 {
+                return;
+            }
+            if slot.is_none() {
+                let max = stack.item().stack_size();
+                *slot = Some(stack.take(max));
+            }
+            i += 1;
+        }
+    }
+}
+
 /// Determines whether the given area will accept the given item
 /// for shift-click transfer.
 fn will_accept(area: Area, stack: &ItemStack) -> bool {
@@ -586,6 +619,24 @@ mod tests {
         assert_eq!(window.cursor_item, Some(ItemStack::new(Item::Stone, 62)));
     }
 
+    #[test]
+    fn insert_item_fills_stacks_then_empty_slots() {
+        let inventory = Inventory::player();
+
+        let mut stack = ItemStack::new(Item::Diamond, 100);
+        insert_item(&inventory, &mut stack);
+        assert_eq!(stack.count(), 0);
+
+        let mut stack = ItemStack::new(Item::Diamond, 30);
+        insert_item(&inventory, &mut stack);
+        assert_eq!(stack.count(), 0);
+
+        let slot = |i| inventory.item(Area::Hotbar, i).unwrap().clone();
+        assert_eq!(slot(0), Some(ItemStack::new(Item::Diamond, 64)));
+        assert_eq!(slot(1), Some(ItemStack::new(Item::Diamond, 64)));
+        assert_eq!(slot(2), Some(ItemStack::new(Item::Diamond, 2)));
+    }
+
     fn window() -> Window {
         Window::new(BackingWindow::Player {
             player: Inventory::player(),
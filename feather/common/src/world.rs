@@ -1,10 +1,12 @@
 use ahash::{AHashMap, AHashSet};
-use base::{BlockPosition, Chunk, ChunkPosition, CHUNK_HEIGHT};
+use base::{anvil::level::LevelData, BlockPosition, Chunk, ChunkPosition, CHUNK_HEIGHT};
 use blocks::BlockId;
 use ecs::Ecs;
 use parking_lot::{RwLock, RwLockReadGuard, RwLockWriteGuard};
 use std::sync::Arc;
 
+use quill_common::events::ChunkLoadEvent as PluginChunkLoadEvent;
+
 use crate::{
     events::ChunkLoadEvent,
     world_source::{null::NullWorldSource, ChunkLoadResult, WorldSource},
@@ -21,6 +23,10 @@ pub struct World {
     world_source: Box<dyn WorldSource>,
     loading_chunks: AHashSet<ChunkPosition>,
     canceled_chunk_loads: AHashSet<ChunkPosition>,
+    /// Chunks modified since they were last saved.
+    dirty_chunks: AHashSet<ChunkPosition>,
+    /// Global world data stored in `level.dat`.
+    level: LevelData,
 }
 
 impl Default for World {
@@ -30,6 +36,8 @@ impl Default for World {
             world_source: Box::new(NullWorldSource::default()),
             loading_chunks: AHashSet::new(),
             canceled_chunk_loads: AHashSet::new(),
+            dirty_chunks: AHashSet::new(),
+            level: LevelData::default(),
         }
     }
 }
@@ -81,13 +89,21 @@ impl World {
                 chunk: Arc::clone(&self.chunk_map.0[&loaded.pos]),
                 position: loaded.pos,
             });
+            ecs.insert_event(PluginChunkLoadEvent {
+                position: loaded.pos,
+            });
             log::trace!("Loaded chunk {:?}", loaded.pos);
         }
     }
 
-    /// Unloads the given chunk.
+    /// Unloads the given chunk, saving it
+    /// first if it has been modified.
     pub fn unload_chunk(&mut self, pos: ChunkPosition) {
-        self.chunk_map.remove_chunk(pos);
+        if let Some(chunk) = self.chunk_map.remove_chunk(pos) {
+            if self.dirty_chunks.remove(&pos) {
+                self.world_source.queue_save(chunk);
+            }
+        }
         if self.is_chunk_loading(pos) {
             self.canceled_chunk_loads.insert(pos);
         }
@@ -111,8 +127,53 @@ impl World {
     /// if its chunk was not loaded or the coordinates
     /// are out of bounds and thus no operation
     /// was performed.
-    pub fn set_block_at(&self, pos: BlockPosition, block: BlockId) -> bool {
-        self.chunk_map.set_block_at(pos, block)
+    pub fn set_block_at(&mut self, pos: BlockPosition, block: BlockId) -> bool {
+        let was_successful = self.chunk_map.set_block_at(pos, block);
+        if was_successful {
+            self.mark_chunk_dirty(pos.into());
+        }
+        was_successful
+    }
+
+    /// Marks the given chunk as modified so that
+    /// it is saved on unload or the next autosave.
+    ///
+    /// Must be called after modifying a chunk
+    /// through the [`ChunkMap`] directly.
+    pub fn mark_chunk_dirty(&mut self, pos: ChunkPosition) {
+        if self.is_chunk_loaded(pos) {
+            self.dirty_chunks.insert(pos);
+        }
+    }
+
+    /// Returns whether the given chunk has been
+    /// modified since it was last saved.
+    pub fn is_chunk_dirty(&self, pos: ChunkPosition) -> bool {
+        self.dirty_chunks.contains(&pos)
+    }
+
+    /// Queues all modified chunks to be saved.
+    pub fn save_dirty_chunks(&mut self) {
+        for pos in self.dirty_chunks.drain() {
+            if let Some(chunk) = self.chunk_map.chunk_handle_at(pos) {
+                self.world_source.queue_save(chunk);
+            }
+        }
+    }
+
+    /// Saves all modified chunks and blocks until
+    /// the world source has written them.
+    pub fn flush(&mut self) {
+        self.save_dirty_chunks();
+        self.world_source.flush();
+    }
+
+    /// Saves all modified chunks and stops the world source,
+    /// waiting for its worker threads to exit. Call this
+    /// once, before the server exits.
+    pub fn shutdown(&mut self) {
+        self.save_dirty_chunks();
+        self.world_source.shutdown();
     }
 
     /// Retrieves the block at the specified
@@ -123,6 +184,17 @@ impl World {
         self.chunk_map.block_at(pos)
     }
 
+    /// Returns the level data, which stores the spawn
+    /// point, seed, time, weather and game rules.
+    pub fn level(&self) -> &LevelData {
+        &self.level
+    }
+
+    /// Mutably gets the level data.
+    pub fn level_mut(&mut self) -> &mut LevelData {
+        &mut self.level
+    }
+
     /// Returns the chunk map.
     pub fn chunk_map(&self) -> &ChunkMap {
         &self.chunk_map
@@ -186,6 +258,7 @@ impl ChunkMap {
 
         self.chunk_at_mut(pos.into())
             .map(|mut chunk| chunk.set_block_at(x, y, z, block))
+            .flatten()
             .is_some()
     }
 
@@ -200,9 +273,9 @@ impl ChunkMap {
             .insert(chunk.position(), Arc::new(RwLock::new(chunk)));
     }
 
-    /// Removes the chunk at the given position, returning `true` if it existed.
-    pub fn remove_chunk(&mut self, pos: ChunkPosition) -> bool {
-        self.0.remove(&pos).is_some()
+    /// Removes the chunk at the given position, returning it if it existed.
+    pub fn remove_chunk(&mut self, pos: ChunkPosition) -> Option<Arc<RwLock<Chunk>>> {
+        self.0.remove(&pos)
     }
 }
 
@@ -236,4 +309,21 @@ mod tests {
         assert!(world.block_at(BlockPosition::new(0, -1, 0)).is_none());
         assert!(world.block_at(BlockPosition::new(0, 0, 0)).is_some());
     }
+
+    #[test]
+    fn set_block_marks_chunk_dirty() {
+        let mut world = World::new();
+        let pos = ChunkPosition::new(0, 0);
+        world.chunk_map_mut().insert_chunk(Chunk::new(pos));
+
+        assert!(!world.is_chunk_dirty(pos));
+        assert!(!world.set_block_at(BlockPosition::new(0, -1, 0), BlockId::stone()));
+        assert!(!world.is_chunk_dirty(pos));
+
+        assert!(world.set_block_at(BlockPosition::new(0, 64, 0), BlockId::stone()));
+        assert!(world.is_chunk_dirty(pos));
+
+        world.save_dirty_chunks();
+        assert!(!world.is_chunk_dirty(pos));
+    }
 }
@@ -1,6 +1,10 @@
+use std::sync::Arc;
+
 use base::{Chunk, ChunkPosition};
+use parking_lot::RwLock;
 
 pub mod flat;
+pub mod generating;
 pub mod null;
 pub mod region;
 
@@ -35,6 +39,24 @@ pub trait WorldSource: 'static {
     /// same order they were queued for loading.
     fn poll_loaded_chunk(&mut self) -> Option<LoadedChunk>;
 
+    /// Enqueues the given chunk to be saved.
+    ///
+    /// The default implementation discards the chunk,
+    /// which is correct for sources that cannot persist data.
+    fn queue_save(&mut self, _chunk: Arc<RwLock<Chunk>>) {}
+
+    /// Blocks until all chunks queued with `queue_save`
+    /// have been written.
+    fn flush(&mut self) {}
+
+    /// Writes all queued chunks and stops any background
+    /// threads. No methods are called after this one.
+    ///
+    /// The default implementation calls `flush`.
+    fn shutdown(&mut self) {
+        self.flush();
+    }
+
     /// Creates a `WorldSource` that falls back to `fallback`
     /// if chunks in `self` are missing or corrupt.
     fn with_fallback(self, fallback: impl WorldSource) -> FallbackWorldSource
@@ -81,4 +103,19 @@ impl WorldSource for FallbackWorldSource {
             .flatten()
             .or_else(|| self.fallback.poll_loaded_chunk())
     }
+
+    fn queue_save(&mut self, chunk: Arc<RwLock<Chunk>>) {
+        // Chunks generated by the fallback are saved
+        // to the first source so they persist.
+        self.first.queue_save(chunk);
+    }
+
+    fn flush(&mut self) {
+        self.first.flush();
+    }
+
+    fn shutdown(&mut self) {
+        self.first.shutdown();
+        self.fallback.shutdown();
+    }
 }
@@ -0,0 +1,71 @@
+use std::{sync::Arc, thread};
+
+use base::ChunkPosition;
+use flume::{Receiver, Sender};
+use worldgen::WorldGenerator;
+
+use super::{ChunkLoadResult, LoadedChunk, WorldSource};
+
+/// Number of threads used to generate chunks.
+const NUM_WORKERS: usize = 4;
+
+/// World source generating chunks with a [`WorldGenerator`].
+///
+/// Generation and initial lighting run on a pool of
+/// worker threads so that they do not block the main thread.
+pub struct GeneratingWorldSource {
+    request_sender: Sender<ChunkPosition>,
+    result_receiver: Receiver<LoadedChunk>,
+}
+
+impl GeneratingWorldSource {
+    pub fn new(generator: Arc<dyn WorldGenerator>) -> Self {
+        let (request_sender, request_receiver) = flume::unbounded();
+        let (result_sender, result_receiver) = flume::unbounded();
+
+        for i in 0..NUM_WORKERS {
+            let generator = Arc::clone(&generator);
+            let request_receiver = request_receiver.clone();
+            let result_sender = result_sender.clone();
+            thread::Builder::new()
+                .name(format!("worldgen_worker_{}", i))
+                .spawn(move || run_worker(&*generator, request_receiver, result_sender))
+                .expect("failed to create worldgen worker thread");
+        }
+
+        Self {
+            request_sender,
+            result_receiver,
+        }
+    }
+}
+
+impl WorldSource for GeneratingWorldSource {
+    fn queue_load(&mut self, pos: ChunkPosition) {
+        self.request_sender
+            .send(pos)
+            .expect("worldgen workers panicked");
+    }
+
+    fn poll_loaded_chunk(&mut self) -> Option<LoadedChunk> {
+        self.result_receiver.try_recv().ok()
+    }
+}
+
+fn run_worker(
+    generator: &dyn WorldGenerator,
+    request_receiver: Receiver<ChunkPosition>,
+    result_sender: Sender<LoadedChunk>,
+) {
+    for pos in request_receiver {
+        let mut chunk = generator.generate_chunk(pos);
+        crate::lighting::light_chunk(&mut chunk);
+        let loaded = LoadedChunk {
+            pos,
+            result: ChunkLoadResult::Loaded { chunk },
+        };
+        if result_sender.send(loaded).is_err() {
+            return;
+        }
+    }
+}
@@ -1,22 +1,27 @@
 use std::{
     collections::hash_map::Entry,
+    io::ErrorKind,
     path::PathBuf,
+    sync::Arc,
+    thread::JoinHandle,
     time::{Duration, Instant},
 };
 
 use ahash::AHashMap;
 use base::{
-    anvil::region::{RegionHandle, RegionPosition},
-    ChunkPosition,
+    anvil::region::{self, ChunkEntityTags, RegionHandle, RegionPosition},
+    Chunk, ChunkPosition,
 };
 use flume::{Receiver, Sender};
+use parking_lot::RwLock;
 
 use super::{ChunkLoadResult, LoadedChunk, WorldSource};
 
 /// World source loading from a vanilla (Anvil) world.
 pub struct RegionWorldSource {
-    request_sender: Sender<ChunkPosition>,
+    request_sender: Sender<Request>,
     result_receiver: Receiver<LoadedChunk>,
+    worker_thread: Option<JoinHandle<()>>,
 }
 
 impl RegionWorldSource {
@@ -24,11 +29,12 @@ impl RegionWorldSource {
         let (request_sender, request_receiver) = flume::unbounded();
         let (worker, result_receiver) = Worker::new(world_dir.into(), request_receiver);
 
-        worker.start();
+        let worker_thread = worker.start();
 
         Self {
             request_sender,
             result_receiver,
+            worker_thread: Some(worker_thread),
         }
     }
 }
@@ -36,13 +42,51 @@ impl RegionWorldSource {
 impl WorldSource for RegionWorldSource {
     fn queue_load(&mut self, pos: ChunkPosition) {
         self.request_sender
-            .send(pos)
+            .send(Request::Load(pos))
             .expect("chunk worker panicked");
     }
 
     fn poll_loaded_chunk(&mut self) -> Option<super::LoadedChunk> {
         self.result_receiver.try_recv().ok()
     }
+
+    fn queue_save(&mut self, chunk: Arc<RwLock<Chunk>>) {
+        self.request_sender
+            .send(Request::Save(chunk))
+            .expect("chunk worker panicked");
+    }
+
+    fn flush(&mut self) {
+        let (sender, receiver) = flume::bounded(1);
+        self.request_sender
+            .send(Request::Flush(sender))
+            .expect("chunk worker panicked");
+        let _ = receiver.recv();
+    }
+
+    fn shutdown(&mut self) {
+        if let Some(worker_thread) = self.worker_thread.take() {
+            let _ = self.request_sender.send(Request::Shutdown);
+            if worker_thread.join().is_err() {
+                log::error!("Chunk worker panicked");
+            }
+        }
+    }
+}
+
+/// A request sent to the chunk worker.
+///
+/// Loads and saves share a channel so that a chunk
+/// saved on unload is written before it is loaded again.
+enum Request {
+    Load(ChunkPosition),
+    Save(Arc<RwLock<Chunk>>),
+    /// Sends a message on the contained channel
+    /// once all previous requests have been handled.
+    Flush(Sender<()>),
+    /// Stops the worker after all previous
+    /// requests have been handled.
+    Shutdown,
 }
 
 /// Duration to keep a region file open when not in use.
@@ -67,7 +111,7 @@ impl OpenRegionFile {
 }
 
 struct Worker {
-    request_receiver: Receiver<ChunkPosition>,
+    request_receiver: Receiver<Request>,
     result_sender: Sender<LoadedChunk>,
     world_dir: PathBuf,
     region_files: AHashMap<RegionPosition, OpenRegionFile>,
@@ -77,7 +121,7 @@ struct Worker {
 impl Worker {
     pub fn new(
         world_dir: PathBuf,
-        request_receiver: Receiver<ChunkPosition>,
+        request_receiver: Receiver<Request>,
     ) -> (Self, Receiver<LoadedChunk>) {
         let (result_sender, result_receiver) = flume::bounded(256);
         (
@@ -92,7 +136,7 @@ impl Worker {
         )
     }
 
-    pub fn start(self) {
+    pub fn start(self) -> JoinHandle<()> {
         std::thread::Builder::new()
             .name("chunk_worker".to_owned())
             .spawn(move || self.run())
@@ -103,9 +147,13 @@ impl Worker {
         log::info!("Chunk worker started");
         loop {
             match self.request_receiver.recv_timeout(Duration::from_secs(30)) {
-                Ok(pos) => self.load_chunk(pos),
+                Ok(Request::Load(pos)) => self.load_chunk(pos),
+                Ok(Request::Save(chunk)) => self.save_chunk(&chunk.read()),
+                Ok(Request::Flush(sender)) => {
+                    let _ = sender.send(());
+                }
                 Err(flume::RecvTimeoutError::Timeout) => (),
-                Err(flume::RecvTimeoutError::Disconnected) => {
+                Ok(Request::Shutdown) | Err(flume::RecvTimeoutError::Disconnected) => {
                     log::info!("Chunk worker shutting down");
                     return;
                 }
@@ -136,6 +184,53 @@ impl Worker {
         ChunkLoadResult::Loaded { chunk }
     }
 
+    fn save_chunk(&mut self, chunk: &Chunk) {
+        let pos = chunk.position();
+        let region = RegionPosition::from_chunk(pos);
+        let file = match self.region_file_handle_or_create(region) {
+            Ok(file) => file,
+            Err(e) => {
+                log::error!("Failed to open region file for chunk {:?}: {}", pos, e);
+                return;
+            }
+        };
+
+        // Entities and block entities are not simulated yet,
+        // so keep those stored in the region file.
+        let tags = match file.handle.load_entity_tags(pos) {
+            Ok(tags) => tags,
+            Err(region::Error::ChunkNotExist) => ChunkEntityTags::default(),
+            Err(e) => {
+                log::error!("Failed to read entities of chunk {:?}: {}", pos, e);
+                return;
+            }
+        };
+
+        if let Err(e) = file.handle.save_chunk_with_tags(chunk, &tags) {
+            log::error!("Failed to save chunk {:?}: {}", pos, e);
+        }
+
+        file.last_used = Instant::now();
+    }
+
+    fn region_file_handle_or_create(
+        &mut self,
+        region: RegionPosition,
+    ) -> Result<&mut OpenRegionFile, region::Error> {
+        match self.region_files.entry(region) {
+            Entry::Occupied(e) => Ok(e.into_mut()),
+            Entry::Vacant(e) => {
+                let handle = match region::load_region(&self.world_dir, region) {
+                    Err(region::Error::Io(err)) if err.kind() == ErrorKind::NotFound => {
+                        region::create_region(&self.world_dir, region)
+                    }
+                    result => result,
+                }?;
+                Ok(e.insert(OpenRegionFile::new(handle)))
+            }
+        }
+    }
+
     fn region_file_handle(&mut self, region: RegionPosition) -> Option<&mut OpenRegionFile> {
         match self.region_files.entry(region) {
             Entry::Occupied(e) => Some(e.into_mut()),
@@ -168,3 +263,51 @@ impl Worker {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use nbt::Value;
+
+    use super::*;
+
+    #[test]
+    fn saving_keeps_block_entities() {
+        let dir =
+            std::env::temp_dir().join(format!("feather-region-source-test-{}", std::process::id()));
+        let pos = ChunkPosition::new(5, -7);
+        let chunk = Chunk::new(pos);
+
+        let sign = Value::Compound(
+            vec![
+                ("id".to_owned(), Value::String("minecraft:sign".to_owned())),
+                ("x".to_owned(), Value::Int(80)),
+                ("y".to_owned(), Value::Int(64)),
+                ("z".to_owned(), Value::Int(-112)),
+                (
+                    "Text1".to_owned(),
+                    Value::String("{\"text\":\"hi\"}".to_owned()),
+                ),
+            ]
+            .into_iter()
+            .collect(),
+        );
+        let tags = ChunkEntityTags {
+            entities: Vec::new(),
+            block_entities: vec![sign],
+        };
+        let mut region = region::create_region(&dir, RegionPosition::from_chunk(pos)).unwrap();
+        region.save_chunk_with_tags(&chunk, &tags).unwrap();
+        drop(region);
+
+        let mut source = RegionWorldSource::new(&dir);
+        source.queue_save(Arc::new(RwLock::new(chunk)));
+        source.flush();
+        source.shutdown();
+
+        let mut region = region::load_region(&dir, RegionPosition::from_chunk(pos)).unwrap();
+        let loaded = region.load_entity_tags(pos).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(loaded, tags);
+    }
+}
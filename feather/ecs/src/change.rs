@@ -4,51 +4,92 @@ use ahash::AHashMap;
 use hecs::{Component, DynamicBundle, Entity};
 
 /// Tracks changes made to certain components.
+///
+/// A change has the same lifecycle as an event:
+/// it is visible to each system exactly once and is
+/// forgotten immediately before the system that made
+/// the change runs again.
 #[derive(Default)]
 pub struct ChangeTracker {
     registries: AHashMap<TypeId, ChangeRegistry>,
+    current_system_index: usize,
 }
 
 impl ChangeTracker {
     pub fn track_component<T: Component>(&mut self) {
         self.registries
-            .insert(TypeId::of::<T>(), ChangeRegistry::default());
+            .entry(TypeId::of::<T>())
+            .or_insert_with(ChangeRegistry::default);
     }
 
     pub fn on_insert(&mut self, entity: Entity, components: &impl DynamicBundle) {
+        let system = self.current_system_index;
+        let registries = &mut self.registries;
         components.with_ids(|typs| {
             for ty in typs {
-                let registry = self.registries.get_mut(ty);
+                let registry = registries.get_mut(ty);
                 if let Some(registry) = registry {
-                    registry.mark_changed(entity);
+                    registry.mark_changed(entity, system);
                 }
             }
         });
     }
 
+    pub fn mark_changed<T: Component>(&mut self, entity: Entity) {
+        let system = self.current_system_index;
+        self.registry::<T>().mark_changed(entity, system);
+    }
+
     pub fn iter_changed<T: Component>(&self) -> impl Iterator<Item = Entity> + '_ {
         self.registries.get(&TypeId::of::<T>())
         .unwrap_or_else(|| panic!("Components of type {} are not tracked for changes. Call `Ecs::track_component` to enable change tracking.", std::any::type_name::<T>()))
         .changed_entities
         .iter()
-        .copied()
+        .map(|&(entity, _)| entity)
+    }
+
+    pub fn set_current_system_index(&mut self, index: usize) {
+        self.current_system_index = index;
+    }
+
+    /// Forgets changes made by the current system
+    /// on the previous tick.
+    pub fn remove_old_changes(&mut self) {
+        let system = self.current_system_index;
+        for registry in self.registries.values_mut() {
+            registry.remove_changes_by(system);
+        }
+    }
+
+    fn registry<T: Component>(&mut self) -> &mut ChangeRegistry {
+        self.registries.get_mut(&TypeId::of::<T>())
+        .unwrap_or_else(|| panic!("Components of type {} are not tracked for changes. Call `Ecs::track_component` to enable change tracking.", std::any::type_name::<T>()))
     }
 }
 
 #[derive(Default)]
 struct ChangeRegistry {
-    changed_entities: VecDeque<Entity>,
+    /// Changed entities and the index of
+    /// the system which last changed them.
+    changed_entities: VecDeque<(Entity, usize)>,
 }
 
 impl ChangeRegistry {
-    pub fn mark_changed(&mut self, entity: Entity) {
-        if !self.changed_entities.contains(&entity) {
-            self.changed_entities.push_back(entity);
+    pub fn mark_changed(&mut self, entity: Entity, system: usize) {
+        match self
+            .changed_entities
+            .iter_mut()
+            .find(|(changed, _)| *changed == entity)
+        {
+            // Keep the change visible until the
+            // system which last made it runs again.
+            Some(change) => change.1 = system,
+            None => self.changed_entities.push_back((entity, system)),
         }
     }
 
-    #[allow(unused)]
-    pub fn pop(&mut self) {
-        self.changed_entities.pop_front();
+    pub fn remove_changes_by(&mut self, system: usize) {
+        self.changed_entities
+            .retain(|&(_, changed_by)| changed_by != system);
     }
 }
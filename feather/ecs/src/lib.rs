@@ -103,7 +103,9 @@ impl Ecs {
         entity: Entity,
         component: impl Component,
     ) -> Result<(), NoSuchEntity> {
-        self.world.insert_one(entity, component)
+        let components = (component,);
+        self.change_tracker.on_insert(entity, &components);
+        self.world.insert(entity, components)
     }
 
     /// Creates an event not related to any entity. Use
@@ -160,21 +162,39 @@ impl Ecs {
     /// used for event tracking.
     pub fn set_current_system_index(&mut self, index: usize) {
         self.event_tracker.set_current_system_index(index);
+        self.change_tracker.set_current_system_index(index);
     }
 
     /// Should be called before each system runs.
+    ///
+    /// Also forgets the component changes made
+    /// by the system on the previous tick.
     pub fn remove_old_events(&mut self) {
         self.event_tracker.remove_old_events(&mut self.world);
+        self.change_tracker.remove_old_changes();
     }
 
     /// Enables change tracking for `T` components.
     ///
-    /// Calling this allows using `query_changed`
+    /// Calling this allows using `for_each_changed`
     /// to iterate over entities whose `T` has changed.
     pub fn track_component<T: Component>(&mut self) {
         self.change_tracker.track_component::<T>()
     }
 
+    /// Marks the `T` component of an entity as changed.
+    ///
+    /// Inserting a component marks it as changed
+    /// automatically, but components mutated in place
+    /// through [`Ecs::get_mut`] or a query must be marked
+    /// with this function.
+    ///
+    /// # Panics
+    /// Panics if `track_component` was not called for `T`.
+    pub fn mark_changed<T: Component>(&mut self, entity: Entity) {
+        self.change_tracker.mark_changed::<T>(entity)
+    }
+
     /// Iterates over entities whose `T` component
     /// changed since the previous time the current
     /// system was executed.
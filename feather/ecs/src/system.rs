@@ -71,6 +71,8 @@ impl HasEcs for Ecs {
 /// Systems run sequentially in the order they are added to the executor.
 pub struct SystemExecutor<Input> {
     systems: Vec<System<Input>>,
+    /// Indices of removed systems, which can be reused.
+    removed: Vec<usize>,
 
     is_first_run: bool,
 }
@@ -79,6 +81,7 @@ impl<Input> Default for SystemExecutor<Input> {
     fn default() -> Self {
         Self {
             systems: Vec::new(),
+            removed: Vec::new(),
             is_first_run: true,
         }
     }
@@ -99,14 +102,63 @@ impl<Input> SystemExecutor<Input> {
         self
     }
 
+    /// Adds a system with the given name to the executor.
+    ///
+    /// Returns the index of the system, which can
+    /// be passed to [`SystemExecutor::remove_system`].
     pub fn add_system_with_name(
         &mut self,
         system: impl FnMut(&mut Input) -> SysResult + 'static,
         name: &str,
-    ) {
+    ) -> usize {
         let mut system = System::from_fn(system);
         system.name = name.to_owned();
         self.systems.push(system);
+        self.systems.len() - 1
+    }
+
+    /// Adds a system with the given name in place of a
+    /// removed system, or at the end if no system was removed.
+    ///
+    /// Unlike [`SystemExecutor::add_system_with_name`], the system
+    /// may run before systems added earlier. Use this for systems
+    /// added and removed repeatedly at runtime, so that the
+    /// executor does not grow each time.
+    pub fn add_system_reusing_slot(
+        &mut self,
+        system: impl FnMut(&mut Input) -> SysResult + 'static,
+        name: &str,
+    ) -> usize {
+        match self.removed.pop() {
+            Some(index) => {
+                let mut system = System::from_fn(system);
+                system.name = name.to_owned();
+                self.systems[index] = system;
+                index
+            }
+            None => self.add_system_with_name(system, name),
+        }
+    }
+
+    /// Removes the system at `index`.
+    ///
+    /// The system is replaced with one doing nothing,
+    /// so that the indices of the other systems, which
+    /// are used to track events, do not change. Its slot
+    /// is reused by [`SystemExecutor::add_system_reusing_slot`].
+    pub fn remove_system(&mut self, index: usize)
+    where
+        Input: 'static,
+    {
+        if self.removed.contains(&index) {
+            return;
+        }
+        if let Some(system) = self.systems.get_mut(index) {
+            let name = format!("{} (removed)", system.name);
+            *system = System::from_fn(|_: &mut Input| Ok(()));
+            system.name = name;
+            self.removed.push(index);
+        }
     }
 
     /// Begins a group with the provided group state type.
@@ -0,0 +1,86 @@
+#![allow(clippy::unnecessary_wraps)]
+
+use feather_ecs::{Ecs, HasEcs, SysResult, SystemExecutor};
+
+#[derive(Debug, PartialEq, Eq)]
+struct Health(u32);
+
+#[derive(Debug, PartialEq, Eq)]
+struct Name(&'static str);
+
+struct Input {
+    ecs: Ecs,
+    is_first_run: bool,
+    observed: Vec<u32>,
+}
+
+impl HasEcs for Input {
+    fn ecs(&self) -> &Ecs {
+        &self.ecs
+    }
+
+    fn ecs_mut(&mut self) -> &mut Ecs {
+        &mut self.ecs
+    }
+}
+
+fn change_system(input: &mut Input) -> SysResult {
+    if input.is_first_run {
+        let entity = input.ecs.spawn((Health(20), Name("zombie")));
+        input.ecs.insert(entity, Health(10))?;
+    }
+    Ok(())
+}
+
+fn observe_system(input: &mut Input) -> SysResult {
+    let mut observed = Vec::new();
+    input.ecs.for_each_changed::<Health, &Name>(|health, name| {
+        assert_eq!(name, &Name("zombie"));
+        observed.push(health.0)
+    });
+    input.observed.extend(observed);
+    Ok(())
+}
+
+#[test]
+fn changes_observed_once() {
+    let mut systems = SystemExecutor::<Input>::new();
+    systems.add_system(change_system).add_system(observe_system);
+
+    let mut ecs = Ecs::new();
+    ecs.track_component::<Health>();
+    let mut input = Input {
+        ecs,
+        is_first_run: true,
+        observed: Vec::new(),
+    };
+    systems.run(&mut input);
+    input.is_first_run = false;
+    systems.run(&mut input);
+
+    assert_eq!(input.observed, vec![10]);
+}
+
+#[test]
+fn mutations_are_marked_manually() {
+    let mut ecs = Ecs::new();
+    ecs.track_component::<Health>();
+    let entity = ecs.spawn((Health(20), Name("zombie")));
+    ecs.set_current_system_index(1);
+    ecs.remove_old_events();
+
+    let mut changed = 0;
+    ecs.for_each_changed::<Health, &Name>(|_, _| changed += 1);
+    assert_eq!(changed, 1);
+
+    ecs.set_current_system_index(0);
+    ecs.remove_old_events();
+    ecs.get_mut::<Health>(entity).unwrap().0 = 15;
+    ecs.mark_changed::<Health>(entity);
+    ecs.set_current_system_index(1);
+    ecs.remove_old_events();
+
+    let mut changed = Vec::new();
+    ecs.for_each_changed::<Health, &Name>(|health, _| changed.push(health.0));
+    assert_eq!(changed, vec![15]);
+}
@@ -40,3 +40,35 @@ fn systems_are_executed_in_order() {
     executor.run(&mut input);
     assert_eq!(input.x, 110);
 }
+
+#[test]
+fn removed_systems_are_not_executed() {
+    let mut executor = SystemExecutor::new();
+    executor.add_system(system1);
+    let index = executor.add_system_with_name(system2, "system2");
+    executor.remove_system(index);
+
+    let mut input = Input {
+        x: 1,
+        ecs: Ecs::new(),
+    };
+    executor.run(&mut input);
+    assert_eq!(input.x, 11);
+    assert_eq!(executor.system_names().count(), 2);
+}
+
+#[test]
+fn removed_system_slots_are_reused() {
+    let mut executor = SystemExecutor::new();
+    let index = executor.add_system_reusing_slot(system1, "system1");
+    executor.remove_system(index);
+    assert_eq!(executor.add_system_reusing_slot(system2, "system2"), index);
+
+    let mut input = Input {
+        x: 1,
+        ecs: Ecs::new(),
+    };
+    executor.run(&mut input);
+    assert_eq!(input.x, 10);
+    assert_eq!(executor.system_names().count(), 1);
+}
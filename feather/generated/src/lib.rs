@@ -1,3 +1,4 @@
+use libcraft_items::{Enchantment, EnchantmentKind};
 use parking_lot::{Mutex, MutexGuard};
 use std::sync::Arc;
 
@@ -31,6 +32,9 @@ pub struct ItemStack {
 
     /// Damage to the item, if it's damageable.
     pub damage: Option<u32>,
+
+    /// Enchantments applied to the item.
+    pub enchantments: Vec<Enchantment>,
 }
 
 impl ItemStack {
@@ -40,6 +44,7 @@ impl ItemStack {
             item,
             count,
             damage: item.durability().map(|_| 0),
+            enchantments: Vec::new(),
         }
     }
 
@@ -47,7 +52,9 @@ impl ItemStack {
     /// the same type as (but not necessarily the same
     /// amount as) `self`.
     pub fn has_same_type(&self, other: &ItemStack) -> bool {
-        other.item == self.item && other.damage == self.damage
+        other.item == self.item
+            && other.damage == self.damage
+            && other.enchantments == self.enchantments
     }
 
     /// Returns the item type for this `ItemStack`.
@@ -76,6 +83,16 @@ impl ItemStack {
         true
     }
 
+    /// Returns the level of the given enchantment
+    /// on this `ItemStack`, or 0 if it is not enchanted with it.
+    pub fn enchantment_level(&self, kind: EnchantmentKind) -> u32 {
+        self.enchantments
+            .iter()
+            .find(|enchantment| enchantment.kind() == kind)
+            .map(Enchantment::level)
+            .unwrap_or(0)
+    }
+
     /// Sets the item for this `ItemStack`.
     pub fn set_item(&mut self, item: Item) {
         self.item = item;
@@ -4,13 +4,14 @@ use std::{
     marker::PhantomData,
     mem::size_of,
     panic::AssertUnwindSafe,
+    path::PathBuf,
     ptr::NonNull,
     sync::atomic::{AtomicBool, Ordering},
 };
 
 use anyhow::anyhow;
 use bytemuck::{Pod, Zeroable};
-use feather_common::Game;
+use feather_common::{commands::Registration, Game};
 use feather_ecs::EntityBuilder;
 use quill_common::Component;
 use serde::de::DeserializeOwned;
@@ -179,30 +180,58 @@ pub struct PluginContext {
     /// ID of the plugin.
     id: PluginId,
 
+    /// Whether the plugin exports `quill_run_command`,
+    /// without which it cannot register commands.
+    runs_commands: AtomicBool,
+
+    /// Identifier of the plugin from its metadata.
+    pub identifier: String,
+
     /// Active entity builders for the plugin.
     pub entity_builders: ThreadPinned<Arena<EntityBuilder>>,
+
+    /// Indices of the systems registered by the plugin.
+    pub systems: ThreadPinned<Vec<usize>>,
+
+    /// Changes made to the command tree by the plugin's
+    /// registered commands, reverted when it is unloaded.
+    pub commands: ThreadPinned<Vec<Registration>>,
+
+    /// The plugin's private data directory, as
+    /// seen from inside the plugin.
+    pub data_dir: PathBuf,
 }
 
 impl PluginContext {
     /// Creates a new WASM plugin context.
-    pub fn new_wasm(id: PluginId) -> Self {
+    pub fn new_wasm(id: PluginId, identifier: String, data_dir: PathBuf) -> Self {
         Self {
             inner: Inner::Wasm(ThreadPinned::new(wasm::WasmPluginContext::new())),
             invoking_on_main_thread: AtomicBool::new(false),
             game: ThreadPinned::new(None),
             id,
+            runs_commands: AtomicBool::new(false),
+            identifier,
             entity_builders: ThreadPinned::new(Arena::new()),
+            systems: ThreadPinned::new(Vec::new()),
+            commands: ThreadPinned::new(Vec::new()),
+            data_dir,
         }
     }
 
     /// Creates a new native plugin context.
-    pub fn new_native(id: PluginId) -> Self {
+    pub fn new_native(id: PluginId, identifier: String, data_dir: PathBuf) -> Self {
         Self {
             inner: Inner::Native(native::NativePluginContext::new()),
             invoking_on_main_thread: AtomicBool::new(false),
             game: ThreadPinned::new(None),
             id,
+            runs_commands: AtomicBool::new(false),
+            identifier,
             entity_builders: ThreadPinned::new(Arena::new()),
+            systems: ThreadPinned::new(Vec::new()),
+            commands: ThreadPinned::new(Vec::new()),
+            data_dir,
         }
     }
 
@@ -261,6 +290,15 @@ impl PluginContext {
         self.id
     }
 
+    /// Returns whether the plugin can run the commands it registers.
+    pub fn runs_commands(&self) -> bool {
+        self.runs_commands.load(Ordering::Relaxed)
+    }
+
+    pub fn set_runs_commands(&self, runs_commands: bool) {
+        self.runs_commands.store(runs_commands, Ordering::Relaxed);
+    }
+
     /// Accesses a byte slice in the plugin's memory space.
     ///
     /// # Safety
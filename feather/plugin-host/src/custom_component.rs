@@ -0,0 +1,176 @@
+//! Components defined by plugins, stored on the host
+//! as opaque `bincode` blobs.
+
+use ahash::AHashMap;
+use anyhow::{bail, Context};
+
+/// Unique ID of a custom component type.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct CustomComponentId(pub u32);
+
+struct CustomComponentType {
+    /// Identifier of the plugin defining the component.
+    plugin: String,
+    /// Whether plugins other than the defining one
+    /// may access the component.
+    shared: bool,
+}
+
+/// Resource storing the custom component types of all plugins.
+///
+/// A component type is keyed by the identifier of the plugin
+/// defining it and its name. Only the defining plugin may access
+/// its components unless it shares them with other plugins.
+#[derive(Default)]
+pub struct CustomComponentRegistry {
+    /// Indexed by ID. Types of unloaded plugins are `None`.
+    types: Vec<Option<CustomComponentType>>,
+    ids: AHashMap<(String, String), CustomComponentId>,
+}
+
+impl CustomComponentRegistry {
+    /// Gets the ID of the component `name` defined by `plugin`,
+    /// creating it if needed.
+    ///
+    /// IDs are never reused, so components left over from
+    /// an unloaded plugin cannot be read as another type.
+    pub fn id(&mut self, plugin: &str, name: &str) -> CustomComponentId {
+        let types = &mut self.types;
+        *self
+            .ids
+            .entry((plugin.to_owned(), name.to_owned()))
+            .or_insert_with(|| {
+                types.push(Some(CustomComponentType {
+                    plugin: plugin.to_owned(),
+                    shared: false,
+                }));
+                CustomComponentId(types.len() as u32 - 1)
+            })
+    }
+
+    /// Allows all plugins to access the component `id`.
+    /// `plugin` must be the plugin defining the component.
+    pub fn share(&mut self, id: CustomComponentId, plugin: &str) -> anyhow::Result<()> {
+        let typ = self.get_mut(id)?;
+        if typ.plugin != plugin {
+            bail!("only plugin {} may share its components", typ.plugin);
+        }
+        typ.shared = true;
+        Ok(())
+    }
+
+    /// Removes the component types defined by `plugin`,
+    /// returning their IDs.
+    pub fn remove_plugin(&mut self, plugin: &str) -> Vec<CustomComponentId> {
+        let mut removed = Vec::new();
+        self.ids.retain(|(owner, _), &mut id| {
+            if owner == plugin {
+                removed.push(id);
+                false
+            } else {
+                true
+            }
+        });
+        for id in &removed {
+            self.types[id.0 as usize] = None;
+        }
+        removed
+    }
+
+    /// Returns an error if `plugin` may not access the component `id`.
+    pub fn check_access(&self, id: CustomComponentId, plugin: &str) -> anyhow::Result<()> {
+        let typ = self
+            .types
+            .get(id.0 as usize)
+            .and_then(Option::as_ref)
+            .context("invalid custom component")?;
+        if typ.plugin != plugin && !typ.shared {
+            bail!(
+                "plugin {} does not share this component with plugin {}",
+                typ.plugin,
+                plugin
+            );
+        }
+        Ok(())
+    }
+
+    fn get_mut(&mut self, id: CustomComponentId) -> anyhow::Result<&mut CustomComponentType> {
+        self.types
+            .get_mut(id.0 as usize)
+            .and_then(Option::as_mut)
+            .context("invalid custom component")
+    }
+}
+
+/// Component storing the serialized custom components of an entity.
+///
+/// They are dropped along with the entity when it despawns.
+#[derive(Clone, Debug, Default)]
+pub struct CustomComponents(AHashMap<CustomComponentId, Vec<u8>>);
+
+impl CustomComponents {
+    pub fn get(&self, id: CustomComponentId) -> Option<&[u8]> {
+        self.0.get(&id).map(Vec::as_slice)
+    }
+
+    pub fn contains(&self, id: CustomComponentId) -> bool {
+        self.0.contains_key(&id)
+    }
+
+    pub fn insert(&mut self, id: CustomComponentId, bytes: Vec<u8>) {
+        self.0.insert(id, bytes);
+    }
+
+    pub fn remove(&mut self, id: CustomComponentId) {
+        self.0.remove(&id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn owner_has_access() {
+        let mut registry = CustomComponentRegistry::default();
+        let id = registry.id("owner", "mana");
+        assert!(registry.check_access(id, "owner").is_ok());
+        assert_eq!(registry.id("owner", "mana"), id);
+    }
+
+    #[test]
+    fn other_plugins_need_sharing() {
+        let mut registry = CustomComponentRegistry::default();
+        let id = registry.id("owner", "mana");
+        assert!(registry.check_access(id, "other").is_err());
+
+        registry.share(id, "owner").unwrap();
+        assert!(registry.check_access(id, "other").is_ok());
+    }
+
+    #[test]
+    fn only_owner_can_share() {
+        let mut registry = CustomComponentRegistry::default();
+        let id = registry.id("owner", "mana");
+        assert!(registry.share(id, "other").is_err());
+        assert!(registry.check_access(id, "other").is_err());
+    }
+
+    #[test]
+    fn removing_plugin_drops_its_components() {
+        let mut registry = CustomComponentRegistry::default();
+        let id = registry.id("owner", "mana");
+        let other = registry.id("other", "mana");
+        registry.share(id, "owner").unwrap();
+
+        assert_eq!(registry.remove_plugin("owner"), vec![id]);
+        assert!(registry.check_access(id, "other").is_err());
+        assert!(registry.check_access(id, "owner").is_err());
+        assert!(registry.check_access(other, "other").is_ok());
+
+        // A reloaded plugin gets a new, unshared component.
+        let reloaded = registry.id("owner", "mana");
+        assert_ne!(reloaded, id);
+        assert!(registry.check_access(reloaded, "other").is_err());
+    }
+}
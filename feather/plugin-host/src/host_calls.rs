@@ -9,9 +9,12 @@ use crate::env::PluginEnv;
 use crate::host_function::{NativeHostFunction, WasmHostFunction};
 
 mod block;
+mod command;
 mod component;
+mod custom_component;
 mod entity;
 mod entity_builder;
+mod plugin;
 mod plugin_message;
 mod query;
 mod system;
@@ -46,17 +49,26 @@ macro_rules! host_calls {
 }
 
 use block::*;
+use command::*;
 use component::*;
+use custom_component::*;
 use entity::*;
 use entity_builder::*;
+use plugin::*;
 use plugin_message::*;
 use query::*;
 use system::*;
 
 host_calls! {
     "register_system" => register_system,
+    "register_command" => register_command,
     "entity_get_component" => entity_get_component,
     "entity_set_component" => entity_set_component,
+    "custom_component_id" => custom_component_id,
+    "custom_component_share" => custom_component_share,
+    "entity_get_custom_component" => entity_get_custom_component,
+    "entity_set_custom_component" => entity_set_custom_component,
+    "entity_remove_custom_component" => entity_remove_custom_component,
     "entity_builder_new_empty" => entity_builder_new_empty,
     "entity_builder_new" => entity_builder_new,
     "entity_builder_add_component" => entity_builder_add_component,
@@ -65,8 +77,10 @@ host_calls! {
     "entity_exists" => entity_exists,
     "entity_send_message" => entity_send_message,
     "entity_send_title" => entity_send_title,
+    "entity_set_gamemode" => entity_set_gamemode,
     "block_get" => block_get,
     "block_set" => block_set,
     "block_fill_chunk_section" => block_fill_chunk_section,
     "plugin_message_send" => plugin_message_send,
+    "plugin_data_dir" => plugin_data_dir,
 }
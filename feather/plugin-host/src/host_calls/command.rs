@@ -0,0 +1,154 @@
+use std::{cell::RefCell, rc::Rc};
+
+use anyhow::bail;
+use feather_common::commands::{self, CommandBuilder, CommandCtx, CommandDispatcher};
+use feather_plugin_host_macros::host_function;
+use quill_common::{
+    commands::{ArgumentKind, ArgumentValue, CommandInvocation, CommandNode, CommandNodeKind},
+    EntityId,
+};
+
+use crate::{
+    context::{PluginContext, PluginPtr, PluginPtrMut},
+    PluginId, PluginManager,
+};
+
+#[host_function]
+pub fn register_command(
+    cx: &PluginContext,
+    command_ptr: PluginPtr<u8>,
+    command_len: u32,
+) -> anyhow::Result<()> {
+    if !cx.runs_commands() {
+        bail!(
+            "plugin {} registered a command but does not export quill_run_command",
+            cx.identifier
+        );
+    }
+    let command: CommandNode = cx.read_bincode(command_ptr, command_len)?;
+    let command = convert_node(cx.plugin_id(), command, &mut Vec::new());
+
+    let game = cx.game_mut();
+    let registration = game
+        .resources
+        .get_mut::<CommandDispatcher>()?
+        .register(command);
+    cx.commands.borrow_mut().push(registration);
+
+    Ok(())
+}
+
+/// Converts a plugin's command node into a `CommandBuilder`.
+///
+/// `path` contains the arguments on the path
+/// from the root to this node.
+fn convert_node(
+    id: PluginId,
+    node: CommandNode,
+    path: &mut Vec<(String, ArgumentKind)>,
+) -> CommandBuilder {
+    let is_argument = matches!(node.kind, CommandNodeKind::Argument { .. });
+    let mut builder = match node.kind {
+        CommandNodeKind::Literal(name) => commands::literal(name),
+        CommandNodeKind::Argument { name, kind } => {
+            path.push((name.clone(), kind.clone()));
+            commands::argument(name, convert_argument_kind(kind))
+        }
+    };
+    builder = builder.requires(node.permission_level);
+
+    if let Some(executor) = node.executor {
+        let data_ptr = PluginPtrMut {
+            ptr: executor,
+            _marker: Default::default(),
+        };
+        builder = builder.executes(plugin_command(id, data_ptr, path.clone()));
+    }
+
+    for child in node.children {
+        builder = builder.then(convert_node(id, child, path));
+    }
+
+    if is_argument {
+        path.pop();
+    }
+    builder
+}
+
+fn convert_argument_kind(kind: ArgumentKind) -> commands::ArgumentKind {
+    match kind {
+        ArgumentKind::Bool => commands::ArgumentKind::Bool,
+        ArgumentKind::Integer { min, max } => commands::ArgumentKind::Integer { min, max },
+        ArgumentKind::Double { min, max } => commands::ArgumentKind::Double { min, max },
+        ArgumentKind::Word => commands::ArgumentKind::Word,
+        ArgumentKind::String => commands::ArgumentKind::String,
+        ArgumentKind::GreedyString => commands::ArgumentKind::GreedyString,
+        ArgumentKind::Entities {
+            single,
+            players_only,
+        } => commands::ArgumentKind::Entities {
+            single,
+            players_only,
+        },
+        ArgumentKind::Position => commands::ArgumentKind::Coordinates,
+        ArgumentKind::BlockPosition => commands::ArgumentKind::BlockPosition,
+        ArgumentKind::Gamemode => commands::ArgumentKind::Gamemode,
+        ArgumentKind::Message => commands::ArgumentKind::Message,
+        ArgumentKind::Item => commands::ArgumentKind::Item,
+        ArgumentKind::Time => commands::ArgumentKind::Time,
+    }
+}
+
+fn plugin_command(
+    id: PluginId,
+    data_ptr: PluginPtrMut<u8>,
+    arguments: Vec<(String, ArgumentKind)>,
+) -> impl Fn(&mut CommandCtx) -> anyhow::Result<()> {
+    move |ctx: &mut CommandCtx| {
+        let args = arguments
+            .iter()
+            .map(|(name, kind)| Ok((name.clone(), argument_value(ctx, name, kind)?)))
+            .collect::<anyhow::Result<_>>()?;
+        let invocation = CommandInvocation {
+            sender: EntityId(ctx.sender.to_bits()),
+            args,
+        };
+        let invocation = bincode::serialize(&invocation)?;
+
+        let plugin_manager = Rc::clone(&*ctx.game.resources.get::<Rc<RefCell<PluginManager>>>()?);
+        let plugin_manager = plugin_manager.borrow();
+        if let Some(plugin) = plugin_manager.plugin(id) {
+            plugin.run_command(ctx.game, data_ptr, &invocation)?;
+        }
+
+        Ok(())
+    }
+}
+
+fn argument_value(
+    ctx: &CommandCtx,
+    name: &str,
+    kind: &ArgumentKind,
+) -> anyhow::Result<ArgumentValue> {
+    Ok(match kind {
+        ArgumentKind::Bool => ArgumentValue::Bool(ctx.args.bool(name)?),
+        ArgumentKind::Integer { .. } => ArgumentValue::Integer(ctx.args.integer(name)?),
+        ArgumentKind::Double { .. } => ArgumentValue::Double(ctx.args.double(name)?),
+        ArgumentKind::Word
+        | ArgumentKind::String
+        | ArgumentKind::GreedyString
+        | ArgumentKind::Message => ArgumentValue::String(ctx.args.string(name)?),
+        ArgumentKind::Entities { .. } => ArgumentValue::Entities(
+            ctx.args
+                .entities(name)?
+                .into_iter()
+                .map(|entity| EntityId(entity.to_bits()))
+                .collect(),
+        ),
+        ArgumentKind::Position => ArgumentValue::Position(ctx.position(name)?),
+        ArgumentKind::BlockPosition => ArgumentValue::BlockPosition(ctx.block_position(name)?),
+        ArgumentKind::Gamemode => ArgumentValue::Gamemode(ctx.args.gamemode(name)?),
+        ArgumentKind::Item => ArgumentValue::Item(ctx.args.item(name)?.name().to_owned()),
+        ArgumentKind::Time => ArgumentValue::Time(ctx.args.time(name)?),
+    })
+}
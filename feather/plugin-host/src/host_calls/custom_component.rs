@@ -0,0 +1,123 @@
+use feather_common::Game;
+use feather_ecs::Entity;
+use feather_plugin_host_macros::host_function;
+
+use crate::{
+    context::{PluginContext, PluginPtr, PluginPtrMut},
+    custom_component::{CustomComponentId, CustomComponentRegistry, CustomComponents},
+};
+
+/// Gets the ID of a custom component, returning an error
+/// if the plugin may not access the component.
+fn checked_id(
+    cx: &PluginContext,
+    game: &Game,
+    component: u32,
+) -> anyhow::Result<CustomComponentId> {
+    let id = CustomComponentId(component);
+    game.resources
+        .get::<CustomComponentRegistry>()?
+        .check_access(id, &cx.identifier)?;
+    Ok(id)
+}
+
+#[host_function]
+pub fn custom_component_id(
+    cx: &PluginContext,
+    plugin_ptr: PluginPtr<u8>,
+    plugin_len: u32,
+    name_ptr: PluginPtr<u8>,
+    name_len: u32,
+) -> anyhow::Result<u32> {
+    let plugin = cx.read_string(plugin_ptr, plugin_len)?;
+    let name = cx.read_string(name_ptr, name_len)?;
+
+    let game = cx.game_mut();
+    let id = game
+        .resources
+        .get_mut::<CustomComponentRegistry>()?
+        .id(&plugin, &name);
+    Ok(id.0)
+}
+
+#[host_function]
+pub fn custom_component_share(cx: &PluginContext, component: u32) -> anyhow::Result<()> {
+    let game = cx.game_mut();
+    game.resources
+        .get_mut::<CustomComponentRegistry>()?
+        .share(CustomComponentId(component), &cx.identifier)
+}
+
+#[host_function]
+pub fn entity_get_custom_component(
+    cx: &PluginContext,
+    entity: u64,
+    component: u32,
+    bytes_ptr_ptr: PluginPtrMut<PluginPtrMut<u8>>,
+    bytes_len_ptr: PluginPtrMut<u32>,
+) -> anyhow::Result<()> {
+    let entity = Entity::from_bits(entity);
+    let game = cx.game_mut();
+    let id = checked_id(cx, &game, component)?;
+
+    let bytes = game
+        .ecs
+        .get::<CustomComponents>(entity)
+        .ok()
+        .and_then(|components| components.get(id).map(<[u8]>::to_vec));
+    let (bytes_ptr, bytes_len) = match bytes {
+        Some(bytes) => (
+            cx.bump_allocate_and_write_bytes(&bytes)?,
+            bytes.len() as u32,
+        ),
+        None => (unsafe { PluginPtrMut::null() }, 0),
+    };
+
+    cx.write_pod(bytes_ptr_ptr, bytes_ptr)?;
+    cx.write_pod(bytes_len_ptr, bytes_len)?;
+
+    Ok(())
+}
+
+#[host_function]
+pub fn entity_set_custom_component(
+    cx: &PluginContext,
+    entity: u64,
+    component: u32,
+    bytes_ptr: PluginPtr<u8>,
+    bytes_len: u32,
+) -> anyhow::Result<()> {
+    let entity = Entity::from_bits(entity);
+    let bytes = cx.read_bytes(bytes_ptr, bytes_len)?;
+    let mut game = cx.game_mut();
+    let id = checked_id(cx, &game, component)?;
+
+    if let Ok(mut components) = game.ecs.get_mut::<CustomComponents>(entity) {
+        components.insert(id, bytes);
+        return Ok(());
+    }
+
+    let mut components = CustomComponents::default();
+    components.insert(id, bytes);
+    // Does nothing if the entity does not exist.
+    let _ = game.ecs.insert(entity, components);
+
+    Ok(())
+}
+
+#[host_function]
+pub fn entity_remove_custom_component(
+    cx: &PluginContext,
+    entity: u64,
+    component: u32,
+) -> anyhow::Result<()> {
+    let entity = Entity::from_bits(entity);
+    let game = cx.game_mut();
+    let id = checked_id(cx, &game, component)?;
+
+    if let Ok(mut components) = game.ecs.get_mut::<CustomComponents>(entity) {
+        components.remove(id);
+    }
+
+    Ok(())
+}
@@ -1,4 +1,7 @@
-use feather_base::Text;
+use std::convert::TryFrom;
+
+use anyhow::Context;
+use feather_base::{Gamemode, Text};
 use feather_common::chat::{ChatKind, ChatMessage};
 use feather_ecs::Entity;
 use feather_plugin_host_macros::host_function;
@@ -38,3 +41,14 @@ pub fn entity_send_title(
     cx.game_mut().send_title(entity, title);
     Ok(())
 }
+
+#[host_function]
+pub fn entity_set_gamemode(cx: &PluginContext, entity: u64, gamemode: u32) -> anyhow::Result<()> {
+    let gamemode = u8::try_from(gamemode)
+        .ok()
+        .and_then(Gamemode::from_id)
+        .context("invalid gamemode")?;
+    let entity = Entity::from_bits(entity);
+    let _ = cx.game_mut().set_gamemode(entity, gamemode);
+    Ok(())
+}
@@ -3,7 +3,10 @@ use feather_base::Position;
 use feather_plugin_host_macros::host_function;
 use quill_common::{component::ComponentVisitor, HostComponent};
 
-use crate::context::{PluginContext, PluginPtr};
+use crate::{
+    context::{PluginContext, PluginPtr},
+    PluginEntity,
+};
 
 #[host_function]
 pub fn entity_builder_new_empty(cx: &PluginContext) -> anyhow::Result<u32> {
@@ -78,11 +81,12 @@ pub fn entity_builder_add_component(
 
 #[host_function]
 pub fn entity_builder_finish(cx: &PluginContext, builder: u32) -> anyhow::Result<u64> {
-    let builder = cx
+    let mut builder = cx
         .entity_builders
         .borrow_mut()
         .remove(builder as usize)
         .context("invalid entity builder")?;
+    builder.add(PluginEntity(cx.plugin_id()));
 
     let entity = cx.game_mut().spawn_entity(builder);
     Ok(entity.to_bits())
@@ -1,17 +1,26 @@
 //! Implements the `entity_query` host call.
 
-use std::{alloc::Layout, any::TypeId, mem::size_of, ptr};
+use std::{
+    alloc::Layout,
+    any::TypeId,
+    mem::{align_of, size_of},
+    ptr,
+};
 
 use anyhow::Context;
-use feather_ecs::{DynamicQuery, DynamicQueryTypes, Ecs};
+use feather_common::Game;
+use feather_ecs::{DynamicQuery, DynamicQueryTypes, Ecs, Entity};
 use feather_plugin_host_macros::host_function;
 use quill_common::{
-    component::{ComponentVisitor, SerializationMethod},
+    component::{ComponentVisitor, SerializationMethod, CUSTOM_COMPONENT_OFFSET},
     entity::QueryData,
     Component, EntityId, HostComponent, PointerMut,
 };
 
-use crate::context::{PluginContext, PluginPtr, PluginPtrMut};
+use crate::{
+    context::{PluginContext, PluginPtr, PluginPtrMut},
+    custom_component::{CustomComponentId, CustomComponentRegistry, CustomComponents},
+};
 
 #[host_function]
 pub fn entity_query(
@@ -23,19 +32,44 @@ pub fn entity_query(
     let mut components = Vec::with_capacity(components_len as usize);
     for i in 0..components_len {
         let ptr = unsafe { components_ptr.add(i as usize) };
-        let id = cx.read_pod(ptr)?;
+        let id: u32 = cx.read_pod(ptr)?;
 
-        let component = HostComponent::from_u32(id).context("bad component type")?;
+        let component = if id >= CUSTOM_COMPONENT_OFFSET {
+            QueryComponent::Custom(CustomComponentId(id - CUSTOM_COMPONENT_OFFSET))
+        } else {
+            QueryComponent::Host(HostComponent::from_u32(id).context("bad component type")?)
+        };
         components.push(component);
     }
 
     let game = cx.game_mut();
-    let query_data = create_query_data(cx, &game.ecs, &components)?;
+    let query_data = if components
+        .iter()
+        .any(|component| matches!(component, QueryComponent::Custom(_)))
+    {
+        create_custom_query_data(cx, &game, &components)?
+    } else {
+        let components: Vec<HostComponent> = components
+            .iter()
+            .filter_map(|component| match component {
+                QueryComponent::Host(component) => Some(*component),
+                QueryComponent::Custom(_) => None,
+            })
+            .collect();
+        create_query_data(cx, &game.ecs, &components)?
+    };
     cx.write_pod(query_data_out, query_data)?;
 
     Ok(())
 }
 
+/// A component requested by a query.
+#[derive(Copy, Clone)]
+enum QueryComponent {
+    Host(HostComponent),
+    Custom(CustomComponentId),
+}
+
 struct WrittenComponentData {
     pointer: PluginPtrMut<u8>,
     len: u32,
@@ -104,6 +138,158 @@ impl<'a> ComponentVisitor<anyhow::Result<WrittenComponentData>> for WriteCompone
     }
 }
 
+/// `ComponentVisitor` implementation used to write the
+/// components of the given entities to plugin memory.
+///
+/// Slower than [`WriteComponentsVisitor`] because it gets
+/// the component of each entity individually.
+struct WriteEntityComponentsVisitor<'a> {
+    ecs: &'a Ecs,
+    entities: &'a [Entity],
+    cx: &'a PluginContext,
+}
+
+impl<'a> ComponentVisitor<anyhow::Result<WrittenComponentData>>
+    for WriteEntityComponentsVisitor<'a>
+{
+    fn visit<T: Component>(self) -> anyhow::Result<WrittenComponentData> {
+        let mut bytes = Vec::with_capacity(self.entities.len() * size_of::<T>());
+        for &entity in self.entities {
+            let component = self.ecs.get::<T>(entity)?;
+            bytes.extend_from_slice(&component.to_cow_bytes());
+        }
+
+        // `Bytemuck` components are read in place,
+        // so the buffer must be aligned for `T`.
+        let layout = Layout::from_size_align(bytes.len(), align_of::<T>())?;
+        let buffer = self.cx.bump_allocate(layout)?;
+        unsafe {
+            self.cx.write_bytes(buffer, &bytes)?;
+        }
+
+        Ok(WrittenComponentData {
+            pointer: buffer,
+            len: bytes.len() as u32,
+        })
+    }
+}
+
+/// Writes the custom component `id` of the given
+/// entities to plugin memory, each prefixed with its length.
+fn write_custom_components(
+    cx: &PluginContext,
+    ecs: &Ecs,
+    entities: &[Entity],
+    id: CustomComponentId,
+) -> anyhow::Result<WrittenComponentData> {
+    let mut bytes = Vec::new();
+    for &entity in entities {
+        let components = ecs.get::<CustomComponents>(entity)?;
+        let component = components.get(id).context("missing custom component")?;
+        bytes.extend_from_slice(&(component.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(component);
+    }
+
+    let buffer = cx.bump_allocate_and_write_bytes(&bytes)?;
+    Ok(WrittenComponentData {
+        pointer: buffer,
+        len: bytes.len() as u32,
+    })
+}
+
+/// Creates the data of a query including custom components.
+fn create_custom_query_data(
+    cx: &PluginContext,
+    game: &Game,
+    components: &[QueryComponent],
+) -> anyhow::Result<QueryData> {
+    let registry = game.resources.get::<CustomComponentRegistry>()?;
+    let mut query_types = vec![TypeId::of::<CustomComponents>()];
+    let mut custom_ids = Vec::new();
+    for component in components {
+        match component {
+            QueryComponent::Host(component) => query_types.push(component.type_id()),
+            QueryComponent::Custom(id) => {
+                registry.check_access(*id, &cx.identifier)?;
+                custom_ids.push(*id);
+            }
+        }
+    }
+
+    let ecs = &game.ecs;
+    let query = ecs.query_dynamic(DynamicQueryTypes::new(&query_types, &[]));
+    let entities: Vec<Entity> = query
+        .iter_entities()
+        .filter(|&entity| match ecs.get::<CustomComponents>(entity) {
+            Ok(custom) => custom_ids.iter().all(|&id| custom.contains(id)),
+            Err(_) => false,
+        })
+        .collect();
+    if entities.is_empty() {
+        return Ok(empty_query_data());
+    }
+
+    let component_ptrs = cx.bump_allocate(Layout::array::<PluginPtrMut<u8>>(components.len())?)?;
+    let component_lens = cx.bump_allocate(Layout::array::<u32>(components.len())?)?;
+    for (i, &component) in components.iter().enumerate() {
+        let data = match component {
+            QueryComponent::Host(typ) => typ.visit(WriteEntityComponentsVisitor {
+                ecs,
+                entities: &entities,
+                cx,
+            })?,
+            QueryComponent::Custom(id) => write_custom_components(cx, ecs, &entities, id)?,
+        };
+
+        unsafe {
+            cx.write_pod(component_ptrs.cast().add(i), data.pointer)?;
+            cx.write_pod(component_lens.cast().add(i), data.len)?;
+        }
+    }
+
+    write_entities(
+        cx,
+        component_ptrs,
+        component_lens,
+        entities.len(),
+        entities.into_iter(),
+    )
+}
+
+fn empty_query_data() -> QueryData {
+    QueryData {
+        num_entities: 0,
+        entities_ptr: PointerMut::new(ptr::null_mut()),
+        component_ptrs: PointerMut::new(ptr::null_mut()),
+        component_lens: PointerMut::new(ptr::null_mut()),
+    }
+}
+
+/// Writes the queried entities to plugin memory
+/// and returns the query data.
+fn write_entities(
+    cx: &PluginContext,
+    component_ptrs: PluginPtrMut<u8>,
+    component_lens: PluginPtrMut<u8>,
+    num_entities: usize,
+    entities: impl Iterator<Item = Entity>,
+) -> anyhow::Result<QueryData> {
+    let entities_ptr = cx.bump_allocate(Layout::array::<EntityId>(num_entities)?)?;
+    for (i, entity) in entities.enumerate() {
+        let bits = entity.to_bits();
+        unsafe {
+            cx.write_pod(entities_ptr.cast().add(i), bits)?;
+        }
+    }
+
+    Ok(QueryData {
+        num_entities: num_entities as u64,
+        entities_ptr: PointerMut::new(entities_ptr.as_native().cast()),
+        component_ptrs: PointerMut::new(component_ptrs.as_native().cast()),
+        component_lens: PointerMut::new(component_lens.as_native().cast()),
+    })
+}
+
 fn create_query_data(
     cx: &PluginContext,
     ecs: &Ecs,
@@ -114,12 +300,7 @@ fn create_query_data(
 
     let num_entities = query.iter_entities().count();
     if num_entities == 0 {
-        return Ok(QueryData {
-            num_entities: 0,
-            entities_ptr: PointerMut::new(ptr::null_mut()),
-            component_ptrs: PointerMut::new(ptr::null_mut()),
-            component_lens: PointerMut::new(ptr::null_mut()),
-        });
+        return Ok(empty_query_data());
     }
 
     let component_ptrs = cx.bump_allocate(Layout::array::<PluginPtrMut<u8>>(types.len())?)?;
@@ -137,18 +318,11 @@ fn create_query_data(
         }
     }
 
-    let entities_ptr = cx.bump_allocate(Layout::array::<EntityId>(num_entities)?)?;
-    for (i, entity) in query.iter_entities().enumerate() {
-        let bits = entity.to_bits();
-        unsafe {
-            cx.write_pod(entities_ptr.cast().add(i), bits)?;
-        }
-    }
-
-    Ok(QueryData {
-        num_entities: num_entities as u64,
-        entities_ptr: PointerMut::new(entities_ptr.as_native().cast()),
-        component_ptrs: PointerMut::new(component_ptrs.as_native().cast()),
-        component_lens: PointerMut::new(component_lens.as_native().cast()),
-    })
+    write_entities(
+        cx,
+        component_ptrs,
+        component_lens,
+        num_entities,
+        query.iter_entities(),
+    )
 }
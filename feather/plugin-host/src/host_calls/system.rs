@@ -21,9 +21,11 @@ pub fn register_system(
     let name = cx.read_string(name_ptr, name_len)?;
 
     let game = cx.game_mut();
-    game.system_executor
+    let index = game
+        .system_executor
         .borrow_mut()
-        .add_system_with_name(plugin_system(cx.plugin_id(), data_ptr), &name);
+        .add_system_reusing_slot(plugin_system(cx.plugin_id(), data_ptr), &name);
+    cx.systems.borrow_mut().push(index);
 
     Ok(())
 }
@@ -7,29 +7,38 @@
 
 use std::{
     fs,
-    path::Path,
-    sync::atomic::{AtomicUsize, Ordering},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::{Duration, SystemTime},
 };
 
 use ahash::AHashMap;
-use anyhow::Context;
+use anyhow::{bail, Context};
+use custom_component::{CustomComponentRegistry, CustomComponents};
 use env::PluginEnv;
-use feather_common::Game;
+use feather_common::{commands::CommandDispatcher, events::CommandsChangeEvent, Game};
+use feather_ecs::Entity;
 use plugin::Plugin;
 use quill_plugin_format::{PluginFile, PluginMetadata};
-use vec_arena::Arena;
+use tunables::LimitingTunables;
 use wasmer::{
     ChainableNamedResolver, CompilerConfig, ExportError, Features, Function, ImportObject,
-    Instance, Module, Store, JIT,
+    Instance, Module, Pages, Store, JIT, WASM_PAGE_SIZE,
 };
+use wasmer_middlewares::Metering;
 use wasmer_wasi::{WasiEnv, WasiState, WasiVersion};
 
 mod context;
+mod custom_component;
 mod env;
 mod host_calls;
 mod host_function;
 mod plugin;
 mod thread_pinned;
+mod tunables;
 mod wasm_ptr_ext;
 
 /// Features enabled for WASM plugins
@@ -46,14 +55,78 @@ const WASM_FEATURES: Features = Features {
 };
 
 /// Unique ID of a plugin.
-#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+///
+/// IDs are never reused, so callbacks registered by an unloaded
+/// plugin cannot reach a plugin loaded later.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct PluginId(usize);
 
+/// Component added to entities spawned by a plugin,
+/// which are despawned when the plugin is unloaded.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct PluginEntity(pub PluginId);
+
+/// Limits on the resources each WebAssembly plugin may use.
+///
+/// A plugin breaching its fuel or memory limit is suspended:
+/// its systems and commands are no longer run until it is reloaded.
+/// A plugin exceeding its time budget is suspended for the rest of the tick.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct PluginLimits {
+    /// The maximum number of WebAssembly instructions executed
+    /// by a single call into the plugin, e.g. one run of a system.
+    pub max_fuel: Option<u64>,
+    /// The maximum size of the plugin's linear memory in bytes.
+    pub max_memory: Option<usize>,
+    /// The maximum time spent running the plugin during one tick.
+    pub tick_time_budget: Option<Duration>,
+}
+
+impl Default for PluginLimits {
+    fn default() -> Self {
+        Self {
+            max_fuel: Some(50_000_000),
+            max_memory: Some(256 * 1024 * 1024),
+            tick_time_budget: Some(Duration::from_millis(20)),
+        }
+    }
+}
+
+impl PluginLimits {
+    /// Limits allowing plugins to use unbounded resources.
+    pub fn unlimited() -> Self {
+        Self {
+            max_fuel: None,
+            max_memory: None,
+            tick_time_budget: None,
+        }
+    }
+
+    /// Gets the maximum memory size in WebAssembly pages.
+    pub(crate) fn max_memory_pages(&self) -> Option<Pages> {
+        self.max_memory
+            .map(|bytes| Pages((bytes / WASM_PAGE_SIZE) as u32))
+    }
+}
+
 /// Resource storing all enabled plugins plus the WebAssembly VM.
 pub struct PluginManager {
-    plugins: Arena<Plugin>,
+    plugins: AHashMap<PluginId, Plugin>,
+    next_id: usize,
+
+    limits: PluginLimits,
+
+    /// Plugin files loaded from a plugin directory.
+    files: AHashMap<PathBuf, PluginFileState>,
+}
 
-    store: wasmer::Store,
+/// A plugin file loaded from a plugin directory.
+struct PluginFileState {
+    /// The plugin loaded from the file, or `None`
+    /// if it failed to load.
+    plugin: Option<PluginId>,
+    /// The modification time of the file when it was loaded.
+    modified: SystemTime,
 }
 
 impl Default for PluginManager {
@@ -63,72 +136,297 @@ impl Default for PluginManager {
 }
 
 impl PluginManager {
-    /// Creates a plugin manager with no plugins.
+    /// Creates a plugin manager with no plugins
+    /// and the default [`PluginLimits`].
     pub fn new() -> Self {
-        let compiler_config = compiler_config();
-        let engine_config = JIT::new(compiler_config).features(WASM_FEATURES);
-        let engine = engine_config.engine();
-        let store = Store::new(&engine);
+        Self::with_limits(PluginLimits::default())
+    }
 
+    /// Creates a plugin manager with no plugins,
+    /// enforcing the given limits on WebAssembly plugins.
+    pub fn with_limits(limits: PluginLimits) -> Self {
         Self {
-            plugins: Arena::new(),
-            store,
+            plugins: AHashMap::new(),
+            next_id: 0,
+            limits,
+            files: AHashMap::new(),
         }
     }
 
     /// Loads all plugins in the given directory.
     pub fn load_dir(&mut self, game: &mut Game, dir: impl AsRef<Path>) -> anyhow::Result<()> {
-        let dir = dir.as_ref();
-        if !dir.exists() {
-            return Ok(());
+        for (path, modified) in plugin_files(dir.as_ref())? {
+            let plugin = self.load_file(game, &path)?;
+            self.files.insert(
+                path,
+                PluginFileState {
+                    plugin: Some(plugin),
+                    modified,
+                },
+            );
         }
 
-        for entry in std::fs::read_dir(dir)? {
-            let entry = entry?;
-            if entry.file_type()?.is_dir() {
-                continue;
+        Ok(())
+    }
+
+    /// Reloads the plugins in the given directory whose files
+    /// changed since they were loaded. Also loads new plugin files
+    /// and unloads plugins whose files were removed.
+    ///
+    /// Errors are logged so that a broken plugin
+    /// does not prevent the others from reloading.
+    pub fn reload_dir(&mut self, game: &mut Game, dir: impl AsRef<Path>) -> anyhow::Result<()> {
+        let files = plugin_files(dir.as_ref())?;
+
+        let removed: Vec<PathBuf> = self
+            .files
+            .keys()
+            .filter(|path| !files.iter().any(|(file, _)| file == *path))
+            .cloned()
+            .collect();
+        for path in removed {
+            log::info!("Plugin file {} was removed", path.display());
+            if let Some(PluginFileState {
+                plugin: Some(plugin),
+                ..
+            }) = self.files.remove(&path)
+            {
+                self.unload_logging_errors(game, plugin);
             }
+        }
 
-            if entry.path().extension() != Some("plugin".as_ref()) {
-                continue;
+        for (path, modified) in files {
+            let previous = match self.files.get(&path) {
+                Some(state) if state.modified == modified => continue,
+                Some(state) => {
+                    log::info!("Reloading plugin file {}", path.display());
+                    state.plugin
+                }
+                None => None,
+            };
+            if let Some(plugin) = previous {
+                self.unload_logging_errors(game, plugin);
             }
 
-            let bytes = fs::read(entry.path())?;
-            self.load(game, &bytes).with_context(|| {
-                format!("failed to load plugin from {}", entry.path().display())
-            })?;
+            let plugin = match self.load_file(game, &path) {
+                Ok(plugin) => Some(plugin),
+                Err(e) => {
+                    log::error!("{:?}", e);
+                    None
+                }
+            };
+            self.files
+                .insert(path, PluginFileState { plugin, modified });
         }
 
         Ok(())
     }
 
+    fn load_file(&mut self, game: &mut Game, path: &Path) -> anyhow::Result<PluginId> {
+        let bytes = fs::read(path)?;
+        let dir = path.parent().unwrap_or_else(|| Path::new("."));
+        self.load(game, &bytes, dir)
+            .with_context(|| format!("failed to load plugin from {}", path.display()))
+    }
+
     /// Loads and enables a plugin from the given plugin file bytes.
     ///
+    /// The plugin's private data directory is created
+    /// at `<plugins_dir>/<identifier>`. WebAssembly plugins
+    /// cannot access any other part of the filesystem.
+    ///
     /// Returns the ID of the loaded plugin.
-    pub fn load(&mut self, game: &mut Game, file: &[u8]) -> anyhow::Result<PluginId> {
+    pub fn load(
+        &mut self,
+        game: &mut Game,
+        file: &[u8],
+        plugins_dir: impl AsRef<Path>,
+    ) -> anyhow::Result<PluginId> {
         let file = PluginFile::decode(file).context("malformed plugin file")?;
 
-        let id = PluginId(self.plugins.next_vacant());
-        let mut plugin = Plugin::load(self, &file, id)?;
+        let identifier = &file.metadata().identifier;
+        if !is_valid_identifier(identifier) {
+            bail!("invalid plugin identifier {:?}", identifier);
+        }
+        // Reloading unloads the previous version of
+        // a plugin first, so it is not a duplicate.
+        if self
+            .plugins
+            .values()
+            .any(|plugin| &plugin.metadata().identifier == identifier)
+        {
+            bail!(
+                "a plugin with identifier {:?} is already loaded",
+                identifier
+            );
+        }
+        let data_dir = plugins_dir.as_ref().join(identifier);
+        fs::create_dir_all(&data_dir).with_context(|| {
+            format!(
+                "failed to create plugin data directory {}",
+                data_dir.display()
+            )
+        })?;
 
-        plugin.enable(game).context("failed to enable plugin")?;
+        if game.resources.get::<CustomComponentRegistry>().is_err() {
+            game.insert_resource(CustomComponentRegistry::default());
+        }
 
-        self.plugins.insert(plugin);
+        let id = PluginId(self.next_id);
+        self.next_id += 1;
+        let mut plugin = Plugin::load(self, &file, id, &data_dir)?;
+
+        if let Err(e) = plugin.enable(game) {
+            // Undo whatever the plugin registered before failing.
+            if let Err(cleanup_error) = remove_registered_state(game, id, &plugin) {
+                log::error!("Failed to clean up plugin: {:?}", cleanup_error);
+            }
+            return Err(e.context("failed to enable plugin"));
+        }
+
+        self.plugins.insert(id, plugin);
+        game.ecs.insert_event(CommandsChangeEvent);
 
         Ok(id)
     }
 
+    /// Disables and unloads a plugin.
+    ///
+    /// The systems and commands registered by the plugin
+    /// are removed, and the entities it spawned are despawned.
+    pub fn unload(&mut self, game: &mut Game, id: PluginId) -> anyhow::Result<()> {
+        let mut plugin = self.plugins.remove(&id).context("plugin is not loaded")?;
+        let result = plugin.disable(game);
+        remove_registered_state(game, id, &plugin)?;
+
+        log::info!("Unloaded plugin {}", plugin.metadata().name);
+        result
+    }
+
+    fn unload_logging_errors(&mut self, game: &mut Game, id: PluginId) {
+        if let Err(e) = self.unload(game, id) {
+            log::error!("Failed to unload plugin: {:?}", e);
+        }
+    }
+
+    /// Disables all plugins in the order they were loaded.
+    ///
+    /// Errors are logged so that a failing plugin does
+    /// not prevent the others from being disabled.
+    pub fn disable_all(&mut self, game: &mut Game) {
+        let mut ids: Vec<PluginId> = self.plugins.keys().copied().collect();
+        ids.sort_unstable();
+        for id in ids {
+            let mut plugin = self.plugins.remove(&id).expect("plugin exists");
+            if let Err(e) = plugin.disable(game) {
+                log::error!("Failed to disable plugin: {:?}", e);
+            }
+        }
+    }
+
     /// Gets the plugin with the given ID,
     /// or `None` if it has been unloaded.
     pub fn plugin(&self, id: PluginId) -> Option<&Plugin> {
-        self.plugins.get(id.0)
+        self.plugins.get(&id)
     }
 
     /// Mutably gets the plugin with the given ID,
     /// or `None` if it has been unloaded.
     pub fn plugin_mut(&mut self, id: PluginId) -> Option<&mut Plugin> {
-        self.plugins.get_mut(id.0)
+        self.plugins.get_mut(&id)
+    }
+}
+
+/// Removes the systems, commands and custom components
+/// registered by a plugin and despawns the entities it spawned.
+fn remove_registered_state(game: &mut Game, id: PluginId, plugin: &Plugin) -> anyhow::Result<()> {
+    let context = plugin.context();
+    for &system in context.systems.borrow().iter() {
+        game.system_executor.borrow_mut().remove_system(system);
+    }
+    let mut dispatcher = game.resources.get_mut::<CommandDispatcher>()?;
+    for registration in context.commands.borrow_mut().drain(..).rev() {
+        dispatcher.revert(registration);
+    }
+    drop(dispatcher);
+    game.ecs.insert_event(CommandsChangeEvent);
+
+    let removed = game
+        .resources
+        .get_mut::<CustomComponentRegistry>()?
+        .remove_plugin(&context.identifier);
+    if !removed.is_empty() {
+        for (_, components) in game.ecs.query::<&mut CustomComponents>().iter() {
+            for &id in &removed {
+                components.remove(id);
+            }
+        }
     }
+
+    let entities: Vec<Entity> = game
+        .ecs
+        .query::<&PluginEntity>()
+        .iter()
+        .filter(|(_, owner)| owner.0 == id)
+        .map(|(entity, _)| entity)
+        .collect();
+    for entity in entities {
+        game.remove_entity(entity)?;
+    }
+    Ok(())
+}
+
+/// Creates a store to compile a single WebAssembly module,
+/// enforcing the given limits on it.
+///
+/// A `Metering` middleware may only instrument one module,
+/// so every module needs its own engine and store.
+pub(crate) fn new_store(limits: &PluginLimits) -> Store {
+    let mut compiler_config = compiler_config();
+    if let Some(max_fuel) = limits.max_fuel {
+        // Every instruction costs one unit of fuel.
+        let metering = Metering::new(max_fuel, |_| 1);
+        compiler_config.push_middleware(Arc::new(metering));
+    }
+    let engine_config = JIT::new(compiler_config).features(WASM_FEATURES);
+    let engine = engine_config.engine();
+    match limits.max_memory_pages() {
+        Some(max_pages) => Store::new_with_tunables(&engine, LimitingTunables::new(max_pages)),
+        None => Store::new(&engine),
+    }
+}
+
+/// Returns whether a plugin identifier can safely be used
+/// as the name of its data directory, i.e. it cannot
+/// refer to a path outside of the plugins directory.
+fn is_valid_identifier(identifier: &str) -> bool {
+    !identifier.is_empty()
+        && identifier
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// Lists the plugin files in `dir` and their modification times.
+fn plugin_files(dir: &Path) -> anyhow::Result<Vec<(PathBuf, SystemTime)>> {
+    let mut files = Vec::new();
+    if !dir.exists() {
+        return Ok(files);
+    }
+
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        if entry.file_type()?.is_dir() {
+            continue;
+        }
+
+        if entry.path().extension() != Some("plugin".as_ref()) {
+            continue;
+        }
+
+        files.push((entry.path(), entry.metadata()?.modified()?));
+    }
+
+    Ok(files)
 }
 
 #[cfg(all(feature = "cranelift", not(feature = "llvm")))]
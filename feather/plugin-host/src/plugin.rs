@@ -1,4 +1,9 @@
-use std::sync::Arc;
+use std::{
+    cell::Cell,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use anyhow::bail;
 use feather_common::Game;
@@ -16,13 +21,27 @@ pub struct Plugin {
     inner: Inner,
     context: Arc<PluginContext>,
     metadata: PluginMetadata,
+
+    /// Whether the plugin breached its resource limits,
+    /// in which case it is no longer called into.
+    suspended: Cell<bool>,
+    /// The last tick during which the plugin ran
+    /// and the time it ran for during that tick.
+    tick_time: Cell<(u64, Duration)>,
 }
 
 impl Plugin {
     /// Loads a plugin from the given plugin file.
+    /// `data_dir` is the plugin's private data directory,
+    /// which must exist.
     ///
     /// Does not enable the plugin.
-    pub fn load(manager: &PluginManager, file: &PluginFile, id: PluginId) -> anyhow::Result<Self> {
+    pub fn load(
+        manager: &PluginManager,
+        file: &PluginFile,
+        id: PluginId,
+        data_dir: &Path,
+    ) -> anyhow::Result<Self> {
         let plugin_type = match &file.metadata().target {
             PluginTarget::Wasm => "WebAssembly",
             PluginTarget::Native { .. } => "native",
@@ -36,9 +55,18 @@ impl Plugin {
 
         let (inner, context) = match &file.metadata().target {
             PluginTarget::Wasm => {
-                let context = Arc::new(PluginContext::new_wasm(id));
-                let plugin =
-                    wasm::WasmPlugin::load(manager, &context, file.module(), file.metadata())?;
+                let context = Arc::new(PluginContext::new_wasm(
+                    id,
+                    file.metadata().identifier.clone(),
+                    PathBuf::from(wasm::WASI_DATA_DIR),
+                ));
+                let plugin = wasm::WasmPlugin::load(
+                    manager,
+                    &context,
+                    file.module(),
+                    file.metadata(),
+                    data_dir,
+                )?;
                 (Inner::Wasm(plugin), context)
             }
             PluginTarget::Native { target_triple } => {
@@ -50,18 +78,48 @@ impl Plugin {
                     );
                 }
                 let plugin = native::NativePlugin::load(file.module())?;
-                let context = PluginContext::new_native(id);
+                // Native plugins are not sandboxed, so they
+                // access their directory through its host path.
+                let context = PluginContext::new_native(
+                    id,
+                    file.metadata().identifier.clone(),
+                    data_dir.to_path_buf(),
+                );
                 (Inner::Native(plugin), Arc::new(context))
             }
         };
 
+        context.set_runs_commands(match &inner {
+            Inner::Wasm(w) => w.runs_commands(),
+            Inner::Native(n) => n.runs_commands(),
+        });
+
         Ok(Self {
             inner,
             context,
             metadata: file.metadata().clone(),
+            suspended: Cell::new(false),
+            tick_time: Cell::new((0, Duration::default())),
         })
     }
 
+    /// Gets the metadata of the plugin.
+    pub fn metadata(&self) -> &PluginMetadata {
+        &self.metadata
+    }
+
+    /// Gets the context of the plugin, which tracks
+    /// the systems and commands it registered.
+    pub(crate) fn context(&self) -> &PluginContext {
+        &self.context
+    }
+
+    /// Returns whether the plugin was suspended
+    /// for breaching its resource limits.
+    pub fn is_suspended(&self) -> bool {
+        self.suspended.get()
+    }
+
     /// Enables the plugin.
     ///
     /// # Panics
@@ -69,24 +127,42 @@ impl Plugin {
     pub fn enable(&mut self, game: &mut Game) -> anyhow::Result<()> {
         let context = Arc::clone(&self.context);
 
-        self.context.enter(game, || match &self.inner {
+        self.call(game, || match &self.inner {
             Inner::Wasm(w) => w.enable(),
             Inner::Native(n) => {
                 n.enable(context);
                 Ok(())
             }
         })?;
+        if self.is_suspended() {
+            bail!("plugin exceeded its resource limits while enabling");
+        }
 
         log::info!("Enabled plugin {} ", self.metadata.name);
         Ok(())
     }
 
+    /// Disables the plugin. Its systems and
+    /// commands must not be run afterward.
+    pub fn disable(&mut self, game: &mut Game) -> anyhow::Result<()> {
+        self.call(game, || match &self.inner {
+            Inner::Wasm(w) => w.disable(),
+            Inner::Native(n) => {
+                n.disable();
+                Ok(())
+            }
+        })?;
+
+        log::info!("Disabled plugin {}", self.metadata.name);
+        Ok(())
+    }
+
     /// Runs a plugin system.
     ///
     /// `data` must be the data pointer passed
     /// to the `register_system` host call.
     pub fn run_system(&self, game: &mut Game, data: PluginPtrMut<u8>) -> anyhow::Result<()> {
-        self.context.enter(game, || match &self.inner {
+        self.call(game, || match &self.inner {
             Inner::Wasm(w) => w.run_system(data),
             Inner::Native(n) => {
                 n.run_system(data);
@@ -94,6 +170,94 @@ impl Plugin {
             }
         })
     }
+
+    /// Runs a plugin command.
+    ///
+    /// `data` must be an executor pointer passed
+    /// to the `register_command` host call. `invocation`
+    /// is a `bincode`-serialized `CommandInvocation`.
+    pub fn run_command(
+        &self,
+        game: &mut Game,
+        data: PluginPtrMut<u8>,
+        invocation: &[u8],
+    ) -> anyhow::Result<()> {
+        self.call(game, || {
+            let invocation_ptr = self.context.bump_allocate_and_write_bytes(invocation)?;
+            match &self.inner {
+                Inner::Wasm(w) => w.run_command(data, invocation_ptr, invocation.len() as u32),
+                Inner::Native(n) => n.run_command(data, invocation_ptr, invocation.len()),
+            }
+        })
+    }
+
+    /// Calls into the plugin, enforcing the resource limits
+    /// of WebAssembly plugins.
+    ///
+    /// Does nothing if the plugin is suspended or used up
+    /// its time budget for the current tick. A plugin breaching
+    /// its fuel or memory limit is suspended.
+    fn call(&self, game: &mut Game, f: impl FnOnce() -> anyhow::Result<()>) -> anyhow::Result<()> {
+        let wasm = match &self.inner {
+            Inner::Wasm(w) => w,
+            Inner::Native(_) => return self.context.enter(game, f),
+        };
+        let tick = game.tick_count;
+        if self.is_suspended() || self.is_over_time_budget(wasm, tick) {
+            return Ok(());
+        }
+
+        wasm.refuel();
+        let start = Instant::now();
+        let result = self.context.enter(game, f);
+        self.record_time(wasm, tick, start.elapsed());
+
+        if result.is_err() {
+            if let Some(limit) = wasm.breached_limit() {
+                self.suspended.set(true);
+                log::error!(
+                    "Plugin {} exceeded its {} and was suspended until it is reloaded",
+                    self.metadata.name,
+                    limit
+                );
+                return Ok(());
+            }
+        }
+        result
+    }
+
+    fn is_over_time_budget(&self, wasm: &wasm::WasmPlugin, tick: u64) -> bool {
+        match wasm.limits().tick_time_budget {
+            Some(budget) => {
+                let (last_tick, time) = self.tick_time.get();
+                last_tick == tick && time >= budget
+            }
+            None => false,
+        }
+    }
+
+    fn record_time(&self, wasm: &wasm::WasmPlugin, tick: u64, elapsed: Duration) {
+        let (last_tick, time) = self.tick_time.get();
+        let previous = if last_tick == tick {
+            time
+        } else {
+            Duration::default()
+        };
+        let total = previous + elapsed;
+        self.tick_time.set((tick, total));
+
+        if let Some(budget) = wasm.limits().tick_time_budget {
+            if previous < budget && total >= budget {
+                log::warn!(
+                    "Plugin {} exceeded its time budget of {:?} during tick {}; \
+                     suspending it until the next tick",
+                    self.metadata.name,
+                    budget,
+                    tick
+                );
+            }
+        }
+    }
 }
 
 enum Inner {
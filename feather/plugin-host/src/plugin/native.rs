@@ -22,11 +22,24 @@ pub struct NativePlugin {
     /// 3. Length of bincode-encoded vtable
     enable: unsafe extern "C" fn(*const u8, *const u8, usize),
 
+    /// The plugin's exported quill_disable function.
+    /// Plugins built before it was added do not export it.
+    disable: Option<unsafe extern "C" fn()>,
+
     /// The plugin's exported quill_run_system function.
     ///
     /// Parameters:
     /// 1. Plugin data pointer for this system
     run_system: unsafe extern "C" fn(*mut u8),
+
+    /// The plugin's exported quill_run_command function.
+    /// Plugins without commands need not export it.
+    ///
+    /// Parameters:
+    /// 1. Plugin data pointer for this command
+    /// 2. Pointer to the bincode-encoded invocation
+    /// 3. Length of the bincode-encoded invocation
+    run_command: Option<unsafe extern "C" fn(*mut u8, *const u8, usize)>,
 }
 
 impl NativePlugin {
@@ -50,17 +63,33 @@ impl NativePlugin {
                 .get("quill_setup".as_bytes())
                 .context("plugin is missing quill_setup export")?
         };
+        let disable = unsafe {
+            library
+                .get::<unsafe extern "C" fn()>("quill_disable".as_bytes())
+                .ok()
+                .map(|disable| *disable)
+        };
         let run_system = unsafe {
             *library
                 .get("quill_run_system".as_bytes())
                 .context("plugin is missing quill_run_system export")?
         };
+        let run_command = unsafe {
+            library
+                .get::<unsafe extern "C" fn(*mut u8, *const u8, usize)>(
+                    "quill_run_command".as_bytes(),
+                )
+                .ok()
+                .map(|run_command| *run_command)
+        };
 
         Ok(Self {
             tempfile: path,
             library,
             enable,
+            disable,
             run_system,
+            run_command,
         })
     }
 
@@ -80,6 +109,13 @@ impl NativePlugin {
         }
     }
 
+    pub fn disable(&self) {
+        if let Some(disable) = self.disable {
+            // SAFETY: we assume the plugin is sound.
+            unsafe { disable() }
+        }
+    }
+
     fn generate_vtable(&self) -> Vec<u8> {
         let vtable = crate::host_calls::generate_vtable();
         bincode::serialize(&vtable).expect("can't serialize vtable")
@@ -89,4 +125,22 @@ impl NativePlugin {
         // SAFETY: we assume the plugin is sound.
         unsafe { (self.run_system)(data.as_native()) }
     }
+
+    pub fn runs_commands(&self) -> bool {
+        self.run_command.is_some()
+    }
+
+    pub fn run_command(
+        &self,
+        data: PluginPtrMut<u8>,
+        invocation: PluginPtrMut<u8>,
+        len: usize,
+    ) -> anyhow::Result<()> {
+        let run_command = self
+            .run_command
+            .context("plugin is missing quill_run_command export")?;
+        // SAFETY: we assume the plugin is sound.
+        unsafe { run_command(data.as_native(), invocation.as_native(), len) }
+        Ok(())
+    }
 }
@@ -1,17 +1,24 @@
-use std::sync::Arc;
+use std::{path::Path, sync::Arc};
+
+use anyhow::Context;
 
 use quill_plugin_format::PluginMetadata;
 use wasmer::{
     ChainableNamedResolver, Features, Function, ImportObject, Instance, Module, NativeFunc, Store,
 };
+use wasmer_middlewares::metering::{self, MeteringPoints};
 use wasmer_wasi::{WasiEnv, WasiState, WasiVersion};
 
 use crate::{
     context::{PluginContext, PluginPtr, PluginPtrMut},
     env::PluginEnv,
-    PluginManager,
+    PluginLimits, PluginManager,
 };
 
+/// The path at which a plugin's data directory
+/// is preopened in its WASI filesystem.
+pub const WASI_DATA_DIR: &str = "/data";
+
 pub struct WasmPlugin {
     /// The WebAssembly instancing containing
     /// the plugin.
@@ -20,8 +27,20 @@ pub struct WasmPlugin {
     /// Exported function to enable the plugin.
     enable: Function,
 
+    /// Exported function to disable the plugin.
+    /// Plugins built before it was added do not export it.
+    disable: Option<Function>,
+
     /// Exported function to run a system given its data pointer.
     run_system: NativeFunc<u32>,
+
+    /// Exported function to run a command given its data pointer
+    /// and a pointer to the serialized invocation.
+    /// Plugins without commands need not export it.
+    run_command: Option<NativeFunc<(u32, u32, u32)>>,
+
+    /// The resource limits enforced on the plugin.
+    limits: PluginLimits,
 }
 
 impl WasmPlugin {
@@ -30,15 +49,17 @@ impl WasmPlugin {
         cx: &Arc<PluginContext>,
         module: &[u8],
         metadata: &PluginMetadata,
+        data_dir: &Path,
     ) -> anyhow::Result<Self> {
         let env = PluginEnv {
             context: Arc::clone(cx),
         };
-        let quill_imports = crate::host_calls::generate_import_object(&manager.store, &env);
-        let wasi_imports = generate_wasi_import_object(&manager.store, &metadata.identifier)?;
+        let store = crate::new_store(&manager.limits);
+        let quill_imports = crate::host_calls::generate_import_object(&store, &env);
+        let wasi_imports = generate_wasi_import_object(&store, &metadata.identifier, data_dir)?;
         let imports = quill_imports.chain_back(wasi_imports);
 
-        let module = Module::new(&manager.store, module)?;
+        let module = Module::new(&store, module)?;
         let instance = Instance::new(&module, &imports)?;
 
         let run_system = instance
@@ -46,28 +67,109 @@ impl WasmPlugin {
             .get_function("quill_run_system")?
             .native()?
             .clone();
+        let run_command = match instance.exports.get_function("quill_run_command") {
+            Ok(run_command) => Some(run_command.native()?.clone()),
+            Err(_) => None,
+        };
         let enable = instance.exports.get_function("quill_setup")?.clone();
+        let disable = instance.exports.get_function("quill_disable").ok().cloned();
 
         Ok(Self {
             instance,
             run_system,
+            run_command,
             enable,
+            disable,
+            limits: manager.limits,
         })
     }
 
+    pub fn limits(&self) -> &PluginLimits {
+        &self.limits
+    }
+
+    /// Refills the fuel available to the next call into the plugin.
+    pub fn refuel(&self) {
+        if let Some(max_fuel) = self.limits.max_fuel {
+            metering::set_remaining_points(&self.instance, max_fuel);
+        }
+    }
+
+    /// Returns a description of the limit the plugin breached
+    /// during the last call into it, if any. Should be
+    /// called after a call into the plugin failed.
+    pub fn breached_limit(&self) -> Option<String> {
+        if let Some(max_fuel) = self.limits.max_fuel {
+            if metering::get_remaining_points(&self.instance) == MeteringPoints::Exhausted {
+                return Some(format!("fuel limit of {} instructions", max_fuel));
+            }
+        }
+
+        // A failed allocation surfaces as a trap once
+        // `memory.grow` fails at the limit.
+        if let (Some(max_memory), Some(max_pages)) =
+            (self.limits.max_memory, self.limits.max_memory_pages())
+        {
+            let memory = self.instance.exports.get_memory("memory").ok()?;
+            if memory.size() >= max_pages {
+                return Some(format!("memory limit of {} bytes", max_memory));
+            }
+        }
+
+        None
+    }
+
     pub fn enable(&self) -> anyhow::Result<()> {
         self.enable.call(&[])?;
         Ok(())
     }
 
+    pub fn disable(&self) -> anyhow::Result<()> {
+        if let Some(disable) = &self.disable {
+            disable.call(&[])?;
+        }
+        Ok(())
+    }
+
     pub fn run_system(&self, data_ptr: PluginPtrMut<u8>) -> anyhow::Result<()> {
         self.run_system.call(data_ptr.ptr as u32)?;
         Ok(())
     }
+
+    pub fn runs_commands(&self) -> bool {
+        self.run_command.is_some()
+    }
+
+    pub fn run_command(
+        &self,
+        data_ptr: PluginPtrMut<u8>,
+        invocation_ptr: PluginPtrMut<u8>,
+        invocation_len: u32,
+    ) -> anyhow::Result<()> {
+        let run_command = self
+            .run_command
+            .as_ref()
+            .context("plugin is missing quill_run_command export")?;
+        run_command.call(
+            data_ptr.ptr as u32,
+            invocation_ptr.ptr as u32,
+            invocation_len,
+        )?;
+        Ok(())
+    }
 }
 
-fn generate_wasi_import_object(store: &Store, plugin_name: &str) -> anyhow::Result<ImportObject> {
-    let state = WasiState::new(plugin_name).build()?;
+/// Generates the WASI imports for a plugin. The only directory
+/// accessible to the plugin is its data directory,
+/// preopened at [`WASI_DATA_DIR`].
+fn generate_wasi_import_object(
+    store: &Store,
+    plugin_name: &str,
+    data_dir: &Path,
+) -> anyhow::Result<ImportObject> {
+    let state = WasiState::new(plugin_name)
+        .map_dir(WASI_DATA_DIR, data_dir)?
+        .build()?;
     let env = WasiEnv::new(state);
     Ok(wasmer_wasi::generate_import_object_from_env(
         store,
@@ -75,3 +177,29 @@ fn generate_wasi_import_object(store: &Store, plugin_name: &str) -> anyhow::Resu
         WasiVersion::Latest,
     ))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The smallest valid WebAssembly module.
+    const EMPTY_MODULE: &[u8] = b"\0asm\x01\0\0\0";
+
+    #[test]
+    fn compile_several_modules_with_fuel_limit() {
+        let limits = PluginLimits {
+            max_fuel: Some(1000),
+            ..PluginLimits::default()
+        };
+        for _ in 0..2 {
+            let store = crate::new_store(&limits);
+            let module = Module::new(&store, EMPTY_MODULE).unwrap();
+            let instance = Instance::new(&module, &ImportObject::new()).unwrap();
+            metering::set_remaining_points(&instance, 1000);
+            assert_eq!(
+                metering::get_remaining_points(&instance),
+                MeteringPoints::Remaining(1000)
+            );
+        }
+    }
+}
@@ -4,7 +4,7 @@ use crate::{ProtocolVersion, Slot};
 use anyhow::{anyhow, bail, Context};
 use base::{
     anvil::entity::ItemNbt, metadata::MetaEntry, BlockId, BlockPosition, Direction, EntityMetadata,
-    Gamemode, Item, ItemStack,
+    Gamemode, Item,
 };
 use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
 use num_traits::{FromPrimitive, ToPrimitive};
@@ -541,7 +541,7 @@ impl Readable for Slot {
 
         if present {
             let item_id = VarInt::read(buffer, version)?.0;
-            let count = u8::read(buffer, version)? as u32;
+            let count = u8::read(buffer, version)?;
 
             // Read NBT, but make sure to reset the buffer position if it's missing.
             let position = buffer.position();
@@ -553,11 +553,7 @@ impl Readable for Slot {
             let item = Item::from_id(item_id.try_into()?)
                 .ok_or_else(|| anyhow!("unknown item ID {}", item_id))?;
 
-            Ok(Some(ItemStack {
-                item,
-                count,
-                damage: tags.map(|t| t.damage).flatten().map(|d| d as u32),
-            }))
+            Ok(Some(ItemNbt::item_stack(&tags, item, count)))
         } else {
             Ok(None)
         }
@@ -10,10 +10,15 @@ pub use chunk_data::{ChunkData, ChunkDataKind};
 mod update_light;
 pub use update_light::UpdateLight;
 
+mod declare_commands;
+pub use declare_commands::{
+    ArgumentParser, CommandNode, CommandNodeKind, DeclareCommands, StringKind,
+};
+
 packets! {
     SpawnEntity {
         entity_id VarInt;
-        uuid String;
+        uuid Uuid;
         kind VarInt;
         x f64;
         y f64;
@@ -229,28 +234,9 @@ packets! {
 
     TabCompleteMatch {
         value String;
-        has_tooltip bool;
         tooltip Option<String>;
     }
 
-    DeclareCommands {
-        // (not implemented)
-        __todo__ LengthInferredVecU8;
-        /* nodes LengthPrefixedVec<CommandNode>;
-        root_index VarInt; */
-    }
-
-    CommandNode {
-        flags u8;
-        children VarIntPrefixedVec<VarInt>;
-        redirect_node Option<VarInt>;
-        name Option<String>;
-        parser Option<String>;
-        // TODO: handle properties, which vary depending on the value of `parser`.
-        // This can be handled with an enum.
-        __todo__ LengthInferredVecU8;
-    }
-
     WindowConfirmation {
         window_id u8;
         action_number i16;
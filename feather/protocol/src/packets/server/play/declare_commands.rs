@@ -0,0 +1,359 @@
+use std::io::Cursor;
+
+use anyhow::bail;
+
+use crate::{io::VarInt, ProtocolVersion, Readable, Writeable};
+
+const NODE_TYPE_MASK: u8 = 0x03;
+const FLAG_EXECUTABLE: u8 = 0x04;
+const FLAG_REDIRECT: u8 = 0x08;
+const FLAG_SUGGESTIONS: u8 = 0x10;
+
+const RANGE_MIN: u8 = 0x01;
+const RANGE_MAX: u8 = 0x02;
+
+const ENTITY_SINGLE: u8 = 0x01;
+const ENTITY_ONLY_PLAYERS: u8 = 0x02;
+
+/// The command graph, used by the client for
+/// command suggestions and syntax highlighting.
+#[derive(Debug, Clone)]
+pub struct DeclareCommands {
+    pub nodes: Vec<CommandNode>,
+    /// Index of the root node in `nodes`.
+    pub root_index: i32,
+}
+
+#[derive(Debug, Clone)]
+pub struct CommandNode {
+    pub kind: CommandNodeKind,
+    /// Whether the command can be executed
+    /// if it ends at this node.
+    pub executable: bool,
+    /// Indices of the children of this node.
+    pub children: Vec<i32>,
+    pub redirect_node: Option<i32>,
+}
+
+#[derive(Debug, Clone)]
+pub enum CommandNodeKind {
+    Root,
+    Literal {
+        name: String,
+    },
+    Argument {
+        name: String,
+        parser: ArgumentParser,
+        /// Identifier of the suggestions provider,
+        /// e.g. `minecraft:ask_server`.
+        suggestions_type: Option<String>,
+    },
+}
+
+/// The parser of an argument node, including its properties.
+#[derive(Debug, Clone)]
+pub enum ArgumentParser {
+    Bool,
+    Double {
+        min: Option<f64>,
+        max: Option<f64>,
+    },
+    Float {
+        min: Option<f32>,
+        max: Option<f32>,
+    },
+    Integer {
+        min: Option<i32>,
+        max: Option<i32>,
+    },
+    Long {
+        min: Option<i64>,
+        max: Option<i64>,
+    },
+    String(StringKind),
+    Entity {
+        single: bool,
+        only_players: bool,
+    },
+    ScoreHolder {
+        multiple: bool,
+    },
+    Range {
+        decimals: bool,
+    },
+    /// A parser without properties, e.g. `minecraft:vec3`.
+    Other(String),
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum StringKind {
+    SingleWord = 0,
+    QuotablePhrase = 1,
+    GreedyPhrase = 2,
+}
+
+impl ArgumentParser {
+    /// Returns the identifier of this parser.
+    pub fn identifier(&self) -> &str {
+        match self {
+            ArgumentParser::Bool => "brigadier:bool",
+            ArgumentParser::Double { .. } => "brigadier:double",
+            ArgumentParser::Float { .. } => "brigadier:float",
+            ArgumentParser::Integer { .. } => "brigadier:integer",
+            ArgumentParser::Long { .. } => "brigadier:long",
+            ArgumentParser::String(_) => "brigadier:string",
+            ArgumentParser::Entity { .. } => "minecraft:entity",
+            ArgumentParser::ScoreHolder { .. } => "minecraft:score_holder",
+            ArgumentParser::Range { .. } => "minecraft:range",
+            ArgumentParser::Other(identifier) => identifier,
+        }
+    }
+
+    fn read_properties(
+        identifier: String,
+        buffer: &mut Cursor<&[u8]>,
+        version: ProtocolVersion,
+    ) -> anyhow::Result<Self> {
+        Ok(match identifier.as_str() {
+            "brigadier:bool" => ArgumentParser::Bool,
+            "brigadier:double" => {
+                let (min, max) = read_range(buffer, version)?;
+                ArgumentParser::Double { min, max }
+            }
+            "brigadier:float" => {
+                let (min, max) = read_range(buffer, version)?;
+                ArgumentParser::Float { min, max }
+            }
+            "brigadier:integer" => {
+                let (min, max) = read_range(buffer, version)?;
+                ArgumentParser::Integer { min, max }
+            }
+            "brigadier:long" => {
+                let (min, max) = read_range(buffer, version)?;
+                ArgumentParser::Long { min, max }
+            }
+            "brigadier:string" => ArgumentParser::String(match VarInt::read(buffer, version)?.0 {
+                0 => StringKind::SingleWord,
+                1 => StringKind::QuotablePhrase,
+                2 => StringKind::GreedyPhrase,
+                x => bail!("invalid string argument kind {}", x),
+            }),
+            "minecraft:entity" => {
+                let flags = u8::read(buffer, version)?;
+                ArgumentParser::Entity {
+                    single: flags & ENTITY_SINGLE != 0,
+                    only_players: flags & ENTITY_ONLY_PLAYERS != 0,
+                }
+            }
+            "minecraft:score_holder" => ArgumentParser::ScoreHolder {
+                multiple: u8::read(buffer, version)? & 0x01 != 0,
+            },
+            "minecraft:range" => ArgumentParser::Range {
+                decimals: bool::read(buffer, version)?,
+            },
+            _ => ArgumentParser::Other(identifier),
+        })
+    }
+
+    fn write_properties(
+        &self,
+        buffer: &mut Vec<u8>,
+        version: ProtocolVersion,
+    ) -> anyhow::Result<()> {
+        match self {
+            ArgumentParser::Double { min, max } => write_range(*min, *max, buffer, version)?,
+            ArgumentParser::Float { min, max } => write_range(*min, *max, buffer, version)?,
+            ArgumentParser::Integer { min, max } => write_range(*min, *max, buffer, version)?,
+            ArgumentParser::Long { min, max } => write_range(*min, *max, buffer, version)?,
+            ArgumentParser::String(kind) => VarInt(*kind as i32).write(buffer, version)?,
+            ArgumentParser::Entity {
+                single,
+                only_players,
+            } => {
+                let mut flags = 0;
+                if *single {
+                    flags |= ENTITY_SINGLE;
+                }
+                if *only_players {
+                    flags |= ENTITY_ONLY_PLAYERS;
+                }
+                flags.write(buffer, version)?;
+            }
+            ArgumentParser::ScoreHolder { multiple } => (*multiple as u8).write(buffer, version)?,
+            ArgumentParser::Range { decimals } => decimals.write(buffer, version)?,
+            ArgumentParser::Bool | ArgumentParser::Other(_) => {}
+        }
+        Ok(())
+    }
+}
+
+fn read_range<T: Readable>(
+    buffer: &mut Cursor<&[u8]>,
+    version: ProtocolVersion,
+) -> anyhow::Result<(Option<T>, Option<T>)> {
+    let flags = u8::read(buffer, version)?;
+    let min = if flags & RANGE_MIN != 0 {
+        Some(T::read(buffer, version)?)
+    } else {
+        None
+    };
+    let max = if flags & RANGE_MAX != 0 {
+        Some(T::read(buffer, version)?)
+    } else {
+        None
+    };
+    Ok((min, max))
+}
+
+fn write_range<T: Writeable>(
+    min: Option<T>,
+    max: Option<T>,
+    buffer: &mut Vec<u8>,
+    version: ProtocolVersion,
+) -> anyhow::Result<()> {
+    let mut flags = 0;
+    if min.is_some() {
+        flags |= RANGE_MIN;
+    }
+    if max.is_some() {
+        flags |= RANGE_MAX;
+    }
+    flags.write(buffer, version)?;
+    if let Some(min) = min {
+        min.write(buffer, version)?;
+    }
+    if let Some(max) = max {
+        max.write(buffer, version)?;
+    }
+    Ok(())
+}
+
+impl Readable for DeclareCommands {
+    fn read(buffer: &mut Cursor<&[u8]>, version: ProtocolVersion) -> anyhow::Result<Self>
+    where
+        Self: Sized,
+    {
+        let num_nodes = VarInt::read(buffer, version)?.0;
+        let mut nodes = Vec::new();
+        for _ in 0..num_nodes {
+            nodes.push(CommandNode::read(buffer, version)?);
+        }
+        let root_index = VarInt::read(buffer, version)?.0;
+        Ok(Self { nodes, root_index })
+    }
+}
+
+impl Writeable for DeclareCommands {
+    fn write(&self, buffer: &mut Vec<u8>, version: ProtocolVersion) -> anyhow::Result<()> {
+        VarInt(self.nodes.len() as i32).write(buffer, version)?;
+        for node in &self.nodes {
+            node.write(buffer, version)?;
+        }
+        VarInt(self.root_index).write(buffer, version)?;
+        Ok(())
+    }
+}
+
+impl Readable for CommandNode {
+    fn read(buffer: &mut Cursor<&[u8]>, version: ProtocolVersion) -> anyhow::Result<Self>
+    where
+        Self: Sized,
+    {
+        let flags = u8::read(buffer, version)?;
+
+        let num_children = VarInt::read(buffer, version)?.0;
+        let mut children = Vec::new();
+        for _ in 0..num_children {
+            children.push(VarInt::read(buffer, version)?.0);
+        }
+
+        let redirect_node = if flags & FLAG_REDIRECT != 0 {
+            Some(VarInt::read(buffer, version)?.0)
+        } else {
+            None
+        };
+
+        let kind = match flags & NODE_TYPE_MASK {
+            0 => CommandNodeKind::Root,
+            1 => CommandNodeKind::Literal {
+                name: String::read(buffer, version)?,
+            },
+            2 => {
+                let name = String::read(buffer, version)?;
+                let identifier = String::read(buffer, version)?;
+                let parser = ArgumentParser::read_properties(identifier, buffer, version)?;
+                let suggestions_type = if flags & FLAG_SUGGESTIONS != 0 {
+                    Some(String::read(buffer, version)?)
+                } else {
+                    None
+                };
+                CommandNodeKind::Argument {
+                    name,
+                    parser,
+                    suggestions_type,
+                }
+            }
+            x => bail!("invalid command node type {}", x),
+        };
+
+        Ok(Self {
+            kind,
+            executable: flags & FLAG_EXECUTABLE != 0,
+            children,
+            redirect_node,
+        })
+    }
+}
+
+impl Writeable for CommandNode {
+    fn write(&self, buffer: &mut Vec<u8>, version: ProtocolVersion) -> anyhow::Result<()> {
+        let mut flags = match &self.kind {
+            CommandNodeKind::Root => 0,
+            CommandNodeKind::Literal { .. } => 1,
+            CommandNodeKind::Argument {
+                suggestions_type, ..
+            } => {
+                if suggestions_type.is_some() {
+                    2 | FLAG_SUGGESTIONS
+                } else {
+                    2
+                }
+            }
+        };
+        if self.executable {
+            flags |= FLAG_EXECUTABLE;
+        }
+        if self.redirect_node.is_some() {
+            flags |= FLAG_REDIRECT;
+        }
+        flags.write(buffer, version)?;
+
+        VarInt(self.children.len() as i32).write(buffer, version)?;
+        for &child in &self.children {
+            VarInt(child).write(buffer, version)?;
+        }
+
+        if let Some(redirect_node) = self.redirect_node {
+            VarInt(redirect_node).write(buffer, version)?;
+        }
+
+        match &self.kind {
+            CommandNodeKind::Root => {}
+            CommandNodeKind::Literal { name } => name.write(buffer, version)?,
+            CommandNodeKind::Argument {
+                name,
+                parser,
+                suggestions_type,
+            } => {
+                name.write(buffer, version)?;
+                parser.identifier().to_owned().write(buffer, version)?;
+                parser.write_properties(buffer, version)?;
+                if let Some(suggestions_type) = suggestions_type {
+                    suggestions_type.write(buffer, version)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
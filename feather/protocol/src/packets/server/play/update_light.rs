@@ -26,21 +26,27 @@ impl Writeable for UpdateLight {
 
         true.write(buffer, version)?; // trust edges?
 
-        let mut mask = 0;
+        // Missing sections have no block light and full sky light,
+        // so their sky light has to be sent explicitly.
+        let mut block_light_mask = 0;
         for (y, section) in chunk.sections().iter().enumerate() {
             if section.is_some() {
-                mask |= 1 << y;
+                block_light_mask |= 1 << y;
             }
         }
+        let sky_light_mask = (1 << chunk.sections().len()) - 1;
 
-        VarInt(mask).write(buffer, version)?; // sky light mask
-        VarInt(mask).write(buffer, version)?; // block light mask
+        VarInt(sky_light_mask).write(buffer, version)?; // sky light mask
+        VarInt(block_light_mask).write(buffer, version)?; // block light mask
 
-        VarInt(!mask).write(buffer, version)?; // empty sky light mask
-        VarInt(!mask).write(buffer, version)?; // empty block light mask
+        VarInt(0).write(buffer, version)?; // empty sky light mask
+        VarInt(!block_light_mask).write(buffer, version)?; // empty block light mask
 
-        for section in chunk.sections().iter().flatten() {
-            encode_light(section.light().sky_light(), buffer, version);
+        for section in chunk.sections() {
+            match section {
+                Some(section) => encode_light(section.light().sky_light(), buffer, version),
+                None => encode_full_light(buffer, version),
+            }
         }
 
         for section in chunk.sections().iter().flatten() {
@@ -58,6 +64,11 @@ fn encode_light(light: &PackedArray, buffer: &mut Vec<u8>, version: ProtocolVers
     buffer.extend_from_slice(light_data);
 }
 
+fn encode_full_light(buffer: &mut Vec<u8>, version: ProtocolVersion) {
+    VarInt(2048).write(buffer, version).unwrap();
+    buffer.extend_from_slice(&[0xFF; 2048]);
+}
+
 #[cfg(feature = "proxy")]
 impl Readable for UpdateLight {
     fn read(
@@ -0,0 +1,468 @@
+//! The operator, whitelist and ban lists, stored in
+//! `ops.json`, `whitelist.json`, `banned-players.json`
+//! and `banned-ips.json` using the vanilla formats.
+
+use std::{
+    fs,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use anyhow::Context;
+use base::Text;
+use chrono::{DateTime, Local};
+use parking_lot::RwLock;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use uuid::Uuid;
+
+pub mod commands;
+
+const OPS_FILE: &str = "ops.json";
+const WHITELIST_FILE: &str = "whitelist.json";
+const BANNED_PLAYERS_FILE: &str = "banned-players.json";
+const BANNED_IPS_FILE: &str = "banned-ips.json";
+
+/// Format of the dates in ban entries.
+const DATE_FORMAT: &str = "%Y-%m-%d %H:%M:%S %z";
+/// The `expires` value of permanent bans.
+const FOREVER: &str = "forever";
+
+pub const DEFAULT_BAN_REASON: &str = "Banned by an operator.";
+
+/// An entry in `ops.json`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OpEntry {
+    pub uuid: Uuid,
+    pub name: String,
+    /// The permission level of the operator, from 1 to 4.
+    pub level: u8,
+    #[serde(default)]
+    pub bypasses_player_limit: bool,
+}
+
+/// An entry in `whitelist.json`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WhitelistEntry {
+    pub uuid: Uuid,
+    pub name: String,
+}
+
+/// An entry in `banned-players.json`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BannedPlayerEntry {
+    pub uuid: Uuid,
+    pub name: String,
+    #[serde(flatten)]
+    pub ban: Ban,
+}
+
+/// An entry in `banned-ips.json`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BannedIpEntry {
+    pub ip: String,
+    #[serde(flatten)]
+    pub ban: Ban,
+}
+
+/// The details of a player or IP ban.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Ban {
+    /// When the ban was created, e.g. `2021-01-01 12:00:00 +0000`.
+    pub created: String,
+    /// Name of the player who created the ban,
+    /// or `Server` for the console.
+    pub source: String,
+    /// When the ban expires, or `forever`.
+    pub expires: String,
+    pub reason: String,
+}
+
+impl Ban {
+    /// Creates a permanent ban starting now.
+    pub fn new(source: impl Into<String>, reason: impl Into<String>) -> Self {
+        Self {
+            created: Local::now().format(DATE_FORMAT).to_string(),
+            source: source.into(),
+            expires: FOREVER.to_owned(),
+            reason: reason.into(),
+        }
+    }
+
+    /// Returns the expiry date, or `None` if the
+    /// ban is permanent.
+    fn expiry(&self) -> Option<DateTime<chrono::FixedOffset>> {
+        if self.expires == FOREVER {
+            return None;
+        }
+        DateTime::parse_from_str(&self.expires, DATE_FORMAT).ok()
+    }
+
+    pub fn is_expired(&self) -> bool {
+        match self.expiry() {
+            Some(expiry) => expiry < Local::now(),
+            None => false,
+        }
+    }
+
+    /// Returns the message shown to banned players
+    /// trying to join.
+    fn disconnect_reason(&self, key: &'static str) -> Text {
+        let reason = Text::translate_with(key, vec![self.reason.clone()]);
+        match self.expiry() {
+            Some(expiry) => {
+                reason
+                    + Text::translate_with(
+                        "multiplayer.disconnect.banned.expiration",
+                        vec![expiry.format(DATE_FORMAT).to_string()],
+                    )
+            }
+            None => reason,
+        }
+    }
+}
+
+/// Handle to the access lists.
+///
+/// Can be cloned to create a new handle. Changes
+/// are saved to the files immediately.
+#[derive(Clone)]
+pub struct AccessLists {
+    inner: Arc<RwLock<Inner>>,
+}
+
+struct Inner {
+    dir: PathBuf,
+    whitelist_enabled: bool,
+    ops: Vec<OpEntry>,
+    whitelist: Vec<WhitelistEntry>,
+    banned_players: Vec<BannedPlayerEntry>,
+    banned_ips: Vec<BannedIpEntry>,
+}
+
+impl AccessLists {
+    /// Loads the lists from the files in `dir`,
+    /// creating empty files for missing lists.
+    pub fn load(dir: impl Into<PathBuf>, whitelist_enabled: bool) -> anyhow::Result<Self> {
+        let dir = dir.into();
+        let inner = Inner {
+            ops: load_list(&dir.join(OPS_FILE))?,
+            whitelist: load_list(&dir.join(WHITELIST_FILE))?,
+            banned_players: load_list(&dir.join(BANNED_PLAYERS_FILE))?,
+            banned_ips: load_list(&dir.join(BANNED_IPS_FILE))?,
+            dir,
+            whitelist_enabled,
+        };
+        Ok(Self {
+            inner: Arc::new(RwLock::new(inner)),
+        })
+    }
+
+    /// Creates empty lists which are not saved.
+    pub fn empty() -> Self {
+        Self {
+            inner: Arc::new(RwLock::new(Inner {
+                dir: PathBuf::new(),
+                whitelist_enabled: false,
+                ops: Vec::new(),
+                whitelist: Vec::new(),
+                banned_players: Vec::new(),
+                banned_ips: Vec::new(),
+            })),
+        }
+    }
+
+    /// Reloads all lists from their files.
+    pub fn reload(&self) -> anyhow::Result<()> {
+        let mut inner = self.inner.write();
+        inner.ops = load_list(&inner.dir.join(OPS_FILE))?;
+        inner.whitelist = load_list(&inner.dir.join(WHITELIST_FILE))?;
+        inner.banned_players = load_list(&inner.dir.join(BANNED_PLAYERS_FILE))?;
+        inner.banned_ips = load_list(&inner.dir.join(BANNED_IPS_FILE))?;
+        Ok(())
+    }
+
+    /// Determines whether a player may join. Returns
+    /// the disconnect reason if not.
+    pub fn check_login(&self, uuid: Uuid, ip: &str) -> Result<(), Text> {
+        if let Some(entry) = self.banned_player(uuid) {
+            return Err(entry
+                .ban
+                .disconnect_reason("multiplayer.disconnect.banned.reason"));
+        }
+        if let Some(entry) = self.banned_ip(ip) {
+            return Err(entry
+                .ban
+                .disconnect_reason("multiplayer.disconnect.banned_ip.reason"));
+        }
+        if self.is_whitelist_enabled() && !self.is_whitelisted(uuid) && self.op(uuid).is_none() {
+            return Err(Text::translate_with(
+                "multiplayer.disconnect.not_whitelisted",
+                Vec::<Text>::new(),
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn ops(&self) -> Vec<OpEntry> {
+        self.inner.read().ops.clone()
+    }
+
+    pub fn op(&self, uuid: Uuid) -> Option<OpEntry> {
+        self.inner
+            .read()
+            .ops
+            .iter()
+            .find(|entry| entry.uuid == uuid)
+            .cloned()
+    }
+
+    /// Returns the permission level of a player, which
+    /// is 0 for players who are not operators.
+    pub fn permission_level(&self, uuid: Uuid) -> u8 {
+        self.op(uuid).map(|entry| entry.level).unwrap_or(0)
+    }
+
+    /// Adds an operator. Returns `false` if the
+    /// player already is an operator.
+    pub fn add_op(&self, entry: OpEntry) -> bool {
+        let mut inner = self.inner.write();
+        if inner.ops.iter().any(|op| op.uuid == entry.uuid) {
+            return false;
+        }
+        inner.ops.push(entry);
+        save_list(&inner.dir, OPS_FILE, &inner.ops);
+        true
+    }
+
+    /// Removes an operator. Returns `false` if the
+    /// player is not an operator.
+    pub fn remove_op(&self, uuid: Uuid) -> bool {
+        let mut inner = self.inner.write();
+        let len = inner.ops.len();
+        inner.ops.retain(|op| op.uuid != uuid);
+        if inner.ops.len() == len {
+            return false;
+        }
+        save_list(&inner.dir, OPS_FILE, &inner.ops);
+        true
+    }
+
+    pub fn is_whitelist_enabled(&self) -> bool {
+        self.inner.read().whitelist_enabled
+    }
+
+    /// Enables or disables the whitelist until
+    /// the server restarts.
+    pub fn set_whitelist_enabled(&self, enabled: bool) {
+        self.inner.write().whitelist_enabled = enabled;
+    }
+
+    pub fn whitelist(&self) -> Vec<WhitelistEntry> {
+        self.inner.read().whitelist.clone()
+    }
+
+    pub fn is_whitelisted(&self, uuid: Uuid) -> bool {
+        self.inner
+            .read()
+            .whitelist
+            .iter()
+            .any(|entry| entry.uuid == uuid)
+    }
+
+    /// Adds a player to the whitelist. Returns `false`
+    /// if the player already is whitelisted.
+    pub fn add_to_whitelist(&self, entry: WhitelistEntry) -> bool {
+        let mut inner = self.inner.write();
+        if inner.whitelist.iter().any(|e| e.uuid == entry.uuid) {
+            return false;
+        }
+        inner.whitelist.push(entry);
+        save_list(&inner.dir, WHITELIST_FILE, &inner.whitelist);
+        true
+    }
+
+    /// Removes a player from the whitelist. Returns
+    /// `false` if the player is not whitelisted.
+    pub fn remove_from_whitelist(&self, uuid: Uuid) -> bool {
+        let mut inner = self.inner.write();
+        let len = inner.whitelist.len();
+        inner.whitelist.retain(|entry| entry.uuid != uuid);
+        if inner.whitelist.len() == len {
+            return false;
+        }
+        save_list(&inner.dir, WHITELIST_FILE, &inner.whitelist);
+        true
+    }
+
+    pub fn banned_players(&self) -> Vec<BannedPlayerEntry> {
+        self.inner.read().banned_players.clone()
+    }
+
+    /// Returns the ban of a player, unless
+    /// it has expired.
+    pub fn banned_player(&self, uuid: Uuid) -> Option<BannedPlayerEntry> {
+        self.inner
+            .read()
+            .banned_players
+            .iter()
+            .find(|entry| entry.uuid == uuid && !entry.ban.is_expired())
+            .cloned()
+    }
+
+    /// Bans a player. Returns `false` if the
+    /// player already is banned.
+    pub fn ban_player(&self, entry: BannedPlayerEntry) -> bool {
+        if self.banned_player(entry.uuid).is_some() {
+            return false;
+        }
+        let mut inner = self.inner.write();
+        inner.banned_players.retain(|e| e.uuid != entry.uuid);
+        inner.banned_players.push(entry);
+        save_list(&inner.dir, BANNED_PLAYERS_FILE, &inner.banned_players);
+        true
+    }
+
+    /// Unbans a player. Returns `false` if the
+    /// player is not banned.
+    pub fn pardon_player(&self, uuid: Uuid) -> bool {
+        let mut inner = self.inner.write();
+        let len = inner.banned_players.len();
+        inner.banned_players.retain(|entry| entry.uuid != uuid);
+        if inner.banned_players.len() == len {
+            return false;
+        }
+        save_list(&inner.dir, BANNED_PLAYERS_FILE, &inner.banned_players);
+        true
+    }
+
+    pub fn banned_ips(&self) -> Vec<BannedIpEntry> {
+        self.inner.read().banned_ips.clone()
+    }
+
+    /// Returns the ban of an IP address, unless
+    /// it has expired.
+    pub fn banned_ip(&self, ip: &str) -> Option<BannedIpEntry> {
+        self.inner
+            .read()
+            .banned_ips
+            .iter()
+            .find(|entry| entry.ip == ip && !entry.ban.is_expired())
+            .cloned()
+    }
+
+    /// Bans an IP address. Returns `false` if the
+    /// address already is banned.
+    pub fn ban_ip(&self, entry: BannedIpEntry) -> bool {
+        if self.banned_ip(&entry.ip).is_some() {
+            return false;
+        }
+        let mut inner = self.inner.write();
+        inner.banned_ips.retain(|e| e.ip != entry.ip);
+        inner.banned_ips.push(entry);
+        save_list(&inner.dir, BANNED_IPS_FILE, &inner.banned_ips);
+        true
+    }
+
+    /// Unbans an IP address. Returns `false` if the
+    /// address is not banned.
+    pub fn pardon_ip(&self, ip: &str) -> bool {
+        let mut inner = self.inner.write();
+        let len = inner.banned_ips.len();
+        inner.banned_ips.retain(|entry| entry.ip != ip);
+        if inner.banned_ips.len() == len {
+            return false;
+        }
+        save_list(&inner.dir, BANNED_IPS_FILE, &inner.banned_ips);
+        true
+    }
+}
+
+/// Loads a list from a JSON file, creating
+/// the file if it doesn't exist.
+fn load_list<T: DeserializeOwned>(path: &Path) -> anyhow::Result<Vec<T>> {
+    match fs::read_to_string(path) {
+        Ok(json) => {
+            serde_json::from_str(&json).with_context(|| format!("invalid {}", path.display()))
+        }
+        Err(e) if e.kind() == ErrorKind::NotFound => {
+            fs::write(path, "[]")
+                .with_context(|| format!("failed to create {}", path.display()))?;
+            Ok(Vec::new())
+        }
+        Err(e) => Err(e).with_context(|| format!("failed to read {}", path.display())),
+    }
+}
+
+fn save_list<T: Serialize>(dir: &Path, file: &str, list: &[T]) {
+    if dir.as_os_str().is_empty() {
+        return;
+    }
+    let path = dir.join(file);
+    let result = serde_json::to_string_pretty(list)
+        .map_err(anyhow::Error::from)
+        .and_then(|json| fs::write(&path, json).map_err(anyhow::Error::from));
+    if let Err(e) = result {
+        log::error!("Failed to save {}: {:?}", path.display(), e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_vanilla_files() {
+        let ops: Vec<OpEntry> = serde_json::from_str(
+            r#"[{"uuid":"069a79f4-44e9-4726-a5be-fca90e38aaf5","name":"Notch","level":4,"bypassesPlayerLimit":false}]"#,
+        )
+        .unwrap();
+        assert_eq!(ops[0].name, "Notch");
+        assert_eq!(ops[0].level, 4);
+
+        let bans: Vec<BannedPlayerEntry> = serde_json::from_str(
+            r#"[{"uuid":"069a79f4-44e9-4726-a5be-fca90e38aaf5","name":"Notch","created":"2021-01-01 12:00:00 +0000","source":"Server","expires":"forever","reason":"Banned by an operator."}]"#,
+        )
+        .unwrap();
+        assert_eq!(bans[0].ban.reason, DEFAULT_BAN_REASON);
+        assert!(!bans[0].ban.is_expired());
+
+        let ip_bans: Vec<BannedIpEntry> = serde_json::from_str(
+            r#"[{"ip":"127.0.0.1","created":"2021-01-01 12:00:00 +0000","source":"Server","expires":"2021-01-02 12:00:00 +0000","reason":"Spam"}]"#,
+        )
+        .unwrap();
+        assert!(ip_bans[0].ban.is_expired());
+    }
+
+    #[test]
+    fn check_login() {
+        let lists = AccessLists::empty();
+        let uuid = Uuid::from_u128(1);
+        let other = Uuid::from_u128(2);
+        assert!(lists.check_login(uuid, "127.0.0.1").is_ok());
+
+        lists.ban_player(BannedPlayerEntry {
+            uuid,
+            name: "banned".to_owned(),
+            ban: Ban::new("Server", DEFAULT_BAN_REASON),
+        });
+        assert!(lists.check_login(uuid, "127.0.0.1").is_err());
+        assert!(lists.pardon_player(uuid));
+        assert!(lists.check_login(uuid, "127.0.0.1").is_ok());
+
+        lists.ban_ip(BannedIpEntry {
+            ip: "10.0.0.1".to_owned(),
+            ban: Ban::new("Server", DEFAULT_BAN_REASON),
+        });
+        assert!(lists.check_login(uuid, "10.0.0.1").is_err());
+
+        lists.set_whitelist_enabled(true);
+        lists.add_to_whitelist(WhitelistEntry {
+            uuid,
+            name: "whitelisted".to_owned(),
+        });
+        assert!(lists.check_login(uuid, "127.0.0.1").is_ok());
+        assert!(lists.check_login(other, "127.0.0.1").is_err());
+    }
+}
@@ -0,0 +1,395 @@
+//! Commands to manage the operator, whitelist and ban lists.
+
+use std::net::IpAddr;
+
+use anyhow::bail;
+use base::Text;
+use common::{
+    commands::{self, argument, literal, ArgumentKind, CommandCtx, CommandDispatcher},
+    events::PlayerKickEvent,
+    Game,
+};
+use ecs::{Entity, SysResult};
+use quill_common::components::Name;
+use uuid::Uuid;
+
+use crate::ClientIp;
+
+use super::{
+    AccessLists, Ban, BannedIpEntry, BannedPlayerEntry, OpEntry, WhitelistEntry, DEFAULT_BAN_REASON,
+};
+
+/// The permission level given to new operators.
+const OP_LEVEL: u8 = 4;
+
+/// Registers the commands. Players who have never joined
+/// can only be referred to by name if `online_mode` is disabled.
+pub fn register(dispatcher: &mut CommandDispatcher, online_mode: bool) {
+    register_op(dispatcher, online_mode);
+    register_whitelist(dispatcher, online_mode);
+    register_ban(dispatcher, online_mode);
+    register_ban_ip(dispatcher);
+}
+
+fn register_op(dispatcher: &mut CommandDispatcher, online_mode: bool) {
+    dispatcher.register(literal("op").requires(3).then(
+        argument("targets", ArgumentKind::Word).executes(move |ctx| {
+            let profile = resolve_profile(ctx.game, &ctx.args.string("targets")?, online_mode)?;
+            let added = access_lists(ctx.game)?.add_op(OpEntry {
+                uuid: profile.uuid,
+                name: profile.name.clone(),
+                level: OP_LEVEL,
+                bypasses_player_limit: false,
+            });
+            if !added {
+                bail!("Nothing changed. The player already is an operator");
+            }
+            update_permission_level(ctx.game, profile.uuid)?;
+            ctx.send_message(Text::translate_with(
+                "commands.op.success",
+                vec![profile.name],
+            ));
+            Ok(())
+        }),
+    ));
+
+    dispatcher.register(literal("deop").requires(3).then(
+        argument("targets", ArgumentKind::Word).executes(move |ctx| {
+            let lists = access_lists(ctx.game)?;
+            let name = ctx.args.string("targets")?;
+            let profile = find_profile(
+                lists.ops().into_iter().map(|op| (op.uuid, op.name)),
+                ctx.game,
+                &name,
+                online_mode,
+            )?;
+            if !lists.remove_op(profile.uuid) {
+                bail!("Nothing changed. The player is not an operator");
+            }
+            update_permission_level(ctx.game, profile.uuid)?;
+            ctx.send_message(Text::translate_with(
+                "commands.deop.success",
+                vec![profile.name],
+            ));
+            Ok(())
+        }),
+    ));
+}
+
+fn register_whitelist(dispatcher: &mut CommandDispatcher, online_mode: bool) {
+    dispatcher.register(
+        literal("whitelist")
+            .requires(3)
+            .then(literal("on").executes(|ctx| {
+                let lists = access_lists(ctx.game)?;
+                if lists.is_whitelist_enabled() {
+                    bail!("Whitelist is already turned on");
+                }
+                lists.set_whitelist_enabled(true);
+                ctx.send_message(translate("commands.whitelist.enabled"));
+                Ok(())
+            }))
+            .then(literal("off").executes(|ctx| {
+                let lists = access_lists(ctx.game)?;
+                if !lists.is_whitelist_enabled() {
+                    bail!("Whitelist is already turned off");
+                }
+                lists.set_whitelist_enabled(false);
+                ctx.send_message(translate("commands.whitelist.disabled"));
+                Ok(())
+            }))
+            .then(literal("list").executes(|ctx| {
+                let whitelist = access_lists(ctx.game)?.whitelist();
+                if whitelist.is_empty() {
+                    ctx.send_message(translate("commands.whitelist.none"));
+                } else {
+                    let names: Vec<String> =
+                        whitelist.into_iter().map(|entry| entry.name).collect();
+                    ctx.send_message(Text::translate_with(
+                        "commands.whitelist.list",
+                        vec![names.len().to_string(), names.join(", ")],
+                    ));
+                }
+                Ok(())
+            }))
+            .then(literal("reload").executes(|ctx| {
+                access_lists(ctx.game)?.reload()?;
+                let players: Vec<Uuid> = ctx
+                    .game
+                    .ecs
+                    .query::<&Uuid>()
+                    .iter()
+                    .map(|(_, &uuid)| uuid)
+                    .collect();
+                for uuid in players {
+                    update_permission_level(ctx.game, uuid)?;
+                }
+                ctx.send_message(translate("commands.whitelist.reloaded"));
+                Ok(())
+            }))
+            .then(
+                literal("add").then(
+                    argument("targets", ArgumentKind::Word).executes(move |ctx| {
+                        let profile =
+                            resolve_profile(ctx.game, &ctx.args.string("targets")?, online_mode)?;
+                        let added = access_lists(ctx.game)?.add_to_whitelist(WhitelistEntry {
+                            uuid: profile.uuid,
+                            name: profile.name.clone(),
+                        });
+                        if !added {
+                            bail!("Player is already whitelisted");
+                        }
+                        ctx.send_message(Text::translate_with(
+                            "commands.whitelist.add.success",
+                            vec![profile.name],
+                        ));
+                        Ok(())
+                    }),
+                ),
+            )
+            .then(
+                literal("remove").then(argument("targets", ArgumentKind::Word).executes(
+                    move |ctx| {
+                        let lists = access_lists(ctx.game)?;
+                        let name = ctx.args.string("targets")?;
+                        let profile = find_profile(
+                            lists
+                                .whitelist()
+                                .into_iter()
+                                .map(|entry| (entry.uuid, entry.name)),
+                            ctx.game,
+                            &name,
+                            online_mode,
+                        )?;
+                        if !lists.remove_from_whitelist(profile.uuid) {
+                            bail!("Player is not whitelisted");
+                        }
+                        ctx.send_message(Text::translate_with(
+                            "commands.whitelist.remove.success",
+                            vec![profile.name],
+                        ));
+                        Ok(())
+                    },
+                )),
+            ),
+    );
+}
+
+fn register_ban(dispatcher: &mut CommandDispatcher, online_mode: bool) {
+    dispatcher.register(
+        literal("ban").requires(3).then(
+            argument("targets", ArgumentKind::Word)
+                .executes(move |ctx| ban(ctx, DEFAULT_BAN_REASON.to_owned(), online_mode))
+                .then(
+                    argument("reason", ArgumentKind::Message).executes(move |ctx| {
+                        let reason = ctx.args.string("reason")?;
+                        ban(ctx, reason, online_mode)
+                    }),
+                ),
+        ),
+    );
+
+    dispatcher.register(literal("pardon").requires(3).then(
+        argument("targets", ArgumentKind::Word).executes(move |ctx| {
+            let lists = access_lists(ctx.game)?;
+            let name = ctx.args.string("targets")?;
+            let profile = find_profile(
+                lists
+                    .banned_players()
+                    .into_iter()
+                    .map(|entry| (entry.uuid, entry.name)),
+                ctx.game,
+                &name,
+                online_mode,
+            )?;
+            if !lists.pardon_player(profile.uuid) {
+                bail!("Nothing changed. The player isn't banned");
+            }
+            ctx.send_message(Text::translate_with(
+                "commands.pardon.success",
+                vec![profile.name],
+            ));
+            Ok(())
+        }),
+    ));
+}
+
+fn ban(ctx: &mut CommandCtx, reason: String, online_mode: bool) -> anyhow::Result<()> {
+    let profile = resolve_profile(ctx.game, &ctx.args.string("targets")?, online_mode)?;
+    let banned = access_lists(ctx.game)?.ban_player(BannedPlayerEntry {
+        uuid: profile.uuid,
+        name: profile.name.clone(),
+        ban: Ban::new(source_name(ctx.game, ctx.sender), reason.clone()),
+    });
+    if !banned {
+        bail!("Nothing changed. The player is already banned");
+    }
+
+    if let Some(player) = online_player(ctx.game, profile.uuid) {
+        ctx.game.ecs.insert_entity_event(
+            player,
+            PlayerKickEvent {
+                reason: translate("multiplayer.disconnect.banned"),
+            },
+        )?;
+    }
+
+    ctx.send_message(Text::translate_with(
+        "commands.ban.success",
+        vec![profile.name, reason],
+    ));
+    Ok(())
+}
+
+fn register_ban_ip(dispatcher: &mut CommandDispatcher) {
+    dispatcher.register(
+        literal("ban-ip").requires(3).then(
+            argument("target", ArgumentKind::Word)
+                .executes(|ctx| ban_ip(ctx, DEFAULT_BAN_REASON.to_owned()))
+                .then(argument("reason", ArgumentKind::Message).executes(|ctx| {
+                    let reason = ctx.args.string("reason")?;
+                    ban_ip(ctx, reason)
+                })),
+        ),
+    );
+
+    dispatcher.register(literal("pardon-ip").requires(3).then(
+        argument("target", ArgumentKind::Word).executes(|ctx| {
+            let ip = ctx.args.string("target")?;
+            if ip.parse::<IpAddr>().is_err() {
+                bail!("Invalid IP address");
+            }
+            if !access_lists(ctx.game)?.pardon_ip(&ip) {
+                bail!("Nothing changed. That IP isn't banned");
+            }
+            ctx.send_message(Text::translate_with("commands.pardonip.success", vec![ip]));
+            Ok(())
+        }),
+    ));
+}
+
+/// Bans an IP address. Players connected from the
+/// address are kicked and rejected when they join again.
+fn ban_ip(ctx: &mut CommandCtx, reason: String) -> anyhow::Result<()> {
+    let ip = ctx.args.string("target")?;
+    let address = match ip.parse::<IpAddr>() {
+        Ok(address) => address,
+        Err(_) => bail!("Invalid IP address"),
+    };
+    let banned = access_lists(ctx.game)?.ban_ip(BannedIpEntry {
+        ip: ip.clone(),
+        ban: Ban::new(source_name(ctx.game, ctx.sender), reason.clone()),
+    });
+    if !banned {
+        bail!("Nothing changed. That IP is already banned");
+    }
+
+    let players: Vec<(Entity, String)> = ctx
+        .game
+        .ecs
+        .query::<(&ClientIp, &Name)>()
+        .iter()
+        .filter(|(_, (client_ip, _))| client_ip.0.parse::<IpAddr>().ok() == Some(address))
+        .map(|(player, (_, name))| (player, name.to_string()))
+        .collect();
+    for (player, _) in &players {
+        ctx.game.ecs.insert_entity_event(
+            *player,
+            PlayerKickEvent {
+                reason: translate("multiplayer.disconnect.ip_banned"),
+            },
+        )?;
+    }
+
+    ctx.send_message(Text::translate_with(
+        "commands.banip.success",
+        vec![ip, reason],
+    ));
+    if !players.is_empty() {
+        let names: Vec<String> = players.into_iter().map(|(_, name)| name).collect();
+        ctx.send_message(Text::translate_with(
+            "commands.banip.info",
+            vec![names.len().to_string(), names.join(", ")],
+        ));
+    }
+    Ok(())
+}
+
+/// A player's UUID and name.
+struct Profile {
+    uuid: Uuid,
+    name: String,
+}
+
+fn access_lists(game: &Game) -> anyhow::Result<AccessLists> {
+    Ok(game.resources.get::<AccessLists>()?.clone())
+}
+
+/// Finds the profile of a player by name. Players who are
+/// not online are only found if the server is in offline mode,
+/// because their UUID is then derived from their name.
+fn resolve_profile(game: &Game, name: &str, online_mode: bool) -> anyhow::Result<Profile> {
+    let online = game
+        .ecs
+        .query::<(&Name, &Uuid)>()
+        .iter()
+        .find(|(_, (n, _))| n.eq_ignore_ascii_case(name))
+        .map(|(_, (name, &uuid))| Profile {
+            uuid,
+            name: name.to_string(),
+        });
+    match online {
+        Some(profile) => Ok(profile),
+        None if !online_mode => Ok(Profile {
+            uuid: crate::initial_handler::offline_mode_uuid(name),
+            name: name.to_owned(),
+        }),
+        None => bail!("That player does not exist"),
+    }
+}
+
+/// Finds the profile of a player by name, first
+/// among the entries of a list.
+fn find_profile(
+    mut entries: impl Iterator<Item = (Uuid, String)>,
+    game: &Game,
+    name: &str,
+    online_mode: bool,
+) -> anyhow::Result<Profile> {
+    match entries.find(|(_, n)| n.eq_ignore_ascii_case(name)) {
+        Some((uuid, name)) => Ok(Profile { uuid, name }),
+        None => resolve_profile(game, name, online_mode),
+    }
+}
+
+fn online_player(game: &Game, uuid: Uuid) -> Option<Entity> {
+    game.ecs
+        .query::<&Uuid>()
+        .iter()
+        .find(|(_, &u)| u == uuid)
+        .map(|(player, _)| player)
+}
+
+/// Updates the permission level of an online
+/// player to their level in `ops.json`.
+fn update_permission_level(game: &mut Game, uuid: Uuid) -> SysResult {
+    if let Some(player) = online_player(game, uuid) {
+        let level = access_lists(game)?.permission_level(uuid);
+        if commands::permission_level(game, player) != level {
+            commands::set_permission_level(game, player, level)?;
+        }
+    }
+    Ok(())
+}
+
+/// Returns the name recorded as the source of a ban.
+fn source_name(game: &Game, sender: Entity) -> String {
+    game.ecs
+        .get::<Name>(sender)
+        .map(|name| name.to_string())
+        .unwrap_or_else(|_| "Server".to_owned())
+}
+
+fn translate(key: &'static str) -> Text {
+    Text::translate_with(key, Vec::<Text>::new())
+}
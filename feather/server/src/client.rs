@@ -1,35 +1,43 @@
 use std::{
     cell::{Cell, RefCell},
     collections::VecDeque,
+    convert::TryFrom,
     io::Cursor,
     sync::Arc,
 };
 
 use ahash::AHashSet;
 use base::{
-    BlockId, BlockPosition, Chunk, ChunkPosition, EntityKind, EntityMetadata, Gamemode, ItemStack,
-    Position, ProfileProperty, Text,
+    metadata::META_INDEX_ITEM_SLOT, BlockId, BlockPosition, Chunk, ChunkPosition, EntityKind,
+    EntityMetadata, Gamemode, ItemStack, Position, ProfileProperty, Text,
 };
 use common::{
     chat::{ChatKind, ChatMessage},
     Window,
 };
 use flume::{Receiver, Sender};
-use packets::server::{Particle, SetSlot, SpawnLivingEntity, UpdateLight, WindowConfirmation};
+use packets::server::{
+    ChangeGameState, CombatEvent, CombatEventKind, DeclareCommands, EntityEquipment, EntityStatus,
+    EquipmentEntry, Particle, PlayerAbilities, Respawn, SetSlot, SpawnEntity, SpawnLivingEntity,
+    SpawnPosition, TimeUpdate, UpdateHealth, UpdateLight, WindowConfirmation,
+};
 use parking_lot::RwLock;
 use protocol::{
     packets::{
         self,
         server::{
-            AddPlayer, Animation, BlockChange, ChatPosition, ChunkData, ChunkDataKind,
-            DestroyEntities, Disconnect, EntityAnimation, EntityHeadLook, EntityTeleport, JoinGame,
-            KeepAlive, PlayerInfo, PlayerPositionAndLook, PluginMessage, SendEntityMetadata,
-            SpawnPlayer, Title, UnloadChunk, UpdateViewPosition, WindowItems,
+            AcknowledgePlayerDigging, AddPlayer, Animation, BlockChange, ChatPosition, ChunkData,
+            ChunkDataKind, CollectItem, DestroyEntities, Disconnect, EntityAnimation,
+            EntityHeadLook, EntityPosition, EntityPositionAndRotation, EntityRotation,
+            EntityTeleport, EntityVelocity, HeldItemChange, JoinGame, KeepAlive,
+            PlayerDiggingStatus, PlayerInfo, PlayerPositionAndLook, PluginMessage,
+            SendEntityMetadata, SpawnPlayer, TabComplete, TabCompleteMatch, Title, UnloadChunk,
+            UpdateViewDistance, UpdateViewPosition, WindowItems,
         },
     },
     ClientPlayPacket, Nbt, ProtocolVersion, ServerPlayPacket, Writeable,
 };
-use quill_common::components::OnGround;
+use quill_common::components::{OnGround, Velocity};
 use uuid::Uuid;
 use vec_arena::Arena;
 
@@ -42,6 +50,10 @@ const MAX_CHUNKS_PER_TICK: usize = 10;
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub struct ClientId(usize);
 
+/// The IP address a player connected from.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ClientIp(pub String);
+
 /// Stores all `Client`s.
 #[derive(Default)]
 pub struct Clients {
@@ -81,6 +93,7 @@ pub struct Client {
     username: String,
     profile: Vec<ProfileProperty>,
     uuid: Uuid,
+    ip: String,
 
     teleport_id_counter: Cell<i32>,
 
@@ -110,6 +123,7 @@ impl Client {
             network_id,
             profile: player.profile,
             uuid: player.uuid,
+            ip: player.ip,
             sent_entities: RefCell::new(AHashSet::new()),
             knows_position: Cell::new(false),
             known_chunks: RefCell::new(AHashSet::new()),
@@ -139,6 +153,10 @@ impl Client {
         self.uuid
     }
 
+    pub fn ip(&self) -> &str {
+        &self.ip
+    }
+
     pub fn username(&self) -> &str {
         &self.username
     }
@@ -186,10 +204,7 @@ impl Client {
             "../../../assets/dimension_codec.nbt"
         )))
         .expect("dimension codec asset is malformed");
-        let dimension = nbt::Blob::from_reader(&mut Cursor::new(include_bytes!(
-            "../../../assets/dimension.nbt"
-        )))
-        .expect("dimension asset is malformed");
+        let dimension = dimension();
 
         self.send_packet(JoinGame {
             entity_id: self.network_id.0,
@@ -210,6 +225,26 @@ impl Client {
         });
     }
 
+    /// Respawns the player after they died.
+    ///
+    /// The client keeps its chunks, since the dimension does not
+    /// change, but it removes all entities, so they are marked as
+    /// unloaded and have to be sent again.
+    pub fn send_respawn(&self, gamemode: Gamemode) {
+        log::trace!("Sending Respawn to {}", self.username);
+        self.sent_entities.borrow_mut().clear();
+        self.send_packet(Respawn {
+            dimension: Nbt(dimension()),
+            world_name: "world".to_owned(),
+            hashed_seed: 0,
+            gamemode,
+            previous_gamemode: gamemode,
+            is_debug: false,
+            is_flat: false,
+            copy_metadata: false,
+        });
+    }
+
     pub fn send_brand(&self) {
         let mut data = Vec::new();
         "Feather"
@@ -249,6 +284,56 @@ impl Client {
         self.client_known_position.set(Some(new_position));
     }
 
+    /// Changes the player's own gamemode.
+    pub fn send_gamemode(&self, gamemode: Gamemode) {
+        log::trace!("Changing gamemode of {} to {:?}", self.username, gamemode);
+        self.send_packet(ChangeGameState {
+            reason: 3,
+            value: gamemode as u8 as f32,
+        });
+    }
+
+    /// Sends the abilities a player has in `gamemode`.
+    pub fn send_abilities(&self, gamemode: Gamemode, flying: bool) {
+        let mut flags = 0;
+        if matches!(gamemode, Gamemode::Creative | Gamemode::Spectator) {
+            // Invulnerable and allowed to fly
+            flags |= 0x01 | 0x04;
+        }
+        if flying {
+            flags |= 0x02;
+        }
+        if gamemode == Gamemode::Creative {
+            // Breaks blocks instantly
+            flags |= 0x08;
+        }
+        self.send_packet(PlayerAbilities {
+            flags,
+            flying_speed: 0.05,
+            fov_modifier: 0.1,
+        });
+    }
+
+    pub fn update_health(&self, health: f32, food: u32, saturation: f32) {
+        self.send_packet(UpdateHealth {
+            health,
+            food: food as i32,
+            food_saturation: saturation,
+        });
+    }
+
+    /// Shows the death screen with the given message.
+    /// `killer` is the entity which killed the player, if any.
+    pub fn send_death(&self, network_id: NetworkId, killer: Option<NetworkId>, message: &Text) {
+        self.send_packet(CombatEvent {
+            event: CombatEventKind::EntityDead {
+                player_id: network_id.0,
+                entity_id: killer.map_or(-1, |killer| killer.0),
+                message: message.to_string(),
+            },
+        });
+    }
+
     pub fn update_own_chunk(&self, pos: ChunkPosition) {
         log::trace!("Updating chunk position of {} to {:?}", self.username, pos);
         self.send_packet(UpdateViewPosition {
@@ -257,6 +342,13 @@ impl Client {
         });
     }
 
+    /// Tells the client how many chunks around it are sent.
+    pub fn send_view_distance(&self, view_distance: u32) {
+        self.send_packet(UpdateViewDistance {
+            view_distance: view_distance as i32,
+        });
+    }
+
     pub fn send_chunk(&self, chunk: &Arc<RwLock<Chunk>>) {
         self.chunk_send_queue.borrow_mut().push_back(ChunkData {
             chunk: Arc::clone(chunk),
@@ -267,6 +359,19 @@ impl Client {
             .insert(chunk.read().position());
     }
 
+    /// Resends the light of a chunk, if the client knows it.
+    pub fn send_light_update(&self, chunk: &Arc<RwLock<Chunk>>) {
+        if self
+            .known_chunks
+            .borrow()
+            .contains(&chunk.read().position())
+        {
+            self.send_packet(UpdateLight {
+                chunk: Arc::clone(chunk),
+            });
+        }
+    }
+
     pub fn overwrite_chunk_sections(&self, chunk: &Arc<RwLock<Chunk>>, sections: Vec<usize>) {
         self.send_packet(ChunkData {
             chunk: Arc::clone(chunk),
@@ -281,6 +386,21 @@ impl Client {
         });
     }
 
+    pub fn acknowledge_digging(
+        &self,
+        position: BlockPosition,
+        block: BlockId,
+        status: PlayerDiggingStatus,
+        successful: bool,
+    ) {
+        self.send_packet(AcknowledgePlayerDigging {
+            position,
+            block,
+            status,
+            successful,
+        });
+    }
+
     pub fn unload_chunk(&self, pos: ChunkPosition) {
         log::trace!("Unloading chunk at {:?} on {}", pos, self.username);
         self.send_packet(UnloadChunk {
@@ -309,6 +429,10 @@ impl Client {
         self.send_packet(PlayerInfo::AddPlayers(vec![action]));
     }
 
+    pub fn update_tablist_gamemode(&self, uuid: Uuid, gamemode: Gamemode) {
+        self.send_packet(PlayerInfo::UpdateGamemodes(vec![(uuid, gamemode)]));
+    }
+
     pub fn remove_tablist_player(&self, uuid: Uuid) {
         log::trace!("Sending RemovePlayer({}) to {}", uuid, self.username);
         self.send_packet(PlayerInfo::RemovePlayers(vec![uuid]));
@@ -342,6 +466,7 @@ impl Client {
         network_id: NetworkId,
         uuid: Uuid,
         pos: Position,
+        velocity: Velocity,
         kind: EntityKind,
     ) {
         log::trace!(
@@ -360,17 +485,80 @@ impl Client {
             yaw: pos.yaw,
             pitch: pos.pitch,
             head_pitch: pos.pitch,
-            velocity_x: 0,
-            velocity_y: 0,
-            velocity_z: 0,
+            velocity_x: protocol_velocity(velocity.x),
+            velocity_y: protocol_velocity(velocity.y),
+            velocity_z: protocol_velocity(velocity.z),
         });
     }
 
+    pub fn send_item_entity(
+        &self,
+        network_id: NetworkId,
+        uuid: Uuid,
+        pos: Position,
+        velocity: Velocity,
+        item: &ItemStack,
+    ) {
+        log::trace!("Spawning an item entity on {}", self.username);
+        self.send_packet(SpawnEntity {
+            entity_id: network_id.0,
+            uuid,
+            kind: EntityKind::Item.id() as i32,
+            x: pos.x,
+            y: pos.y,
+            z: pos.z,
+            pitch: pos.pitch,
+            yaw: pos.yaw,
+            data: 1,
+            velocity_x: protocol_velocity(velocity.x),
+            velocity_y: protocol_velocity(velocity.y),
+            velocity_z: protocol_velocity(velocity.z),
+        });
+        self.send_packet(SendEntityMetadata {
+            entity_id: network_id.0,
+            entries: EntityMetadata::entity_base().with(META_INDEX_ITEM_SLOT, Some(item.clone())),
+        });
+    }
+
+    pub fn update_entity_velocity(&self, network_id: NetworkId, velocity: Velocity) {
+        self.send_packet(EntityVelocity {
+            entity_id: network_id.0,
+            velocity_x: protocol_velocity(velocity.x),
+            velocity_y: protocol_velocity(velocity.y),
+            velocity_z: protocol_velocity(velocity.z),
+        });
+    }
+
+    /// Updates the item stack shown by an item entity.
+    pub fn update_item_entity(&self, network_id: NetworkId, item: &ItemStack) {
+        self.send_packet(SendEntityMetadata {
+            entity_id: network_id.0,
+            entries: EntityMetadata::new().with(META_INDEX_ITEM_SLOT, Some(item.clone())),
+        });
+    }
+
+    /// Plays the animation of `collector` picking up `count` items
+    /// from an item entity.
+    pub fn collect_item(&self, collected: NetworkId, collector: NetworkId, count: u32) {
+        self.send_packet(CollectItem {
+            collected_entity_id: collected.0,
+            collector_entity_id: collector.0,
+            item_count: count as i32,
+        });
+    }
+
+    /// Sends the movement of an entity from `previous` to `position`.
+    ///
+    /// Relative move packets are sent when the move fits in them.
+    /// Otherwise, or if `resync` is set, the absolute position is
+    /// sent so that rounding errors on the client are corrected.
     pub fn update_entity_position(
         &self,
         network_id: NetworkId,
         position: Position,
+        previous: Position,
         on_ground: OnGround,
+        resync: bool,
     ) {
         if network_id == self.network_id {
             // This entity is the client. Only update
@@ -381,23 +569,58 @@ impl Client {
             }
             return;
         }
-        // Consider using the relative movement packets in the future.
-        // (Entity Teleport works fine, but the relative movement packets
-        // save bandwidth.)
-        self.send_packet(EntityTeleport {
-            entity_id: network_id.0,
-            x: position.x,
-            y: position.y,
-            z: position.z,
-            yaw: position.yaw,
-            pitch: position.pitch,
-            on_ground: on_ground.0,
-        });
+
+        let rotated = position.yaw != previous.yaw || position.pitch != previous.pitch;
+        match relative_move(previous, position) {
+            Some((0, 0, 0)) if !resync => {
+                if rotated {
+                    self.send_packet(EntityRotation {
+                        entity_id: network_id.0,
+                        yaw: position.yaw,
+                        pitch: position.pitch,
+                        on_ground: on_ground.0,
+                    });
+                }
+            }
+            Some((delta_x, delta_y, delta_z)) if !resync => {
+                if rotated {
+                    self.send_packet(EntityPositionAndRotation {
+                        entity_id: network_id.0,
+                        delta_x,
+                        delta_y,
+                        delta_z,
+                        yaw: position.yaw,
+                        pitch: position.pitch,
+                        on_ground: on_ground.0,
+                    });
+                } else {
+                    self.send_packet(EntityPosition {
+                        entity_id: network_id.0,
+                        delta_x,
+                        delta_y,
+                        delta_z,
+                        on_ground: on_ground.0,
+                    });
+                }
+            }
+            _ => self.send_packet(EntityTeleport {
+                entity_id: network_id.0,
+                x: position.x,
+                y: position.y,
+                z: position.z,
+                yaw: position.yaw,
+                pitch: position.pitch,
+                on_ground: on_ground.0,
+            }),
+        }
+
         // Needed for head orientation
-        self.send_packet(EntityHeadLook {
-            entity_id: network_id.0,
-            head_yaw: position.yaw,
-        });
+        if position.yaw != previous.yaw || resync {
+            self.send_packet(EntityHeadLook {
+                entity_id: network_id.0,
+                head_yaw: position.yaw,
+            });
+        }
     }
 
     pub fn send_keepalive(&self) {
@@ -405,6 +628,15 @@ impl Client {
         self.send_packet(KeepAlive { id: 0 });
     }
 
+    /// Plays an entity status effect, such as the animation
+    /// of an entity taking damage.
+    pub fn send_entity_status(&self, network_id: NetworkId, status: i8) {
+        self.send_packet(EntityStatus {
+            entity_id: network_id.0,
+            status,
+        });
+    }
+
     pub fn send_entity_animation(&self, network_id: NetworkId, animation: Animation) {
         if network_id == self.network_id {
             return;
@@ -483,6 +715,65 @@ impl Client {
         self.send_packet(packet);
     }
 
+    pub fn send_time(&self, world_age: i64, time_of_day: i64) {
+        self.send_packet(TimeUpdate {
+            world_age: world_age as u64,
+            time_of_day: time_of_day as u64,
+        });
+    }
+
+    pub fn send_weather(&self, raining: bool, thundering: bool) {
+        log::trace!(
+            "Sending weather to {} (raining: {}, thundering: {})",
+            self.username,
+            raining,
+            thundering
+        );
+        self.send_packet(ChangeGameState {
+            reason: if raining { 2 } else { 1 },
+            value: 0.0,
+        });
+        self.send_packet(ChangeGameState {
+            reason: 7,
+            value: if raining { 1.0 } else { 0.0 },
+        });
+        self.send_packet(ChangeGameState {
+            reason: 8,
+            value: if thundering { 1.0 } else { 0.0 },
+        });
+    }
+
+    pub fn send_spawn_position(&self, position: BlockPosition) {
+        self.send_packet(SpawnPosition { position });
+    }
+
+    pub fn send_hotbar_slot(&self, slot: usize) {
+        log::trace!("Setting hotbar slot of {} to {}", self.username, slot);
+        self.send_packet(HeldItemChange { slot: slot as u8 });
+    }
+
+    pub fn send_declare_commands(&self, packet: DeclareCommands) {
+        self.send_packet(packet);
+    }
+
+    /// Sends command suggestions in response to a tab complete request.
+    ///
+    /// `start` and `length` denote the text to replace, in bytes.
+    pub fn send_tab_complete(&self, id: i32, start: usize, length: usize, matches: Vec<String>) {
+        self.send_packet(TabComplete {
+            id,
+            start: start as i32,
+            length: length as i32,
+            matches: matches
+                .into_iter()
+                .map(|value| TabCompleteMatch {
+                    value,
+                    tooltip: None,
+                })
+                .collect(),
+        });
+    }
+
     pub fn set_slot(&self, slot: i16, item: Option<ItemStack>) {
         log::trace!("Setting slot {} of {} to {:?}", slot, self.username, item);
         self.send_packet(SetSlot {
@@ -512,6 +803,20 @@ impl Client {
         self.set_slot(-1, item);
     }
 
+    pub fn send_entity_metadata(&self, network_id: NetworkId, metadata: EntityMetadata) {
+        self.send_packet(SendEntityMetadata {
+            entity_id: network_id.0,
+            entries: metadata,
+        });
+    }
+
+    pub fn send_entity_equipment(&self, network_id: NetworkId, entries: Vec<EquipmentEntry>) {
+        self.send_packet(EntityEquipment {
+            entity_id: network_id.0,
+            entries,
+        });
+    }
+
     pub fn send_player_model_flags(&self, netowrk_id: NetworkId, model_flags: u8) {
         let mut entity_metadata = EntityMetadata::new();
         entity_metadata.set(16, model_flags);
@@ -529,14 +834,23 @@ impl Client {
         let _ = self.packets_to_send.try_send(packet.into());
     }
 
-    pub fn disconnect(&self, reason: &str) {
+    pub fn disconnect(&self, reason: impl Into<Text>) {
         self.disconnected.set(true);
         self.send_packet(Disconnect {
-            reason: Text::from(reason.to_owned()).to_string(),
+            reason: reason.into().to_string(),
         });
     }
 }
 
+/// Loads the dimension the player is in. It is
+/// the overworld sent by the default vanilla server.
+fn dimension() -> nbt::Blob {
+    nbt::Blob::from_reader(&mut Cursor::new(include_bytes!(
+        "../../../assets/dimension.nbt"
+    )))
+    .expect("dimension asset is malformed")
+}
+
 fn chat_packet(message: ChatMessage) -> packets::server::ChatMessage {
     packets::server::ChatMessage {
         message: message.text().to_string(),
@@ -548,3 +862,28 @@ fn chat_packet(message: ChatMessage) -> packets::server::ChatMessage {
         sender: Uuid::default(),
     }
 }
+
+/// Converts a velocity in blocks per tick to
+/// the protocol's units of 1/8000 block per tick.
+pub(crate) fn protocol_velocity(velocity: f64) -> i16 {
+    (velocity.max(-3.9).min(3.9) * 8000.0) as i16
+}
+
+/// Encodes the move from `from` to `to` in the units of relative
+/// move packets (1/4096 of a block), if it fits in them.
+///
+/// Deltas are taken between encoded positions so that
+/// the client does not accumulate rounding errors.
+fn relative_move(from: Position, to: Position) -> Option<(i16, i16, i16)> {
+    let delta =
+        |from: f64, to: f64| i16::try_from(encode_coordinate(to) - encode_coordinate(from)).ok();
+    Some((
+        delta(from.x, to.x)?,
+        delta(from.y, to.y)?,
+        delta(from.z, to.z)?,
+    ))
+}
+
+fn encode_coordinate(coordinate: f64) -> i64 {
+    (coordinate * 4096.0).floor() as i64
+}
@@ -1,9 +1,16 @@
 //! Loads an `Options` from a TOML config.
 
-use std::{fs, net::Ipv4Addr, path::Path, str::FromStr};
+use std::{
+    fs,
+    net::Ipv4Addr,
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::Duration,
+};
 
 use anyhow::Context;
 use base::Gamemode;
+use plugin_host::PluginLimits;
 use serde::{Deserialize, Deserializer};
 
 use crate::{favicon::Favicon, Options};
@@ -44,6 +51,8 @@ pub struct Config {
     pub log: Log,
     pub world: World,
     pub proxy: Proxy,
+    #[serde(default)]
+    pub plugins: Plugins,
 }
 
 impl Config {
@@ -65,13 +74,16 @@ impl Config {
             },
             view_distance: self.server.view_distance,
             max_players: self.server.max_players,
+            whitelist: self.server.whitelist,
             default_gamemode: self.server.default_gamemode,
+            shutdown_message: self.server.shutdown_message.clone(),
             proxy_mode: match self.proxy.proxy_mode {
                 ProxyMode::None => None,
                 ProxyMode::Bungee => Some(crate::options::ProxyMode::Bungeecord),
                 ProxyMode::Velocity => Some(crate::options::ProxyMode::Velocity),
             },
             velocity_secret: self.proxy.velocity_secret.clone(),
+            world_dir: PathBuf::from(&self.world.name),
         }
     }
 }
@@ -88,8 +100,52 @@ pub struct ServerConfig {
     pub online_mode: bool,
     pub motd: String,
     pub max_players: u32,
+    #[serde(default)]
+    pub whitelist: bool,
     pub default_gamemode: Gamemode,
     pub view_distance: u32,
+    #[serde(default = "default_shutdown_message")]
+    pub shutdown_message: String,
+}
+
+fn default_shutdown_message() -> String {
+    "Server closed".to_owned()
+}
+
+/// Limits on the resources of WebAssembly plugins.
+/// A limit of 0 disables it.
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct Plugins {
+    pub max_fuel: u64,
+    /// In mebibytes.
+    pub max_memory: usize,
+    /// In milliseconds.
+    pub tick_time_budget: u64,
+}
+
+impl Default for Plugins {
+    fn default() -> Self {
+        Self {
+            max_fuel: 50_000_000,
+            max_memory: 256,
+            tick_time_budget: 20,
+        }
+    }
+}
+
+impl Plugins {
+    pub fn to_limits(&self) -> PluginLimits {
+        PluginLimits {
+            max_fuel: Some(self.max_fuel).filter(|&fuel| fuel > 0),
+            max_memory: Some(self.max_memory)
+                .filter(|&memory| memory > 0)
+                .map(|memory| memory * 1024 * 1024),
+            tick_time_budget: Some(self.tick_time_budget)
+                .filter(|&millis| millis > 0)
+                .map(Duration::from_millis),
+        }
+    }
 }
 
 #[derive(Debug, Deserialize)]
@@ -105,6 +161,28 @@ pub struct World {
     pub seed: String,
 }
 
+impl World {
+    /// Computes the world seed from the configured string.
+    ///
+    /// Like vanilla, integer strings are used directly, other
+    /// strings are hashed with Java's `String.hashCode`, and an
+    /// empty string yields a random seed.
+    pub fn parse_seed(&self) -> i64 {
+        let seed = self.seed.trim();
+        if seed.is_empty() {
+            return rand::random();
+        }
+        seed.parse()
+            .unwrap_or_else(|_| java_string_hash(seed) as i64)
+    }
+}
+
+/// Java's `String.hashCode`, computed over UTF-16 code units.
+fn java_string_hash(s: &str) -> i32 {
+    s.encode_utf16()
+        .fold(0i32, |hash, c| hash.wrapping_mul(31).wrapping_add(c as i32))
+}
+
 #[derive(Debug, Deserialize)]
 pub struct Proxy {
     pub proxy_mode: ProxyMode,
@@ -139,4 +217,44 @@ mod tests {
     fn default_config_is_valid() {
         let _config: Config = toml::from_str(DEFAULT_CONFIG).unwrap();
     }
+
+    #[test]
+    fn default_plugin_limits_match_config() {
+        let config: Config = toml::from_str(DEFAULT_CONFIG).unwrap();
+        assert_eq!(config.plugins.to_limits(), PluginLimits::default());
+    }
+
+    #[test]
+    fn zero_disables_plugin_limits() {
+        let plugins = Plugins {
+            max_fuel: 0,
+            max_memory: 0,
+            tick_time_budget: 0,
+        };
+        assert_eq!(plugins.to_limits(), PluginLimits::unlimited());
+    }
+
+    fn world_with_seed(seed: &str) -> World {
+        World {
+            name: "world".to_owned(),
+            generator: "default".to_owned(),
+            seed: seed.to_owned(),
+        }
+    }
+
+    #[test]
+    fn parse_integer_seed() {
+        assert_eq!(
+            world_with_seed("-4530634556500121041").parse_seed(),
+            -4530634556500121041
+        );
+        assert_eq!(world_with_seed(" 42 ").parse_seed(), 42);
+    }
+
+    #[test]
+    fn parse_string_seed() {
+        // Values of `"...".hashCode()` in Java.
+        assert_eq!(world_with_seed("feather").parse_seed(), -979220317);
+        assert_eq!(world_with_seed("Glacier").parse_seed(), 1772835215);
+    }
 }
@@ -18,6 +18,7 @@ use tokio::{
 };
 
 use crate::{
+    access::AccessLists,
     initial_handler::{InitialHandling, NewPlayer},
     options::Options,
     player_count::PlayerCount,
@@ -34,8 +35,10 @@ use crate::{
 pub struct Worker {
     reader: Reader,
     writer: Writer,
+    addr: SocketAddr,
     options: Arc<Options>,
     player_count: PlayerCount,
+    access_lists: AccessLists,
     packets_to_send_tx: Sender<ServerPlayPacket>,
     received_packets_rx: Receiver<ClientPlayPacket>,
     new_players: Sender<NewPlayer>,
@@ -44,9 +47,10 @@ pub struct Worker {
 impl Worker {
     pub fn new(
         stream: TcpStream,
-        _addr: SocketAddr,
+        addr: SocketAddr,
         options: Arc<Options>,
         player_count: PlayerCount,
+        access_lists: AccessLists,
         new_players: Sender<NewPlayer>,
     ) -> Self {
         let (reader, writer) = stream.into_split();
@@ -59,8 +63,10 @@ impl Worker {
         Self {
             reader,
             writer,
+            addr,
             options,
             player_count,
+            access_lists,
             packets_to_send_tx,
             received_packets_rx,
             new_players,
@@ -105,6 +111,16 @@ impl Worker {
         &self.options
     }
 
+    /// Returns the address of the connection. This is
+    /// the proxy's address when a proxy is used.
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    pub fn access_lists(&self) -> &AccessLists {
+        &self.access_lists
+    }
+
     pub fn player_count(&self) -> u32 {
         self.player_count.get()
     }
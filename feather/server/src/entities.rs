@@ -1,39 +1,142 @@
-use base::{EntityKind, Position};
+use base::{
+    metadata::{
+        EntityBitMask, MetaEntry, META_INDEX_CUSTOM_NAME, META_INDEX_ENTITY_BITMASK,
+        META_INDEX_IS_CUSTOM_NAME_VISIBLE, META_INDEX_POSE,
+    },
+    Area, EntityKind, EntityMetadata, Inventory, ItemStack, Position, Text,
+};
+use common::entities::player::HotbarSlot;
 use ecs::{EntityBuilder, EntityRef, SysResult};
-use quill_common::entity_init::EntityInit;
+use protocol::packets::server::{EquipmentEntry, EquipmentSlot};
+use quill_common::{
+    components::{CustomName, Glowing, Invisible, Sneaking, Sprinting, Velocity},
+    entity_init::EntityInit,
+};
 use uuid::Uuid;
 
 use crate::{Client, NetworkId};
 
+const POSE_STANDING: i32 = 0;
+const POSE_SNEAKING: i32 = 5;
+
 /// Component that sends the spawn packet for an entity
-/// using its components.
+/// using its components, followed by its metadata
+/// and equipment.
 pub struct SpawnPacketSender(fn(&EntityRef, &Client) -> SysResult);
 
 impl SpawnPacketSender {
     pub fn send(&self, entity: &EntityRef, client: &Client) -> SysResult {
-        (self.0)(entity, client)
+        (self.0)(entity, client)?;
+        let network_id = *entity.get::<NetworkId>()?;
+        client.send_entity_metadata(network_id, entity_metadata(entity));
+        if let Some(equipment) = entity_equipment(entity) {
+            client.send_entity_equipment(network_id, equipment);
+        }
+        Ok(())
     }
 }
 
+/// Derives the equipment of an entity holding an [`Inventory`],
+/// i.e. its held items and armor.
+pub fn entity_equipment(entity: &EntityRef) -> Option<Vec<EquipmentEntry>> {
+    let inventory = entity.get::<Inventory>().ok()?;
+    let hotbar_slot = entity.get::<HotbarSlot>().ok()?.get();
+    let entry = |slot, area, index| EquipmentEntry {
+        slot,
+        item: inventory.item(area, index).and_then(|item| item.clone()),
+    };
+    Some(vec![
+        entry(EquipmentSlot::MainHand, Area::Hotbar, hotbar_slot),
+        entry(EquipmentSlot::OffHand, Area::Offhand, 0),
+        entry(EquipmentSlot::Boots, Area::Boots, 0),
+        entry(EquipmentSlot::Leggings, Area::Leggings, 0),
+        entry(EquipmentSlot::Chestplate, Area::Chestplate, 0),
+        entry(EquipmentSlot::Helmet, Area::Helmet, 0),
+    ])
+}
+
+/// Components which determine the metadata of an entity.
+pub type MetadataComponents<'a> = (
+    Option<&'a Sneaking>,
+    Option<&'a Sprinting>,
+    Option<&'a Invisible>,
+    Option<&'a Glowing>,
+    Option<&'a CustomName>,
+);
+
+/// Derives the metadata of an entity from its [`MetadataComponents`].
+pub fn metadata(components: MetadataComponents) -> EntityMetadata {
+    let (sneaking, sprinting, invisible, glowing, custom_name) = components;
+
+    let mut bitmask = EntityBitMask::empty();
+    bitmask.set(EntityBitMask::CROUCHED, sneaking.map_or(false, |s| s.0));
+    bitmask.set(EntityBitMask::SPRINTING, sprinting.map_or(false, |s| s.0));
+    bitmask.set(EntityBitMask::INVISIBLE, invisible.map_or(false, |i| i.0));
+    bitmask.set(
+        EntityBitMask::GLOWING_EFFECT,
+        glowing.map_or(false, |g| g.0),
+    );
+    let pose = if bitmask.contains(EntityBitMask::CROUCHED) {
+        POSE_SNEAKING
+    } else {
+        POSE_STANDING
+    };
+
+    EntityMetadata::new()
+        .with(META_INDEX_ENTITY_BITMASK, bitmask.bits())
+        .with(
+            META_INDEX_CUSTOM_NAME,
+            custom_name.map(|name| Text::from(name.as_str().to_owned()).to_string()),
+        )
+        .with(META_INDEX_IS_CUSTOM_NAME_VISIBLE, custom_name.is_some())
+        .with_many(&[(META_INDEX_POSE, MetaEntry::Pose(pose))])
+}
+
+/// Derives the metadata of an entity from its components.
+pub fn entity_metadata(entity: &EntityRef) -> EntityMetadata {
+    let sneaking = entity.get::<Sneaking>().ok();
+    let sprinting = entity.get::<Sprinting>().ok();
+    let invisible = entity.get::<Invisible>().ok();
+    let glowing = entity.get::<Glowing>().ok();
+    let custom_name = entity.get::<CustomName>().ok();
+    metadata((
+        sneaking.as_deref(),
+        sprinting.as_deref(),
+        invisible.as_deref(),
+        glowing.as_deref(),
+        custom_name.as_deref(),
+    ))
+}
+
 /// Stores the position of an entity on
 /// the previous tick. Used to determine
 /// when to send movement updates.
 #[derive(Copy, Clone, Debug)]
 pub struct PreviousPosition(pub Position);
 
+/// Stores the velocity of an entity on
+/// the previous tick. Used to determine
+/// when to send velocity updates.
+#[derive(Copy, Clone, Debug)]
+pub struct PreviousVelocity(pub Velocity);
+
 pub fn add_entity_components(builder: &mut EntityBuilder, init: &EntityInit) {
     if !builder.has::<NetworkId>() {
         builder.add(NetworkId::new());
     }
     builder.add(PreviousPosition(*builder.get::<Position>().unwrap()));
+    builder.add(PreviousVelocity(
+        builder.get::<Velocity>().copied().unwrap_or_default(),
+    ));
     add_spawn_packet(builder, init);
 }
 
 fn add_spawn_packet(builder: &mut EntityBuilder, init: &EntityInit) {
-    // TODO: object entities spawned with Spawn Entity
-    // (minecarts, items, ...)
+    // TODO: other object entities spawned with Spawn Entity
+    // (minecarts, ...)
     let spawn_packet = match init {
         EntityInit::Player => spawn_player,
+        EntityInit::Item => spawn_item,
         _ => spawn_living_entity,
     };
     builder.add(SpawnPacketSender(spawn_packet));
@@ -48,12 +151,68 @@ fn spawn_player(entity: &EntityRef, client: &Client) -> SysResult {
     Ok(())
 }
 
+fn spawn_item(entity: &EntityRef, client: &Client) -> SysResult {
+    let network_id = *entity.get::<NetworkId>()?;
+    let uuid = *entity.get::<Uuid>()?;
+    let pos = *entity.get::<Position>()?;
+    let velocity = *entity.get::<Velocity>()?;
+    let item = entity.get::<ItemStack>()?;
+
+    client.send_item_entity(network_id, uuid, pos, velocity, &item);
+    Ok(())
+}
+
 fn spawn_living_entity(entity: &EntityRef, client: &Client) -> SysResult {
     let network_id = *entity.get::<NetworkId>()?;
     let uuid = *entity.get::<Uuid>()?;
     let pos = *entity.get::<Position>()?;
+    let velocity = *entity.get::<Velocity>()?;
     let kind = *entity.get::<EntityKind>()?;
 
-    client.send_living_entity(network_id, uuid, pos, kind);
+    client.send_living_entity(network_id, uuid, pos, velocity, kind);
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sneaking_entities_crouch() {
+        let metadata = metadata((
+            Some(&Sneaking(true)),
+            None,
+            None,
+            Some(&Glowing(true)),
+            None,
+        ));
+        assert_eq!(
+            metadata.get(META_INDEX_ENTITY_BITMASK),
+            Some(MetaEntry::Byte(
+                (EntityBitMask::CROUCHED | EntityBitMask::GLOWING_EFFECT).bits() as i8
+            ))
+        );
+        assert_eq!(
+            metadata.get(META_INDEX_POSE),
+            Some(MetaEntry::Pose(POSE_SNEAKING))
+        );
+        assert_eq!(
+            metadata.get(META_INDEX_IS_CUSTOM_NAME_VISIBLE),
+            Some(MetaEntry::Boolean(false))
+        );
+    }
+
+    #[test]
+    fn custom_names_are_visible() {
+        let name = CustomName::new("Steve");
+        let metadata = metadata((None, None, None, None, Some(&name)));
+        assert_eq!(
+            metadata.get(META_INDEX_CUSTOM_NAME),
+            Some(MetaEntry::OptChat(Some("\"Steve\"".to_owned())))
+        );
+        assert_eq!(
+            metadata.get(META_INDEX_IS_CUSTOM_NAME_VISIBLE),
+            Some(MetaEntry::Boolean(true))
+        );
+    }
+}
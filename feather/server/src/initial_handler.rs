@@ -38,6 +38,8 @@ pub struct NewPlayer {
     pub uuid: Uuid,
     pub username: String,
     pub profile: Vec<ProfileProperty>,
+    /// The IP address of the client (not of the proxy).
+    pub ip: String,
 
     pub received_packets: Receiver<ClientPlayPacket>,
     pub packets_to_send: Sender<ServerPlayPacket>,
@@ -163,8 +165,14 @@ async fn handle_login(
         proxy_data = Some(proxy::do_velocity_ip_forwarding(worker).await?);
     }
 
+    // Bans apply to the client's address, not the proxy's.
+    let ip = match &proxy_data {
+        Some(proxy_data) => proxy_data.client.clone(),
+        None => worker.addr().ip().to_string(),
+    };
+
     if worker.options().online_mode {
-        enable_encryption(worker, login_start.name).await
+        enable_encryption(worker, login_start.name, ip).await
     } else {
         let profile = match proxy_data {
             Some(proxy_data) => AuthResponse {
@@ -174,7 +182,7 @@ async fn handle_login(
             },
             None => offline_mode_profile(login_start.name),
         };
-        finish_login(worker, profile).await
+        finish_login(worker, profile, ip).await
     }
 }
 
@@ -187,7 +195,7 @@ fn offline_mode_profile(username: String) -> AuthResponse {
     }
 }
 
-fn offline_mode_uuid(username: &str) -> Uuid {
+pub(crate) fn offline_mode_uuid(username: &str) -> Uuid {
     // See: https://gist.github.com/games647/2b6a00a8fc21fd3b88375f03c9e2e603
     let mut hasher = md5::Md5::default();
     hasher.update(format!("OfflinePlayer:{}", username).as_bytes());
@@ -214,6 +222,7 @@ static RSA_KEY_ENCODED: Lazy<Vec<u8>> = Lazy::new(|| {
 async fn enable_encryption(
     worker: &mut Worker,
     username: String,
+    ip: String,
 ) -> anyhow::Result<InitialHandling> {
     log::debug!("Authenticating {}", username);
     let shared_secret = do_encryption_handshake(worker).await?;
@@ -221,7 +230,7 @@ async fn enable_encryption(
 
     let response = authenticate(shared_secret, username).await?;
 
-    finish_login(worker, response).await
+    finish_login(worker, response, ip).await
 }
 
 async fn do_encryption_handshake(worker: &mut Worker) -> anyhow::Result<CryptKey> {
@@ -293,7 +302,19 @@ fn hexdigest(bytes: &[u8]) -> String {
 async fn finish_login(
     worker: &mut Worker,
     response: AuthResponse,
+    ip: String,
 ) -> anyhow::Result<InitialHandling> {
+    if let Err(reason) = worker.access_lists().check_login(response.id, &ip) {
+        log::info!("Disconnecting {} ({}): {}", response.name, ip, reason);
+        worker
+            .write(ServerLoginPacket::DisconnectLogin(DisconnectLogin {
+                reason: reason.to_string(),
+            }))
+            .await
+            .ok();
+        return Ok(InitialHandling::Disconnect);
+    }
+
     enable_compression(worker).await?;
 
     let success = LoginSuccess {
@@ -308,6 +329,7 @@ async fn finish_login(
         username: response.name,
         uuid: response.id,
         profile: response.properties,
+        ip,
         received_packets: worker.received_packets(),
         packets_to_send: worker.packets_to_send(),
     };
@@ -1,15 +1,21 @@
 #![allow(clippy::unnecessary_wraps)] // systems are required to return Results
 
-use std::{sync::Arc, time::Instant};
+use std::{
+    future::Future,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
+use access::AccessLists;
 use base::Position;
 use chunk_subscriptions::ChunkSubscriptions;
-use common::Game;
+use common::{view::MaxViewDistance, CommandDispatcher, Game};
 use ecs::SystemExecutor;
 use flume::Receiver;
 use initial_handler::NewPlayer;
 use listener::Listener;
 
+pub mod access;
 mod chunk_subscriptions;
 pub mod client;
 pub mod config;
@@ -24,7 +30,7 @@ mod packet_handlers;
 mod player_count;
 mod systems;
 
-pub use client::{Client, ClientId, Clients};
+pub use client::{Client, ClientId, ClientIp, Clients};
 pub use network_id_registry::NetworkId;
 pub use options::Options;
 use player_count::PlayerCount;
@@ -48,6 +54,7 @@ pub struct Server {
     last_keepalive_time: Instant,
 
     player_count: PlayerCount,
+    access_lists: AccessLists,
 }
 
 impl Server {
@@ -57,9 +64,16 @@ impl Server {
     pub async fn bind(options: Options) -> anyhow::Result<Self> {
         let options = Arc::new(options);
         let player_count = PlayerCount::new(options.max_players);
+        let access_lists = AccessLists::load(".", options.whitelist)?;
 
         let (new_players_tx, new_players) = flume::bounded(4);
-        Listener::start(Arc::clone(&options), player_count.clone(), new_players_tx).await?;
+        Listener::start(
+            Arc::clone(&options),
+            player_count.clone(),
+            access_lists.clone(),
+            new_players_tx,
+        )
+        .await?;
 
         log::info!(
             "Server is listening on {}:{}",
@@ -75,12 +89,22 @@ impl Server {
             chunk_subscriptions: ChunkSubscriptions::default(),
             last_keepalive_time: Instant::now(),
             player_count,
+            access_lists,
         })
     }
 
     /// Links this server with a `Game` so that players connecting
     /// to the server become part of this `Game`.
     pub fn link_with_game(self, game: &mut Game, systems: &mut SystemExecutor<Game>) {
+        access::commands::register(
+            &mut *game
+                .resources
+                .get_mut::<CommandDispatcher>()
+                .expect("common systems must be registered before the server"),
+            self.options.online_mode,
+        );
+        game.insert_resource(self.access_lists.clone());
+        game.insert_resource(MaxViewDistance(self.options.view_distance));
         systems::register(self, game, systems);
         game.add_entity_spawn_callback(entities::add_entity_components);
     }
@@ -89,6 +113,25 @@ impl Server {
     pub fn player_count(&self) -> u32 {
         self.player_count.get()
     }
+
+    /// Gets the operator, whitelist and ban lists.
+    pub fn access_lists(&self) -> &AccessLists {
+        &self.access_lists
+    }
+
+    /// Returns a future completing once all player connections
+    /// are closed.
+    ///
+    /// Connections close after the server is dropped and the
+    /// packets queued for them, e.g. disconnect messages, are sent.
+    pub fn connections_closed(&self) -> impl Future<Output = ()> + 'static {
+        let player_count = self.player_count.clone();
+        async move {
+            while player_count.get() > 0 {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        }
+    }
 }
 
 /// Low-level functions, mostly used internally.
@@ -5,7 +5,7 @@ use flume::Sender;
 use tokio::net::{TcpListener, TcpStream};
 
 use crate::{
-    connection_worker::Worker, initial_handler::NewPlayer, options::Options,
+    access::AccessLists, connection_worker::Worker, initial_handler::NewPlayer, options::Options,
     player_count::PlayerCount,
 };
 
@@ -14,6 +14,7 @@ pub struct Listener {
     listener: TcpListener,
     options: Arc<Options>,
     player_count: PlayerCount,
+    access_lists: AccessLists,
     new_players: Sender<NewPlayer>,
 }
 
@@ -21,6 +22,7 @@ impl Listener {
     pub async fn start(
         options: Arc<Options>,
         player_count: PlayerCount,
+        access_lists: AccessLists,
         new_players: Sender<NewPlayer>,
     ) -> anyhow::Result<()> {
         let listener = TcpListener::bind(format!("{}:{}", options.bind_address, options.port))
@@ -31,6 +33,7 @@ impl Listener {
             listener,
             options,
             player_count,
+            access_lists,
             new_players,
         };
         tokio::task::spawn(async move {
@@ -54,6 +57,7 @@ impl Listener {
             addr,
             Arc::clone(&self.options),
             self.player_count.clone(),
+            self.access_lists.clone(),
             self.new_players.clone(),
         );
         worker.start();
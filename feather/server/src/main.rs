@@ -1,19 +1,42 @@
-use std::{cell::RefCell, rc::Rc};
+use std::{
+    cell::RefCell,
+    io::{self, ErrorKind},
+    path::Path,
+    rc::Rc,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
 
 use anyhow::Context;
+use base::{
+    anvil::level::{
+        self, LevelData, LevelGeneratorType, LevelVersion, SuperflatGeneratorOptions,
+        WorldGenSettings,
+    },
+    Biome, ChunkPosition,
+};
 use common::{
-    world_source::{flat::FlatWorldSource, region::RegionWorldSource, WorldSource},
+    events::ShutdownEvent,
+    world_source::{generating::GeneratingWorldSource, region::RegionWorldSource, WorldSource},
     Game, TickLoop, World,
 };
 use ecs::SystemExecutor;
-use feather_server::Server;
-use plugin_host::PluginManager;
+use feather_server::{config::Config, Server};
+use plugin_host::{PluginLimits, PluginManager};
+use worldgen::{ComposableGenerator, EmptyWorldGenerator, SuperflatWorldGenerator, WorldGenerator};
 
 mod logging;
 
 const PLUGINS_DIRECTORY: &str = "plugins";
-const WORLD_DIRECTORY: &str = "world";
+/// The interval in ticks between checks for changed plugin files.
+const PLUGIN_RELOAD_INTERVAL: u64 = 20;
 const CONFIG_PATH: &str = "config.toml";
+/// The maximum time to wait for the packets queued for
+/// players, e.g. disconnect messages, to be sent on shutdown.
+const CONNECTION_FLUSH_TIMEOUT: Duration = Duration::from_secs(5);
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
@@ -29,26 +52,55 @@ async fn main() -> anyhow::Result<()> {
 
     log::info!("Creating server");
     let options = config.to_options();
+    let world_dir = options.world_dir.clone();
     let server = Server::bind(options).await?;
 
-    let game = init_game(server)?;
+    let shutdown_requested = listen_for_shutdown_signals();
+    let connections_closed = server.connections_closed();
+    let game = init_game(server, &config, &world_dir, shutdown_requested)?;
 
     run(game);
 
+    // The game, and with it the server, has been dropped, so the
+    // connections close once their queued packets are sent.
+    if tokio::time::timeout(CONNECTION_FLUSH_TIMEOUT, connections_closed)
+        .await
+        .is_err()
+    {
+        log::warn!("Timed out waiting for player connections to close");
+    }
+
     Ok(())
 }
 
-fn init_game(server: Server) -> anyhow::Result<Game> {
+fn init_game(
+    server: Server,
+    config: &Config,
+    world_dir: &Path,
+    shutdown_requested: Arc<AtomicBool>,
+) -> anyhow::Result<Game> {
     let mut game = Game::new();
-    init_systems(&mut game, server);
-    init_world_source(&mut game);
-    init_plugin_manager(&mut game)?;
+    init_systems(&mut game, server, shutdown_requested);
+    init_world(&mut game, config, world_dir)?;
+    init_plugin_manager(&mut game, config.plugins.to_limits())?;
     Ok(game)
 }
 
-fn init_systems(game: &mut Game, server: Server) {
+fn init_systems(game: &mut Game, server: Server, shutdown_requested: Arc<AtomicBool>) {
     let mut systems = SystemExecutor::new();
 
+    // Runs first so that all other systems
+    // observe the `ShutdownEvent` in the same tick.
+    systems.add_system_with_name(
+        move |game: &mut Game| {
+            if shutdown_requested.load(Ordering::SeqCst) {
+                game.ecs.insert_event(ShutdownEvent);
+            }
+            Ok(())
+        },
+        "stop_on_signal",
+    );
+
     // Register common before server code, so
     // that packet broadcasting happens after
     // gameplay actions.
@@ -60,18 +112,103 @@ fn init_systems(game: &mut Game, server: Server) {
     game.system_executor = Rc::new(RefCell::new(systems));
 }
 
-fn init_world_source(game: &mut Game) {
+fn init_world(game: &mut Game, config: &Config, world_dir: &Path) -> anyhow::Result<()> {
+    let (level, generator) = match level::load_level_data(world_dir) {
+        Ok(level) => {
+            log::info!("Loaded level.dat");
+            let generator = create_generator(&level);
+            (level, generator)
+        }
+        Err(nbt::Error::IoError(e)) if e.kind() == ErrorKind::NotFound => {
+            log::info!("World save not found; creating it");
+            let mut level = create_level(config);
+            let generator = create_generator(&level);
+            level.spawn_y = find_spawn_height(&*generator);
+            level::save_level_data(world_dir, &level).context("failed to save level.dat")?;
+            (level, generator)
+        }
+        Err(e) => return Err(e).context("failed to load level.dat"),
+    };
+    log::info!(
+        "Using world generator {:?} with seed {}",
+        level.generator_type(),
+        level.world_seed()
+    );
+
     // Load chunks from the world save first,
-    // and fall back to generating a superflat
-    // world otherwise. This is a placeholder:
-    // we don't have proper world generation yet.
+    // and fall back to generating them otherwise.
     let world_source =
-        RegionWorldSource::new(WORLD_DIRECTORY).with_fallback(FlatWorldSource::new());
+        RegionWorldSource::new(world_dir).with_fallback(GeneratingWorldSource::new(generator));
     game.world = World::with_source(world_source);
+    *game.world.level_mut() = level;
+    Ok(())
 }
 
-fn init_plugin_manager(game: &mut Game) -> anyhow::Result<()> {
-    let mut plugin_manager = PluginManager::new();
+/// Creates the level data for a new world from the config.
+fn create_level(config: &Config) -> LevelData {
+    let seed = config.world.parse_seed();
+    let superflat_options = match config.world.generator.as_str() {
+        "default" => None,
+        "flat" => Some(SuperflatGeneratorOptions::default()),
+        "empty" => Some(SuperflatGeneratorOptions {
+            layers: Vec::new(),
+            biome: format!("minecraft:{}", Biome::TheVoid.name()),
+            ..Default::default()
+        }),
+        generator => {
+            log::warn!(
+                "Unknown world generator '{}'; using the default generator",
+                generator
+            );
+            None
+        }
+    };
+
+    LevelData {
+        allow_commands: true,
+        border_damage_per_block: 0.2,
+        border_safe_zone: 5.0,
+        border_size: 60_000_000.0,
+        data_version: level::DATA_VERSION,
+        difficulty: 2,
+        game_type: config.server.default_gamemode as i32,
+        initialized: true,
+        seed,
+        version: LevelVersion::new(level::DATA_VERSION, "1.16.2"),
+        world_gen_settings: Some(WorldGenSettings::new(seed, superflat_options)),
+        ..Default::default()
+    }
+}
+
+fn create_generator(level: &LevelData) -> Arc<dyn WorldGenerator> {
+    match level.generator_type() {
+        LevelGeneratorType::Flat => {
+            let options = level.superflat_options().unwrap_or_default();
+            if options.layers.is_empty() {
+                Arc::new(EmptyWorldGenerator {})
+            } else {
+                Arc::new(SuperflatWorldGenerator { options })
+            }
+        }
+        _ => Arc::new(ComposableGenerator::default_with_seed(
+            level.world_seed() as u64
+        )),
+    }
+}
+
+/// Finds the height of the surface at the origin,
+/// which is the spawn point of new worlds.
+fn find_spawn_height(generator: &dyn WorldGenerator) -> i32 {
+    let chunk = generator.generate_chunk(ChunkPosition::new(0, 0));
+    chunk
+        .heightmaps()
+        .motion_blocking
+        .height(0, 0)
+        .unwrap_or_default() as i32
+}
+
+fn init_plugin_manager(game: &mut Game, limits: PluginLimits) -> anyhow::Result<()> {
+    let mut plugin_manager = PluginManager::with_limits(limits);
     plugin_manager.load_dir(game, PLUGINS_DIRECTORY)?;
 
     let plugin_manager_rc = Rc::new(RefCell::new(plugin_manager));
@@ -79,6 +216,46 @@ fn init_plugin_manager(game: &mut Game) -> anyhow::Result<()> {
     Ok(())
 }
 
+/// Spawns a task waiting for SIGINT (Ctrl-C) or SIGTERM.
+///
+/// The returned flag is set on the first signal, which stops
+/// the server at the end of the next tick. A second signal
+/// exits immediately in case the shutdown hangs.
+fn listen_for_shutdown_signals() -> Arc<AtomicBool> {
+    let shutdown_requested = Arc::new(AtomicBool::new(false));
+    let flag = Arc::clone(&shutdown_requested);
+    tokio::spawn(async move {
+        loop {
+            if let Err(e) = shutdown_signal().await {
+                log::error!("Failed to listen for shutdown signals: {}", e);
+                return;
+            }
+            if flag.swap(true, Ordering::SeqCst) {
+                log::warn!("Received a second shutdown signal; exiting without saving");
+                std::process::exit(1);
+            }
+            log::info!("Received shutdown signal");
+        }
+    });
+    shutdown_requested
+}
+
+#[cfg(unix)]
+async fn shutdown_signal() -> io::Result<()> {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut terminate = signal(SignalKind::terminate())?;
+    tokio::select! {
+        result = tokio::signal::ctrl_c() => result,
+        _ = terminate.recv() => Ok(()),
+    }
+}
+
+#[cfg(not(unix))]
+async fn shutdown_signal() -> io::Result<()> {
+    tokio::signal::ctrl_c().await
+}
+
 fn print_systems(systems: &SystemExecutor<Game>) {
     let systems: Vec<&str> = systems.system_names().collect();
     log::debug!("---SYSTEMS---\n{:#?}\n", systems);
@@ -96,6 +273,53 @@ fn create_tick_loop(mut game: Game) -> TickLoop {
         systems.borrow_mut().run(&mut game);
         game.tick_count += 1;
 
+        // Plugins are reloaded outside of systems, since unloading
+        // a plugin removes its systems from the executor.
+        if game.tick_count % PLUGIN_RELOAD_INTERVAL == 0 {
+            reload_plugins(&mut game);
+        }
+
+        // Players were disconnected and saved
+        // by the systems that observed the event.
+        if game.ecs.query::<&ShutdownEvent>().iter().next().is_some() {
+            shutdown(&mut game);
+            return true;
+        }
         false
     })
 }
+
+/// Reloads plugins whose files changed, loads new plugin
+/// files and unloads plugins whose files were removed.
+fn reload_plugins(game: &mut Game) {
+    let plugin_manager = game
+        .resources
+        .get::<Rc<RefCell<PluginManager>>>()
+        .map(|plugin_manager| Rc::clone(&*plugin_manager));
+    if let Ok(plugin_manager) = plugin_manager {
+        if let Err(e) = plugin_manager
+            .borrow_mut()
+            .reload_dir(game, PLUGINS_DIRECTORY)
+        {
+            log::error!("Failed to reload plugins: {:?}", e);
+        }
+    }
+}
+
+/// Disables plugins in the order they were loaded, then
+/// saves dirty chunks and waits for the world source to exit.
+fn shutdown(game: &mut Game) {
+    log::info!("Stopping the server");
+
+    let plugin_manager = game
+        .resources
+        .get::<Rc<RefCell<PluginManager>>>()
+        .map(|plugin_manager| Rc::clone(&*plugin_manager));
+    if let Ok(plugin_manager) = plugin_manager {
+        plugin_manager.borrow_mut().disable_all(game);
+    }
+
+    log::info!("Saving the world");
+    game.world.shutdown();
+    log::info!("Server stopped");
+}
@@ -1,3 +1,5 @@
+use std::path::PathBuf;
+
 use base::Gamemode;
 
 use crate::favicon::Favicon;
@@ -25,9 +27,16 @@ pub struct Options {
     /// Maximum number of players to allow on the server.
     pub max_players: u32,
 
+    /// Whether only players in `whitelist.json`
+    /// and operators may join.
+    pub whitelist: bool,
+
     /// The default gamemode for new players.
     pub default_gamemode: Gamemode,
 
+    /// The message shown to players when the server stops.
+    pub shutdown_message: String,
+
     /// Proxy IP forwarding mode
     pub proxy_mode: Option<ProxyMode>,
     // HMAC key used with Velocity IP forwarding.
@@ -35,6 +44,9 @@ pub struct Options {
 
     /// Packet size threshold at which to compress data
     pub compression_threshold: Option<usize>,
+
+    /// Directory containing the world save.
+    pub world_dir: PathBuf,
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
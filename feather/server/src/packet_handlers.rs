@@ -1,5 +1,5 @@
-use base::{Position, Text};
-use common::{chat::ChatKind, Game};
+use base::Position;
+use common::{commands, events::ChatEvent, view::ClientViewDistance, Game};
 use ecs::{Entity, EntityRef, SysResult};
 use interaction::{
     handle_held_item_change, handle_interact_entity, handle_player_block_placement,
@@ -12,7 +12,7 @@ use protocol::{
     },
     ClientPlayPacket,
 };
-use quill_common::components::Name;
+use quill_common::EntityId;
 
 use crate::{NetworkId, Server};
 
@@ -31,13 +31,13 @@ pub fn handle_packet(
     let player = game.ecs.entity(player_id)?;
     match packet {
         ClientPlayPacket::PlayerPosition(packet) => {
-            movement::handle_player_position(server, player, packet)
+            movement::handle_player_position(game, server, player_id, packet)
         }
         ClientPlayPacket::PlayerPositionAndRotation(packet) => {
-            movement::handle_player_position_and_rotation(server, player, packet)
+            movement::handle_player_position_and_rotation(game, server, player_id, packet)
         }
         ClientPlayPacket::PlayerRotation(packet) => {
-            movement::handle_player_rotation(server, player, packet)
+            movement::handle_player_rotation(game, server, player_id, packet)
         }
         ClientPlayPacket::PlayerMovement(packet) => {
             movement::handle_player_movement(player, packet)
@@ -45,9 +45,14 @@ pub fn handle_packet(
 
         ClientPlayPacket::Animation(packet) => handle_animation(server, player, packet),
 
-        ClientPlayPacket::ChatMessage(packet) => handle_chat_message(game, player, packet),
+        ClientPlayPacket::ChatMessage(packet) => handle_chat_message(game, player_id, packet),
+        ClientPlayPacket::TabComplete(packet) => {
+            crate::systems::commands::handle_tab_complete(game, server, player_id, packet)
+        }
 
-        ClientPlayPacket::PlayerDigging(packet) => handle_player_digging(game, packet, player_id),
+        ClientPlayPacket::PlayerDigging(packet) => {
+            handle_player_digging(game, server, packet, player_id)
+        }
 
         ClientPlayPacket::CreativeInventoryAction(packet) => {
             inventory::handle_creative_inventory_action(player, packet)
@@ -68,18 +73,18 @@ pub fn handle_packet(
         ClientPlayPacket::ClientSettings(packet) => handle_client_settings(server, player, packet),
 
         ClientPlayPacket::PlayerAbilities(packet) => {
-            movement::handle_player_abilities(game, player_id, packet)
+            movement::handle_player_abilities(game, server, player_id, packet)
         }
 
         ClientPlayPacket::EntityAction(packet) => {
             entity_action::handle_entity_action(game, player_id, packet)
         }
 
+        ClientPlayPacket::ClientStatus(packet) => handle_client_status(game, player_id, packet),
+
         ClientPlayPacket::TeleportConfirm(_)
         | ClientPlayPacket::QueryBlockNbt(_)
         | ClientPlayPacket::SetDifficulty(_)
-        | ClientPlayPacket::ClientStatus(_)
-        | ClientPlayPacket::TabComplete(_)
         | ClientPlayPacket::WindowConfirmation(_)
         | ClientPlayPacket::ClickWindowButton(_)
         | ClientPlayPacket::CloseWindow(_)
@@ -130,13 +135,31 @@ fn handle_animation(
     Ok(())
 }
 
-fn handle_chat_message(game: &Game, player: EntityRef, packet: client::ChatMessage) -> SysResult {
-    let name = player.get::<Name>()?;
-    let message = Text::translate_with("chat.type.text", vec![name.to_string(), packet.message]);
-    game.broadcast_chat(ChatKind::PlayerChat, message);
+fn handle_chat_message(game: &mut Game, player: Entity, packet: client::ChatMessage) -> SysResult {
+    if let Some(command) = packet.message.strip_prefix('/') {
+        return commands::execute_command(game, player, command);
+    }
+
+    // The message is broadcast next tick unless a plugin cancels the event.
+    game.ecs
+        .insert_event(ChatEvent::new(EntityId(player.to_bits()), packet.message));
     Ok(())
 }
 
+fn handle_client_status(
+    game: &mut Game,
+    player: Entity,
+    packet: client::ClientStatus,
+) -> SysResult {
+    match packet {
+        client::ClientStatus::PerformRespawn => {
+            crate::systems::health::respawn_player(game, player)
+        }
+        // Statistics are not supported yet.
+        client::ClientStatus::RequestStats => Ok(()),
+    }
+}
+
 fn handle_client_settings(
     server: &mut Server,
     player: EntityRef,
@@ -146,5 +169,8 @@ fn handle_client_settings(
     server.broadcast_with(|client| {
         client.send_player_model_flags(network_id, packet.displayed_skin_parts)
     });
+
+    // The view is resized by `common::view` on the next tick.
+    player.get_mut::<ClientViewDistance>()?.0 = u32::from(packet.view_distance).max(1);
     Ok(())
 }
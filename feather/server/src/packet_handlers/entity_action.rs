@@ -1,7 +1,11 @@
-use common::Game;
+use base::{Area, Inventory, Item};
+use common::{movement::Gliding, Game};
 use ecs::{Entity, SysResult};
 use protocol::packets::client::{EntityAction, EntityActionKind};
-use quill_common::{components::Sneaking, events::SneakEvent};
+use quill_common::{
+    components::{Sneaking, Sprinting},
+    events::SneakEvent,
+};
 
 ///  From [wiki](https://wiki.vg/Protocol#Entity_Action)
 ///  Sent by the client to indicate that it has performed certain actions:
@@ -18,7 +22,7 @@ pub fn handle_entity_action(game: &mut Game, player: Entity, packet: EntityActio
             if !is_sneaking {
                 game.ecs
                     .insert_entity_event(player, SneakEvent::new(true))?;
-                game.ecs.get_mut::<Sneaking>(player)?.0 = true;
+                game.ecs.insert(player, Sneaking(true))?;
             }
         }
         EntityActionKind::StopSneaking => {
@@ -26,7 +30,7 @@ pub fn handle_entity_action(game: &mut Game, player: Entity, packet: EntityActio
             if is_sneaking {
                 game.ecs
                     .insert_entity_event(player, SneakEvent::new(false))?;
-                game.ecs.get_mut::<Sneaking>(player)?.0 = false;
+                game.ecs.insert(player, Sneaking(false))?;
             }
         }
         EntityActionKind::LeaveBed => {
@@ -36,10 +40,10 @@ pub fn handle_entity_action(game: &mut Game, player: Entity, packet: EntityActio
             // a notice that bed state might have changed.
         }
         EntityActionKind::StartSprinting => {
-            //TODO issue #423
+            game.ecs.insert(player, Sprinting(true))?;
         }
         EntityActionKind::StopSprinting => {
-            //TODO issue #423
+            game.ecs.insert(player, Sprinting(false))?;
         }
         EntityActionKind::StartHorseJump => {
             //TODO issue #423
@@ -51,7 +55,17 @@ pub fn handle_entity_action(game: &mut Game, player: Entity, packet: EntityActio
             //TODO issue #423
         }
         EntityActionKind::StartElytraFlight => {
-            //TODO issue #423
+            let wears_elytra = game
+                .ecs
+                .get::<Inventory>(player)?
+                .item(Area::Chestplate, 0)
+                .map_or(false, |item| {
+                    item.as_ref()
+                        .map_or(false, |stack| stack.item == Item::Elytra)
+                });
+            if wears_elytra {
+                game.ecs.insert(player, Gliding)?;
+            }
         }
     }
 
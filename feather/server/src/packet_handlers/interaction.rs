@@ -1,7 +1,9 @@
 use crate::{ClientId, NetworkId, Server};
-use common::entities::player::HotbarSlot;
+use base::{BlockId, BlockPosition, Gamemode, Position, Vec3d};
+use common::digging::{self, Digging, BREAK_TIME_TOLERANCE, PLAYER_EYE_HEIGHT};
+use common::entities::player::{drop_held_item, held_item, HotbarSlot};
 use common::interactable::InteractableRegistry;
-use common::Game;
+use common::{block_placement, Game, Window};
 use ecs::{Entity, EntityRef, SysResult};
 use libcraft_core::{BlockFace as LibcraftBlockFace, Hand};
 use libcraft_core::{InteractionType, Vec3f};
@@ -9,14 +11,16 @@ use protocol::packets::client::{
     BlockFace, HeldItemChange, InteractEntity, InteractEntityKind, PlayerBlockPlacement,
     PlayerDigging, PlayerDiggingStatus,
 };
+use protocol::packets::server::PlayerDiggingStatus as DiggingAcknowledgement;
 use quill_common::{
     events::{BlockInteractEvent, BlockPlacementEvent, InteractEntityEvent},
     EntityId,
 };
-/// Handles the player block placement packet. Currently just removes the block client side for the player.
+/// Handles the player block placement packet, which either interacts
+/// with the clicked block or places the held block against it.
 pub fn handle_player_block_placement(
     game: &mut Game,
-    _server: &mut Server,
+    server: &mut Server,
     packet: PlayerBlockPlacement,
     player: Entity,
 ) -> SysResult {
@@ -26,7 +30,7 @@ pub fn handle_player_block_placement(
         _ => {
             let client_id = game.ecs.get::<ClientId>(player).unwrap();
 
-            let client = _server.clients.get(*client_id).unwrap();
+            let client = server.clients.get(*client_id).unwrap();
 
             client.disconnect("Malformed Packet!");
 
@@ -59,7 +63,7 @@ pub fn handle_player_block_placement(
             None => {
                 let client_id = game.ecs.get::<ClientId>(player).unwrap();
 
-                let client = _server.clients.get(*client_id).unwrap();
+                let client = server.clients.get(*client_id).unwrap();
 
                 client.disconnect("Attempted to interact with an unloaded block!");
 
@@ -71,12 +75,18 @@ pub fn handle_player_block_placement(
         }
     };
 
-    let interactable_registry = game
+    let is_interactable = game
         .resources
         .get::<InteractableRegistry>()
-        .expect("Failed to get the interactable registry");
+        .expect("Failed to get the interactable registry")
+        .is_registered(block_kind);
+
+    if is_interactable {
+        // Spectators cannot interact with blocks.
+        if is_spectator(game, player)? {
+            return Ok(());
+        }
 
-    if interactable_registry.is_registered(block_kind) {
         // Handle this as a block interaction
         let event = BlockInteractEvent {
             hand,
@@ -97,12 +107,32 @@ pub fn handle_player_block_placement(
             inside_block: packet.inside_block,
         };
 
+        if !block_placement::place_block(game, player, &event)? {
+            // Undo the client's prediction
+            let client_id = *game.ecs.get::<ClientId>(player)?;
+            if let Some(client) = server.clients.get(client_id) {
+                for &position in &[
+                    packet.position,
+                    block_placement::adjacent(packet.position, face),
+                ] {
+                    if let Some(block) = game.block(position) {
+                        client.send_block_change(position, block);
+                    }
+                }
+                client.send_window_items(&*game.ecs.get::<Window>(player)?);
+            }
+        }
+
         game.ecs.insert_entity_event(player, event)?;
     }
 
     Ok(())
 }
 
+/// The squared distance from a player's eyes
+/// within which they can break blocks.
+const MAX_DIG_DISTANCE_SQUARED: f64 = 36.0;
+
 /// Handles the Player Digging packet sent for the following
 /// actions:
 /// * Breaking blocks.
@@ -110,15 +140,133 @@ pub fn handle_player_block_placement(
 /// * Shooting arrows.
 /// * Eating.
 /// * Swapping items between the main and off hand.
-pub fn handle_player_digging(game: &mut Game, packet: PlayerDigging, _player: Entity) -> SysResult {
+///
+/// Creative players break blocks as soon as they start digging.
+/// Other players only break a block once they have dug it for
+/// long enough, and receive its drops.
+pub fn handle_player_digging(
+    game: &mut Game,
+    server: &mut Server,
+    packet: PlayerDigging,
+    player: Entity,
+) -> SysResult {
     log::trace!("Got player digging with status {:?}", packet.status);
-    match packet.status {
-        PlayerDiggingStatus::StartDigging | PlayerDiggingStatus::CancelDigging => {
-            game.break_block(packet.position);
-            Ok(())
+    let (status, successful) = match packet.status {
+        PlayerDiggingStatus::StartDigging => (
+            DiggingAcknowledgement::Started,
+            start_digging(game, packet.position, player)?,
+        ),
+        PlayerDiggingStatus::CancelDigging => {
+            let _ = game.ecs.remove::<Digging>(player);
+            (DiggingAcknowledgement::Cancelled, true)
         }
-        _ => Ok(()),
+        PlayerDiggingStatus::FinishDigging => (
+            DiggingAcknowledgement::Finished,
+            finish_digging(game, packet.position, player)?,
+        ),
+        PlayerDiggingStatus::DropItemStack | PlayerDiggingStatus::DropItem
+            if is_spectator(game, player)? =>
+        {
+            return Ok(())
+        }
+        PlayerDiggingStatus::DropItemStack => return drop_held_item(game, player, true),
+        PlayerDiggingStatus::DropItem => return drop_held_item(game, player, false),
+        _ => return Ok(()),
+    };
+
+    // On failure, the acknowledged block
+    // undoes the client's prediction.
+    if let Some(block) = game.block(packet.position) {
+        let client_id = *game.ecs.get::<ClientId>(player)?;
+        if let Some(client) = server.clients.get(client_id) {
+            client.acknowledge_digging(packet.position, block, status, successful);
+        }
+    }
+    Ok(())
+}
+
+fn start_digging(game: &mut Game, position: BlockPosition, player: Entity) -> SysResult<bool> {
+    let _ = game.ecs.remove::<Digging>(player);
+    let block = match game.block(position) {
+        Some(block) if !block.is_air() && can_reach(game, position, player)? => block,
+        _ => return Ok(false),
+    };
+
+    match *game.ecs.get::<Gamemode>(player)? {
+        Gamemode::Creative => Ok(game.break_block(position)),
+        Gamemode::Survival => match digging::player_break_ticks(game, player, block)? {
+            Some(0) => break_block(game, position, block, player),
+            Some(_) => {
+                game.ecs.insert(
+                    player,
+                    Digging {
+                        position,
+                        start_tick: game.tick_count,
+                    },
+                )?;
+                Ok(true)
+            }
+            None => Ok(false),
+        },
+        Gamemode::Adventure | Gamemode::Spectator => Ok(false),
+    }
+}
+
+fn finish_digging(game: &mut Game, position: BlockPosition, player: Entity) -> SysResult<bool> {
+    let digging = match game.ecs.remove::<Digging>(player) {
+        Ok(digging) if digging.position == position => digging,
+        _ => return Ok(false),
+    };
+    let block = match game.block(position) {
+        Some(block) if !block.is_air() && can_reach(game, position, player)? => block,
+        _ => return Ok(false),
+    };
+
+    let ticks = match digging::player_break_ticks(game, player, block)? {
+        Some(ticks) => ticks,
+        None => return Ok(false),
+    };
+    let elapsed = game.tick_count.saturating_sub(digging.start_tick) + 1;
+    if (elapsed as f32) < ticks as f32 * BREAK_TIME_TOLERANCE {
+        log::debug!(
+            "Player finished digging after {} ticks, expected {}",
+            elapsed,
+            ticks
+        );
+        return Ok(false);
+    }
+
+    break_block(game, position, block, player)
+}
+
+/// Breaks a block dug by a player and drops its item.
+fn break_block(
+    game: &mut Game,
+    position: BlockPosition,
+    block: BlockId,
+    player: Entity,
+) -> SysResult<bool> {
+    if !game.break_block(position) {
+        return Ok(false);
     }
+    let tool = held_item(game, player)?;
+    digging::drop_block_item(game, position, block, tool.as_ref());
+    Ok(true)
+}
+
+fn is_spectator(game: &Game, player: Entity) -> SysResult<bool> {
+    Ok(*game.ecs.get::<Gamemode>(player)? == Gamemode::Spectator)
+}
+
+fn can_reach(game: &Game, position: BlockPosition, player: Entity) -> SysResult<bool> {
+    let player_position = *game.ecs.get::<Position>(player)?;
+    let eyes = player_position.vec() + Vec3d::new(0., PLAYER_EYE_HEIGHT, 0.);
+    let center = Vec3d::new(
+        position.x as f64 + 0.5,
+        position.y as f64 + 0.5,
+        position.z as f64 + 0.5,
+    );
+    Ok(eyes.distance_squared(center) <= MAX_DIG_DISTANCE_SQUARED)
 }
 
 pub fn handle_interact_entity(
@@ -150,16 +298,21 @@ pub fn handle_interact_entity(
         }
     };
 
+    // Spectators cannot interact with or attack entities.
+    if is_spectator(game, player)? {
+        return Ok(());
+    }
+
     let event = match packet.kind {
         InteractEntityKind::Attack => InteractEntityEvent {
-            target: EntityId(target.id() as u64),
+            target: EntityId(target.to_bits()),
             ty: InteractionType::Attack,
             target_pos: None,
             hand: None,
             sneaking: packet.sneaking,
         },
         InteractEntityKind::Interact => InteractEntityEvent {
-            target: EntityId(target.id() as u64),
+            target: EntityId(target.to_bits()),
             ty: InteractionType::Interact,
             target_pos: None,
             hand: None,
@@ -178,8 +331,8 @@ pub fn handle_interact_entity(
             };
 
             InteractEntityEvent {
-                target: EntityId(target.id() as u64),
-                ty: InteractionType::Attack,
+                target: EntityId(target.to_bits()),
+                ty: InteractionType::InteractAt,
                 target_pos: Some(Vec3f::new(
                     target_x as f32,
                     target_y as f32,
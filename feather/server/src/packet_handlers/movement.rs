@@ -1,12 +1,16 @@
-use base::Position;
-use common::Game;
+use base::{Gamemode, Position};
+use common::{
+    health::Dead,
+    movement::{self, Gliding},
+    Game,
+};
 use ecs::{Entity, EntityRef, SysResult};
 use protocol::packets::client::{
     PlayerAbilities, PlayerMovement, PlayerPosition, PlayerPositionAndRotation, PlayerRotation,
 };
 use quill_common::{
-    components::{CreativeFlying, OnGround},
-    events::CreativeFlyingEvent,
+    components::{CreativeFlying, Name, OnGround},
+    events::{CreativeFlyingEvent, MovementViolation, MovementViolationEvent},
 };
 
 use crate::{ClientId, Server};
@@ -36,55 +40,92 @@ pub fn handle_player_movement(player: EntityRef, packet: PlayerMovement) -> SysR
 }
 
 pub fn handle_player_position(
+    game: &mut Game,
     server: &Server,
-    player: EntityRef,
+    player: Entity,
     packet: PlayerPosition,
 ) -> SysResult {
-    if should_skip_movement(server, &player)? {
-        return Ok(());
-    }
-    let mut pos = player.get_mut::<Position>()?;
+    let mut pos = *game.ecs.get::<Position>(player)?;
     pos.x = packet.x;
     pos.y = packet.feet_y;
     pos.z = packet.z;
-    player.get_mut::<OnGround>()?.0 = packet.on_ground;
-    update_client_position(server, player, *pos)?;
-    Ok(())
+    move_player(game, server, player, pos, packet.on_ground)
 }
 
 pub fn handle_player_position_and_rotation(
+    game: &mut Game,
     server: &Server,
-    player: EntityRef,
+    player: Entity,
     packet: PlayerPositionAndRotation,
 ) -> SysResult {
-    if should_skip_movement(server, &player)? {
-        return Ok(());
-    }
-    let mut pos = player.get_mut::<Position>()?;
+    let mut pos = *game.ecs.get::<Position>(player)?;
     pos.x = packet.x;
     pos.y = packet.feet_y;
     pos.z = packet.z;
     pos.yaw = packet.yaw;
     pos.pitch = packet.pitch;
-    player.get_mut::<OnGround>()?.0 = packet.on_ground;
-    update_client_position(server, player, *pos)?;
-    Ok(())
+    move_player(game, server, player, pos, packet.on_ground)
 }
 
 pub fn handle_player_rotation(
+    game: &mut Game,
     server: &Server,
-    player: EntityRef,
+    player: Entity,
     packet: PlayerRotation,
 ) -> SysResult {
-    if should_skip_movement(server, &player)? {
-        return Ok(());
-    }
-    let mut pos = player.get_mut::<Position>()?;
+    let mut pos = *game.ecs.get::<Position>(player)?;
     pos.yaw = packet.yaw;
     pos.pitch = packet.pitch;
-    player.get_mut::<OnGround>()?.0 = packet.on_ground;
-    update_client_position(server, player, *pos)?;
-    Ok(())
+    move_player(game, server, player, pos, packet.on_ground)
+}
+
+/// Moves a player to the position sent by their client.
+///
+/// Moves breaking the rules in [`movement::validate_movement`]
+/// are rejected: the player is moved back to their previous position
+/// and `MovementViolationEvent` is triggered.
+fn move_player(
+    game: &mut Game,
+    server: &Server,
+    player: Entity,
+    pos: Position,
+    on_ground: bool,
+) -> SysResult {
+    // Dead players do not move until they respawn.
+    if game.ecs.get::<Dead>(player).is_ok()
+        || should_skip_movement(server, &game.ecs.entity(player)?)?
+    {
+        return Ok(());
+    }
+
+    if let Some(violation) = movement::validate_movement(game, player, pos)? {
+        log::debug!(
+            "Rejected movement of {} to {:?}: {:?}",
+            &**game.ecs.get::<Name>(player)?,
+            pos,
+            violation
+        );
+        let previous = *game.ecs.get::<Position>(player)?;
+        if let Some(client) = server.clients.get(*game.ecs.get::<ClientId>(player)?) {
+            client.update_own_position(previous);
+        }
+        game.ecs.insert_entity_event(
+            player,
+            MovementViolationEvent {
+                violation,
+                attempted: pos,
+            },
+        )?;
+        return Ok(());
+    }
+
+    *game.ecs.get_mut::<Position>(player)? = pos;
+    game.ecs.get_mut::<OnGround>(player)?.0 = on_ground;
+    if on_ground {
+        // Landing ends elytra flight.
+        let _ = game.ecs.remove::<Gliding>(player);
+    }
+    update_client_position(server, game.ecs.entity(player)?, pos)
 }
 
 fn update_client_position(server: &Server, player: EntityRef, pos: Position) -> SysResult {
@@ -98,10 +139,12 @@ fn update_client_position(server: &Server, player: EntityRef, pos: Position) ->
 /// start/stop flying (like in creative mode).
 pub fn handle_player_abilities(
     game: &mut Game,
+    server: &Server,
     player: Entity,
     packet: PlayerAbilities,
 ) -> SysResult {
     let flying = game.ecs.get_mut::<CreativeFlying>(player)?.0;
+    let gamemode = *game.ecs.get::<Gamemode>(player)?;
 
     match packet.flags {
         0 => {
@@ -117,6 +160,20 @@ pub fn handle_player_abilities(
                 game.ecs.get_mut::<CreativeFlying>(player)?.0 = false;
             }
         }
+        2 if !movement::can_fly(gamemode) => {
+            // Flying is not allowed. Make the client stop flying.
+            if let Some(client) = server.clients.get(*game.ecs.get::<ClientId>(player)?) {
+                client.send_abilities(gamemode, false);
+            }
+            let attempted = *game.ecs.get::<Position>(player)?;
+            game.ecs.insert_entity_event(
+                player,
+                MovementViolationEvent {
+                    violation: MovementViolation::Flight,
+                    attempted,
+                },
+            )?;
+        }
         2 => {
             // Flying started
             if !flying {
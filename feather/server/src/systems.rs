@@ -2,8 +2,14 @@
 
 mod block;
 mod chat;
+pub mod commands;
 mod entity;
+mod gamemode;
+pub mod health;
+mod inventory;
+mod level;
 mod particle;
+mod player_data;
 mod player_join;
 mod player_leave;
 mod plugin_message;
@@ -30,10 +36,16 @@ pub fn register(server: Server, game: &mut Game, systems: &mut SystemExecutor<Ga
     view::register(game, systems);
     crate::chunk_subscriptions::register(systems);
     player_leave::register(systems);
+    player_data::register(systems);
+    level::register(systems);
     tablist::register(systems);
     block::register(systems);
+    inventory::register(systems);
     entity::register(game, systems);
+    health::register(systems);
+    gamemode::register(systems);
     chat::register(game, systems);
+    commands::register(systems);
     particle::register(systems);
     plugin_message::register(systems);
 
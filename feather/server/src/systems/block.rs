@@ -12,10 +12,16 @@
 //! Feather is optimized for bulk block updates to cater to plugins
 //! like WorldEdit. This module chooses the optimal packet from
 //! the above three options to achieve ideal performance.
+//!
+//! Light changed as a result of block changes is sent
+//! separately with the `UpdateLight` packet.
 
 use ahash::AHashMap;
 use base::{chunk::SECTION_VOLUME, position, ChunkPosition, CHUNK_WIDTH};
-use common::{events::BlockChangeEvent, Game};
+use common::{
+    events::{BlockChangeEvent, LightUpdateEvent},
+    Game,
+};
 use ecs::{SysResult, SystemExecutor};
 
 use crate::Server;
@@ -23,7 +29,8 @@ use crate::Server;
 pub fn register(systems: &mut SystemExecutor<Game>) {
     systems
         .group::<Server>()
-        .add_system(broadcast_block_changes);
+        .add_system(broadcast_block_changes)
+        .add_system(broadcast_light_updates);
 }
 
 fn broadcast_block_changes(game: &mut Game, server: &mut Server) -> SysResult {
@@ -33,6 +40,18 @@ fn broadcast_block_changes(game: &mut Game, server: &mut Server) -> SysResult {
     Ok(())
 }
 
+fn broadcast_light_updates(game: &mut Game, server: &mut Server) -> SysResult {
+    for (_, event) in game.ecs.query::<&LightUpdateEvent>().iter() {
+        let position = position!(
+            (event.position.x * CHUNK_WIDTH as i32) as f64,
+            0.0,
+            (event.position.z * CHUNK_WIDTH as i32) as f64,
+        );
+        server.broadcast_nearby_with(position, |client| client.send_light_update(&event.chunk));
+    }
+    Ok(())
+}
+
 /// Threshold at which to switch from block change to chunk
 // overwrite packets.
 const CHUNK_OVERWRITE_THRESHOLD: usize = SECTION_VOLUME / 2;
@@ -0,0 +1,220 @@
+//! Sends the command tree to clients and answers
+//! tab completion requests.
+
+use common::{
+    commands::{self, ArgumentKind, CommandDispatcher, CommandNodeKind, ROOT_NODE},
+    events::{CommandsChangeEvent, PermissionLevelChangeEvent, PlayerJoinEvent},
+    Game,
+};
+use ecs::{Entity, SysResult, SystemExecutor};
+use protocol::packets::{
+    client,
+    server::{ArgumentParser, CommandNode, DeclareCommands, StringKind},
+};
+
+use crate::{ClientId, Server};
+
+pub fn register(systems: &mut SystemExecutor<Game>) {
+    systems
+        .group::<Server>()
+        .add_system(send_commands_on_join)
+        .add_system(send_commands_on_permission_level_change)
+        .add_system(send_commands_on_change);
+}
+
+fn send_commands_on_join(game: &mut Game, server: &mut Server) -> SysResult {
+    let joined: Vec<Entity> = game
+        .ecs
+        .query::<&PlayerJoinEvent>()
+        .iter()
+        .map(|(player, _)| player)
+        .collect();
+    for player in joined {
+        send_commands(game, server, player)?;
+    }
+    Ok(())
+}
+
+fn send_commands_on_permission_level_change(game: &mut Game, server: &mut Server) -> SysResult {
+    let changed: Vec<Entity> = game
+        .ecs
+        .query::<(&PermissionLevelChangeEvent, &ClientId)>()
+        .iter()
+        .map(|(player, _)| player)
+        .collect();
+    for player in changed {
+        send_commands(game, server, player)?;
+    }
+    Ok(())
+}
+
+/// Resends the commands to all players
+/// after they were changed by a plugin.
+fn send_commands_on_change(game: &mut Game, server: &mut Server) -> SysResult {
+    if game
+        .ecs
+        .query::<&CommandsChangeEvent>()
+        .iter()
+        .next()
+        .is_none()
+    {
+        return Ok(());
+    }
+
+    let players: Vec<Entity> = game
+        .ecs
+        .query::<&ClientId>()
+        .iter()
+        .map(|(player, _)| player)
+        .collect();
+    for player in players {
+        send_commands(game, server, player)?;
+    }
+    Ok(())
+}
+
+/// Sends the commands available to a player.
+///
+/// Should be called again whenever the player's
+/// permission level changes.
+pub fn send_commands(game: &Game, server: &Server, player: Entity) -> SysResult {
+    let client_id = *game.ecs.get::<ClientId>(player)?;
+    let level = commands::permission_level(game, player);
+    let packet = declare_commands(&*game.resources.get::<CommandDispatcher>()?, level);
+    if let Some(client) = server.clients.get(client_id) {
+        client.send_declare_commands(packet);
+    }
+    Ok(())
+}
+
+/// Answers a tab completion request.
+pub fn handle_tab_complete(
+    game: &Game,
+    server: &Server,
+    player: Entity,
+    packet: client::TabComplete,
+) -> SysResult {
+    let text = packet.text.strip_prefix('/').unwrap_or(&packet.text);
+    let offset = packet.text.len() - text.len();
+
+    let suggestions = game
+        .resources
+        .get::<CommandDispatcher>()?
+        .suggest(game, player, text);
+
+    // The protocol counts UTF-16 code units rather than bytes.
+    let start = utf16_len(&packet.text[..offset + suggestions.start]);
+    let length = utf16_len(&text[suggestions.start..]);
+
+    let client_id = *game.ecs.get::<ClientId>(player)?;
+    if let Some(client) = server.clients.get(client_id) {
+        client.send_tab_complete(packet.transaction_id, start, length, suggestions.matches);
+    }
+    Ok(())
+}
+
+fn utf16_len(s: &str) -> usize {
+    s.encode_utf16().count()
+}
+
+/// Converts the nodes of a dispatcher usable at the
+/// given permission level into a `DeclareCommands` packet.
+fn declare_commands(dispatcher: &CommandDispatcher, level: u8) -> DeclareCommands {
+    let mut nodes = Vec::new();
+    let root_index = add_node(dispatcher, ROOT_NODE, level, &mut nodes);
+    DeclareCommands { nodes, root_index }
+}
+
+fn add_node(
+    dispatcher: &CommandDispatcher,
+    index: usize,
+    level: u8,
+    nodes: &mut Vec<CommandNode>,
+) -> i32 {
+    let node = dispatcher.node(index);
+    let packet_index = nodes.len();
+    nodes.push(CommandNode {
+        kind: node_kind(node.kind()),
+        executable: node.is_executable(),
+        children: Vec::new(),
+        redirect_node: None,
+    });
+
+    let children = node
+        .children()
+        .iter()
+        .copied()
+        .filter(|&child| dispatcher.node(child).permission_level() <= level)
+        .map(|child| add_node(dispatcher, child, level, nodes))
+        .collect();
+    nodes[packet_index].children = children;
+
+    packet_index as i32
+}
+
+fn node_kind(kind: &CommandNodeKind) -> protocol::packets::server::CommandNodeKind {
+    use protocol::packets::server::CommandNodeKind as Kind;
+    match kind {
+        CommandNodeKind::Root => Kind::Root,
+        CommandNodeKind::Literal(name) => Kind::Literal { name: name.clone() },
+        CommandNodeKind::Argument { name, kind } => {
+            let (parser, suggestions_type) = argument_parser(kind);
+            Kind::Argument {
+                name: name.clone(),
+                parser,
+                suggestions_type: suggestions_type.map(str::to_owned),
+            }
+        }
+    }
+}
+
+/// Returns the parser for an argument, along with
+/// the suggestions provider if the client can't
+/// suggest values for the parser itself.
+fn argument_parser(kind: &ArgumentKind) -> (ArgumentParser, Option<&'static str>) {
+    match kind {
+        ArgumentKind::Bool => (ArgumentParser::Bool, None),
+        ArgumentKind::Integer { min, max } => (
+            ArgumentParser::Integer {
+                min: *min,
+                max: *max,
+            },
+            None,
+        ),
+        ArgumentKind::Double { min, max } => (
+            ArgumentParser::Double {
+                min: *min,
+                max: *max,
+            },
+            None,
+        ),
+        ArgumentKind::Word => (ArgumentParser::String(StringKind::SingleWord), None),
+        ArgumentKind::String => (ArgumentParser::String(StringKind::QuotablePhrase), None),
+        ArgumentKind::GreedyString => (ArgumentParser::String(StringKind::GreedyPhrase), None),
+        ArgumentKind::Entities {
+            single,
+            players_only,
+        } => (
+            ArgumentParser::Entity {
+                single: *single,
+                only_players: *players_only,
+            },
+            None,
+        ),
+        ArgumentKind::Coordinates => (ArgumentParser::Other("minecraft:vec3".to_owned()), None),
+        ArgumentKind::BlockPosition => (
+            ArgumentParser::Other("minecraft:block_pos".to_owned()),
+            None,
+        ),
+        ArgumentKind::Gamemode => (
+            ArgumentParser::String(StringKind::SingleWord),
+            Some("minecraft:ask_server"),
+        ),
+        ArgumentKind::Message => (ArgumentParser::Other("minecraft:message".to_owned()), None),
+        ArgumentKind::Item => (
+            ArgumentParser::Other("minecraft:item_stack".to_owned()),
+            None,
+        ),
+        ArgumentKind::Time => (ArgumentParser::Other("minecraft:time".to_owned()), None),
+    }
+}
@@ -2,17 +2,35 @@
 //! Spawn packets, position updates, equipment, animations, etc.
 
 use base::Position;
-use common::Game;
+use common::{events::EntityDamageEvent, health::DamageCause, Game};
 use ecs::{SysResult, SystemExecutor};
-use quill_common::components::OnGround;
+use quill_common::components::{OnGround, Velocity};
 
-use crate::{entities::PreviousPosition, NetworkId, Server};
+use crate::{
+    client::protocol_velocity,
+    entities::{PreviousPosition, PreviousVelocity},
+    NetworkId, Server,
+};
 
+mod item;
+mod metadata;
 mod spawn_packet;
 
+/// The interval in ticks between resending the absolute
+/// positions of entities moved with relative moves.
+const POSITION_RESYNC_INTERVAL: u64 = 400;
+
 pub fn register(game: &mut Game, systems: &mut SystemExecutor<Game>) {
+    item::register(systems);
+    metadata::register(game, systems);
+    // Movement is sent before spawn packets, so that clients
+    // receiving an entity's spawn packet do not apply its
+    // relative moves on top of the spawn position.
+    systems
+        .group::<Server>()
+        .add_system(send_entity_movement)
+        .add_system(send_entity_velocity);
     spawn_packet::register(game, systems);
-    systems.group::<Server>().add_system(send_entity_movement);
 }
 
 /// Sends entity movement packets.
@@ -22,12 +40,59 @@ fn send_entity_movement(game: &mut Game, server: &mut Server) -> SysResult {
         .query::<(&Position, &mut PreviousPosition, &OnGround, &NetworkId)>()
         .iter()
     {
-        if position != prev_position.0 {
+        // Resyncs are spread over the interval by network ID.
+        let resync = (game.tick_count + network_id.0 as u64) % POSITION_RESYNC_INTERVAL == 0;
+        if position != prev_position.0 || resync {
             server.broadcast_nearby_with(position, |client| {
-                client.update_entity_position(network_id, position, on_ground);
+                client.update_entity_position(
+                    network_id,
+                    position,
+                    prev_position.0,
+                    on_ground,
+                    resync,
+                );
             });
             prev_position.0 = position;
         }
     }
     Ok(())
 }
+
+/// Sends entity velocity packets, so that clients
+/// can predict the motion of entities.
+///
+/// Velocity is also sent when an entity is attacked,
+/// since knockback may repeat the previous velocity of
+/// entities which move on the client, such as players.
+fn send_entity_velocity(game: &mut Game, server: &mut Server) -> SysResult {
+    for (_, (&position, &velocity, prev_velocity, &network_id, damage)) in game
+        .ecs
+        .query::<(
+            &Position,
+            &Velocity,
+            &mut PreviousVelocity,
+            &NetworkId,
+            Option<&EntityDamageEvent>,
+        )>()
+        .iter()
+    {
+        let knocked_back = damage.map_or(false, |damage| {
+            matches!(damage.cause, DamageCause::Attack(_))
+        });
+        if !same_protocol_velocity(velocity, prev_velocity.0) || knocked_back {
+            server.broadcast_nearby_with(position, |client| {
+                client.update_entity_velocity(network_id, velocity);
+            });
+            prev_velocity.0 = velocity;
+        }
+    }
+    Ok(())
+}
+
+/// Returns whether two velocities are sent to clients as the same value.
+/// Changes too small to show up in packets are accumulated until they do.
+fn same_protocol_velocity(a: Velocity, b: Velocity) -> bool {
+    protocol_velocity(a.x) == protocol_velocity(b.x)
+        && protocol_velocity(a.y) == protocol_velocity(b.y)
+        && protocol_velocity(a.z) == protocol_velocity(b.z)
+}
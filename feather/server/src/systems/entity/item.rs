@@ -0,0 +1,48 @@
+//! Sends item entity pickups and stack changes.
+
+use base::{ItemStack, Position};
+use common::{
+    events::{ItemPickupEvent, ItemStackChangeEvent},
+    Game,
+};
+use ecs::{SysResult, SystemExecutor};
+
+use crate::{NetworkId, Server};
+
+pub fn register(systems: &mut SystemExecutor<Game>) {
+    systems
+        .group::<Server>()
+        .add_system(send_item_pickups)
+        .add_system(send_item_stack_changes);
+}
+
+/// Plays the pickup animation for collected items.
+///
+/// This runs before the item entity is unloaded
+/// so that clients can still animate it.
+fn send_item_pickups(game: &mut Game, server: &mut Server) -> SysResult {
+    for (_, (event, &network_id, &position)) in game
+        .ecs
+        .query::<(&ItemPickupEvent, &NetworkId, &Position)>()
+        .iter()
+    {
+        let collector = *game.ecs.get::<NetworkId>(event.collector)?;
+        server.broadcast_nearby_with(position, |client| {
+            client.collect_item(network_id, collector, event.count);
+        });
+    }
+    Ok(())
+}
+
+fn send_item_stack_changes(game: &mut Game, server: &mut Server) -> SysResult {
+    for (_, (_, stack, &network_id, &position)) in game
+        .ecs
+        .query::<(&ItemStackChangeEvent, &ItemStack, &NetworkId, &Position)>()
+        .iter()
+    {
+        server.broadcast_nearby_with(position, |client| {
+            client.update_item_entity(network_id, stack);
+        });
+    }
+    Ok(())
+}
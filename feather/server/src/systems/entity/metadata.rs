@@ -0,0 +1,56 @@
+//! Sends entity metadata to clients when the
+//! components it is derived from change.
+
+use base::{EntityMetadata, Position};
+use common::Game;
+use ecs::{SysResult, SystemExecutor};
+use quill_common::components::{CustomName, Glowing, Invisible, Sneaking, Sprinting};
+
+use crate::{
+    entities::{metadata, MetadataComponents},
+    NetworkId, Server,
+};
+
+pub fn register(game: &mut Game, systems: &mut SystemExecutor<Game>) {
+    game.ecs.track_component::<Sneaking>();
+    game.ecs.track_component::<Sprinting>();
+    game.ecs.track_component::<Invisible>();
+    game.ecs.track_component::<Glowing>();
+    game.ecs.track_component::<CustomName>();
+
+    systems.group::<Server>().add_system(send_metadata_changes);
+}
+
+fn send_metadata_changes(game: &mut Game, server: &mut Server) -> SysResult {
+    let mut changes = Vec::new();
+    collect_changes::<Sneaking>(game, &mut changes);
+    collect_changes::<Sprinting>(game, &mut changes);
+    collect_changes::<Invisible>(game, &mut changes);
+    collect_changes::<Glowing>(game, &mut changes);
+    collect_changes::<CustomName>(game, &mut changes);
+
+    for (network_id, position, metadata) in changes {
+        server.broadcast_nearby_with(position, |client| {
+            // Clients predict their own player's metadata.
+            if client.network_id() != network_id {
+                client.send_entity_metadata(network_id, metadata.clone());
+            }
+        });
+    }
+    Ok(())
+}
+
+/// Derives the new metadata of entities whose `T` changed.
+fn collect_changes<T: Send + Sync + 'static>(
+    game: &Game,
+    changes: &mut Vec<(NetworkId, Position, EntityMetadata)>,
+) {
+    game.ecs
+        .for_each_changed::<T, (&NetworkId, &Position, MetadataComponents)>(
+            |_, (&network_id, &position, components)| {
+                if !changes.iter().any(|(changed, _, _)| *changed == network_id) {
+                    changes.push((network_id, position, metadata(components)));
+                }
+            },
+        );
+}
@@ -116,7 +116,11 @@ fn update_entities_on_chunk_cross(game: &mut Game, server: &mut Server) -> SysRe
         let entity_ref = game.ecs.entity(entity)?;
         for send_client in new_clients.difference(&old_clients) {
             if let Some(client) = server.clients.get(*send_client) {
-                spawn_packet.send(&entity_ref, client)?;
+                // The entity may already have been sent, e.g. to
+                // players near a respawned player.
+                if !client.is_entity_loaded(network_id) {
+                    spawn_packet.send(&entity_ref, client)?;
+                }
             }
         }
     }
@@ -0,0 +1,27 @@
+//! Sends gamemode changes to players.
+
+use common::Game;
+use ecs::{SysResult, SystemExecutor};
+use quill_common::{components::CreativeFlying, events::GamemodeChangeEvent};
+
+use crate::{ClientId, Server};
+
+pub fn register(systems: &mut SystemExecutor<Game>) {
+    systems.group::<Server>().add_system(send_gamemode_changes);
+}
+
+/// Sends players their new gamemode
+/// and the abilities that come with it.
+fn send_gamemode_changes(game: &mut Game, server: &mut Server) -> SysResult {
+    for (_, (event, &client_id, flying)) in game
+        .ecs
+        .query::<(&GamemodeChangeEvent, &ClientId, &CreativeFlying)>()
+        .iter()
+    {
+        if let Some(client) = server.clients.get(client_id) {
+            client.send_gamemode(event.gamemode);
+            client.send_abilities(event.gamemode, flying.0);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,145 @@
+//! Sends health, damage, death and respawn packets.
+
+use base::{Gamemode, Position};
+use common::{
+    entities::player::HotbarSlot,
+    events::{EntityDamageEvent, EntityDeathEvent, HealthUpdateEvent, PlayerRespawnEvent},
+    health::{self, DamageCause, Food, Health},
+    view::View,
+    Game, Window,
+};
+use ecs::{Entity, SysResult, SystemExecutor};
+
+use crate::{entities::SpawnPacketSender, ClientId, NetworkId, Server};
+
+use super::level;
+
+/// Entity status playing the animation and sound of taking damage.
+const STATUS_HURT: i8 = 2;
+
+/// Entity status playing the death animation.
+const STATUS_DEATH: i8 = 3;
+
+pub fn register(systems: &mut SystemExecutor<Game>) {
+    systems
+        .group::<Server>()
+        .add_system(send_damage_animations)
+        .add_system(send_deaths)
+        .add_system(send_respawns)
+        .add_system(send_health_updates);
+}
+
+/// Respawns a dead player at the world spawn
+/// after they clicked the respawn button.
+pub fn respawn_player(game: &mut Game, player: Entity) -> SysResult {
+    let position = level::spawn_position(game);
+    health::respawn(game, player, position)
+}
+
+fn send_damage_animations(game: &mut Game, server: &mut Server) -> SysResult {
+    for (_, (_event, &position, &network_id)) in game
+        .ecs
+        .query::<(&EntityDamageEvent, &Position, &NetworkId)>()
+        .iter()
+    {
+        server.broadcast_nearby_with(position, |client| {
+            client.send_entity_status(network_id, STATUS_HURT)
+        });
+    }
+    Ok(())
+}
+
+/// Plays the death animation and shows
+/// dead players the respawn screen.
+fn send_deaths(game: &mut Game, server: &mut Server) -> SysResult {
+    for (_, (event, &position, &network_id, client_id)) in game
+        .ecs
+        .query::<(&EntityDeathEvent, &Position, &NetworkId, Option<&ClientId>)>()
+        .iter()
+    {
+        server.broadcast_nearby_with(position, |client| {
+            client.send_entity_status(network_id, STATUS_DEATH)
+        });
+
+        let killer = match event.cause {
+            DamageCause::Attack(attacker) => game.ecs.get::<NetworkId>(attacker).ok().map(|id| *id),
+            _ => None,
+        };
+        if let Some(client) = client_id.and_then(|&id| server.clients.get(id)) {
+            client.send_death(network_id, killer, &event.message);
+        }
+    }
+    Ok(())
+}
+
+/// Sends respawned players back into the world and
+/// replaces their corpse on other clients.
+fn send_respawns(game: &mut Game, server: &mut Server) -> SysResult {
+    for (
+        player,
+        (_event, &client_id, &gamemode, &position, window, hotbar_slot, &network_id, &view),
+    ) in game
+        .ecs
+        .query::<(
+            &PlayerRespawnEvent,
+            &ClientId,
+            &Gamemode,
+            &Position,
+            &Window,
+            &HotbarSlot,
+            &NetworkId,
+            &View,
+        )>()
+        .iter()
+    {
+        if let Some(client) = server.clients.get(client_id) {
+            client.send_respawn(gamemode);
+            client.update_own_position(position);
+            client.send_abilities(gamemode, false);
+            client.send_window_items(window);
+            client.send_hotbar_slot(hotbar_slot.get());
+
+            // The client removed all entities when respawning.
+            for chunk in view.iter() {
+                for &entity in game.chunk_entities.entities_in_chunk(chunk) {
+                    if entity == player {
+                        continue;
+                    }
+                    let entity_ref = game.ecs.entity(entity)?;
+                    if let Ok(spawn_packet) = entity_ref.get::<SpawnPacketSender>() {
+                        spawn_packet.send(&entity_ref, client)?;
+                    }
+                }
+            }
+        }
+
+        server.broadcast_with(|client| {
+            if client.network_id() != network_id && client.is_entity_loaded(network_id) {
+                client.unload_entity(network_id);
+            }
+        });
+        let entity_ref = game.ecs.entity(player)?;
+        let spawn_packet = entity_ref.get::<SpawnPacketSender>()?;
+        server.broadcast_nearby_with(position, |client| {
+            if client.network_id() != network_id {
+                spawn_packet
+                    .send(&entity_ref, client)
+                    .expect("failed to send spawn packet")
+            }
+        });
+    }
+    Ok(())
+}
+
+fn send_health_updates(game: &mut Game, server: &mut Server) -> SysResult {
+    for (_, (_event, &client_id, health, food)) in game
+        .ecs
+        .query::<(&HealthUpdateEvent, &ClientId, &Health, &Food)>()
+        .iter()
+    {
+        if let Some(client) = server.clients.get(client_id) {
+            client.update_health(health.0, food.level, food.saturation);
+        }
+    }
+    Ok(())
+}
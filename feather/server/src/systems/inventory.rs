@@ -0,0 +1,25 @@
+//! Sends inventory contents changed by the server.
+
+use common::{events::InventoryUpdateEvent, Game, Window};
+use ecs::{SysResult, SystemExecutor};
+
+use crate::{ClientId, Server};
+
+pub fn register(systems: &mut SystemExecutor<Game>) {
+    systems
+        .group::<Server>()
+        .add_system(send_updated_inventories);
+}
+
+fn send_updated_inventories(game: &mut Game, server: &mut Server) -> SysResult {
+    for (_, (&client_id, window, _)) in game
+        .ecs
+        .query::<(&ClientId, &Window, &InventoryUpdateEvent)>()
+        .iter()
+    {
+        if let Some(client) = server.clients.get(client_id) {
+            client.send_window_items(window);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,78 @@
+//! Sends and saves the global world data stored in `level.dat`.
+
+use base::{anvil::level, BlockPosition, Position, TPS};
+use common::{
+    events::{AutosaveEvent, ShutdownEvent, WeatherChangeEvent},
+    Game,
+};
+use ecs::{SysResult, SystemExecutor};
+
+use crate::{Client, Server};
+
+pub fn register(systems: &mut SystemExecutor<Game>) {
+    systems
+        .group::<Server>()
+        .add_system(broadcast_time)
+        .add_system(broadcast_weather)
+        .add_system(save_level);
+}
+
+/// Periodically sends the world time to all players
+/// to keep their clocks in sync.
+fn broadcast_time(game: &mut Game, server: &mut Server) -> SysResult {
+    if game.tick_count % TPS as u64 != 0 {
+        return Ok(());
+    }
+
+    let level = game.world.level();
+    server.broadcast_with(|client| client.send_time(level.time, level.day_time));
+    Ok(())
+}
+
+fn broadcast_weather(game: &mut Game, server: &mut Server) -> SysResult {
+    for (_, event) in game.ecs.query::<&WeatherChangeEvent>().iter() {
+        server.broadcast_with(|client| client.send_weather(event.raining, event.thundering));
+    }
+    Ok(())
+}
+
+/// Saves `level.dat` on autosave and when the server stops.
+fn save_level(game: &mut Game, server: &mut Server) -> SysResult {
+    if game.ecs.query::<&AutosaveEvent>().iter().next().is_some()
+        || game.ecs.query::<&ShutdownEvent>().iter().next().is_some()
+    {
+        save_level_data(game, server);
+    }
+    Ok(())
+}
+
+/// Saves `level.dat`.
+pub fn save_level_data(game: &Game, server: &Server) {
+    if let Err(e) = level::save_level_data(&server.options.world_dir, game.world.level()) {
+        log::error!("Failed to save level.dat: {:?}", e);
+    }
+}
+
+/// Returns the position at which new players spawn.
+pub fn spawn_position(game: &Game) -> Position {
+    let level = game.world.level();
+    Position {
+        x: level.spawn_x as f64 + 0.5,
+        y: level.spawn_y as f64,
+        z: level.spawn_z as f64 + 0.5,
+        yaw: 0.0,
+        pitch: 0.0,
+    }
+}
+
+/// Sends the spawn point, time and weather to a joining player.
+pub fn send_level_state(game: &Game, client: &Client) {
+    let level = game.world.level();
+    client.send_spawn_position(BlockPosition::new(
+        level.spawn_x,
+        level.spawn_y,
+        level.spawn_z,
+    ));
+    client.send_time(level.time, level.day_time);
+    client.send_weather(level.raining, level.thundering);
+}
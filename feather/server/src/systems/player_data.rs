@@ -0,0 +1,131 @@
+//! Loads and saves player data files (`playerdata/<uuid>.dat`).
+
+use std::io::ErrorKind;
+
+use base::{
+    anvil::{
+        entity::BaseEntityData,
+        player::{self, InventorySlot, PlayerData},
+    },
+    Gamemode, Position, Vec3d,
+};
+use common::{
+    entities::player::HotbarSlot,
+    events::AutosaveEvent,
+    health::{Food, Health, MAX_FOOD, MAX_HEALTH},
+    Game, Window,
+};
+use ecs::{Entity, SysResult, SystemExecutor};
+use quill_common::components::Velocity;
+use uuid::Uuid;
+
+use crate::{ClientId, Server};
+
+pub fn register(systems: &mut SystemExecutor<Game>) {
+    systems
+        .group::<Server>()
+        .add_system(save_player_data_on_autosave);
+}
+
+fn save_player_data_on_autosave(game: &mut Game, server: &mut Server) -> SysResult {
+    if game.ecs.query::<&AutosaveEvent>().iter().next().is_none() {
+        return Ok(());
+    }
+
+    let players: Vec<Entity> = game
+        .ecs
+        .query::<&ClientId>()
+        .iter()
+        .map(|(player, _)| player)
+        .collect();
+    for player in players {
+        save_player_data(game, server, player)?;
+    }
+
+    Ok(())
+}
+
+/// Loads the data file of the player with the given UUID.
+///
+/// Returns `None` if the player has never joined
+/// or if the file could not be read.
+pub fn load_player_data(server: &Server, uuid: Uuid) -> Option<PlayerData> {
+    match player::load_player_data(&server.options.world_dir, uuid) {
+        Ok(data) => Some(data),
+        Err(nbt::Error::IoError(e)) if e.kind() == ErrorKind::NotFound => None,
+        Err(e) => {
+            log::error!("Failed to load player data for {}: {}", uuid, e);
+            None
+        }
+    }
+}
+
+/// Restores the inventory stored in `data` into a player's window.
+pub fn restore_inventory(data: &PlayerData, window: &Window) {
+    for slot in &data.inventory {
+        let index = match slot.convert_index() {
+            Some(index) => index,
+            None => {
+                log::warn!("Ignoring item in unknown inventory slot {}", slot.slot);
+                continue;
+            }
+        };
+        let _ = window.set_item(index, Some(slot.into()));
+    }
+}
+
+/// Returns the health and food stored in `data`.
+pub fn restore_health(data: &PlayerData) -> (Health, Food) {
+    let health = Health(data.animal.health.min(MAX_HEALTH));
+    let food = Food::new(
+        (data.food_level.max(0) as u32).min(MAX_FOOD),
+        data.food_saturation,
+        data.food_exhaustion,
+    );
+    (health, food)
+}
+
+/// Saves the data file of the given player.
+///
+/// Only the fields this server keeps track of are updated;
+/// everything else in an existing file is preserved.
+pub fn save_player_data(game: &Game, server: &Server, player: Entity) -> SysResult {
+    let uuid = *game.ecs.get::<Uuid>(player)?;
+    let position = *game.ecs.get::<Position>(player)?;
+    let gamemode = *game.ecs.get::<Gamemode>(player)?;
+    let hotbar_slot = *game.ecs.get::<HotbarSlot>(player)?;
+    let health = game.ecs.get::<Health>(player)?.0;
+    let food = *game.ecs.get::<Food>(player)?;
+    let window = game.ecs.get::<Window>(player)?;
+    let velocity = game
+        .ecs
+        .get::<Velocity>(player)
+        .map(|velocity| Vec3d::new(velocity.x, velocity.y, velocity.z))
+        .unwrap_or_default();
+
+    let inventory = window
+        .inner()
+        .to_vec()
+        .into_iter()
+        .enumerate()
+        .filter_map(|(index, item)| {
+            item.and_then(|stack| InventorySlot::from_network_index(index, stack))
+        })
+        .collect();
+
+    let mut data = load_player_data(server, uuid).unwrap_or_default();
+    data.animal.base = BaseEntityData::new(position, velocity);
+    data.animal.health = health;
+    data.gamemode = gamemode as i32;
+    data.inventory = inventory;
+    data.held_item = hotbar_slot.get() as i32;
+    data.food_level = food.level as i32;
+    data.food_saturation = food.saturation;
+    data.food_exhaustion = food.exhaustion;
+
+    if let Err(e) = player::save_player_data(&server.options.world_dir, uuid, &data) {
+        log::error!("Failed to save player data for {}: {:?}", uuid, e);
+    }
+
+    Ok(())
+}
@@ -1,15 +1,21 @@
-use base::{Inventory, Position, Text};
+use base::{Gamemode, Inventory, Text};
 use common::{
     chat::{ChatKind, ChatPreference},
+    commands::PermissionLevel,
     entities::player::HotbarSlot,
-    view::View,
+    view::{ClientViewDistance, View},
     window::BackingWindow,
     ChatBox, Game, Window,
 };
 use ecs::{SysResult, SystemExecutor};
-use quill_common::{components::Name, entity_init::EntityInit};
+use quill_common::{
+    components::{Name, ViewDistance},
+    entity_init::EntityInit,
+};
+
+use crate::{ClientId, ClientIp, Server};
 
-use crate::{ClientId, Server};
+use super::{level, player_data};
 
 pub fn register(systems: &mut SystemExecutor<Game>) {
     systems.group::<Server>().add_system(poll_new_players);
@@ -26,33 +32,65 @@ fn poll_new_players(game: &mut Game, server: &mut Server) -> SysResult {
 
 fn accept_new_player(game: &mut Game, server: &mut Server, client_id: ClientId) -> SysResult {
     let client = server.clients.get(client_id).unwrap();
-    client.send_join_game(server.options.default_gamemode);
+    let player_data = player_data::load_player_data(server, client.uuid());
+    // Players who left while dead respawn when they join again.
+    let alive_data = player_data.as_ref().filter(|data| data.animal.health > 0.0);
+
+    let position = alive_data
+        .and_then(|data| data.animal.base.read_position().ok())
+        .unwrap_or_else(|| level::spawn_position(game));
+    let gamemode = player_data
+        .as_ref()
+        .and_then(|data| Gamemode::from_id(data.gamemode as u8))
+        .unwrap_or(server.options.default_gamemode);
+    let hotbar_slot = player_data
+        .as_ref()
+        .map(|data| HotbarSlot::new(data.held_item as usize))
+        .filter(|slot| slot.get() <= 8)
+        .unwrap_or_else(HotbarSlot::default);
+
+    client.send_join_game(gamemode);
     client.send_brand();
+    level::send_level_state(game, client);
 
-    let mut builder = game.create_entity_builder(Position::default(), EntityInit::Player);
+    let mut builder = game.create_entity_builder(position, EntityInit::Player);
 
     let inventory = Inventory::player();
     let window = Window::new(BackingWindow::Player {
         player: inventory.new_handle(),
     });
+    if let Some(data) = &player_data {
+        player_data::restore_inventory(data, &window);
+    }
+
+    let (health, food) = alive_data
+        .map(player_data::restore_health)
+        .unwrap_or_default();
 
     client.send_window_items(&window);
+    client.send_hotbar_slot(hotbar_slot.get());
+    client.update_health(health.0, food.level, food.saturation);
 
     builder
         .add(client.network_id())
         .add(client_id)
-        .add(View::new(
-            Position::default().chunk(),
-            server.options.view_distance,
-        ))
-        .add(server.options.default_gamemode)
+        .add(View::new(position.chunk(), server.options.view_distance))
+        .add(ViewDistance(server.options.view_distance))
+        .add(ClientViewDistance(server.options.view_distance))
+        .add(gamemode)
         .add(Name::new(client.username()))
         .add(client.uuid())
+        .add(ClientIp(client.ip().to_owned()))
+        .add(PermissionLevel(
+            server.access_lists().permission_level(client.uuid()),
+        ))
         .add(client.profile().to_vec())
         .add(ChatBox::new(ChatPreference::All))
         .add(inventory)
         .add(window)
-        .add(HotbarSlot::default());
+        .add(hotbar_slot)
+        .add(health)
+        .add(food);
 
     game.spawn_entity(builder);
 
@@ -1,16 +1,43 @@
 use base::Text;
-use common::{chat::ChatKind, Game};
+use common::{
+    chat::ChatKind,
+    events::{PlayerKickEvent, PlayerLeaveEvent, ShutdownEvent},
+    Game,
+};
 use ecs::{SysResult, SystemExecutor};
 use quill_common::components::Name;
 
 use crate::{ClientId, Server};
 
+use super::player_data;
+
 pub fn register(systems: &mut SystemExecutor<Game>) {
     systems
         .group::<Server>()
+        .add_system(disconnect_kicked_players)
+        .add_system(disconnect_players_on_shutdown)
         .add_system(remove_disconnected_clients);
 }
 
+fn disconnect_kicked_players(game: &mut Game, server: &mut Server) -> SysResult {
+    for (_, (&client_id, event)) in game.ecs.query::<(&ClientId, &PlayerKickEvent)>().iter() {
+        if let Some(client) = server.clients.get(client_id) {
+            client.disconnect(event.reason.clone());
+        }
+    }
+    Ok(())
+}
+
+/// Disconnects all players when the server stops, so
+/// that their data is saved before the tick loop exits.
+fn disconnect_players_on_shutdown(game: &mut Game, server: &mut Server) -> SysResult {
+    if game.ecs.query::<&ShutdownEvent>().iter().next().is_some() {
+        let message = server.options.shutdown_message.clone();
+        server.broadcast_with(|client| client.disconnect(message.clone()));
+    }
+    Ok(())
+}
+
 fn remove_disconnected_clients(game: &mut Game, server: &mut Server) -> SysResult {
     let mut entities_to_remove = Vec::new();
     for (player, (&client_id, name)) in game.ecs.query::<(&ClientId, &Name)>().iter() {
@@ -23,6 +50,8 @@ fn remove_disconnected_clients(game: &mut Game, server: &mut Server) -> SysResul
     }
 
     for player in entities_to_remove {
+        player_data::save_player_data(game, server, player)?;
+        game.ecs.insert_entity_event(player, PlayerLeaveEvent)?;
         game.remove_entity(player)?;
     }
 
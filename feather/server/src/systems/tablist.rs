@@ -6,7 +6,7 @@ use common::{
     Game,
 };
 use ecs::{SysResult, SystemExecutor};
-use quill_common::{components::Name, entities::Player};
+use quill_common::{components::Name, entities::Player, events::GamemodeChangeEvent};
 use uuid::Uuid;
 
 use crate::{ClientId, Server};
@@ -15,7 +15,8 @@ pub fn register(systems: &mut SystemExecutor<Game>) {
     systems
         .group::<Server>()
         .add_system(remove_tablist_players)
-        .add_system(add_tablist_players);
+        .add_system(add_tablist_players)
+        .add_system(update_tablist_gamemodes);
 }
 
 fn remove_tablist_players(game: &mut Game, server: &mut Server) -> SysResult {
@@ -62,3 +63,10 @@ fn add_tablist_players(game: &mut Game, server: &mut Server) -> SysResult {
     }
     Ok(())
 }
+
+fn update_tablist_gamemodes(game: &mut Game, server: &mut Server) -> SysResult {
+    for (_, (event, &uuid)) in game.ecs.query::<(&GamemodeChangeEvent, &Uuid)>().iter() {
+        server.broadcast_with(|client| client.update_tablist_gamemode(uuid, event.gamemode));
+    }
+    Ok(())
+}
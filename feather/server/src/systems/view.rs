@@ -7,6 +7,7 @@ use ahash::AHashMap;
 use base::{ChunkPosition, Position};
 use common::{
     events::{ChunkLoadEvent, ViewUpdateEvent},
+    view::View,
     Game,
 };
 use ecs::{Entity, SysResult, SystemExecutor};
@@ -42,6 +43,11 @@ fn send_new_chunks(game: &mut Game, server: &mut Server) -> SysResult {
     {
         let client = server.clients.get(client_id).unwrap();
         client.update_own_chunk(event.new_view.center());
+        if !event.old_view.is_empty()
+            && event.old_view.view_distance() != event.new_view.view_distance()
+        {
+            client.send_view_distance(event.new_view.view_distance());
+        }
         update_chunks(
             game,
             player,
@@ -76,7 +82,7 @@ fn update_chunks(
         client.unload_chunk(pos);
     }
 
-    spawn_client_if_needed(client, position);
+    spawn_client_if_needed(client, event.new_view, position);
 
     Ok(())
 }
@@ -89,19 +95,31 @@ fn send_loaded_chunks(game: &mut Game, server: &mut Server) -> SysResult {
             .waiting_chunks
             .drain_players_waiting_for(event.position)
         {
-            if let Ok(client_id) = game.ecs.get::<ClientId>(player) {
-                if let Some(client) = server.clients.get(*client_id) {
-                    client.send_chunk(&event.chunk);
-                    spawn_client_if_needed(client, *game.ecs.get::<Position>(player)?);
-                }
+            let (client_id, view) = match (
+                game.ecs.get::<ClientId>(player),
+                game.ecs.get::<View>(player),
+            ) {
+                (Ok(client_id), Ok(view)) => (*client_id, *view),
+                _ => continue,
+            };
+            // The player's view may have moved away from the chunk.
+            if !view.contains(event.position) {
+                continue;
+            }
+            if let Some(client) = server.clients.get(client_id) {
+                client.send_chunk(&event.chunk);
+                spawn_client_if_needed(client, view, *game.ecs.get::<Position>(player)?);
             }
         }
     }
     Ok(())
 }
 
-fn spawn_client_if_needed(client: &Client, pos: Position) {
-    if !client.knows_own_position() && client.known_chunks() >= 9 * 9 {
+/// Spawns the client once the chunks around it
+/// (up to 9x9 of them) have been sent.
+fn spawn_client_if_needed(client: &Client, view: View, pos: Position) {
+    let chunks_needed = (2 * view.view_distance().min(4) as usize + 1).pow(2);
+    if !client.knows_own_position() && client.known_chunks() >= chunks_needed {
         log::debug!("Sent all chunks to {}; now spawning", client.username());
         client.update_own_position(pos);
     }
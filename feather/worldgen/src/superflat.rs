@@ -8,7 +8,8 @@ pub struct SuperflatWorldGenerator {
 
 impl WorldGenerator for SuperflatWorldGenerator {
     fn generate_chunk(&self, position: ChunkPosition) -> Chunk {
-        let biome = Biome::from_name(self.options.biome.as_str()).unwrap_or(Biome::Plains);
+        let biome_name = self.options.biome.trim_start_matches("minecraft:");
+        let biome = Biome::from_name(biome_name).unwrap_or(Biome::Plains);
         let mut chunk = Chunk::new_with_default_biome(position, biome);
 
         let mut y_counter = 0;
@@ -1,8 +1,8 @@
 use crate::data::{RawBlockStateProperties, ValidProperties};
 use libcraft_core::block::{
     AttachedFace, Axis, BambooLeaves, BedPart, BellAttachment, BlockFace, BlockHalf, ChestType,
-    ComparatorMode, Instrument, Orientation, PistonType, RailShape, SlabType, StairShape,
-    StructureBlockMode, WallConnection,
+    ComparatorMode, Instrument, Orientation, PistonType, RailShape, SlabType, StairHalf,
+    StairShape, StructureBlockMode, WallConnection,
 };
 use libcraft_macros::BlockData;
 
@@ -483,7 +483,7 @@ pub struct Snow {
 
 #[derive(Debug, BlockData)]
 pub struct Stairs {
-    half: BlockHalf,
+    stair_half: StairHalf,
     facing: BlockFace,
     waterlogged: bool,
     stair_shape: StairShape,
@@ -519,7 +519,7 @@ pub struct Tnt {
 
 #[derive(Debug, BlockData)]
 pub struct TrapDoor {
-    half: BlockHalf,
+    stair_half: StairHalf,
     facing: BlockFace,
     open: bool,
     powered: bool,
@@ -26,6 +26,20 @@ pub struct BlockState {
 }
 
 impl BlockState {
+    /// Returns the default block state for the given block kind.
+    pub fn new(kind: BlockKind) -> Self {
+        let id = *REGISTRY
+            .default_states
+            .get(&kind)
+            .expect("block kind without default state");
+        Self { id }
+    }
+
+    /// Gets the kind of this block state.
+    pub fn kind(self) -> BlockKind {
+        self.raw().kind
+    }
+
     /// Gets this block as a struct implementing the [`BlockData`](crate::BlockData)
     /// interface.
     ///
@@ -99,6 +113,7 @@ struct BlockRegistry {
     states: Vec<RawBlockState>,
     id_mapping: AHashMap<RawBlockStateProperties, u16>,
     valid_properties: AHashMap<BlockKind, ValidProperties>,
+    default_states: AHashMap<BlockKind, u16>,
 }
 
 impl BlockRegistry {
@@ -126,6 +141,12 @@ impl BlockRegistry {
             .map(|state| (state.properties.clone(), state.id))
             .collect();
 
+        let default_states = states
+            .iter()
+            .filter(|state| state.default)
+            .map(|state| (state.kind, state.id))
+            .collect();
+
         let valid_properties = properties
             .iter()
             .map(|properties| (properties.kind, properties.valid_properties.clone()))
@@ -135,6 +156,7 @@ impl BlockRegistry {
             states,
             id_mapping,
             valid_properties,
+            default_states,
         }
     }
 
@@ -155,4 +177,11 @@ mod tests {
     fn block_registry_creates_successfully() {
         let _ = BlockRegistry::new();
     }
+
+    #[test]
+    fn default_state_has_kind() {
+        let state = BlockState::new(BlockKind::OakStairs);
+        assert!(state.is_default());
+        assert_eq!(state.kind(), BlockKind::OakStairs);
+    }
 }
@@ -97,3 +97,106 @@ pub enum EnchantmentKind {
     Thorns,
     Unbreaking,
 }
+
+impl EnchantmentKind {
+    /// Returns the namespaced ID of this enchantment,
+    /// as used in item NBT (e.g. `minecraft:efficiency`).
+    pub fn name(self) -> &'static str {
+        use EnchantmentKind::*;
+        match self {
+            AquaAffinity => "minecraft:aqua_affinity",
+            BaneOfArthropods => "minecraft:bane_of_arthropods",
+            BlastProtection => "minecraft:blast_protection",
+            Channeling => "minecraft:channeling",
+            Cleaving => "minecraft:cleaving",
+            CurseOfBinding => "minecraft:binding_curse",
+            CurseOfVanishing => "minecraft:vanishing_curse",
+            DepthStrider => "minecraft:depth_strider",
+            Efficiency => "minecraft:efficiency",
+            FeatherFalling => "minecraft:feather_falling",
+            FireAspect => "minecraft:fire_aspect",
+            FireProtection => "minecraft:fire_protection",
+            Flame => "minecraft:flame",
+            Fortune => "minecraft:fortune",
+            FrostWalker => "minecraft:frost_walker",
+            Impaling => "minecraft:impaling",
+            Infinity => "minecraft:infinity",
+            Knockback => "minecraft:knockback",
+            Looting => "minecraft:looting",
+            Loyalty => "minecraft:loyalty",
+            LuckOfTheSea => "minecraft:luck_of_the_sea",
+            Lure => "minecraft:lure",
+            Mending => "minecraft:mending",
+            Multishot => "minecraft:multishot",
+            Piercing => "minecraft:piercing",
+            Power => "minecraft:power",
+            ProjectileProtection => "minecraft:projectile_protection",
+            Protection => "minecraft:protection",
+            Punch => "minecraft:punch",
+            QuickCharge => "minecraft:quick_charge",
+            Respiration => "minecraft:respiration",
+            Riptide => "minecraft:riptide",
+            Sharpness => "minecraft:sharpness",
+            SilkTouch => "minecraft:silk_touch",
+            Smite => "minecraft:smite",
+            SoulSpeed => "minecraft:soul_speed",
+            SweepingEdge => "minecraft:sweeping",
+            Thorns => "minecraft:thorns",
+            Unbreaking => "minecraft:unbreaking",
+        }
+    }
+
+    /// Gets an enchantment from its ID. The
+    /// `minecraft:` namespace may be omitted.
+    pub fn from_name(name: &str) -> Option<Self> {
+        let name = name.strip_prefix("minecraft:").unwrap_or(name);
+        ALL.iter()
+            .copied()
+            .find(|kind| &kind.name()["minecraft:".len()..] == name)
+    }
+}
+
+const ALL: [EnchantmentKind; 39] = {
+    use EnchantmentKind::*;
+    [
+        AquaAffinity,
+        BaneOfArthropods,
+        BlastProtection,
+        Channeling,
+        Cleaving,
+        CurseOfBinding,
+        CurseOfVanishing,
+        DepthStrider,
+        Efficiency,
+        FeatherFalling,
+        FireAspect,
+        FireProtection,
+        Flame,
+        Fortune,
+        FrostWalker,
+        Impaling,
+        Infinity,
+        Knockback,
+        Looting,
+        Loyalty,
+        LuckOfTheSea,
+        Lure,
+        Mending,
+        Multishot,
+        Piercing,
+        Power,
+        ProjectileProtection,
+        Protection,
+        Punch,
+        QuickCharge,
+        Respiration,
+        Riptide,
+        Sharpness,
+        SilkTouch,
+        Smite,
+        SoulSpeed,
+        SweepingEdge,
+        Thorns,
+        Unbreaking,
+    ]
+};
@@ -0,0 +1,36 @@
+//! Systems and command executors passed to the host.
+
+use std::cell::RefCell;
+
+thread_local! {
+    /// Callbacks passed to the host, along with
+    /// the functions dropping them.
+    static CALLBACKS: RefCell<Vec<(*mut u8, unsafe fn(*mut u8))>> = RefCell::new(Vec::new());
+}
+
+/// Moves a callback to the heap, returning a pointer to it
+/// which stays valid until [`free_callbacks`] is called.
+pub(crate) fn register_callback<T>(callback: T) -> *mut u8 {
+    let ptr = Box::into_raw(Box::new(callback)).cast::<u8>();
+    CALLBACKS.with(|callbacks| callbacks.borrow_mut().push((ptr, drop_callback::<T>)));
+    ptr
+}
+
+unsafe fn drop_callback<T>(ptr: *mut u8) {
+    drop(Box::from_raw(ptr.cast::<T>()));
+}
+
+/// For Quill internal use only. Do not call.
+///
+/// Drops all callbacks, so that plugins reloaded
+/// by the host do not leak memory.
+///
+/// # Safety
+/// The host must not invoke any callback afterward.
+#[doc(hidden)]
+pub unsafe fn free_callbacks() {
+    let callbacks = CALLBACKS.with(|callbacks| std::mem::take(&mut *callbacks.borrow_mut()));
+    for (ptr, drop_fn) in callbacks {
+        drop_fn(ptr);
+    }
+}
@@ -0,0 +1,206 @@
+//! Commands which players can run from chat.
+
+use libcraft_core::{BlockPosition, Gamemode, Position};
+use quill_common::commands::{ArgumentValue, CommandInvocation, CommandNode, CommandNodeKind};
+
+pub use quill_common::commands::ArgumentKind;
+
+use crate::{callbacks, EntityId, Game};
+
+type CommandCallback<Plugin> = Box<dyn FnMut(&mut Plugin, &mut Game, CommandContext)>;
+
+/// A node in a command tree.
+///
+/// Register a command with [`crate::Setup::register_command`].
+///
+/// # Example
+/// ```no_run
+/// use quill::{commands::{ArgumentKind, Command}, Game};
+/// # struct MyPlugin;
+/// let command = Command::<MyPlugin>::literal("join").then(
+///     Command::argument("arena", ArgumentKind::Word).executes(
+///         |plugin: &mut MyPlugin, game: &mut Game, ctx| {
+///             let arena = ctx.string("arena").unwrap();
+///             // ...
+///         },
+///     ),
+/// );
+/// ```
+pub struct Command<Plugin> {
+    kind: CommandNodeKind,
+    permission_level: u8,
+    executor: Option<CommandCallback<Plugin>>,
+    children: Vec<Command<Plugin>>,
+}
+
+impl<Plugin> Command<Plugin> {
+    /// Creates a node matching the given word.
+    pub fn literal(name: impl Into<String>) -> Self {
+        Self::new(CommandNodeKind::Literal(name.into()))
+    }
+
+    /// Creates a node parsing an argument.
+    pub fn argument(name: impl Into<String>, kind: ArgumentKind) -> Self {
+        Self::new(CommandNodeKind::Argument {
+            name: name.into(),
+            kind,
+        })
+    }
+
+    fn new(kind: CommandNodeKind) -> Self {
+        Self {
+            kind,
+            permission_level: 0,
+            executor: None,
+            children: Vec::new(),
+        }
+    }
+
+    /// Requires the given permission level (0 to 4)
+    /// to use this node.
+    pub fn requires(mut self, permission_level: u8) -> Self {
+        self.permission_level = permission_level;
+        self
+    }
+
+    /// Adds a child node.
+    pub fn then(mut self, child: Command<Plugin>) -> Self {
+        self.children.push(child);
+        self
+    }
+
+    /// Sets the function invoked when a command
+    /// ending at this node is executed.
+    pub fn executes(
+        mut self,
+        executor: impl FnMut(&mut Plugin, &mut Game, CommandContext) + 'static,
+    ) -> Self {
+        self.executor = Some(Box::new(executor));
+        self
+    }
+
+    /// Converts this command into the form passed to the host,
+    /// moving its executors to the heap until the plugin is disabled.
+    pub(crate) fn into_node(self) -> CommandNode {
+        let executor = self
+            .executor
+            .map(|executor| callbacks::register_callback(executor) as usize as u64);
+        CommandNode {
+            kind: self.kind,
+            permission_level: self.permission_level,
+            executor,
+            children: self.children.into_iter().map(Command::into_node).collect(),
+        }
+    }
+}
+
+/// Passed to command executors. Contains
+/// the sender and the parsed arguments.
+#[derive(Debug, Clone)]
+pub struct CommandContext {
+    sender: EntityId,
+    args: Vec<(String, ArgumentValue)>,
+}
+
+impl CommandContext {
+    /// For Quill internal use only. Do not call.
+    #[doc(hidden)]
+    pub fn new(invocation: CommandInvocation) -> Self {
+        Self {
+            sender: EntityId(invocation.sender),
+            args: invocation.args,
+        }
+    }
+
+    /// Returns the entity which executed the command.
+    pub fn sender(&self) -> EntityId {
+        self.sender
+    }
+
+    /// Gets an argument by name.
+    pub fn get(&self, name: &str) -> Option<&ArgumentValue> {
+        self.args
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, value)| value)
+    }
+
+    pub fn bool(&self, name: &str) -> Option<bool> {
+        match self.get(name)? {
+            ArgumentValue::Bool(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn integer(&self, name: &str) -> Option<i32> {
+        match self.get(name)? {
+            ArgumentValue::Integer(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn double(&self, name: &str) -> Option<f64> {
+        match self.get(name)? {
+            ArgumentValue::Double(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// Gets a word, string, greedy string or message argument.
+    pub fn string(&self, name: &str) -> Option<&str> {
+        match self.get(name)? {
+            ArgumentValue::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn entities(&self, name: &str) -> Option<Vec<EntityId>> {
+        match self.get(name)? {
+            ArgumentValue::Entities(entities) => {
+                Some(entities.iter().copied().map(EntityId).collect())
+            }
+            _ => None,
+        }
+    }
+
+    /// Gets a position argument. Relative
+    /// coordinates have already been resolved.
+    pub fn position(&self, name: &str) -> Option<Position> {
+        match self.get(name)? {
+            ArgumentValue::Position(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// Gets a block position argument. Relative
+    /// coordinates have already been resolved.
+    pub fn block_position(&self, name: &str) -> Option<BlockPosition> {
+        match self.get(name)? {
+            ArgumentValue::BlockPosition(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn gamemode(&self, name: &str) -> Option<Gamemode> {
+        match self.get(name)? {
+            ArgumentValue::Gamemode(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// Gets the name of an item argument, without the namespace.
+    pub fn item(&self, name: &str) -> Option<&str> {
+        match self.get(name)? {
+            ArgumentValue::Item(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Gets a time argument in ticks.
+    pub fn time(&self, name: &str) -> Option<i32> {
+        match self.get(name)? {
+            ArgumentValue::Time(value) => Some(*value),
+            _ => None,
+        }
+    }
+}
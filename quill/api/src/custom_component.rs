@@ -0,0 +1,89 @@
+//! Components defined by plugins.
+
+use std::{any::TypeId, cell::RefCell, collections::HashMap};
+
+use serde::{de::DeserializeOwned, Serialize};
+
+/// A component defined by a plugin.
+///
+/// Custom components are stored on the server as
+/// `bincode`-serialized blobs, so they are removed along
+/// with their entity. Other plugins can access them if the
+/// defining plugin shares them with [`Setup::share_component`](crate::Setup::share_component)
+/// and they know the type.
+///
+/// Implement this trait with [`custom_component!`](crate::custom_component).
+pub trait CustomComponent: Serialize + DeserializeOwned + 'static {
+    /// Identifier of the plugin defining this component.
+    const PLUGIN: &'static str;
+    /// Name of this component, unique within the defining plugin.
+    const NAME: &'static str;
+}
+
+/// Implements [`CustomComponent`] for a type.
+///
+/// # Examples
+/// A component defined by this plugin:
+/// ```no_run
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Serialize, Deserialize)]
+/// pub struct Team(pub String);
+///
+/// quill::custom_component!(Team);
+/// ```
+///
+/// A component shared by another plugin:
+/// ```no_run
+/// # use serde::{Deserialize, Serialize};
+/// # #[derive(Serialize, Deserialize)]
+/// # pub struct Team(pub String);
+/// quill::custom_component!(Team, plugin = "teams");
+/// ```
+#[macro_export]
+macro_rules! custom_component {
+    ($ty:ident) => {
+        $crate::custom_component!($ty, plugin = env!("CARGO_PKG_NAME"));
+    };
+    ($ty:ident, plugin = $plugin:expr) => {
+        impl $crate::CustomComponent for $ty {
+            const PLUGIN: &'static str = $plugin;
+            const NAME: &'static str = stringify!($ty);
+        }
+    };
+}
+
+thread_local! {
+    static IDS: RefCell<HashMap<TypeId, u32>> = RefCell::new(HashMap::new());
+}
+
+/// Gets the ID of a custom component on the host,
+/// caching it to avoid host calls.
+pub(crate) fn custom_component_id<T: CustomComponent>() -> u32 {
+    IDS.with(|ids| {
+        *ids.borrow_mut()
+            .entry(TypeId::of::<T>())
+            .or_insert_with(|| unsafe {
+                quill_sys::custom_component_id(
+                    T::PLUGIN.as_ptr().into(),
+                    T::PLUGIN.len() as u32,
+                    T::NAME.as_ptr().into(),
+                    T::NAME.len() as u32,
+                )
+            })
+    })
+}
+
+pub(crate) fn serialize<T: CustomComponent>(component: &T) -> Vec<u8> {
+    bincode::serialize(component).expect("failed to serialize custom component")
+}
+
+pub(crate) fn deserialize<T: CustomComponent>(bytes: &[u8]) -> T {
+    bincode::deserialize(bytes).unwrap_or_else(|_| {
+        panic!(
+            "custom component {} of plugin {} has a different type",
+            T::NAME,
+            T::PLUGIN
+        )
+    })
+}
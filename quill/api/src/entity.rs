@@ -2,6 +2,8 @@ use std::{marker::PhantomData, ptr};
 
 use quill_common::{Component, Pointer, PointerMut};
 
+use crate::{custom_component, CustomComponent};
+
 /// Unique internal ID of an entity.
 ///
 /// Can be passed to [`crate::Game::entity`] to get an [`Entity`]
@@ -88,6 +90,62 @@ impl Entity {
         }
     }
 
+    /// Gets a custom component of this entity. Returns
+    /// `Err(MissingComponent)` if the entity does not have this component.
+    ///
+    /// # Panics
+    /// Panics if the component was set by a plugin
+    /// using a different type for it.
+    pub fn get_custom<T: CustomComponent>(&self) -> Result<T, MissingComponent> {
+        let id = custom_component::custom_component_id::<T>();
+
+        unsafe {
+            let mut bytes_ptr = Pointer::new(ptr::null());
+            let mut bytes_len = 0u32;
+            quill_sys::entity_get_custom_component(
+                self.id.0,
+                id,
+                PointerMut::new(&mut bytes_ptr),
+                PointerMut::new(&mut bytes_len),
+            );
+
+            if bytes_ptr.as_ptr().is_null() {
+                return Err(MissingComponent(std::any::type_name::<T>()));
+            }
+
+            let bytes = std::slice::from_raw_parts(bytes_ptr.as_ptr(), bytes_len as usize);
+            Ok(custom_component::deserialize(bytes))
+        }
+    }
+
+    /// Sets or replaces a custom component of this entity.
+    ///
+    /// If the entity already has this component,
+    /// the component is overwritten.
+    pub fn set_custom<T: CustomComponent>(&self, component: T) {
+        let id = custom_component::custom_component_id::<T>();
+        let bytes = custom_component::serialize(&component);
+
+        unsafe {
+            quill_sys::entity_set_custom_component(
+                self.id.0,
+                id,
+                bytes.as_ptr().into(),
+                bytes.len() as u32,
+            );
+        }
+    }
+
+    /// Removes a custom component from this entity.
+    ///
+    /// Does nothing if the entity does not have this component.
+    pub fn remove_custom<T: CustomComponent>(&self) {
+        let id = custom_component::custom_component_id::<T>();
+        unsafe {
+            quill_sys::entity_remove_custom_component(self.id.0, id);
+        }
+    }
+
     /// Sends the given message to this entity.
     ///
     /// The message sends as a "system" message.
@@ -117,6 +175,16 @@ impl Entity {
         self.send_title(&libcraft_text::title::Title::RESET)
     }
 
+    /// Sets the gamemode of this player, updating
+    /// their abilities and the tablist.
+    ///
+    /// Does nothing if this entity has no gamemode.
+    pub fn set_gamemode(&self, gamemode: crate::Gamemode) {
+        unsafe {
+            quill_sys::entity_set_gamemode(self.id.0, gamemode as u32);
+        }
+    }
+
     /// Gets the unique ID of this entity.
     pub fn id(&self) -> EntityId {
         self.id
@@ -1,5 +1,8 @@
 //! A WebAssembly-based plugin API for Minecraft servers.
 
+mod callbacks;
+pub mod commands;
+mod custom_component;
 pub mod entities;
 mod entity;
 mod entity_builder;
@@ -7,10 +10,11 @@ mod game;
 pub mod query;
 mod setup;
 
+pub use custom_component::CustomComponent;
 pub use entity::{Entity, EntityId};
 pub use entity_builder::EntityBuilder;
 pub use game::Game;
-pub use setup::Setup;
+pub use setup::{ConfigError, Setup};
 
 #[doc(inline)]
 pub use libcraft_blocks::{BlockKind, BlockState};
@@ -28,6 +32,8 @@ pub use uuid::Uuid;
 
 // Needed for macros
 #[doc(hidden)]
+pub use callbacks::free_callbacks;
+#[doc(hidden)]
 pub extern crate bincode;
 #[doc(hidden)]
 pub extern crate quill_sys as sys;
@@ -119,6 +125,14 @@ macro_rules! plugin {
             PLUGIN = Some(plugin);
         }
 
+        #[no_mangle]
+        #[doc(hidden)]
+        pub unsafe extern "C" fn quill_disable() {
+            let plugin = PLUGIN.take().expect("quill_setup never called");
+            plugin.disable(&mut $crate::Game::new());
+            $crate::free_callbacks();
+        }
+
         #[no_mangle]
         #[doc(hidden)]
         pub unsafe extern "C" fn quill_allocate(size: usize, align: usize) -> *mut u8 {
@@ -142,6 +156,28 @@ macro_rules! plugin {
             system(plugin, &mut $crate::Game::new());
         }
 
+        #[no_mangle]
+        #[doc(hidden)]
+        pub unsafe extern "C" fn quill_run_command(
+            data: *mut u8,
+            invocation_ptr: *const u8,
+            invocation_len: usize,
+        ) {
+            let invocation_bytes = ::std::slice::from_raw_parts(invocation_ptr, invocation_len);
+            let invocation =
+                $crate::bincode::deserialize(invocation_bytes).expect("invalid command invocation");
+            let command =
+                &mut *data.cast::<Box<
+                    dyn FnMut(&mut $plugin, &mut $crate::Game, $crate::commands::CommandContext),
+                >>();
+            let plugin = PLUGIN.as_mut().expect("quill_setup never called");
+            command(
+                plugin,
+                &mut $crate::Game::new(),
+                $crate::commands::CommandContext::new(invocation),
+            );
+        }
+
         /// Never called by Quill, but this is needed
         /// to avoid linker errors with WASI.
         #[doc(hidden)]
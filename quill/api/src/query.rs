@@ -1,10 +1,10 @@
 //! Query for all entities with a certain set of components.
 
-use std::{marker::PhantomData, mem::MaybeUninit};
+use std::{marker::PhantomData, mem::MaybeUninit, ptr};
 
-use quill_common::{entity::QueryData, Component, HostComponent, PointerMut};
+use quill_common::{component::CUSTOM_COMPONENT_OFFSET, entity::QueryData, Component, PointerMut};
 
-use crate::{Entity, EntityId};
+use crate::{custom_component, CustomComponent, Entity, EntityId};
 
 /// A type that can be used for a query.
 ///
@@ -12,7 +12,8 @@ use crate::{Entity, EntityId};
 pub trait Query {
     type Item;
 
-    fn add_component_types(types: &mut Vec<HostComponent>);
+    /// Adds the IDs of the queried components to `types`.
+    fn add_component_types(types: &mut Vec<u32>);
 
     /// # Safety
     /// `component_index` must be a valid index less
@@ -34,8 +35,8 @@ where
 {
     type Item = T;
 
-    fn add_component_types(types: &mut Vec<HostComponent>) {
-        types.push(T::host_component());
+    fn add_component_types(types: &mut Vec<u32>) {
+        types.push(T::host_component() as u32);
     }
 
     unsafe fn get_unchecked(
@@ -62,11 +63,63 @@ where
     }
 }
 
+/// Queries a [`CustomComponent`].
+///
+/// # Examples
+/// ```no_run
+/// use quill::{query::Custom, Position};
+/// # use serde::{Deserialize, Serialize};
+/// # #[derive(Serialize, Deserialize)]
+/// # pub struct Team(pub String);
+/// # quill::custom_component!(Team);
+/// # let game: quill::Game = todo!();
+/// for (entity, (position, team)) in game.query::<(&Position, Custom<Team>)>() {
+///     println!("Found a member of team {} at {:?}", team.0, position);
+/// }
+/// ```
+pub struct Custom<T>(PhantomData<T>);
+
+impl<T> Query for Custom<T>
+where
+    T: CustomComponent,
+{
+    type Item = T;
+
+    fn add_component_types(types: &mut Vec<u32>) {
+        types.push(CUSTOM_COMPONENT_OFFSET + custom_component::custom_component_id::<T>());
+    }
+
+    unsafe fn get_unchecked(
+        data: &QueryData,
+        component_index: &mut usize,
+        component_offsets: &mut [usize],
+    ) -> T {
+        let component_ptr =
+            (*(data.component_ptrs.as_mut_ptr().add(*component_index))).as_mut_ptr();
+        let offset = component_offsets[*component_index];
+        let component_ptr = component_ptr.add(offset);
+
+        // Each component is prefixed with its length.
+        let mut len = [0; 4];
+        ptr::copy_nonoverlapping(component_ptr, len.as_mut_ptr(), len.len());
+        let len = u32::from_le_bytes(len) as usize;
+
+        let component_bytes = std::slice::from_raw_parts(component_ptr.add(4), len);
+        let value = custom_component::deserialize(component_bytes);
+
+        component_offsets[*component_index] += 4 + len;
+
+        *component_index += 1;
+
+        value
+    }
+}
+
 macro_rules! impl_query_tuple {
     ($($query:ident),* $(,)?) => {
         impl <$($query: Query),*> Query for ($($query,)*) {
             type Item = ($($query::Item),*);
-            fn add_component_types(types: &mut Vec<HostComponent>) {
+            fn add_component_types(types: &mut Vec<u32>) {
                 $(
                     $query::add_component_types(types);
                 )*
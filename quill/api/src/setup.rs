@@ -1,6 +1,20 @@
-use std::marker::PhantomData;
+use std::{fs, io, marker::PhantomData, path::PathBuf, ptr};
 
-use crate::Game;
+use quill_common::{Pointer, PointerMut};
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{callbacks, commands::Command, custom_component, CustomComponent, Game};
+
+/// Error returned from [`Setup::load_config`].
+#[derive(Debug, thiserror::Error)]
+pub enum ConfigError {
+    #[error("failed to access the config file: {0}")]
+    Io(#[from] io::Error),
+    #[error("malformed config file: {0}")]
+    Parse(#[from] toml::de::Error),
+    #[error("failed to serialize the default config: {0}")]
+    Serialize(#[from] toml::ser::Error),
+}
 
 /// Struct passed to your plugin's `enable()` function.
 ///
@@ -26,7 +40,7 @@ impl<Plugin> Setup<Plugin> {
     /// plugin instance and an `&mut Game` and return nothing.
     pub fn add_system<T: FnMut(&mut Plugin, &mut Game)>(&mut self, system: T) -> &mut Self {
         let system: Box<dyn FnMut(&mut Plugin, &mut Game)> = Box::new(system);
-        let system_data = Box::leak(Box::new(system)) as *mut Box<_> as *mut u8;
+        let system_data = callbacks::register_callback(system);
 
         let name = std::any::type_name::<T>();
 
@@ -36,4 +50,70 @@ impl<Plugin> Setup<Plugin> {
 
         self
     }
+
+    /// Registers a command.
+    ///
+    /// The command's executors are invoked with
+    /// your plugin instance, an `&mut Game`, and a
+    /// [`CommandContext`](crate::commands::CommandContext)
+    /// containing the sender and parsed arguments.
+    pub fn register_command(&mut self, command: Command<Plugin>) -> &mut Self {
+        let node = command.into_node();
+        let bytes = bincode::serialize(&node).expect("failed to serialize command");
+
+        unsafe {
+            quill_sys::register_command(bytes.as_ptr().into(), bytes.len() as u32);
+        }
+
+        self
+    }
+
+    /// Allows other plugins to access the custom component `T`
+    /// defined by your plugin.
+    pub fn share_component<T: CustomComponent>(&mut self) -> &mut Self {
+        let id = custom_component::custom_component_id::<T>();
+        unsafe {
+            quill_sys::custom_component_share(id);
+        }
+        self
+    }
+
+    /// Gets the path of your plugin's private data directory,
+    /// `plugins/<identifier>` on the server.
+    ///
+    /// Use it to store config files and persistent state.
+    /// WebAssembly plugins cannot access files outside of it.
+    pub fn data_dir(&self) -> PathBuf {
+        unsafe {
+            let mut path_ptr = Pointer::new(ptr::null());
+            let mut path_len = 0u32;
+            quill_sys::plugin_data_dir(
+                PointerMut::new(&mut path_ptr),
+                PointerMut::new(&mut path_len),
+            );
+
+            let bytes = std::slice::from_raw_parts(path_ptr.as_ptr(), path_len as usize);
+            PathBuf::from(String::from_utf8_lossy(bytes).into_owned())
+        }
+    }
+
+    /// Loads the TOML config file `file_name`
+    /// from your plugin's data directory.
+    ///
+    /// If the file does not exist, it is created
+    /// containing the default config.
+    pub fn load_config<T>(&self, file_name: &str) -> Result<T, ConfigError>
+    where
+        T: Serialize + DeserializeOwned + Default,
+    {
+        let path = self.data_dir().join(file_name);
+        if !path.exists() {
+            let config = T::default();
+            fs::write(&path, toml::to_string_pretty(&config)?)?;
+            return Ok(config);
+        }
+
+        let string = fs::read_to_string(&path)?;
+        Ok(toml::from_str(&string)?)
+    }
 }
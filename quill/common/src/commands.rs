@@ -0,0 +1,87 @@
+//! Command trees registered by plugins and
+//! the invocations passed back to them.
+
+use libcraft_core::{BlockPosition, Gamemode, Position};
+use serde::{Deserialize, Serialize};
+
+use crate::EntityId;
+
+/// A node in a command tree, passed to the
+/// `register_command` host call.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CommandNode {
+    pub kind: CommandNodeKind,
+    /// The permission level required to use this node.
+    pub permission_level: u8,
+    /// The data pointer passed to `quill_run_command`
+    /// when a command ending at this node is executed.
+    pub executor: Option<u64>,
+    pub children: Vec<CommandNode>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum CommandNodeKind {
+    Literal(String),
+    Argument { name: String, kind: ArgumentKind },
+}
+
+/// Determines how the input of an argument is parsed.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum ArgumentKind {
+    Bool,
+    Integer {
+        min: Option<i32>,
+        max: Option<i32>,
+    },
+    Double {
+        min: Option<f64>,
+        max: Option<f64>,
+    },
+    /// A single word.
+    Word,
+    /// A single word or a phrase in double quotes.
+    String,
+    /// The rest of the input.
+    GreedyString,
+    /// An entity selector or a player name.
+    Entities {
+        single: bool,
+        players_only: bool,
+    },
+    /// A position, possibly relative to the sender (`~`).
+    Position,
+    /// A block position, possibly relative to the sender (`~`).
+    BlockPosition,
+    Gamemode,
+    /// A chat message, taking the rest of the input.
+    Message,
+    /// An item name, e.g. `minecraft:diamond`.
+    Item,
+    /// A duration in ticks.
+    Time,
+}
+
+/// A parsed argument.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum ArgumentValue {
+    Bool(bool),
+    Integer(i32),
+    Double(f64),
+    String(String),
+    Entities(Vec<EntityId>),
+    Position(Position),
+    BlockPosition(BlockPosition),
+    Gamemode(Gamemode),
+    /// The name of an item, without the namespace.
+    Item(String),
+    /// A duration in ticks.
+    Time(i32),
+}
+
+/// Passed to `quill_run_command` when a command is executed.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CommandInvocation {
+    pub sender: EntityId,
+    /// The arguments on the path to the executed node, by name.
+    pub args: Vec<(String, ArgumentValue)>,
+}
@@ -188,11 +188,32 @@ host_component_enum! {
         CreativeFlyingEvent = 1010,
         Sneaking = 1011,
         SneakEvent = 1012,
-
-
+        Velocity = 1013,
+        MovementViolationEvent = 1014,
+        GamemodeChangeEvent = 1015,
+        ViewDistance = 1016,
+        Sprinting = 1017,
+        Invisible = 1018,
+        Glowing = 1019,
+        PlayerJoinEvent = 1020,
+        PlayerLeaveEvent = 1021,
+        EntityCreateEvent = 1022,
+        EntityRemoveEvent = 1023,
+        BlockChangeEvent = 1024,
+        ChunkLoadEvent = 1025,
+        ChatEvent = 1026,
     }
 }
 
+/// Offset added to the IDs of custom components defined by
+/// plugins when passing them to the `entity_query` host call,
+/// distinguishing them from [`HostComponent`]s.
+///
+/// In query results, each custom component is prefixed with
+/// its length in bytes as a little-endian `u32`, followed
+/// by its `bincode` representation.
+pub const CUSTOM_COMPONENT_OFFSET: u32 = 0x8000_0000;
+
 /// How a component will be serialized.
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum SerializationMethod {
@@ -352,3 +373,12 @@ bincode_component_impl!(BlockPlacementEvent);
 bincode_component_impl!(BlockInteractEvent);
 bincode_component_impl!(CreativeFlyingEvent);
 bincode_component_impl!(SneakEvent);
+bincode_component_impl!(MovementViolationEvent);
+bincode_component_impl!(GamemodeChangeEvent);
+bincode_component_impl!(PlayerJoinEvent);
+bincode_component_impl!(PlayerLeaveEvent);
+bincode_component_impl!(EntityCreateEvent);
+bincode_component_impl!(EntityRemoveEvent);
+bincode_component_impl!(BlockChangeEvent);
+bincode_component_impl!(ChunkLoadEvent);
+bincode_component_impl!(ChatEvent);
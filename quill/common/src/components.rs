@@ -109,3 +109,50 @@ bincode_component_impl!(CreativeFlying);
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct Sneaking(pub bool);
 bincode_component_impl!(Sneaking);
+
+/// Whether an entity is sprinting.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Sprinting(pub bool);
+bincode_component_impl!(Sprinting);
+
+/// Whether an entity is invisible to players.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Invisible(pub bool);
+bincode_component_impl!(Invisible);
+
+/// Whether an entity glows, showing its
+/// outline through blocks.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Glowing(pub bool);
+bincode_component_impl!(Glowing);
+
+/// An entity's velocity, in blocks per tick.
+///
+/// Entities other than players are moved by their velocity
+/// each tick and are affected by gravity and drag.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Velocity {
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+}
+
+bincode_component_impl!(Velocity);
+
+impl Velocity {
+    pub fn new(x: f64, y: f64, z: f64) -> Self {
+        Self { x, y, z }
+    }
+}
+
+/// The distance in chunks around a player within
+/// which chunks and entities are sent to them.
+///
+/// Changing this component resizes the player's view on
+/// the next tick, sending or unloading chunks as needed.
+/// A player's view never exceeds the distance requested
+/// by their client nor the server's maximum.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ViewDistance(pub u32);
+
+bincode_component_impl!(ViewDistance);
@@ -1,7 +1,15 @@
 mod block_interact;
 mod change;
+mod chat;
 mod interact_entity;
+mod lifecycle;
+mod movement;
+mod world;
 
 pub use block_interact::{BlockInteractEvent, BlockPlacementEvent};
-pub use change::{CreativeFlyingEvent, SneakEvent};
+pub use change::{CreativeFlyingEvent, GamemodeChangeEvent, SneakEvent};
+pub use chat::ChatEvent;
 pub use interact_entity::InteractEntityEvent;
+pub use lifecycle::{EntityCreateEvent, EntityRemoveEvent, PlayerJoinEvent, PlayerLeaveEvent};
+pub use movement::{MovementViolation, MovementViolationEvent};
+pub use world::{BlockChangeEvent, ChunkLoadEvent};
@@ -2,6 +2,7 @@
 All events in this file are triggerd when there is a change in a certain value.
 */
 
+use libcraft_core::Gamemode;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -29,3 +30,18 @@ impl SneakEvent {
         }
     }
 }
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GamemodeChangeEvent {
+    pub gamemode: Gamemode,
+    pub previous: Gamemode,
+}
+
+impl GamemodeChangeEvent {
+    pub fn new(changed_to: Gamemode, previous: Gamemode) -> Self {
+        Self {
+            gamemode: changed_to,
+            previous,
+        }
+    }
+}
@@ -0,0 +1,31 @@
+use serde::{Deserialize, Serialize};
+
+use crate::EntityId;
+
+/// Triggered when a player sends a chat message.
+///
+/// The message is broadcast on the next tick unless the
+/// event is cancelled. Plugins may also change the message
+/// by setting the event component.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ChatEvent {
+    /// The player who sent the message.
+    pub sender: EntityId,
+    pub message: String,
+    pub cancelled: bool,
+}
+
+impl ChatEvent {
+    pub fn new(sender: EntityId, message: String) -> Self {
+        Self {
+            sender,
+            message,
+            cancelled: false,
+        }
+    }
+
+    /// Prevents the message from being broadcast.
+    pub fn cancel(&mut self) {
+        self.cancelled = true;
+    }
+}
@@ -0,0 +1,23 @@
+use serde::{Deserialize, Serialize};
+
+/// Triggered when a player joins the game.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PlayerJoinEvent;
+
+/// Triggered when a player leaves the game.
+///
+/// The player entity is removed along with this
+/// event, so it also has an [`EntityRemoveEvent`].
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PlayerLeaveEvent;
+
+/// Triggered when an entity is added into the world.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct EntityCreateEvent;
+
+/// Triggered when an entity is removed from the world.
+///
+/// The entity will remain alive for one tick after it is
+/// destroyed to allow systems to observe this event.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct EntityRemoveEvent;
@@ -0,0 +1,25 @@
+use libcraft_core::Position;
+use serde::{Deserialize, Serialize};
+
+/// A reason why the server rejected a player's movement.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MovementViolation {
+    /// The player moved further than allowed in a single packet.
+    TooFast,
+    /// The player moved into a solid block.
+    Collision,
+    /// The player flew without being allowed to.
+    Flight,
+    /// The player moved into a chunk which is not loaded.
+    UnloadedChunk,
+}
+
+/// Triggered when the server rejects a player's movement.
+///
+/// The player is moved back to their previous position.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MovementViolationEvent {
+    pub violation: MovementViolation,
+    /// The position the player attempted to move to.
+    pub attempted: Position,
+}
@@ -0,0 +1,18 @@
+use libcraft_core::{BlockPosition, ChunkPosition};
+use serde::{Deserialize, Serialize};
+
+/// Triggered when one or more blocks are changed.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub enum BlockChangeEvent {
+    /// A single block was changed.
+    Single { position: BlockPosition },
+    /// A chunk section (16x16x16 blocks) was
+    /// filled with the same block.
+    FillChunkSection { chunk: ChunkPosition, section: u32 },
+}
+
+/// Triggered when a chunk is loaded.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ChunkLoadEvent {
+    pub position: ChunkPosition,
+}
@@ -3,6 +3,7 @@ mod utils;
 #[macro_use]
 pub mod component;
 pub mod block;
+pub mod commands;
 pub mod components;
 pub mod entities;
 pub mod entity;
@@ -40,14 +40,27 @@ extern "C" {
     /// to this host call.
     pub fn register_system(system_data: PointerMut<u8>, name_ptr: Pointer<u8>, name_len: u32);
 
+    /// Registers a command.
+    ///
+    /// `command_ptr` points to a `bincode`-serialized
+    /// `quill_common::commands::CommandNode`. When a command
+    /// ending at a node with an `executor` is run, the host
+    /// invokes the plugin's exported `quill_run_command` method
+    /// with that executor pointer and a `bincode`-serialized
+    /// `CommandInvocation`.
+    pub fn register_command(command_ptr: Pointer<u8>, command_len: u32);
+
     /// Initiates a query. Returns the query data.
     ///
+    /// Each component is either a `HostComponent` or the ID of
+    /// a custom component plus `CUSTOM_COMPONENT_OFFSET`.
+    ///
     /// The returned query buffers are allocated within
     /// the plugin's bump allocator. They will be
     /// freed automatically after the plugin finishes
     /// executing the current system.
     pub fn entity_query(
-        components_ptr: Pointer<HostComponent>,
+        components_ptr: Pointer<u32>,
         components_len: u32,
         query_data: PointerMut<MaybeUninit<QueryData>>,
     );
@@ -85,6 +98,54 @@ extern "C" {
         bytes_len: u32,
     );
 
+    /// Gets the ID of the custom component `name`
+    /// defined by the plugin `plugin`.
+    ///
+    /// IDs remain the same while the server runs.
+    pub fn custom_component_id(
+        plugin_ptr: Pointer<u8>,
+        plugin_len: u32,
+        name_ptr: Pointer<u8>,
+        name_len: u32,
+    ) -> u32;
+
+    /// Allows all plugins to access a custom
+    /// component defined by this plugin.
+    pub fn custom_component_share(component: u32);
+
+    /// Gets a custom component for an entity.
+    ///
+    /// Sets `bytes_ptr` to a pointer to the `bincode`-serialized
+    /// component and `bytes_len` to the number of bytes.
+    ///
+    /// If the entity does not have the component,
+    /// then `bytes_ptr` is set to null, and `bytes_len`
+    /// is left untouched.
+    pub fn entity_get_custom_component(
+        entity: EntityId,
+        component: u32,
+        bytes_ptr: PointerMut<Pointer<u8>>,
+        bytes_len: PointerMut<u32>,
+    );
+
+    /// Sets or replaces a custom component for an entity.
+    ///
+    /// `bytes_ptr` is a pointer to the `bincode`-serialized
+    /// component.
+    ///
+    /// Does nothing if `entity` does not exist.
+    pub fn entity_set_custom_component(
+        entity: EntityId,
+        component: u32,
+        bytes_ptr: Pointer<u8>,
+        bytes_len: u32,
+    );
+
+    /// Removes a custom component from an entity.
+    ///
+    /// Does nothing if the entity does not have the component.
+    pub fn entity_remove_custom_component(entity: EntityId, component: u32);
+
     /// Sends a message to an entity.
     ///
     /// The given message should be in the JSON format.
@@ -99,6 +160,14 @@ extern "C" {
     /// Does nothing if the entity does not exist or if it does not have the `Chat` component.
     pub fn entity_send_title(entity: EntityId, title_ptr: Pointer<u8>, title_len: u32);
 
+    /// Sets the gamemode of a player.
+    ///
+    /// `gamemode` is the ID of a `Gamemode`. The player's client
+    /// is sent their new gamemode and abilities.
+    ///
+    /// Does nothing if the entity does not exist or it does not have the `Gamemode` component.
+    pub fn entity_set_gamemode(entity: EntityId, gamemode: u32);
+
     /// Creates an empty entity builder.
     ///
     /// This builder is used for creating an ecs-entity
@@ -174,4 +243,14 @@ extern "C" {
         data_ptr: Pointer<u8>,
         data_len: u32,
     );
+
+    /// Gets the path of the plugin's private data directory.
+    ///
+    /// Sets `path_ptr` to a pointer to the UTF-8 path
+    /// and `path_len` to its length in bytes. The path
+    /// is allocated within the plugin's bump allocator.
+    ///
+    /// On WASM, this is the only directory
+    /// the plugin can access.
+    pub fn plugin_data_dir(path_ptr: PointerMut<Pointer<u8>>, path_len: PointerMut<u32>);
 }